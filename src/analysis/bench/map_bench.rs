@@ -0,0 +1,314 @@
+use core::{
+    fmt,
+    hash::{BuildHasher, Hash},
+    time::Duration,
+};
+use std::{collections::HashMap, hint::black_box};
+
+use crate::{
+    map::{
+        dense_hash_map::DenseHashMap,
+        hash_map::{HashGet, HashRemove},
+        MapInsert,
+    },
+    ops::{clear::Clear, unit::HumanDuration},
+};
+
+use super::{BenchIterControl, Bencher, BencherConfig, HeapRandomizer};
+
+pub const DEFAULT_N: usize = 2 << 16;
+const VALUE_SIZE: usize = 2 << 5;
+
+#[derive(Debug, Clone)]
+pub struct BenchValue {
+    #[allow(dead_code)]
+    buf: [u8; VALUE_SIZE],
+}
+impl BenchValue {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; VALUE_SIZE],
+        }
+    }
+}
+impl Default for BenchValue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub trait MapForEach<K, V> {
+    fn for_each(&self, f: impl FnMut(&K, &V));
+    fn for_each_value(&self, f: impl FnMut(&V));
+}
+impl<K, V, S> MapForEach<K, V> for HashMap<K, V, S> {
+    fn for_each(&self, mut f: impl FnMut(&K, &V)) {
+        self.iter().for_each(|(k, v)| f(k, v));
+    }
+    fn for_each_value(&self, f: impl FnMut(&V)) {
+        self.values().for_each(f);
+    }
+}
+impl<K, V, H> MapForEach<K, V> for DenseHashMap<K, V, H>
+where
+    K: Eq + Hash,
+    H: BuildHasher,
+{
+    fn for_each(&self, mut f: impl FnMut(&K, &V)) {
+        self.iter().for_each(|(k, v)| f(k, v));
+    }
+    fn for_each_value(&self, f: impl FnMut(&V)) {
+        self.values().for_each(f);
+    }
+}
+
+/// Every operation exercised by [`run()`]
+pub trait BenchMap:
+    MapInsert<usize, BenchValue>
+    + HashGet<usize, BenchValue>
+    + HashRemove<usize, BenchValue>
+    + MapForEach<usize, BenchValue>
+    + Clear
+{
+}
+impl<T> BenchMap for T where
+    T: MapInsert<usize, BenchValue>
+        + HashGet<usize, BenchValue>
+        + HashRemove<usize, BenchValue>
+        + MapForEach<usize, BenchValue>
+        + Clear
+{
+}
+
+#[derive(Debug, Clone)]
+pub struct MapBenchConfig {
+    pub bencher: BencherConfig,
+    /// Number of entries per scenario
+    pub n: usize,
+}
+impl Default for MapBenchConfig {
+    fn default() -> Self {
+        Self {
+            bencher: BencherConfig::default(),
+            n: DEFAULT_N,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MapScenario {
+    Get,
+    Iter,
+    InsertRemove,
+    InsertIterRemove,
+    InsertClear,
+    Values,
+}
+impl MapScenario {
+    pub const ALL: [Self; 6] = [
+        Self::Get,
+        Self::Iter,
+        Self::InsertRemove,
+        Self::InsertIterRemove,
+        Self::InsertClear,
+        Self::Values,
+    ];
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Get => "get",
+            Self::Iter => "iter",
+            Self::InsertRemove => "insert_remove",
+            Self::InsertIterRemove => "insert_iter_remove",
+            Self::InsertClear => "insert_clear",
+            Self::Values => "values",
+        }
+    }
+
+    const fn is_prefilled(&self) -> bool {
+        matches!(self, Self::Get | Self::Iter | Self::Values)
+    }
+    /// Run the scenario once on a map set up by [`run()`]
+    fn run<M: BenchMap>(&self, map: &mut M, n: usize) {
+        match self {
+            Self::Get => get(map, n),
+            Self::Iter => iter(map),
+            Self::InsertRemove => insert_remove(map, n),
+            Self::InsertIterRemove => insert_iter_remove(map, n),
+            Self::InsertClear => insert_clear(map, n),
+            Self::Values => values(map),
+        }
+    }
+}
+
+/// Run all the [`MapScenario`]s on fresh instances of `M`
+pub fn run<M: BenchMap + Default>(config: &MapBenchConfig) -> MapBenchReport {
+    let bencher = Bencher::new(config.bencher.clone());
+    let n = config.n;
+    let rows = MapScenario::ALL
+        .into_iter()
+        .map(|scenario| {
+            let setup = || {
+                let mut map = M::default();
+                if scenario.is_prefilled() {
+                    fill(&mut map, n);
+                }
+                map
+            };
+            let stats = bencher.iter(setup, |map| {
+                scenario.run(map, n);
+                BenchIterControl::Continue
+            });
+            MapBenchRow {
+                scenario,
+                iterations: stats.iterations,
+                mean: Duration::from_secs_f64(stats.mean_secs()),
+                standard_deviation: Duration::from_secs_f64(stats.standard_deviation_secs()),
+            }
+        })
+        .collect();
+    MapBenchReport { rows }
+}
+
+#[derive(Debug, Clone)]
+pub struct MapBenchReport {
+    pub rows: Vec<MapBenchRow>,
+}
+impl MapBenchReport {
+    #[must_use]
+    pub fn get(&self, scenario: MapScenario) -> Option<&MapBenchRow> {
+        self.rows.iter().find(|row| row.scenario == scenario)
+    }
+}
+impl fmt::Display for MapBenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<20} {:>12} {:>12} {:>12}",
+            "scenario", "mean", "std", "iterations"
+        )?;
+        for row in &self.rows {
+            let mean = format!("{:.1}", HumanDuration(row.mean));
+            let std = format!("{:.1}", HumanDuration(row.standard_deviation));
+            writeln!(
+                f,
+                "{:<20} {:>12} {:>12} {:>12}",
+                row.scenario.name(),
+                mean,
+                std,
+                row.iterations
+            )?;
+        }
+        Ok(())
+    }
+}
+#[derive(Debug, Clone)]
+pub struct MapBenchRow {
+    pub scenario: MapScenario,
+    pub iterations: usize,
+    pub mean: Duration,
+    pub standard_deviation: Duration,
+}
+
+pub fn fill<M: MapInsert<usize, BenchValue>>(map: &mut M, n: usize) {
+    for i in 0..n {
+        map.insert(i, BenchValue::new());
+    }
+}
+/// [`fill()`] but with the allocations of the map scattered across the heap
+pub fn fill_scattered<M: MapInsert<usize, BenchValue>>(map: &mut M, n: usize) {
+    let mut heap = HeapRandomizer::new();
+    for i in 0..n {
+        heap.randomize();
+        map.insert(i, BenchValue::new());
+    }
+}
+/// Look up the keys `0..n` alternating from both ends
+pub fn get<M: HashGet<usize, BenchValue>>(map: &M, n: usize) {
+    for i in alternate(n) {
+        black_box(map.get(&i));
+    }
+}
+pub fn iter<M: MapForEach<usize, BenchValue>>(map: &M) {
+    map.for_each(|k, v| {
+        black_box((k, v));
+    });
+}
+pub fn values<M: MapForEach<usize, BenchValue>>(map: &M) {
+    map.for_each_value(|v| {
+        black_box(v);
+    });
+}
+pub fn insert_remove<M>(map: &mut M, n: usize)
+where
+    M: MapInsert<usize, BenchValue> + HashRemove<usize, BenchValue>,
+{
+    fill(map, n);
+    for i in alternate(n) {
+        map.remove(&i);
+    }
+}
+/// Iterate the whole map before each removal; only `sqrt(n)` keys are used
+pub fn insert_iter_remove<M>(map: &mut M, n: usize)
+where
+    M: MapInsert<usize, BenchValue> + HashRemove<usize, BenchValue> + MapForEach<usize, BenchValue>,
+{
+    let n = (n as f64).sqrt().round() as usize;
+    fill(map, n);
+    for i in alternate(n) {
+        iter(map);
+        map.remove(&i);
+    }
+}
+pub fn insert_clear<M>(map: &mut M, n: usize)
+where
+    M: MapInsert<usize, BenchValue> + Clear,
+{
+    fill(map, n);
+    map.clear();
+}
+
+/// `0, n - 2, 2, n - 4, ...`
+fn alternate(n: usize) -> impl Iterator<Item = usize> {
+    (0..n).map(move |i| if i % 2 == 0 { i } else { n - 1 - i })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::{dense_hash_map::IntDenseHashMap, hash_map::NoHashBuilder};
+
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        let config = MapBenchConfig {
+            bencher: BencherConfig {
+                warmup_duration: Duration::from_millis(1),
+                cool_down_duration: Duration::ZERO,
+                measuring_duration: Duration::from_millis(5),
+                ..Default::default()
+            },
+            n: 1 << 6,
+        };
+        for report in [
+            run::<HashMap<_, _>>(&config),
+            run::<HashMap<_, _, NoHashBuilder<usize>>>(&config),
+            run::<DenseHashMap<_, _>>(&config),
+            run::<IntDenseHashMap<_, _>>(&config),
+        ] {
+            assert_eq!(report.rows.len(), MapScenario::ALL.len());
+            for scenario in MapScenario::ALL {
+                assert!(0 < report.get(scenario).unwrap().iterations);
+            }
+            let table = report.to_string();
+            assert_eq!(table.lines().count(), MapScenario::ALL.len() + 1);
+            assert!(table.contains("insert_iter_remove"));
+            println!("{table}");
+        }
+    }
+
+    #[test]
+    fn test_alternate() {
+        assert_eq!(alternate(5).collect::<Vec<_>>(), [0, 3, 2, 1, 4]);
+    }
+}
@@ -1,3 +1,5 @@
+pub mod map_bench;
+
 use core::{num::NonZeroUsize, time::Duration};
 use std::collections::LinkedList;
 
@@ -6,11 +8,13 @@ use num_traits::Float;
 use crate::{
     ops::{
         clear::Clear,
-        float::{NonNegR, PosR, UnitR},
+        float::{sort_floats, NonNegR, PosR, UnitR},
     },
     time::stopwatch::ElapsedStopwatch,
 };
 
+use super::metrics::{HistogramSnapshotSource, MetricSource, MetricVisitor};
+
 #[derive(Debug)]
 pub struct HeapRandomizer {
     list: LinkedList<usize>,
@@ -35,11 +39,48 @@ impl Default for HeapRandomizer {
     }
 }
 
+/// Evict the caches by walking a buffer larger than them
+///
+/// One write per cache line so that the lines of the workload are dirty-evicted rather than just aged.
+#[derive(Debug)]
+pub struct CachePolluter {
+    buf: Box<[u64]>,
+}
+impl CachePolluter {
+    const LINE_WORDS: usize = 64 / core::mem::size_of::<u64>();
+
+    /// Occupy at least `bytes` rounded up to a cache line
+    #[must_use]
+    pub fn new(bytes: usize) -> Self {
+        let lines = bytes.div_ceil(Self::LINE_WORDS * core::mem::size_of::<u64>());
+        Self {
+            buf: vec![0; lines * Self::LINE_WORDS].into_boxed_slice(),
+        }
+    }
+    #[must_use]
+    pub fn footprint(&self) -> usize {
+        core::mem::size_of_val(&*self.buf)
+    }
+    /// Bump the first word of every cache line and return the sum of the bumped words
+    pub fn pollute(&mut self) -> u64 {
+        let mut checksum: u64 = 0;
+        for word in self.buf.iter_mut().step_by(Self::LINE_WORDS) {
+            *word = word.wrapping_add(1);
+            checksum = checksum.wrapping_add(*word);
+        }
+        core::hint::black_box(checksum)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct BencherConfig {
     pub warmup_duration: Duration,
     pub cool_down_duration: Duration,
     pub measuring_duration: Duration,
+    /// Keep the time per iteration of every measured batch in [`BenchIterStats::samples`]
+    pub collect_samples: bool,
+    /// Walk a [`CachePolluter`] of this many bytes between batches to measure the cold path
+    pub pollute_cache: Option<usize>,
 }
 impl Default for BencherConfig {
     fn default() -> Self {
@@ -47,6 +88,8 @@ impl Default for BencherConfig {
             warmup_duration: Duration::from_millis(100),
             cool_down_duration: Duration::from_secs(1),
             measuring_duration: Duration::from_secs(5),
+            collect_samples: false,
+            pollute_cache: None,
         }
     }
 }
@@ -64,19 +107,34 @@ impl Bencher {
         setup: impl Fn() -> T,
         mut workload: impl FnMut(&mut T) -> BenchIterControl,
     ) -> BenchIterStats {
+        let mut polluter = self.config.pollute_cache.map(CachePolluter::new);
+        let mut between_batches = || {
+            if let Some(polluter) = &mut polluter {
+                polluter.pollute();
+            }
+        };
         let warmup = spin(
             self.config.warmup_duration,
             1,
-            None,
+            |_| {},
+            &mut between_batches,
             &mut setup(),
             &mut workload,
         );
         std::thread::sleep(self.config.cool_down_duration);
+        let batch_size = warmup.iterations;
         let mut cum_var_secs = CumVar::new(warmup.mean_secs());
+        let mut samples = vec![];
         let measuring = spin(
             self.config.measuring_duration,
-            warmup.iterations,
-            Some(&mut cum_var_secs),
+            batch_size,
+            |secs| {
+                cum_var_secs.update(secs);
+                if self.config.collect_samples {
+                    samples.push(secs);
+                }
+            },
+            &mut between_batches,
             &mut setup(),
             &mut workload,
         );
@@ -84,6 +142,8 @@ impl Bencher {
             iterations: measuring.iterations,
             duration: measuring.duration,
             variance_secs: cum_var_secs.get(),
+            batch_size,
+            samples,
         }
     }
 }
@@ -95,10 +155,12 @@ impl Default for Bencher {
         }
     }
 }
+/// `on_batch` takes the time per iteration of each batch in seconds; `between_batches` is not timed
 fn spin<T>(
     at_least_for: Duration,
     batch_size: usize,
-    mut cum_var_secs: Option<&mut CumVar<f64>>,
+    mut on_batch: impl FnMut(f64),
+    mut between_batches: impl FnMut(),
     spin_env: &mut T,
     mut workload: impl FnMut(&mut T) -> BenchIterControl,
 ) -> SpinStats {
@@ -114,6 +176,7 @@ fn spin<T>(
                 duration,
             };
         }
+        between_batches();
         let batch_running = elapsed.stopwatch_mut().start_scoped();
         for _ in 0..batch_size {
             let ctrl = workload(spin_env);
@@ -127,9 +190,7 @@ fn spin<T>(
             }
         }
         let batch_elapsed = batch_running.stop();
-        if let Some(cum_var) = cum_var_secs.as_deref_mut() {
-            cum_var.update(batch_elapsed.as_secs_f64() / batch_size as f64);
-        }
+        on_batch(batch_elapsed.as_secs_f64() / batch_size as f64);
     }
 }
 #[derive(Debug, Clone)]
@@ -152,6 +213,10 @@ pub struct BenchIterStats {
     pub iterations: usize,
     pub duration: Duration,
     pub variance_secs: f64,
+    /// Iterations per measured batch
+    pub batch_size: usize,
+    /// Seconds per iteration of each measured batch if [`BencherConfig::collect_samples`]
+    pub samples: Vec<f64>,
 }
 impl BenchIterStats {
     pub fn mean_secs(&self) -> f64 {
@@ -160,6 +225,74 @@ impl BenchIterStats {
     pub fn standard_deviation_secs(&self) -> f64 {
         self.variance_secs.sqrt()
     }
+    /// Seconds per iteration at `p` of the [`Self::samples`] interpolated linearly; [`None`] without samples
+    #[must_use]
+    pub fn percentile(&self, p: UnitR<f64>) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut sorted = self.samples.clone();
+        sort_floats(&mut sorted);
+        let rank = p.get() * (sorted.len() - 1) as f64;
+        let low = rank.floor() as usize;
+        let high = rank.ceil() as usize;
+        let t = rank - low as f64;
+        Some(sorted[low] + (sorted[high] - sorted[low]) * t)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_samples() {
+    let bencher = Bencher::new(BencherConfig {
+        warmup_duration: Duration::from_millis(1),
+        cool_down_duration: Duration::ZERO,
+        measuring_duration: Duration::from_millis(20),
+        collect_samples: true,
+        pollute_cache: Some(1 << 16),
+    });
+    let stats = bencher.iter(
+        || 0_u64,
+        |x| {
+            *x = core::hint::black_box(*x + 1);
+            BenchIterControl::Continue
+        },
+    );
+    assert_eq!(stats.samples.len(), stats.iterations / stats.batch_size);
+    assert_eq!(stats.iterations % stats.batch_size, 0);
+    let p = |p: f64| stats.percentile(UnitR::new(p).unwrap()).unwrap();
+    let mut prev = p(0.);
+    for i in 1..=20 {
+        let curr = p(i as f64 / 20.);
+        assert!(prev <= curr);
+        prev = curr;
+    }
+    assert_eq!(
+        p(0.),
+        stats.samples.iter().copied().fold(f64::MAX, f64::min)
+    );
+    assert_eq!(p(1.), stats.samples.iter().copied().fold(0., f64::max));
+
+    let stats = Bencher::new(BencherConfig {
+        collect_samples: false,
+        ..bencher.config.clone()
+    })
+    .iter(|| (), |()| BenchIterControl::Break);
+    assert!(stats.samples.is_empty());
+    assert_eq!(stats.percentile(UnitR::new(0.5).unwrap()), None);
+}
+#[cfg(test)]
+#[test]
+fn test_cache_polluter() {
+    let mut polluter = CachePolluter::new(1000);
+    assert_eq!(polluter.footprint(), 1024);
+    let lines = 1024 / 64;
+    assert_eq!(polluter.pollute(), lines);
+    assert_eq!(polluter.pollute(), 2 * lines);
+    assert!(polluter
+        .buf
+        .chunks(8)
+        .all(|line| line[0] == 2 && line[1..] == [0; 7]));
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -261,6 +394,16 @@ where
         &self.var
     }
 }
+impl<R: Float> MetricSource for ExpMovVar<R> {
+    fn visit(&self, visitor: &mut dyn MetricVisitor) {
+        let gauges = [("mean", &self.mean), ("var", &self.var)];
+        for (name, ema) in gauges {
+            if let Some(value) = ema.prev.and_then(|x| x.to_f64()) {
+                visitor.gauge(name, value);
+            }
+        }
+    }
+}
 #[cfg(test)]
 #[test]
 fn test_ema() {
@@ -326,6 +469,24 @@ impl<const N: usize> NearZeroHistogram<N> {
         QuartileResult::Found(value)
     }
 }
+/// The samples beyond the max value only show in the count
+impl<const N: usize> HistogramSnapshotSource for NearZeroHistogram<N> {
+    fn count(&self) -> u64 {
+        self.count as u64
+    }
+    fn buckets(&self, visit: &mut dyn FnMut(f64, u64)) {
+        for (bucket, &samples) in self.buckets.iter().enumerate() {
+            // Values are rounded to the nearest bucket
+            let upper = ((bucket as f64 + 0.5) / self.a).exp_m1();
+            visit(upper, samples);
+        }
+    }
+}
+impl<const N: usize> MetricSource for NearZeroHistogram<N> {
+    fn visit(&self, visitor: &mut dyn MetricVisitor) {
+        visitor.histogram("", self);
+    }
+}
 impl<const N: usize> Clear for NearZeroHistogram<N> {
     fn clear(&mut self) {
         self.buckets = [0; N];
@@ -0,0 +1,149 @@
+use crate::ops::{clear::Clear, matrix::Mat};
+
+/// Online sample covariance of two streams, e.g. queue depth and latency
+#[derive(Debug, Clone, Default)]
+pub struct Covariance2 {
+    n: u64,
+    mean: Mat<2, 1>,
+    /// Sums of the products of the deviations from the mean
+    co_moments: Mat<2, 2>,
+}
+impl Covariance2 {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Welford's update
+    pub fn update(&mut self, x: f64, y: f64) {
+        self.n += 1;
+        let sample = Mat::from_cols([[x, y]]);
+        let before = sample + self.mean.scale(-1.);
+        self.mean = self.mean + before.scale(1. / self.n as f64);
+        let after = sample + self.mean.scale(-1.);
+        self.co_moments = self.co_moments + before * after.transpose();
+    }
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.n
+    }
+    #[must_use]
+    pub fn mean(&self) -> Option<(f64, f64)> {
+        if self.n == 0 {
+            return None;
+        }
+        Some((self.mean[(0, 0)], self.mean[(1, 0)]))
+    }
+    /// Sample covariance matrix of `(x, y)`
+    ///
+    /// [`None`] if fewer than two samples.
+    #[must_use]
+    pub fn cov(&self) -> Option<Mat<2, 2>> {
+        if self.n < 2 {
+            return None;
+        }
+        let mut cov = self.co_moments.scale(1. / (self.n - 1) as f64);
+        // The off-diagonals only differ by rounding
+        let xy = (cov[(0, 1)] + cov[(1, 0)]) / 2.;
+        cov[(0, 1)] = xy;
+        cov[(1, 0)] = xy;
+        Some(cov)
+    }
+    #[must_use]
+    pub fn var_x(&self) -> Option<f64> {
+        self.cov().map(|cov| cov[(0, 0)])
+    }
+    #[must_use]
+    pub fn var_y(&self) -> Option<f64> {
+        self.cov().map(|cov| cov[(1, 1)])
+    }
+    /// Pearson correlation in `[-1, 1]`
+    ///
+    /// [`None`] if either stream does not vary.
+    #[must_use]
+    pub fn corr(&self) -> Option<f64> {
+        let cov = self.cov()?;
+        let denom = (cov[(0, 0)] * cov[(1, 1)]).sqrt();
+        if denom == 0. {
+            return None;
+        }
+        Some((cov[(0, 1)] / denom).clamp(-1., 1.))
+    }
+}
+impl Clear for Covariance2 {
+    fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::rng::{RngLite, SplitMix64};
+
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!(
+            (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.),
+            "{a}; {b}"
+        );
+    }
+
+    #[test]
+    fn test_hand_computed() {
+        let mut c = Covariance2::new();
+        assert_eq!(c.mean(), None);
+        c.update(1., 1.);
+        assert_eq!(c.cov(), None);
+        c.update(2., 3.);
+        c.update(3., 2.);
+        assert_eq!(c.count(), 3);
+        assert_eq!(c.mean(), Some((2., 2.)));
+        assert_close(c.var_x().unwrap(), 1.);
+        assert_close(c.var_y().unwrap(), 1.);
+        assert_close(c.cov().unwrap()[(0, 1)], 0.5);
+        assert_close(c.corr().unwrap(), 0.5);
+
+        c.clear();
+        for i in 0..4 {
+            c.update(i as f64, 7.);
+        }
+        assert_eq!(c.var_y(), Some(0.));
+        assert_eq!(c.corr(), None);
+        c.clear();
+        for i in 0..4 {
+            c.update(i as f64, -2. * i as f64);
+        }
+        assert_close(c.corr().unwrap(), -1.);
+    }
+
+    #[test]
+    fn test_two_pass_reference() {
+        let mut rng = SplitMix64::new(11);
+        let mut next = || rng.next_f64_unit().get();
+        let samples = (0..1000)
+            .map(|_| {
+                let depth = next() * 100.;
+                let latency = 5. + depth * 0.3 + next() * 20.;
+                (depth, latency)
+            })
+            .collect::<Vec<_>>();
+        let mut c = Covariance2::new();
+        for &(x, y) in &samples {
+            c.update(x, y);
+        }
+        let n = samples.len() as f64;
+        let mean_x = samples.iter().map(|s| s.0).sum::<f64>() / n;
+        let mean_y = samples.iter().map(|s| s.1).sum::<f64>() / n;
+        let sum = |f: &dyn Fn(&(f64, f64)) -> f64| samples.iter().map(f).sum::<f64>() / (n - 1.);
+        let var_x = sum(&|s| (s.0 - mean_x).powi(2));
+        let var_y = sum(&|s| (s.1 - mean_y).powi(2));
+        let cov = sum(&|s| (s.0 - mean_x) * (s.1 - mean_y));
+        let (x, y) = c.mean().unwrap();
+        assert_close(x, mean_x);
+        assert_close(y, mean_y);
+        assert_close(c.var_x().unwrap(), var_x);
+        assert_close(c.var_y().unwrap(), var_y);
+        assert_close(c.cov().unwrap()[(1, 0)], cov);
+        assert_close(c.corr().unwrap(), cov / (var_x * var_y).sqrt());
+    }
+}
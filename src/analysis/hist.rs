@@ -0,0 +1,348 @@
+use crate::ops::{clear::Clear, float::UnitR};
+
+use super::metrics::{HistogramSnapshotSource, MetricSource, MetricVisitor};
+
+/// Fixed-bucket histogram over `[min, max]` with equal-width buckets
+///
+/// Out-of-range values are clamped into the edge buckets and also counted in [`Self::underflow()`] and [`Self::overflow()`].
+#[derive(Debug, Clone, Copy)]
+pub struct LinearHistogram<const N: usize> {
+    buckets: [u64; N],
+    count: u64,
+    underflow: u64,
+    overflow: u64,
+    min: f64,
+    max: f64,
+}
+impl<const N: usize> LinearHistogram<N> {
+    /// # Panic
+    ///
+    /// If `min < max` does not hold for finite bounds
+    #[must_use]
+    pub fn new(min: f64, max: f64) -> Self {
+        const {
+            assert!(0 < N);
+        }
+        assert!(min.is_finite() && max.is_finite());
+        assert!(min < max);
+        Self {
+            buckets: [0; N],
+            count: 0,
+            underflow: 0,
+            overflow: 0,
+            min,
+            max,
+        }
+    }
+
+    /// # Panic
+    ///
+    /// If `value` is NaN
+    pub fn insert(&mut self, value: f64) {
+        assert!(!value.is_nan());
+        self.count += 1;
+        let bucket = if value < self.min {
+            self.underflow += 1;
+            0
+        } else if self.max < value {
+            self.overflow += 1;
+            N - 1
+        } else {
+            let bucket = ((value - self.min) / self.bucket_width()) as usize;
+            bucket.min(N - 1)
+        };
+        self.buckets[bucket] += 1;
+    }
+
+    /// Interpolated within the bucket holding the `p`-th sample
+    ///
+    /// Return [`None`] if there are no samples.
+    #[must_use]
+    pub fn quantile(&self, p: UnitR<f64>) -> Option<f64> {
+        let n = self.count.checked_sub(1)?;
+        let rank = (n as f64 * p.get()).round() as u64;
+        let mut below = 0;
+        for (bucket, &count) in self.buckets.iter().enumerate() {
+            if rank < below + count {
+                let (lo, hi) = self.bucket_bounds(bucket);
+                let fraction = ((rank - below) as f64 + 0.5) / count as f64;
+                return Some(lo + fraction * (hi - lo));
+            }
+            below += count;
+        }
+        unreachable!()
+    }
+
+    /// `[lower, upper)` of the bucket at `i`
+    #[must_use]
+    pub fn bucket_bounds(&self, i: usize) -> (f64, f64) {
+        assert!(i < N);
+        let width = self.bucket_width();
+        let lower = self.min + width * i as f64;
+        let upper = if i == N - 1 {
+            self.max
+        } else {
+            self.min + width * (i + 1) as f64
+        };
+        (lower, upper)
+    }
+    fn bucket_width(&self) -> f64 {
+        (self.max - self.min) / N as f64
+    }
+
+    /// # Panic
+    ///
+    /// If the ranges differ
+    pub fn merge(&mut self, other: &Self) {
+        assert_eq!((self.min, self.max), (other.min, other.max));
+        for (a, b) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+            *a += b;
+        }
+        self.count += other.count;
+        self.underflow += other.underflow;
+        self.overflow += other.overflow;
+    }
+
+    #[must_use]
+    pub fn buckets(&self) -> &[u64; N] {
+        &self.buckets
+    }
+    /// Number of all samples including the out-of-range ones
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+    /// Number of samples below `min`
+    #[must_use]
+    pub fn underflow(&self) -> u64 {
+        self.underflow
+    }
+    /// Number of samples above `max`
+    #[must_use]
+    pub fn overflow(&self) -> u64 {
+        self.overflow
+    }
+    #[must_use]
+    pub fn min(&self) -> f64 {
+        self.min
+    }
+    #[must_use]
+    pub fn max(&self) -> f64 {
+        self.max
+    }
+}
+/// The samples above `max` only show in the count
+impl<const N: usize> HistogramSnapshotSource for LinearHistogram<N> {
+    fn count(&self) -> u64 {
+        self.count
+    }
+    fn buckets(&self, visit: &mut dyn FnMut(f64, u64)) {
+        for (i, &samples) in self.buckets.iter().enumerate() {
+            let samples = if i == N - 1 {
+                samples - self.overflow
+            } else {
+                samples
+            };
+            visit(self.bucket_bounds(i).1, samples);
+        }
+    }
+}
+impl<const N: usize> MetricSource for LinearHistogram<N> {
+    fn visit(&self, visitor: &mut dyn MetricVisitor) {
+        visitor.histogram("", self);
+    }
+}
+impl<const N: usize> Clear for LinearHistogram<N> {
+    fn clear(&mut self) {
+        self.buckets = [0; N];
+        self.count = 0;
+        self.underflow = 0;
+        self.overflow = 0;
+    }
+}
+
+/// Fixed-bucket histogram over `[min, max]` with buckets of equal width in log scale
+///
+/// For positive values spanning decades; otherwise the same as [`LinearHistogram`].
+#[derive(Debug, Clone, Copy)]
+pub struct LogHistogram<const N: usize> {
+    ln: LinearHistogram<N>,
+}
+impl<const N: usize> LogHistogram<N> {
+    /// # Panic
+    ///
+    /// If `0 < min < max` does not hold for finite bounds
+    #[must_use]
+    pub fn new(min: f64, max: f64) -> Self {
+        assert!(0. < min);
+        Self {
+            ln: LinearHistogram::new(min.ln(), max.ln()),
+        }
+    }
+
+    /// Non-positive values count as underflows
+    ///
+    /// # Panic
+    ///
+    /// If `value` is NaN
+    pub fn insert(&mut self, value: f64) {
+        assert!(!value.is_nan());
+        let ln = if value <= 0. {
+            f64::NEG_INFINITY
+        } else {
+            value.ln()
+        };
+        self.ln.insert(ln);
+    }
+
+    /// Geometrically interpolated within the bucket holding the `p`-th sample
+    ///
+    /// Return [`None`] if there are no samples.
+    #[must_use]
+    pub fn quantile(&self, p: UnitR<f64>) -> Option<f64> {
+        self.ln.quantile(p).map(f64::exp)
+    }
+
+    /// `[lower, upper)` of the bucket at `i`
+    #[must_use]
+    pub fn bucket_bounds(&self, i: usize) -> (f64, f64) {
+        let (lower, upper) = self.ln.bucket_bounds(i);
+        (lower.exp(), upper.exp())
+    }
+
+    /// # Panic
+    ///
+    /// If the ranges differ
+    pub fn merge(&mut self, other: &Self) {
+        self.ln.merge(&other.ln);
+    }
+
+    #[must_use]
+    pub fn buckets(&self) -> &[u64; N] {
+        self.ln.buckets()
+    }
+    /// Number of all samples including the out-of-range ones
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.ln.count()
+    }
+    /// Number of samples below `min`
+    #[must_use]
+    pub fn underflow(&self) -> u64 {
+        self.ln.underflow()
+    }
+    /// Number of samples above `max`
+    #[must_use]
+    pub fn overflow(&self) -> u64 {
+        self.ln.overflow()
+    }
+}
+impl<const N: usize> HistogramSnapshotSource for LogHistogram<N> {
+    fn count(&self) -> u64 {
+        self.ln.count()
+    }
+    fn buckets(&self, visit: &mut dyn FnMut(f64, u64)) {
+        HistogramSnapshotSource::buckets(&self.ln, &mut |upper: f64, samples| {
+            visit(upper.exp(), samples)
+        });
+    }
+}
+impl<const N: usize> MetricSource for LogHistogram<N> {
+    fn visit(&self, visitor: &mut dyn MetricVisitor) {
+        visitor.histogram("", self);
+    }
+}
+impl<const N: usize> Clear for LogHistogram<N> {
+    fn clear(&mut self) {
+        self.ln.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::rng::{RngLite, SplitMix64};
+
+    use super::*;
+
+    const SAMPLES: usize = 1 << 12;
+    const PS: [f64; 7] = [0., 0.01, 0.25, 0.5, 0.75, 0.99, 1.];
+
+    /// Box-Muller
+    fn next_normal(rng: &mut SplitMix64) -> f64 {
+        // In (0, 1] so that the log stays finite
+        let u = rng.next_f64_unit().complement().get();
+        let v = rng.next_f64_unit().get();
+        (-2. * u.ln()).sqrt() * (core::f64::consts::TAU * v).cos()
+    }
+
+    fn reference(sorted: &[f64], p: f64) -> f64 {
+        let rank = ((sorted.len() - 1) as f64 * p).round() as usize;
+        sorted[rank]
+    }
+
+    #[test]
+    fn test_linear_uniform() {
+        let mut rng = SplitMix64::new(1);
+        let mut hist = LinearHistogram::<64>::new(-5., 5.);
+        assert!(hist.quantile(UnitR::new(0.5).unwrap()).is_none());
+        let mut samples = (0..SAMPLES)
+            .map(|_| rng.next_f64_unit().get() * 10. - 5.)
+            .collect::<Vec<_>>();
+        samples.iter().for_each(|&x| hist.insert(x));
+        samples.sort_by(f64::total_cmp);
+        let width = 10. / 64.;
+        for p in PS {
+            let q = hist.quantile(UnitR::new(p).unwrap()).unwrap();
+            assert!((q - reference(&samples, p)).abs() <= width);
+        }
+        assert_eq!(hist.count(), SAMPLES as u64);
+        assert_eq!((hist.underflow(), hist.overflow()), (0, 0));
+    }
+
+    #[test]
+    fn test_log_normal() {
+        let mut rng = SplitMix64::new(2);
+        let mut hist = LogHistogram::<128>::new(1e-3, 1e3);
+        let mut samples = (0..SAMPLES)
+            .map(|_| (next_normal(&mut rng) * 1.5).exp())
+            .collect::<Vec<_>>();
+        samples.iter().for_each(|&x| hist.insert(x));
+        samples.sort_by(f64::total_cmp);
+        let ratio = (1e6_f64).powf(1. / 128.);
+        for p in PS {
+            let q = hist.quantile(UnitR::new(p).unwrap()).unwrap();
+            let expected = reference(&samples, p);
+            assert!(expected / ratio <= q && q <= expected * ratio);
+        }
+        let (lower, upper) = hist.bucket_bounds(64);
+        assert!((lower - 1.).abs() < 1e-9);
+        assert!((upper / lower - ratio).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let mut hist = LinearHistogram::<4>::new(0., 4.);
+        for x in [-1., 0., 3.5, 4., 4.5, f64::INFINITY, f64::NEG_INFINITY] {
+            hist.insert(x);
+        }
+        assert_eq!((hist.underflow(), hist.overflow()), (2, 2));
+        assert_eq!(hist.buckets(), &[3, 0, 0, 4]);
+        assert_eq!(hist.bucket_bounds(3), (3., 4.));
+
+        let mut other = LinearHistogram::<4>::new(0., 4.);
+        other.insert(1.5);
+        hist.merge(&other);
+        assert_eq!(hist.count(), 8);
+        assert_eq!(hist.buckets(), &[3, 1, 0, 4]);
+        hist.clear();
+        assert_eq!(hist.count(), 0);
+        assert_eq!((hist.underflow(), hist.overflow()), (0, 0));
+
+        let mut hist = LogHistogram::<4>::new(1., 1e4);
+        for x in [-1., 0., 0.5, 1., 1e5] {
+            hist.insert(x);
+        }
+        assert_eq!((hist.underflow(), hist.overflow()), (3, 1));
+        assert_eq!(hist.buckets(), &[4, 0, 0, 1]);
+    }
+}
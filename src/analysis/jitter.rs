@@ -0,0 +1,234 @@
+use core::{num::NonZeroUsize, time::Duration};
+use std::time::Instant;
+
+use crate::ops::clear::Clear;
+
+use super::{
+    bench::ExpMovAvg,
+    metrics::{MetricSource, MetricVisitor},
+};
+
+/// RFC 3550 smoothing factor
+const JITTER_ALPHA: f64 = 1. / 16.;
+
+/// Timing statistics of nominally periodic events
+#[derive(Debug, Clone)]
+pub struct JitterStats {
+    periods: NonZeroUsize,
+    prev_time: Option<Instant>,
+    prev_interval: Option<f64>,
+    interval: ExpMovAvg<f64>,
+    jitter: ExpMovAvg<f64>,
+    window_max_deviation: Option<f64>,
+    window_len: usize,
+    autocorrelation: LagOneAutocorrelation,
+}
+impl JitterStats {
+    /// `periods` is both the smoothing length of the inter-arrival EMA and the length of the max deviation window
+    #[must_use]
+    pub fn new(periods: NonZeroUsize) -> Self {
+        Self {
+            periods,
+            prev_time: None,
+            prev_interval: None,
+            interval: ExpMovAvg::from_periods(periods),
+            jitter: ExpMovAvg::from_alpha(JITTER_ALPHA),
+            window_max_deviation: None,
+            window_len: 0,
+            autocorrelation: LagOneAutocorrelation::new(),
+        }
+    }
+
+    pub fn update(&mut self, t: Instant) {
+        let Some(prev_time) = self.prev_time.replace(t) else {
+            return;
+        };
+        let interval = t.saturating_duration_since(prev_time).as_secs_f64();
+        if let Some(prev_interval) = self.prev_interval {
+            self.jitter.update((interval - prev_interval).abs());
+        }
+        self.prev_interval = Some(interval);
+        if let Some(mean) = self.interval.get() {
+            if self.window_len == self.periods.get() {
+                self.window_len = 0;
+                self.window_max_deviation = None;
+            }
+            let deviation = (interval - mean).abs();
+            let max = self.window_max_deviation.unwrap_or(0.).max(deviation);
+            self.window_max_deviation = Some(max);
+            self.window_len += 1;
+        }
+        self.interval.update(interval);
+        self.autocorrelation.update(interval);
+    }
+
+    #[must_use]
+    pub fn snapshot(&self) -> JitterSnapshot {
+        JitterSnapshot {
+            interval: self.interval.get().map(Duration::from_secs_f64),
+            jitter: self.jitter.get().map(Duration::from_secs_f64),
+            max_deviation: self.window_max_deviation.map(Duration::from_secs_f64),
+            autocorrelation: self.autocorrelation.get(),
+        }
+    }
+}
+/// Only the stats known so far
+impl MetricSource for JitterStats {
+    fn visit(&self, visitor: &mut dyn MetricVisitor) {
+        let snapshot = self.snapshot();
+        let durations = [
+            ("interval_seconds", snapshot.interval),
+            ("jitter_seconds", snapshot.jitter),
+            ("max_deviation_seconds", snapshot.max_deviation),
+        ];
+        for (name, duration) in durations {
+            if let Some(duration) = duration {
+                visitor.gauge(name, duration.as_secs_f64());
+            }
+        }
+        if let Some(autocorrelation) = snapshot.autocorrelation {
+            visitor.gauge("autocorrelation", autocorrelation);
+        }
+    }
+}
+impl Clear for JitterStats {
+    fn clear(&mut self) {
+        *self = Self::new(self.periods);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JitterSnapshot {
+    /// EMA of the inter-arrival times
+    pub interval: Option<Duration>,
+    /// Smoothed difference between consecutive inter-arrival times
+    pub jitter: Option<Duration>,
+    /// Max distance between an inter-arrival time and the EMA in the current window
+    pub max_deviation: Option<Duration>,
+    /// Lag-1 autocorrelation of the inter-arrival times in `[-1, 1]`
+    ///
+    /// [`None`] if the inter-arrival times do not vary.
+    pub autocorrelation: Option<f64>,
+}
+
+/// Centered on the first sample to keep the sums small
+#[derive(Debug, Clone, Copy)]
+struct LagOneAutocorrelation {
+    center: Option<f64>,
+    prev: Option<f64>,
+    first: f64,
+    n: f64,
+    sum: f64,
+    sum_of_squared: f64,
+    sum_of_lagged_products: f64,
+}
+impl LagOneAutocorrelation {
+    const fn new() -> Self {
+        Self {
+            center: None,
+            prev: None,
+            first: 0.,
+            n: 0.,
+            sum: 0.,
+            sum_of_squared: 0.,
+            sum_of_lagged_products: 0.,
+        }
+    }
+    fn update(&mut self, x: f64) {
+        let center = *self.center.get_or_insert(x);
+        let x = x - center;
+        if let Some(prev) = self.prev {
+            self.sum_of_lagged_products += prev * x;
+        } else {
+            self.first = x;
+        }
+        self.prev = Some(x);
+        self.n += 1.;
+        self.sum += x;
+        self.sum_of_squared += x * x;
+    }
+    fn get(&self) -> Option<f64> {
+        let last = self.prev?;
+        let mean = self.sum / self.n;
+        let denominator = self.sum_of_squared - self.n * mean * mean;
+        if denominator <= f64::EPSILON * self.sum_of_squared.max(f64::MIN_POSITIVE) {
+            return None;
+        }
+        let sum_of_currents = self.sum - self.first;
+        let sum_of_prevs = self.sum - last;
+        let numerator = self.sum_of_lagged_products - mean * (sum_of_currents + sum_of_prevs)
+            + (self.n - 1.) * mean * mean;
+        Some(numerator / denominator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::rng::{RngLite, SplitMix64};
+
+    use super::*;
+
+    const PERIOD: Duration = Duration::from_millis(10);
+    const N: usize = 1 << 10;
+
+    fn feed(intervals: impl Iterator<Item = Duration>) -> JitterSnapshot {
+        let mut stats = JitterStats::new(NonZeroUsize::new(16).unwrap());
+        let mut t = Instant::now();
+        stats.update(t);
+        for interval in intervals {
+            t += interval;
+            stats.update(t);
+        }
+        stats.snapshot()
+    }
+
+    #[test]
+    fn test_perfect_period() {
+        let s = feed(core::iter::repeat_n(PERIOD, N));
+        assert_eq!(s.interval.unwrap().as_micros(), PERIOD.as_micros());
+        assert!(s.jitter.unwrap() < Duration::from_micros(1));
+        assert!(s.max_deviation.unwrap() < Duration::from_micros(1));
+        assert!(s.autocorrelation.is_none());
+    }
+
+    #[test]
+    fn test_alternating() {
+        let x = Duration::from_millis(2);
+        let s = feed((0..N).map(|i| if i % 2 == 0 { PERIOD + x } else { PERIOD - x }));
+        assert!(s.interval.unwrap().abs_diff(PERIOD) < x / 4);
+        let jitter = s.jitter.unwrap().as_secs_f64();
+        assert!((jitter - (2 * x).as_secs_f64()).abs() < 1e-4, "{jitter}");
+        assert!(x.as_secs_f64() * 0.9 < s.max_deviation.unwrap().as_secs_f64());
+        assert!(s.autocorrelation.unwrap() < -0.99);
+    }
+
+    #[test]
+    fn test_random_walk() {
+        let mut rng = SplitMix64::new(1);
+        let mut interval = PERIOD.as_secs_f64();
+        let s = feed((0..N).map(|_| {
+            let step = if rng.next_usize_below(2) == 0 {
+                1e-5
+            } else {
+                -1e-5
+            };
+            interval = (interval + step).max(1e-4);
+            Duration::from_secs_f64(interval)
+        }));
+        assert!(0.9 < s.autocorrelation.unwrap());
+        assert!(s.jitter.unwrap() < Duration::from_micros(20));
+
+        let mut stats = JitterStats::new(NonZeroUsize::new(16).unwrap());
+        stats.update(Instant::now());
+        stats.clear();
+        assert_eq!(
+            stats.snapshot(),
+            JitterSnapshot {
+                interval: None,
+                jitter: None,
+                max_deviation: None,
+                autocorrelation: None,
+            }
+        );
+    }
+}
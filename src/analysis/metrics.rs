@@ -0,0 +1,231 @@
+use core::fmt::Write;
+
+/// Publishes its stats without depending on any metrics library
+pub trait MetricSource {
+    fn visit(&self, visitor: &mut dyn MetricVisitor);
+}
+/// Takes the metrics of a [`MetricSource`]
+///
+/// An empty `name` stands for the source itself, e.g. a bare histogram.
+pub trait MetricVisitor {
+    /// Only goes up
+    fn counter(&mut self, name: &str, value: u64);
+    fn gauge(&mut self, name: &str, value: f64);
+    fn histogram(&mut self, name: &str, histogram: &dyn HistogramSnapshotSource);
+}
+/// Bucket counts of a histogram as seen by the exporters
+pub trait HistogramSnapshotSource {
+    /// Number of all samples including the ones above the last bucket
+    fn count(&self) -> u64;
+    /// Visit `(inclusive upper bound, samples)` of each bucket in ascending order
+    ///
+    /// The samples are per bucket, not cumulative.
+    fn buckets(&self, visit: &mut dyn FnMut(f64, u64));
+}
+
+/// Put `prefix` and `_` in front of every name
+#[derive(Debug)]
+pub struct PrefixedVisitor<'a, V: ?Sized> {
+    prefix: &'a str,
+    inner: &'a mut V,
+    name: String,
+}
+impl<'a, V: ?Sized> PrefixedVisitor<'a, V> {
+    pub fn new(prefix: &'a str, inner: &'a mut V) -> Self {
+        Self {
+            prefix,
+            inner,
+            name: String::new(),
+        }
+    }
+    fn join(&mut self, name: &str) {
+        self.name.clear();
+        self.name.push_str(self.prefix);
+        if !self.prefix.is_empty() && !name.is_empty() {
+            self.name.push('_');
+        }
+        self.name.push_str(name);
+    }
+}
+impl<V: MetricVisitor + ?Sized> MetricVisitor for PrefixedVisitor<'_, V> {
+    fn counter(&mut self, name: &str, value: u64) {
+        self.join(name);
+        self.inner.counter(&self.name, value);
+    }
+    fn gauge(&mut self, name: &str, value: f64) {
+        self.join(name);
+        self.inner.gauge(&self.name, value);
+    }
+    fn histogram(&mut self, name: &str, histogram: &dyn HistogramSnapshotSource) {
+        self.join(name);
+        self.inner.histogram(&self.name, histogram);
+    }
+}
+
+/// Renders the metrics in the Prometheus text format into a [`String`]
+///
+/// Histograms come without `_sum` since the sources keep none.
+#[derive(Debug)]
+pub struct TextExporter<'a> {
+    out: &'a mut String,
+}
+impl<'a> TextExporter<'a> {
+    pub fn new(out: &'a mut String) -> Self {
+        Self { out }
+    }
+}
+impl MetricVisitor for TextExporter<'_> {
+    fn counter(&mut self, name: &str, value: u64) {
+        writeln!(self.out, "# TYPE {name} counter").unwrap();
+        writeln!(self.out, "{name} {value}").unwrap();
+    }
+    fn gauge(&mut self, name: &str, value: f64) {
+        writeln!(self.out, "# TYPE {name} gauge").unwrap();
+        writeln!(self.out, "{name} {}", PromFloat(value)).unwrap();
+    }
+    fn histogram(&mut self, name: &str, histogram: &dyn HistogramSnapshotSource) {
+        writeln!(self.out, "# TYPE {name} histogram").unwrap();
+        let mut cumulative = 0;
+        histogram.buckets(&mut |upper, samples| {
+            cumulative += samples;
+            let le = PromFloat(upper);
+            writeln!(self.out, "{name}_bucket{{le=\"{le}\"}} {cumulative}").unwrap();
+        });
+        let count = histogram.count();
+        writeln!(self.out, "{name}_bucket{{le=\"+Inf\"}} {count}").unwrap();
+        writeln!(self.out, "{name}_count {count}").unwrap();
+    }
+}
+/// Spells the non-finite values the Prometheus way
+struct PromFloat(f64);
+impl core::fmt::Display for PromFloat {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.0 {
+            x if x.is_nan() => write!(f, "NaN"),
+            f64::INFINITY => write!(f, "+Inf"),
+            f64::NEG_INFINITY => write!(f, "-Inf"),
+            x => write!(f, "{x}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{num::NonZeroUsize, time::Duration};
+
+    use crate::{
+        analysis::{
+            bench::{ExpMovVar, NearZeroHistogram},
+            hist::LinearHistogram,
+        },
+        ops::float::{NonNegR, PosR},
+        queue::{
+            grow_queue::GrowQueue,
+            seq_queue::WasteReason,
+            waste::{CountWaste, WasteSink},
+        },
+        time::{clock::ManualClock, stopwatch::ElapsedStopwatch},
+    };
+
+    use super::*;
+
+    fn render(sources: &[(&str, &dyn MetricSource)]) -> String {
+        let mut out = String::new();
+        let mut exporter = TextExporter::new(&mut out);
+        for (prefix, source) in sources {
+            source.visit(&mut PrefixedVisitor::new(prefix, &mut exporter));
+        }
+        out
+    }
+
+    #[test]
+    fn test_text_format() {
+        let mut ema = ExpMovVar::from_alpha(0.5);
+        ema.update(1.);
+        ema.update(3.);
+        let mut hist = LinearHistogram::<2>::new(0., 2.);
+        for x in [0.5, 1.5, 1.5, 9.] {
+            hist.insert(x);
+        }
+        let mut waste = CountWaste::new();
+        waste.waste(WasteReason::Stale, (0, ()));
+        waste.waste(WasteReason::Duplicate, (1, ()));
+        waste.waste(WasteReason::Stale, (2, ()));
+        let mut queue = GrowQueue::new();
+        queue.enqueue(1);
+        queue.enqueue(2);
+        queue.dequeue();
+
+        let text = render(&[
+            ("rtt", &ema),
+            ("size", &hist),
+            ("seq_waste", &waste),
+            ("inbox", &queue),
+        ]);
+        let expected = "\
+# TYPE rtt_mean gauge
+rtt_mean 2
+# TYPE rtt_var gauge
+rtt_var 4
+# TYPE size histogram
+size_bucket{le=\"1\"} 1
+size_bucket{le=\"2\"} 3
+size_bucket{le=\"+Inf\"} 4
+size_count 4
+# TYPE seq_waste_stale_total counter
+seq_waste_stale_total 2
+# TYPE seq_waste_out_of_window_total counter
+seq_waste_out_of_window_total 0
+# TYPE seq_waste_duplicate_total counter
+seq_waste_duplicate_total 1
+# TYPE seq_waste_stale_by_set_next_total counter
+seq_waste_stale_by_set_next_total 0
+# TYPE seq_waste_replaced_duplicate_head_total counter
+seq_waste_replaced_duplicate_head_total 0
+# TYPE inbox_len gauge
+inbox_len 1
+# TYPE inbox_high_water_mark gauge
+inbox_high_water_mark 2
+";
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn test_time_sources() {
+        let clock = ManualClock::new();
+        let mut watch = ElapsedStopwatch::with_clock(Duration::from_secs(2), clock.clone());
+        watch.stopwatch_mut().start();
+        clock.advance(Duration::from_millis(500));
+        let mut hist = NearZeroHistogram::<4>::new(PosR::new(10.).unwrap());
+        hist.insert(NonNegR::new(0.).unwrap());
+        hist.insert(NonNegR::new(100.).unwrap());
+        let fresh = ExpMovVar::<f64>::from_periods(NonZeroUsize::new(2).unwrap());
+
+        let text = render(&[("", &watch), ("latency", &hist), ("empty", &fresh)]);
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some("# TYPE elapsed_seconds gauge"));
+        assert_eq!(lines.next(), Some("elapsed_seconds 0.5"));
+        assert_eq!(lines.next(), Some("# TYPE watermark_seconds gauge"));
+        assert_eq!(lines.next(), Some("watermark_seconds 2"));
+        assert_eq!(lines.next(), Some("# TYPE latency histogram"));
+        let buckets = lines.by_ref().take(4).collect::<Vec<_>>();
+        assert!(buckets[0].ends_with("} 1"), "{buckets:?}");
+        assert!(buckets[3].ends_with("} 1"), "{buckets:?}");
+        assert_eq!(lines.next(), Some("latency_bucket{le=\"+Inf\"} 2"));
+        assert_eq!(lines.next(), Some("latency_count 2"));
+        // Nothing to report before the first update
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn test_non_finite() {
+        let mut out = String::new();
+        let mut exporter = TextExporter::new(&mut out);
+        exporter.gauge("a", f64::NAN);
+        exporter.gauge("b", f64::NEG_INFINITY);
+        exporter.gauge("c", f64::INFINITY);
+        assert!(out.contains("a NaN\n"));
+        assert!(out.contains("b -Inf\n"));
+        assert!(out.contains("c +Inf\n"));
+    }
+}
@@ -1,2 +1,8 @@
 pub mod bench;
+pub mod cov;
 pub mod fail;
+pub mod hist;
+pub mod jitter;
+pub mod metrics;
+pub mod rng;
+pub mod sliding_min_max;
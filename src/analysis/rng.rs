@@ -0,0 +1,283 @@
+use crate::ops::float::UnitR;
+
+/// Minimal deterministic random number generator for tests, benches and samplers
+///
+/// Not for cryptography.
+pub trait RngLite {
+    fn next_u64(&mut self) -> u64;
+    /// Uniform in `[0, n)` without the modulo bias
+    ///
+    /// # Panic
+    ///
+    /// `n` is zero
+    fn next_usize_below(&mut self, n: usize) -> usize {
+        assert_ne!(n, 0);
+        let n = n as u64;
+        // `2^64 % n`: the draws below it would make the low residues more likely
+        let threshold = n.wrapping_neg() % n;
+        loop {
+            let x = self.next_u64();
+            if threshold <= x {
+                return (x % n) as usize;
+            }
+        }
+    }
+    fn fill_bytes(&mut self, bytes: &mut [u8]) {
+        for chunk in bytes.chunks_mut(8) {
+            let x = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&x[..chunk.len()]);
+        }
+    }
+    /// Uniform in `[0, 1)` on a grid of `2^-53`
+    fn next_f64_unit(&mut self) -> UnitR<f64> {
+        let x = (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64;
+        UnitR::new(x).unwrap()
+    }
+    /// Fisher-Yates
+    fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            let j = self.next_usize_below(i + 1);
+            slice.swap(i, j);
+        }
+    }
+}
+
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// [SplitMix64](https://doi.org/10.1145/2714064.2660195)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitMix64 {
+    state: u64,
+    /// Odd; each stream walks the states with its own step
+    gamma: u64,
+}
+impl SplitMix64 {
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            state: seed,
+            gamma: GOLDEN_GAMMA,
+        }
+    }
+    /// Fork off a stream with a distinct gamma, which is statistically independent of the rest of `self`
+    #[must_use]
+    pub fn split(&mut self) -> Self {
+        let state = mix64(self.next_state());
+        let gamma = mix_gamma(self.next_state());
+        Self { state, gamma }
+    }
+    fn next_state(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(self.gamma);
+        self.state
+    }
+}
+impl RngLite for SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        let z = self.next_state();
+        mix64(z)
+    }
+}
+/// Stafford's variant 13
+#[must_use]
+const fn mix64(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+/// An odd gamma with enough bit transitions to walk the states well
+#[must_use]
+const fn mix_gamma(z: u64) -> u64 {
+    let mut z = (z ^ (z >> 33)).wrapping_mul(0xff51_afd7_ed55_8ccd);
+    z = (z ^ (z >> 33)).wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    z = (z ^ (z >> 33)) | 1;
+    if (z ^ (z >> 1)).count_ones() < 24 {
+        z ^= 0xaaaa_aaaa_aaaa_aaaa;
+    }
+    z
+}
+
+/// [xorshift128+](https://doi.org/10.1016/j.cam.2016.11.006) with the state seeded by [`SplitMix64`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XorShift128Plus {
+    s0: u64,
+    s1: u64,
+}
+impl XorShift128Plus {
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        let mut seeder = SplitMix64::new(seed);
+        let s0 = seeder.next_u64();
+        let s1 = seeder.next_u64();
+        // The all-zero state is a fixed point
+        let s1 = if s0 | s1 == 0 { GOLDEN_GAMMA } else { s1 };
+        Self { s0, s1 }
+    }
+    /// Hand out the current stream and skip `self` `2^64` draws ahead
+    ///
+    /// The streams do not overlap as long as each draws fewer than `2^64` numbers.
+    #[must_use]
+    pub fn split(&mut self) -> Self {
+        let child = self.clone();
+        self.jump();
+        child
+    }
+    fn jump(&mut self) {
+        const JUMP: [u64; 2] = [0x8a5c_d789_635d_2dff, 0x121f_d215_5c47_2f96];
+        let (mut s0, mut s1) = (0, 0);
+        for word in JUMP {
+            for bit in 0..u64::BITS {
+                if word & (1 << bit) != 0 {
+                    s0 ^= self.s0;
+                    s1 ^= self.s1;
+                }
+                self.next_u64();
+            }
+        }
+        self.s0 = s0;
+        self.s1 = s1;
+    }
+}
+impl RngLite for XorShift128Plus {
+    fn next_u64(&mut self) -> u64 {
+        let mut s1 = self.s0;
+        let s0 = self.s1;
+        let out = s0.wrapping_add(s1);
+        self.s0 = s0;
+        // The shifts of the reference implementation, which [`Self::jump()`] is computed for
+        s1 ^= s1 << 23;
+        self.s1 = s1 ^ s0 ^ (s1 >> 18) ^ (s0 >> 5);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DRAWS: usize = 1 << 20;
+
+    fn assert_deterministic<R: RngLite + Clone>(rng: R) {
+        let mut a = rng.clone();
+        let mut b = rng;
+        for _ in 0..1 << 10 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        let (mut x, mut y) = ([0; 13], [0; 13]);
+        a.fill_bytes(&mut x);
+        b.fill_bytes(&mut y);
+        assert_eq!(x, y);
+    }
+    fn assert_unit_moments(rng: &mut impl RngLite) {
+        let (mut sum, mut sum_sq) = (0., 0.);
+        for _ in 0..DRAWS {
+            let x = rng.next_f64_unit().get();
+            assert!(x < 1.);
+            sum += x;
+            sum_sq += x * x;
+        }
+        let mean = sum / DRAWS as f64;
+        let var = sum_sq / DRAWS as f64 - mean * mean;
+        assert!((mean - 0.5).abs() < 0.005, "{mean}");
+        assert!((var - 1. / 12.).abs() < 0.002, "{var}");
+    }
+    fn assert_no_short_cycle<R: RngLite + Clone + PartialEq>(rng: &mut R) {
+        let start = rng.clone();
+        for _ in 0..DRAWS {
+            rng.next_u64();
+            assert!(*rng != start);
+        }
+    }
+
+    #[test]
+    fn test_split_mix() {
+        assert_deterministic(SplitMix64::new(7));
+        assert_unit_moments(&mut SplitMix64::new(7));
+        assert_no_short_cycle(&mut SplitMix64::new(7));
+        // Reference values of the original algorithm with seed 0
+        let mut rng = SplitMix64::new(0);
+        assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(rng.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+
+        let mut parent = SplitMix64::new(7);
+        let mut child = parent.split();
+        assert_ne!(child.gamma, parent.gamma);
+        assert_eq!(child.gamma % 2, 1);
+        assert_deterministic(child.clone());
+        assert_unit_moments(&mut child);
+        assert_ne!(parent.next_u64(), child.next_u64());
+    }
+
+    #[test]
+    fn test_xor_shift() {
+        assert_deterministic(XorShift128Plus::new(7));
+        assert_unit_moments(&mut XorShift128Plus::new(7));
+        assert_no_short_cycle(&mut XorShift128Plus::new(7));
+        assert_ne!(XorShift128Plus::new(7), XorShift128Plus::new(8));
+
+        let mut parent = XorShift128Plus::new(7);
+        let reference = parent.clone();
+        let mut child = parent.split();
+        assert_eq!(child, reference);
+        assert_ne!(parent, reference);
+        let mut a = vec![];
+        let mut b = vec![];
+        for _ in 0..1 << 10 {
+            a.push(parent.next_u64());
+            b.push(child.next_u64());
+        }
+        assert!(a.iter().all(|x| !b.contains(x)));
+    }
+
+    #[test]
+    fn test_xor_shift_jump() {
+        // The step is linear over GF(2) on the 128 bits of the state, so `2^64` steps are its matrix squared 64 times
+        type Matrix = [u128; 128];
+        let to_bits = |rng: &XorShift128Plus| u128::from(rng.s0) | (u128::from(rng.s1) << 64);
+        let from_bits = |bits: u128| XorShift128Plus {
+            s0: bits as u64,
+            s1: (bits >> 64) as u64,
+        };
+        let apply = |m: &Matrix, bits: u128| {
+            (0..128)
+                .filter(|i| bits & (1 << i) != 0)
+                .fold(0, |acc, i| acc ^ m[i])
+        };
+        let mut step: Matrix = core::array::from_fn(|i| {
+            let mut rng = from_bits(1 << i);
+            rng.next_u64();
+            to_bits(&rng)
+        });
+        for _ in 0..64 {
+            step = step.map(|column| apply(&step, column));
+        }
+        let mut rng = XorShift128Plus::new(7);
+        let stepped = apply(&step, to_bits(&rng));
+        rng.jump();
+        assert_eq!(to_bits(&rng), stepped);
+    }
+
+    #[test]
+    fn test_below() {
+        let mut rng = SplitMix64::new(1);
+        let mut hits = [0_usize; 6];
+        for _ in 0..6 << 10 {
+            hits[rng.next_usize_below(hits.len())] += 1;
+        }
+        assert!(hits.iter().all(|&n| (700..1350).contains(&n)), "{hits:?}");
+        assert_eq!(rng.next_usize_below(1), 0);
+        let big = usize::MAX / 3 * 2;
+        assert!((0..64).all(|_| rng.next_usize_below(big) < big));
+
+        let mut order = (0..64).collect::<Vec<_>>();
+        rng.shuffle(&mut order);
+        assert_ne!(order, (0..64).collect::<Vec<_>>());
+        order.sort_unstable();
+        assert_eq!(order, (0..64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_below_zero() {
+        SplitMix64::new(0).next_usize_below(0);
+    }
+}
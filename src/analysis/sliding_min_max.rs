@@ -0,0 +1,101 @@
+use core::num::NonZeroUsize;
+
+use crate::{
+    ops::{clear::Clear, len::Len},
+    queue::cap_deque::CapVecDeque,
+};
+
+/// Min and max of the last `window` values in amortized O(1) per [`Self::push()`]
+#[derive(Debug)]
+pub struct SlidingMinMax<T> {
+    window: usize,
+    next: usize,
+    /// Ascending values tagged with their positions
+    min: CapVecDeque<(usize, T)>,
+    /// Descending values tagged with their positions
+    max: CapVecDeque<(usize, T)>,
+}
+impl<T> SlidingMinMax<T>
+where
+    T: Ord + Clone,
+{
+    #[must_use]
+    pub fn new(window: NonZeroUsize) -> Self {
+        Self {
+            window: window.get(),
+            next: 0,
+            min: CapVecDeque::new_vec(window.get()),
+            max: CapVecDeque::new_vec(window.get()),
+        }
+    }
+
+    pub fn push(&mut self, value: T) {
+        let position = self.next;
+        self.next = self.next.wrapping_add(1);
+        let window = self.window;
+        let is_expired = |p: usize| window <= position.wrapping_sub(p);
+        for deque in [&mut self.min, &mut self.max] {
+            while deque.front().is_some_and(|(p, _)| is_expired(*p)) {
+                deque.pop_front();
+            }
+        }
+        while self.min.back().is_some_and(|(_, v)| value <= *v) {
+            self.min.pop_back();
+        }
+        while self.max.back().is_some_and(|(_, v)| *v <= value) {
+            self.max.pop_back();
+        }
+        assert!(self.min.push_back((position, value.clone())).is_none());
+        assert!(self.max.push_back((position, value)).is_none());
+    }
+
+    #[must_use]
+    pub fn min(&self) -> Option<&T> {
+        self.min.front().map(|(_, v)| v)
+    }
+    #[must_use]
+    pub fn max(&self) -> Option<&T> {
+        self.max.front().map(|(_, v)| v)
+    }
+}
+impl<T> Clear for SlidingMinMax<T> {
+    fn clear(&mut self) {
+        self.next = 0;
+        self.min.clear();
+        self.max.clear();
+    }
+}
+/// Number of values currently in the window
+impl<T> Len for SlidingMinMax<T> {
+    fn len(&self) -> usize {
+        self.next.min(self.window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::rng::{RngLite, SplitMix64};
+
+    use super::*;
+
+    #[test]
+    fn test_against_brute_force() {
+        for window in [1, 2, 3, 7, 16] {
+            let mut s = SlidingMinMax::new(NonZeroUsize::new(window).unwrap());
+            assert_eq!((s.min(), s.max()), (None, None));
+            let mut values = vec![];
+            let mut rng = SplitMix64::new(3);
+            for _ in 0..1 << 10 {
+                let value = rng.next_usize_below(16);
+                s.push(value);
+                values.push(value);
+                let last = &values[values.len().saturating_sub(window)..];
+                assert_eq!(s.min(), last.iter().min());
+                assert_eq!(s.max(), last.iter().max());
+                assert_eq!(s.len(), last.len());
+            }
+            s.clear();
+            assert_eq!((s.min(), s.max(), s.len()), (None, None, 0));
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use core::{
+    mem::MaybeUninit,
+    ops::{Index, IndexMut},
+};
+
+use crate::ops::{
+    clear::Clear,
+    len::{Capacity, Len},
+    list::{List, ListMut},
+    slice::{assume_init_slice, assume_init_slice_mut},
+};
+
+const START_UP_SIZE: usize = 16;
+
+/// A list with a movable gap at the cursor
+///
+/// optimal if insertions and removals cluster around a slowly moving cursor
+///
+/// [`crate::ops::slice::AsSlice`] is not implemented since the elements are only contiguous after [`Self::make_contiguous()`]
+pub struct GapBuffer<T> {
+    /// Elements live in `..gap_start` and `gap_end..`
+    buf: Vec<MaybeUninit<T>>,
+    gap_start: usize,
+    gap_end: usize,
+}
+impl<T> GapBuffer<T> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            buf: vec![],
+            gap_start: 0,
+            gap_end: 0,
+        }
+    }
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut buf = Vec::with_capacity(capacity);
+        buf.extend((0..capacity).map(|_| MaybeUninit::uninit()));
+        Self {
+            buf,
+            gap_start: 0,
+            gap_end: capacity,
+        }
+    }
+    /// The index right after the last insertion or right at the last removal
+    #[must_use]
+    pub fn cursor(&self) -> usize {
+        self.gap_start
+    }
+    fn gap_len(&self) -> usize {
+        self.gap_end - self.gap_start
+    }
+    /// Move the gap to `index` in O(distance moved)
+    pub fn set_cursor(&mut self, index: usize) {
+        assert!(index <= self.len());
+        let ptr = self.buf.as_mut_ptr();
+        if index < self.gap_start {
+            let count = self.gap_start - index;
+            unsafe { core::ptr::copy(ptr.add(index), ptr.add(self.gap_end - count), count) };
+            self.gap_start -= count;
+            self.gap_end -= count;
+        } else {
+            let count = index - self.gap_start;
+            unsafe { core::ptr::copy(ptr.add(self.gap_end), ptr.add(self.gap_start), count) };
+            self.gap_start += count;
+            self.gap_end += count;
+        }
+    }
+    fn grow(&mut self) {
+        let new_cap = (self.capacity() * 2).max(START_UP_SIZE);
+        let mut new = Self::with_capacity(new_cap);
+        let back_len = self.buf.len() - self.gap_end;
+        let src = self.buf.as_ptr();
+        let dst = new.buf.as_mut_ptr();
+        unsafe {
+            core::ptr::copy_nonoverlapping(src, dst, self.gap_start);
+            core::ptr::copy_nonoverlapping(
+                src.add(self.gap_end),
+                dst.add(new_cap - back_len),
+                back_len,
+            );
+        }
+        new.gap_start = self.gap_start;
+        new.gap_end = new_cap - back_len;
+        // The elements have been moved to `new`
+        self.gap_start = 0;
+        self.gap_end = self.buf.len();
+        *self = new;
+    }
+    pub fn insert(&mut self, index: usize, value: T) {
+        assert!(index <= self.len());
+        if self.gap_len() == 0 {
+            self.grow();
+        }
+        self.set_cursor(index);
+        self.buf[self.gap_start] = MaybeUninit::new(value);
+        self.gap_start += 1;
+    }
+    pub fn remove(&mut self, index: usize) -> T {
+        assert!(index < self.len());
+        self.set_cursor(index);
+        let removed = core::mem::replace(&mut self.buf[self.gap_end], MaybeUninit::uninit());
+        self.gap_end += 1;
+        unsafe { removed.assume_init() }
+    }
+    pub fn push(&mut self, value: T) {
+        self.insert(self.len(), value);
+    }
+    pub fn pop(&mut self) -> Option<T> {
+        let len = self.len();
+        if len == 0 {
+            return None;
+        }
+        Some(self.remove(len - 1))
+    }
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if self.len() <= index {
+            return None;
+        }
+        Some(&self[index])
+    }
+    #[must_use]
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if self.len() <= index {
+            return None;
+        }
+        Some(&mut self[index])
+    }
+    /// The elements before and after the cursor
+    #[must_use]
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let front = &self.buf[..self.gap_start];
+        let back = &self.buf[self.gap_end..];
+        unsafe { (assume_init_slice(front), assume_init_slice(back)) }
+    }
+    #[must_use]
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let (front, back) = self.buf.as_mut_slice().split_at_mut(self.gap_end);
+        let front = &mut front[..self.gap_start];
+        unsafe { (assume_init_slice_mut(front), assume_init_slice_mut(back)) }
+    }
+    /// Move the gap to the end so that all the elements are in one slice
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        self.set_cursor(self.len());
+        self.as_mut_slices().0
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        let (front, back) = self.as_slices();
+        front.iter().chain(back)
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> + '_ {
+        let (front, back) = self.as_mut_slices();
+        front.iter_mut().chain(back)
+    }
+}
+impl<T> Len for GapBuffer<T> {
+    fn len(&self) -> usize {
+        self.buf.len() - self.gap_len()
+    }
+}
+impl<T> Capacity for GapBuffer<T> {
+    fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+}
+impl<T> Clear for GapBuffer<T> {
+    fn clear(&mut self) {
+        let (front, back) = self.as_mut_slices();
+        let (front, back): (*mut [T], *mut [T]) = (front, back);
+        self.gap_start = 0;
+        self.gap_end = self.buf.len();
+        unsafe {
+            core::ptr::drop_in_place(front);
+            core::ptr::drop_in_place(back);
+        }
+    }
+}
+impl<T> Index<usize> for GapBuffer<T> {
+    type Output = T;
+    fn index(&self, index: usize) -> &Self::Output {
+        assert!(index < self.len());
+        let index = if index < self.gap_start {
+            index
+        } else {
+            index + self.gap_len()
+        };
+        unsafe { self.buf[index].assume_init_ref() }
+    }
+}
+impl<T> IndexMut<usize> for GapBuffer<T> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        assert!(index < self.len());
+        let index = if index < self.gap_start {
+            index
+        } else {
+            index + self.gap_len()
+        };
+        unsafe { self.buf[index].assume_init_mut() }
+    }
+}
+impl<T> List<T> for GapBuffer<T> {}
+impl<T> ListMut<T> for GapBuffer<T> {
+    fn swap(&mut self, i: usize, j: usize) {
+        let (i, j) = (i.min(j), i.max(j));
+        assert!(j < self.len());
+        let (front, back) = self.as_mut_slices();
+        let split = front.len();
+        match (i < split, j < split) {
+            (true, true) => front.swap(i, j),
+            (false, false) => back.swap(i - split, j - split),
+            _ => core::mem::swap(&mut front[i], &mut back[j - split]),
+        }
+    }
+}
+impl<T> Default for GapBuffer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<T: Clone> Clone for GapBuffer<T> {
+    fn clone(&self) -> Self {
+        let mut new = Self::with_capacity(self.capacity());
+        for item in self.iter() {
+            new.push(item.clone());
+        }
+        new
+    }
+}
+impl<T: core::fmt::Debug> core::fmt::Debug for GapBuffer<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+impl<T> Drop for GapBuffer<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+
+    #[test]
+    fn test_gap_buffer() {
+        let mut b = GapBuffer::new();
+        let mut v = vec![];
+        for i in 0..(START_UP_SIZE * 3) {
+            let index = (i * 7) % (v.len() + 1);
+            b.insert(index, i);
+            v.insert(index, i);
+            assert_eq!(b.len(), v.len());
+            assert_eq!(b.cursor(), index + 1);
+        }
+        assert_eq!(b.iter().copied().collect::<Vec<_>>(), v);
+        for i in 0..v.len() {
+            assert_eq!(b[i], v[i]);
+        }
+        b.set_cursor(3);
+        let (front, back) = b.as_slices();
+        assert_eq!(front, &v[..3]);
+        assert_eq!(back, &v[3..]);
+        for i in 0..(START_UP_SIZE * 2) {
+            let index = (i * 5) % v.len();
+            assert_eq!(b.remove(index), v.remove(index));
+        }
+        assert_eq!(b.make_contiguous(), &v[..]);
+        assert_eq!(
+            b.clone().iter().collect::<Vec<_>>(),
+            b.iter().collect::<Vec<_>>()
+        );
+        b.clear();
+        assert_eq!(b.len(), 0);
+        assert!(b.pop().is_none());
+    }
+
+    #[test]
+    fn test_swap_across_gap() {
+        let mut b = GapBuffer::new();
+        let mut v = vec![];
+        for i in 0..6 {
+            b.push(i.to_string());
+            v.push(i.to_string());
+        }
+        b.set_cursor(3);
+        for (i, j) in [(1, 4), (0, 2), (5, 3), (5, 5), (4, 5)] {
+            ListMut::swap(&mut b, i, j);
+            v.swap(i, j);
+        }
+        ListMut::rotate_left(&mut b, 2);
+        v.rotate_left(2);
+        assert!(b.iter().eq(&v));
+    }
+
+    #[test]
+    fn test_gap_buffer_drop() {
+        let rc = Rc::new(());
+        let mut b = GapBuffer::with_capacity(2);
+        for i in 0..5 {
+            b.insert(i / 2, Rc::clone(&rc));
+        }
+        assert_eq!(Rc::strong_count(&rc), 6);
+        b.set_cursor(2);
+        drop(b.remove(0));
+        assert_eq!(Rc::strong_count(&rc), 5);
+        b.clear();
+        assert_eq!(Rc::strong_count(&rc), 1);
+        b.push(Rc::clone(&rc));
+        b.push(Rc::clone(&rc));
+        b.set_cursor(1);
+        drop(b);
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+}
+
+#[cfg(feature = "nightly")]
+#[cfg(test)]
+mod benches {
+    use test::Bencher;
+
+    use super::*;
+
+    const N: usize = 1 << 12;
+    const CLUSTER: usize = 1 << 4;
+
+    #[bench]
+    fn bench_clustered_insert_gap_buffer(bencher: &mut Bencher) {
+        let mut b = GapBuffer::new();
+        bencher.iter(|| {
+            b.clear();
+            for i in 0..N {
+                let cursor = (i / CLUSTER) * (CLUSTER / 2);
+                b.insert(cursor.min(b.len()), i);
+            }
+        });
+    }
+    #[bench]
+    fn bench_clustered_insert_vec(bencher: &mut Bencher) {
+        let mut b = vec![];
+        bencher.iter(|| {
+            b.clear();
+            for i in 0..N {
+                let cursor = (i / CLUSTER) * (CLUSTER / 2);
+                b.insert(cursor.min(b.len()), i);
+            }
+        });
+    }
+}
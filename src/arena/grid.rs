@@ -0,0 +1,438 @@
+use core::num::NonZeroUsize;
+
+/// Dense 2D grid in one row-major [`Vec`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    /// Exactly `width * height` cells
+    cells: Vec<T>,
+    width: NonZeroUsize,
+    height: NonZeroUsize,
+}
+impl<T> Grid<T> {
+    /// Fill the cells in row-major order
+    #[must_use]
+    pub fn new(
+        width: NonZeroUsize,
+        height: NonZeroUsize,
+        mut fill: impl FnMut(usize, usize) -> T,
+    ) -> Self {
+        let mut cells = Vec::with_capacity(width.get() * height.get());
+        for y in 0..height.get() {
+            cells.extend((0..width.get()).map(|x| fill(x, y)));
+        }
+        Self {
+            cells,
+            width,
+            height,
+        }
+    }
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width.get()
+    }
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        self.height.get()
+    }
+    #[must_use]
+    fn index(&self, x: usize, y: usize) -> Option<usize> {
+        if self.width() <= x || self.height() <= y {
+            return None;
+        }
+        Some(y * self.width() + x)
+    }
+    #[must_use]
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        let index = self.index(x, y)?;
+        Some(&self.cells[index])
+    }
+    #[must_use]
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        let index = self.index(x, y)?;
+        Some(&mut self.cells[index])
+    }
+    /// # Safety
+    ///
+    /// `x` must be less than the width and `y` less than the height
+    #[must_use]
+    pub unsafe fn get_unchecked(&self, x: usize, y: usize) -> &T {
+        unsafe { self.cells.get_unchecked(y * self.width() + x) }
+    }
+    /// # Safety
+    ///
+    /// `x` must be less than the width and `y` less than the height
+    #[must_use]
+    pub unsafe fn get_unchecked_mut(&mut self, x: usize, y: usize) -> &mut T {
+        let index = y * self.width() + x;
+        unsafe { self.cells.get_unchecked_mut(index) }
+    }
+    /// # Panic
+    ///
+    /// `y` is out of bounds
+    #[must_use]
+    pub fn row(&self, y: usize) -> &[T] {
+        assert!(y < self.height());
+        let start = y * self.width();
+        &self.cells[start..start + self.width()]
+    }
+    /// # Panic
+    ///
+    /// `y` is out of bounds
+    #[must_use]
+    pub fn row_mut(&mut self, y: usize) -> &mut [T] {
+        assert!(y < self.height());
+        let start = y * self.width();
+        let width = self.width();
+        &mut self.cells[start..start + width]
+    }
+    /// `(x, y, cell)` in row-major order
+    pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, usize, &T)> + '_ {
+        let width = self.width();
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| (i % width, i / width, cell))
+    }
+    /// Swap the cells at `(x, y)` `a` and `b`
+    ///
+    /// # Panic
+    ///
+    /// Either is out of bounds
+    pub fn swap(&mut self, a: (usize, usize), b: (usize, usize)) {
+        let a = self.index(a.0, a.1).expect("out of bounds");
+        let b = self.index(b.0, b.1).expect("out of bounds");
+        self.cells.swap(a, b);
+    }
+    /// The in-bounds cells up, left, right and down of `(x, y)` in row-major order
+    pub fn neighbors4(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        const OFFSETS: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+        self.offset_cells(x, y, &OFFSETS)
+    }
+    /// The in-bounds cells around `(x, y)` including the diagonals in row-major order
+    pub fn neighbors8(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        const OFFSETS: [(isize, isize); 8] = [
+            (-1, -1),
+            (0, -1),
+            (1, -1),
+            (-1, 0),
+            (1, 0),
+            (-1, 1),
+            (0, 1),
+            (1, 1),
+        ];
+        self.offset_cells(x, y, &OFFSETS)
+    }
+    fn offset_cells<'a>(
+        &'a self,
+        x: usize,
+        y: usize,
+        offsets: &'static [(isize, isize)],
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        offsets.iter().filter_map(move |&(dx, dy)| {
+            let x = x.checked_add_signed(dx)?;
+            let y = y.checked_add_signed(dy)?;
+            self.index(x, y)?;
+            Some((x, y))
+        })
+    }
+
+    /// Keep the cells in the overlapping top-left region in place and `fill` the new ones
+    ///
+    /// Reallocates at most once, and never when shrinking.
+    /// If `fill` panics, the grid keeps the rows filled so far or its old width.
+    pub fn resize(
+        &mut self,
+        width: NonZeroUsize,
+        height: NonZeroUsize,
+        mut fill: impl FnMut(usize, usize) -> T,
+    ) {
+        // Drop the rows below the new bottom first so that they are never moved
+        if height < self.height {
+            self.cells.truncate(height.get() * self.width());
+            self.height = height;
+        }
+        let len = width.get() * height.get();
+        self.cells
+            .reserve_exact(len.saturating_sub(self.cells.len()));
+        match width.cmp(&self.width) {
+            core::cmp::Ordering::Less => self.shrink_width(width),
+            core::cmp::Ordering::Greater => self.grow_width(width, &mut fill),
+            core::cmp::Ordering::Equal => (),
+        }
+        let mut rows = TruncateOnDrop {
+            len: self.cells.len(),
+            cells: &mut self.cells,
+        };
+        for y in self.height.get()..height.get() {
+            for x in 0..width.get() {
+                rows.cells.push(fill(x, y));
+            }
+            rows.len = rows.cells.len();
+            self.height = NonZeroUsize::new(y + 1).unwrap();
+        }
+    }
+    /// Pack the rows to the left top-down, each row moved to or before where it was
+    fn shrink_width(&mut self, width: NonZeroUsize) {
+        let (old, new, height) = (self.width(), width.get(), self.height());
+        let mut guard = ShrinkGuard {
+            cells: &mut self.cells,
+            width: &mut self.width,
+            new: width,
+            old,
+            height,
+            y: 0,
+        };
+        // Own the cells by hand; the guard still packs the rows if a drop panics
+        unsafe { guard.cells.set_len(0) };
+        while guard.y < height {
+            let ptr = guard.cells.as_mut_ptr();
+            let row = unsafe { ptr.add(guard.y * old) };
+            let cut = core::ptr::slice_from_raw_parts_mut(unsafe { row.add(new) }, old - new);
+            unsafe { core::ptr::drop_in_place(cut) };
+            unsafe { core::ptr::copy(row, ptr.add(guard.y * new), new) };
+            guard.y += 1;
+        }
+    }
+    /// Spread the rows to the right bottom-up, each row moved to or after where it was
+    fn grow_width(&mut self, width: NonZeroUsize, fill: &mut impl FnMut(usize, usize) -> T) {
+        let (old, new, height) = (self.width(), width.get(), self.height());
+        debug_assert!(height * new <= self.cells.capacity());
+        let mut guard = GrowGuard {
+            cells: &mut self.cells,
+            old,
+            new,
+            height,
+            y: height,
+            filled: 0,
+        };
+        // Own the cells by hand; the guard moves the rows back if `fill` panics
+        unsafe { guard.cells.set_len(0) };
+        for y in (0..height).rev() {
+            let ptr = guard.cells.as_mut_ptr();
+            unsafe { core::ptr::copy(ptr.add(y * old), ptr.add(y * new), old) };
+            guard.y = y;
+            guard.filled = 0;
+            for x in old..new {
+                let cell = fill(x, y);
+                unsafe { guard.cells.as_mut_ptr().add(y * new + x).write(cell) };
+                guard.filled += 1;
+            }
+        }
+        core::mem::forget(guard);
+        unsafe { self.cells.set_len(height * new) };
+        self.width = width;
+    }
+}
+
+/// Drop the cells pushed past `len`
+struct TruncateOnDrop<'a, T> {
+    cells: &'a mut Vec<T>,
+    len: usize,
+}
+impl<T> Drop for TruncateOnDrop<'_, T> {
+    fn drop(&mut self) {
+        self.cells.truncate(self.len);
+    }
+}
+
+/// Finishes [`Grid::shrink_width()`]: the rows before `y` are packed and the cut cells of row `y` dropped
+///
+/// After a panicking drop, packs the rest without dropping their cut cells, which are leaked.
+struct ShrinkGuard<'a, T> {
+    cells: &'a mut Vec<T>,
+    width: &'a mut NonZeroUsize,
+    new: NonZeroUsize,
+    old: usize,
+    height: usize,
+    y: usize,
+}
+impl<T> Drop for ShrinkGuard<'_, T> {
+    fn drop(&mut self) {
+        let new = self.new.get();
+        let ptr = self.cells.as_mut_ptr();
+        for y in self.y..self.height {
+            unsafe { core::ptr::copy(ptr.add(y * self.old), ptr.add(y * new), new) };
+        }
+        unsafe { self.cells.set_len(self.height * new) };
+        *self.width = self.new;
+    }
+}
+
+/// Only dropped on a panic in [`Grid::grow_width()`]: rows from `y` on have the new width
+///
+/// Row `y` has only `filled` new cells. Drops the new cells and moves the rows back top-down.
+struct GrowGuard<'a, T> {
+    cells: &'a mut Vec<T>,
+    old: usize,
+    new: usize,
+    height: usize,
+    y: usize,
+    filled: usize,
+}
+impl<T> Drop for GrowGuard<'_, T> {
+    fn drop(&mut self) {
+        let ptr = self.cells.as_mut_ptr();
+        for y in self.y..self.height {
+            let filled = if y == self.y {
+                self.filled
+            } else {
+                self.new - self.old
+            };
+            let row = unsafe { ptr.add(y * self.new) };
+            let added = core::ptr::slice_from_raw_parts_mut(unsafe { row.add(self.old) }, filled);
+            unsafe { core::ptr::drop_in_place(added) };
+            unsafe { core::ptr::copy(row, ptr.add(y * self.old), self.old) };
+        }
+        unsafe { self.cells.set_len(self.height * self.old) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{panic::AssertUnwindSafe, rc::Rc};
+
+    use super::*;
+
+    fn nz(n: usize) -> NonZeroUsize {
+        NonZeroUsize::new(n).unwrap()
+    }
+    fn labeled(width: usize, height: usize) -> Grid<String> {
+        Grid::new(nz(width), nz(height), |x, y| format!("{x},{y}"))
+    }
+    /// The old cells in the overlap and `new` elsewhere
+    fn assert_resized(grid: &Grid<String>, old: (usize, usize), new: (usize, usize)) {
+        assert_eq!((grid.width(), grid.height()), new);
+        assert_eq!(grid.cells.len(), new.0 * new.1);
+        for (x, y, cell) in grid.iter_indexed() {
+            if x < old.0 && y < old.1 {
+                assert_eq!(*cell, format!("{x},{y}"));
+            } else {
+                assert_eq!(*cell, format!("new {x},{y}"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_grid() {
+        let mut grid = labeled(3, 2);
+        assert_eq!(grid.get(2, 1).unwrap(), "2,1");
+        assert_eq!(grid.get(3, 0), None);
+        assert_eq!(grid.get(0, 2), None);
+        assert_eq!(unsafe { grid.get_unchecked(1, 1) }, "1,1");
+        assert_eq!(grid.row(1), ["0,1", "1,1", "2,1"]);
+        grid.get_mut(0, 0).unwrap().push('!');
+        unsafe { grid.get_unchecked_mut(1, 0) }.push('?');
+        grid.row_mut(1)[2].clear();
+        grid.swap((0, 0), (2, 1));
+        assert_eq!(grid.row(0), ["", "1,0?", "2,0"]);
+        assert_eq!(grid.row(1), ["0,1", "1,1", "0,0!"]);
+        let indices = grid
+            .iter_indexed()
+            .map(|(x, y, _)| (x, y))
+            .collect::<Vec<_>>();
+        assert_eq!(indices, [(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]);
+    }
+
+    #[test]
+    fn test_resize() {
+        let new = |x, y| format!("new {x},{y}");
+        for (old, to) in [
+            ((3, 4), (5, 6)),
+            ((5, 6), (3, 4)),
+            ((3, 6), (5, 4)),
+            ((5, 4), (3, 6)),
+            ((4, 4), (4, 7)),
+            ((4, 4), (1, 1)),
+            ((1, 1), (4, 4)),
+        ] {
+            let mut grid = labeled(old.0, old.1);
+            grid.resize(nz(to.0), nz(to.1), new);
+            assert_resized(&grid, old, to);
+        }
+
+        // Reallocates once on growth and never on shrinking
+        let mut grid = labeled(4, 4);
+        grid.resize(nz(8), nz(8), new);
+        assert_eq!(grid.cells.capacity(), 64);
+        let ptr = grid.cells.as_ptr();
+        grid.resize(nz(2), nz(8), new);
+        grid.resize(nz(2), nz(3), new);
+        assert_eq!(grid.cells.as_ptr(), ptr);
+        assert_resized(&grid, (2, 3), (2, 3));
+    }
+
+    #[test]
+    fn test_resize_drops_once() {
+        let counter = Rc::new(());
+        let mut grid = Grid::new(nz(4), nz(3), |_, _| Rc::clone(&counter));
+        assert_eq!(Rc::strong_count(&counter), 13);
+        grid.resize(nz(2), nz(5), |_, _| Rc::clone(&counter));
+        assert_eq!(Rc::strong_count(&counter), 11);
+        grid.resize(nz(6), nz(1), |_, _| Rc::clone(&counter));
+        assert_eq!(Rc::strong_count(&counter), 7);
+        drop(grid);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_resize_fill_panics() {
+        let mut grid = labeled(2, 2);
+        let res = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            grid.resize(nz(4), nz(2), |x, y| {
+                assert!(y != 0, "fill panicked");
+                format!("new {x},{y}")
+            });
+        }));
+        assert!(res.is_err());
+        // Row 1 had been spread and partly filled
+        assert_resized(&grid, (2, 2), (2, 2));
+
+        let res = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            grid.resize(nz(2), nz(5), |x, y| {
+                assert!(y != 3, "fill panicked");
+                format!("new {x},{y}")
+            });
+        }));
+        assert!(res.is_err());
+        assert_resized(&grid, (2, 2), (2, 3));
+        assert_eq!(grid.get(1, 3), None);
+    }
+
+    #[test]
+    fn test_resize_drop_panics() {
+        struct Bomb(bool);
+        impl Drop for Bomb {
+            fn drop(&mut self) {
+                assert!(!self.0, "drop panicked");
+            }
+        }
+        let mut grid = Grid::new(nz(3), nz(3), |x, y| Bomb(x == 2 && y == 1));
+        let res = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            grid.resize(nz(1), nz(3), |_, _| unreachable!())
+        }));
+        assert!(res.is_err());
+        // Still a consistent grid of the new width
+        assert_eq!((grid.width(), grid.height()), (1, 3));
+        assert_eq!(grid.cells.len(), 3);
+        assert!(grid.iter_indexed().all(|(_, _, bomb)| !bomb.0));
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let grid = labeled(3, 3);
+        assert!(grid.neighbors4(1, 1).eq([(1, 0), (0, 1), (2, 1), (1, 2)]));
+        assert!(grid.neighbors4(0, 0).eq([(1, 0), (0, 1)]));
+        assert!(grid.neighbors4(2, 2).eq([(2, 1), (1, 2)]));
+        assert!(grid.neighbors4(2, 0).eq([(1, 0), (2, 1)]));
+        assert_eq!(grid.neighbors8(1, 1).count(), 8);
+        assert!(grid.neighbors8(0, 0).eq([(1, 0), (0, 1), (1, 1)]));
+        assert!(grid
+            .neighbors8(2, 1)
+            .eq([(1, 0), (2, 0), (1, 1), (1, 2), (2, 2)]));
+
+        let line = labeled(1, 1);
+        assert_eq!(line.neighbors8(0, 0).count(), 0);
+        let line = labeled(4, 1);
+        assert!(line.neighbors8(3, 0).eq([(2, 0)]));
+    }
+}
@@ -0,0 +1,188 @@
+use core::hash::BuildHasher;
+use std::collections::{hash_map::RandomState, HashMap};
+
+use crate::{
+    map::hash_map::IsEnabledNoHash,
+    ops::{clear::Clear, len::Len},
+};
+
+/// Handed out by an [`Interner`] and resolvable only by it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(u32);
+impl Symbol {
+    #[must_use]
+    pub const fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+impl IsEnabledNoHash for Symbol {}
+
+/// Bytes per arena chunk unless a string is longer
+const CHUNK_SIZE: usize = 1 << 12;
+
+/// Deduplicates strings into [`Symbol`]s
+///
+/// The bytes live in arena chunks that never reallocate, so a resolved `&str` stays at the same address until the interner is dropped or cleared.
+#[derive(Debug)]
+pub struct Interner<H = RandomState> {
+    /// Borrowed from [`Self::chunks`]; never handed out longer than `&self`
+    strs: Vec<&'static str>,
+    index: HashMap<&'static str, Symbol, H>,
+    /// Each chunk only grows within its capacity
+    ///
+    /// Declared last so that it is dropped after the fields borrowing from it.
+    chunks: Vec<String>,
+}
+impl Interner {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+impl<H> Interner<H> {
+    #[must_use]
+    pub fn with_hasher(hasher: H) -> Self {
+        Self {
+            strs: vec![],
+            index: HashMap::with_hasher(hasher),
+            chunks: vec![],
+        }
+    }
+    /// # Panic
+    ///
+    /// `symbol` is from another interner
+    #[must_use]
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        self.strs[symbol.0 as usize]
+    }
+    /// All the strings in the order of their symbols
+    pub fn iter(&self) -> impl Iterator<Item = (Symbol, &str)> + '_ {
+        self.strs
+            .iter()
+            .enumerate()
+            .map(|(i, &s)| (Symbol(i as u32), s))
+    }
+    /// Copy `s` into the arena
+    fn alloc(&mut self, s: &str) -> &'static str {
+        let fits = self
+            .chunks
+            .last()
+            .is_some_and(|chunk| s.len() <= chunk.capacity() - chunk.len());
+        if !fits {
+            self.chunks
+                .push(String::with_capacity(s.len().max(CHUNK_SIZE)));
+        }
+        let chunk = self.chunks.last_mut().unwrap();
+        let start = chunk.len();
+        // Within the capacity: the buffer does not move
+        chunk.push_str(s);
+        let s = &chunk[start..];
+        // The buffer outlives every reference in `strs` and `index` since the chunks are only cleared or dropped after them,
+        // and the references handed out are bound to `&self`
+        unsafe { core::str::from_utf8_unchecked(core::slice::from_raw_parts(s.as_ptr(), s.len())) }
+    }
+}
+impl<H: BuildHasher> Interner<H> {
+    /// # Panic
+    ///
+    /// More than `u32::MAX` distinct strings
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        if let Some(&symbol) = self.index.get(s) {
+            return symbol;
+        }
+        let symbol = u32::try_from(self.strs.len()).expect("interner is full");
+        let symbol = Symbol(symbol);
+        let s = self.alloc(s);
+        self.strs.push(s);
+        self.index.insert(s, symbol);
+        symbol
+    }
+    /// Look up `s` without interning it
+    #[must_use]
+    pub fn get(&self, s: &str) -> Option<Symbol> {
+        self.index.get(s).copied()
+    }
+}
+impl Default for Interner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<H> Len for Interner<H> {
+    fn len(&self) -> usize {
+        self.strs.len()
+    }
+}
+/// Invalidates all the symbols
+impl<H> Clear for Interner<H> {
+    fn clear(&mut self) {
+        self.index.clear();
+        self.strs.clear();
+        self.chunks.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ops::len::LenExt;
+
+    use super::*;
+
+    #[test]
+    fn test_intern() {
+        let mut interner = Interner::new();
+        assert!(interner.is_empty());
+        let a = interner.intern("content-length");
+        let b = interner.intern("host");
+        assert_ne!(a, b);
+        assert_eq!(interner.intern("content-length"), a);
+        assert_eq!(interner.intern(&String::from("host")), b);
+        assert_eq!(interner.len(), 2);
+        assert_eq!(interner.resolve(a), "content-length");
+        assert_eq!(interner.resolve(b), "host");
+        assert_eq!(interner.get("host"), Some(b));
+        assert_eq!(interner.get("accept"), None);
+        let empty = interner.intern("");
+        assert_eq!(interner.resolve(empty), "");
+        assert_eq!(interner.intern(""), empty);
+        assert!(interner
+            .iter()
+            .map(|(_, s)| s)
+            .eq(["content-length", "host", ""]));
+
+        interner.clear();
+        assert!(interner.is_empty());
+        assert_eq!(interner.get("host"), None);
+    }
+
+    #[test]
+    fn test_stable_addresses() {
+        let mut interner = Interner::new();
+        let first = interner.intern("first");
+        let ptr = interner.resolve(first).as_ptr();
+        let long = "x".repeat(CHUNK_SIZE * 2);
+        let long_symbol = interner.intern(&long);
+        let long_ptr = interner.resolve(long_symbol).as_ptr();
+        let symbols = (0..10_000)
+            .map(|i| interner.intern(&format!("field-{i}")))
+            .collect::<Vec<_>>();
+        assert!(interner.chunks.len() > 2);
+        assert_eq!(interner.resolve(first).as_ptr(), ptr);
+        assert_eq!(interner.resolve(long_symbol).as_ptr(), long_ptr);
+        assert_eq!(interner.resolve(long_symbol), long);
+        for (i, &symbol) in symbols.iter().enumerate() {
+            assert_eq!(interner.resolve(symbol), format!("field-{i}"));
+            assert_eq!(interner.intern(&format!("field-{i}")), symbol);
+        }
+        assert_eq!(interner.len(), 10_002);
+    }
+
+    #[test]
+    fn test_across_threads() {
+        let mut interner = Interner::new();
+        let symbol = interner.intern("shared");
+        std::thread::scope(|s| {
+            s.spawn(|| assert_eq!(interner.resolve(symbol), "shared"));
+        });
+    }
+}
@@ -1,3 +1,6 @@
+pub mod gap_buffer;
+pub mod grid;
+pub mod interner;
 pub mod obj_pool;
 pub mod stable_vec;
 pub mod stack;
@@ -1,12 +1,20 @@
 use core::{
+    cell::RefCell,
     mem::MaybeUninit,
     num::NonZeroUsize,
     ops::{Deref, DerefMut},
     sync::atomic::{AtomicUsize, Ordering},
 };
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 
-use crate::{ops::ring::RingSpace, sync::mutex::SpinMutex};
+use crate::{
+    ops::{len::Len, ring::RingSpace},
+    sync::{
+        cache_pad::CachePadded,
+        mutex::{HybridMutex, SpinMutex},
+        treiber::TreiberStack,
+    },
+};
 
 use super::stack::{DynStack, Stack};
 
@@ -41,10 +49,74 @@ impl<T> ObjPool<T> {
 pub fn arc_buf_pool<T>(capacity: Option<usize>, shards: NonZeroUsize) -> ArcObjPool<Vec<T>> {
     ArcObjPool::new(capacity, shards, Vec::new, |b| b.clear())
 }
-type ArcStacks<T> = Arc<[SpinMutex<DynStack<T>>]>;
+pub fn arc_buf_pool_with_mode<T>(capacity: Option<usize>, mode: ArcPoolMode) -> ArcObjPool<Vec<T>> {
+    ArcObjPool::with_mode(capacity, mode, Vec::new, |b| b.clear())
+}
+/// How [`ArcObjPool`] keeps the idle objects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArcPoolMode {
+    /// Stacks behind spin mutexes, one per shard
+    Sharded(NonZeroUsize),
+    /// [`Self::Sharded`] but behind [`HybridMutex`]es, for more threads than cores
+    HybridSharded(NonZeroUsize),
+    /// One [`TreiberStack`]; the capacity is checked against its approximate length
+    LockFree,
+}
+type ArcStacks<T> = Arc<Stacks<T>>;
+#[derive(Debug)]
+enum Stacks<T> {
+    Sharded(Box<[CachePadded<SpinMutex<DynStack<T>>>]>),
+    HybridSharded(Box<[CachePadded<HybridMutex<DynStack<T>>>]>),
+    LockFree {
+        stack: TreiberStack<T>,
+        capacity: Option<usize>,
+    },
+}
+impl<T> Stacks<T> {
+    fn shards(&self) -> usize {
+        match self {
+            Stacks::Sharded(stacks) => stacks.len(),
+            Stacks::HybridSharded(stacks) => stacks.len(),
+            Stacks::LockFree { .. } => 1,
+        }
+    }
+    fn pop(&self, shard: usize) -> Option<T> {
+        match self {
+            Stacks::Sharded(stacks) => stacks[shard].lock().pop(),
+            Stacks::HybridSharded(stacks) => stacks[shard].lock().pop(),
+            Stacks::LockFree { stack, .. } => stack.pop(),
+        }
+    }
+    fn push(&self, shard: usize, obj: T) {
+        match self {
+            Stacks::Sharded(stacks) => {
+                stacks[shard].lock().push(obj);
+            }
+            Stacks::HybridSharded(stacks) => {
+                stacks[shard].lock().push(obj);
+            }
+            Stacks::LockFree { stack, capacity } => {
+                if capacity.is_some_and(|capacity| capacity <= stack.len()) {
+                    return;
+                }
+                stack.push(obj);
+            }
+        }
+    }
+    fn lens(&self) -> Vec<usize> {
+        match self {
+            Stacks::Sharded(stacks) => stacks.iter().map(|stack| stack.lock().len()).collect(),
+            Stacks::HybridSharded(stacks) => {
+                stacks.iter().map(|stack| stack.lock().len()).collect()
+            }
+            Stacks::LockFree { stack, .. } => vec![stack.len()],
+        }
+    }
+}
 #[derive(Debug)]
 pub struct ArcObjPool<T> {
     stacks: ArcStacks<T>,
+    thread_shards: Arc<ThreadShards>,
     next: AtomicUsize,
     alloc: fn() -> T,
     reset: fn(&mut T),
@@ -57,54 +129,89 @@ impl<T> ArcObjPool<T> {
         alloc: fn() -> T,
         reset: fn(&mut T),
     ) -> Self {
-        let mut stacks = vec![];
-        for _ in 0..shards.get() {
-            stacks.push(SpinMutex::new(DynStack::new(capacity)));
-        }
+        Self::with_mode(capacity, ArcPoolMode::Sharded(shards), alloc, reset)
+    }
+    #[must_use]
+    pub fn with_mode(
+        capacity: Option<usize>,
+        mode: ArcPoolMode,
+        alloc: fn() -> T,
+        reset: fn(&mut T),
+    ) -> Self {
+        let stacks = match mode {
+            ArcPoolMode::Sharded(shards) => {
+                let mut stacks = vec![];
+                for _ in 0..shards.get() {
+                    stacks.push(CachePadded::new(SpinMutex::new(DynStack::new(capacity))));
+                }
+                Stacks::Sharded(stacks.into())
+            }
+            ArcPoolMode::HybridSharded(shards) => {
+                let mut stacks = vec![];
+                for _ in 0..shards.get() {
+                    stacks.push(CachePadded::new(HybridMutex::new(DynStack::new(capacity))));
+                }
+                Stacks::HybridSharded(stacks.into())
+            }
+            ArcPoolMode::LockFree => Stacks::LockFree {
+                stack: TreiberStack::new(),
+                capacity,
+            },
+        };
         Self {
-            stacks: stacks.into(),
+            stacks: Arc::new(stacks),
+            thread_shards: Arc::new(ThreadShards::default()),
             next: AtomicUsize::new(0),
             alloc,
             reset,
         }
     }
+    /// From the shard of the calling thread first, then from the others
     #[must_use]
     pub fn take(&self) -> T {
-        self.stacks[self.shard_incr()]
-            .lock()
-            .pop()
+        let shards = self.stacks.shards();
+        let home = thread_shard(&self.thread_shards, shards);
+        (0..shards)
+            .find_map(|i| self.stacks.pop(home.ring_add(i, shards - 1)))
             .unwrap_or_else(|| (self.alloc)())
     }
     #[must_use]
     pub fn take_scoped(&self) -> ObjScoped<T> {
-        ObjScoped::new(self.recycler(), self.take())
+        ObjScoped::new(self.balanced_recycler(), self.take())
     }
     pub fn put(&self, mut obj: T) {
         (self.reset)(&mut obj);
-        self.stacks[self.shard_incr()].lock().push(obj);
+        self.stacks.push(self.shard_incr(), obj);
     }
+    /// Each thread puts objects back into its own shard
+    ///
+    /// Threads are assigned shards of this pool round-robin on their first take or put, so objects dropped across `n` threads spread over `min(n, shards)` shards.
+    /// Objects all dropped on one thread still end up in one shard.
     #[must_use]
-    pub fn recycler(&self) -> ObjRecycler<T> {
+    pub fn balanced_recycler(&self) -> ObjRecycler<T> {
         ObjRecycler {
             stacks: Arc::clone(&self.stacks),
-            next: self.shard(),
+            thread_shards: Arc::clone(&self.thread_shards),
             reset: self.reset,
         }
     }
+    /// Same as [`Self::balanced_recycler()`]
     #[must_use]
-    fn shard(&self) -> usize {
-        match self.stacks.len() {
-            1 => 0,
-            _ => self.next.load(Ordering::Relaxed),
-        }
+    pub fn recycler(&self) -> ObjRecycler<T> {
+        self.balanced_recycler()
+    }
+    /// Number of idle objects in each shard
+    #[must_use]
+    pub fn shard_lens(&self) -> Vec<usize> {
+        self.stacks.lens()
     }
     #[must_use]
     fn shard_incr(&self) -> usize {
-        match self.stacks.len() {
+        match self.stacks.shards() {
             1 => 0,
-            _ => {
+            shards => {
                 let shard = self.next.load(Ordering::Relaxed);
-                let next = shard.ring_add(1, self.stacks.len() - 1);
+                let next = shard.ring_add(1, shards - 1);
                 self.next.store(next, Ordering::Relaxed);
                 shard
             }
@@ -114,28 +221,53 @@ impl<T> ArcObjPool<T> {
 #[derive(Debug)]
 pub struct ObjRecycler<T> {
     stacks: ArcStacks<T>,
-    next: usize,
+    thread_shards: Arc<ThreadShards>,
     reset: fn(&mut T),
 }
 impl<T> ObjRecycler<T> {
+    /// Into the shard of the calling thread
     pub fn put(&mut self, mut obj: T) {
-        let shard = self.next;
-        if 1 < self.stacks.len() {
-            self.next = self.next.ring_add(1, self.stacks.len() - 1);
-        }
+        let shard = thread_shard(&self.thread_shards, self.stacks.shards());
         (self.reset)(&mut obj);
-        self.stacks[shard].lock().push(obj);
+        self.stacks.push(shard, obj);
     }
 }
 impl<T> Clone for ObjRecycler<T> {
     fn clone(&self) -> Self {
         Self {
             stacks: Arc::clone(&self.stacks),
-            next: self.next,
+            thread_shards: Arc::clone(&self.thread_shards),
             reset: self.reset,
         }
     }
 }
+/// Round-robin counter over the threads putting into one pool
+#[derive(Debug, Default)]
+struct ThreadShards {
+    next: AtomicUsize,
+}
+thread_local! {
+    /// The shard of this thread in each pool it has taken from or put into
+    ///
+    /// The weak pointers keep the counters from being reallocated under the same address while listed.
+    static THREAD_SHARDS: RefCell<Vec<(Weak<ThreadShards>, usize)>> = const { RefCell::new(vec![]) };
+}
+fn thread_shard(thread_shards: &Arc<ThreadShards>, shards: usize) -> usize {
+    if shards == 1 {
+        return 0;
+    }
+    THREAD_SHARDS.with_borrow_mut(|assigned| {
+        let this = Arc::as_ptr(thread_shards);
+        if let Some(&(_, shard)) = assigned.iter().find(|(pool, _)| pool.as_ptr() == this) {
+            return shard;
+        }
+        // Forget the dropped pools
+        assigned.retain(|(pool, _)| pool.strong_count() != 0);
+        let shard = thread_shards.next.fetch_add(1, Ordering::Relaxed) % shards;
+        assigned.push((Arc::downgrade(thread_shards), shard));
+        shard
+    })
+}
 
 #[derive(Debug)]
 pub struct ObjScoped<T> {
@@ -169,6 +301,143 @@ impl<T> Drop for ObjScoped<T> {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_cross_thread_drop_balance() {
+        const THREADS: usize = 4;
+        const N: usize = 1 << 8;
+
+        let pool = arc_buf_pool::<u8>(None, NonZeroUsize::new(THREADS).unwrap());
+        let batches = (0..THREADS)
+            .map(|_| (0..N).map(|_| pool.take_scoped()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        thread::scope(|s| {
+            for batch in batches {
+                s.spawn(move || drop(batch));
+            }
+        });
+        // The threads of other tests do not count towards the shards of this pool
+        assert_eq!(pool.shard_lens(), [N; THREADS]);
+
+        // A thread keeps its shard in each pool
+        let other = arc_buf_pool::<u8>(None, NonZeroUsize::new(THREADS).unwrap());
+        let mut recycler = pool.balanced_recycler();
+        let mut other_recycler = other.balanced_recycler();
+        for _ in 0..N {
+            recycler.put(vec![]);
+            other_recycler.put(vec![]);
+        }
+        let lens = pool.shard_lens();
+        assert_eq!(
+            lens.iter().filter(|&&len| len == N * 2).count(),
+            1,
+            "{lens:?}"
+        );
+        assert_eq!(other.shard_lens()[0], N);
+    }
+
+    #[test]
+    fn test_lock_free_mode() {
+        const THREADS: usize = 4;
+        const N: usize = 1 << 10;
+
+        let pool = ArcObjPool::with_mode(Some(N), ArcPoolMode::LockFree, Vec::<u8>::new, |b| {
+            b.clear()
+        });
+        thread::scope(|s| {
+            for _ in 0..THREADS {
+                s.spawn(|| {
+                    for _ in 0..N {
+                        let mut a = pool.take_scoped();
+                        a.push(1);
+                        let b = pool.take();
+                        pool.put(b);
+                    }
+                });
+            }
+        });
+        let lens = pool.shard_lens();
+        assert_eq!(lens.len(), 1);
+        assert!((1..=THREADS * 2).contains(&lens[0]), "{lens:?}");
+        assert!(pool.take().is_empty());
+
+        let pool = ArcObjPool::with_mode(Some(2), ArcPoolMode::LockFree, Vec::<u8>::new, |b| {
+            b.clear()
+        });
+        for _ in 0..4 {
+            pool.put(vec![1]);
+        }
+        assert_eq!(pool.shard_lens(), [2]);
+    }
+
+    #[test]
+    fn test_single_thread_reuse() {
+        thread_local! {
+            static ALLOCS: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+        }
+        fn alloc() -> Vec<u8> {
+            ALLOCS.set(ALLOCS.get() + 1);
+            vec![]
+        }
+        const N: usize = 1 << 10;
+
+        let pool = ArcObjPool::new(None, NonZeroUsize::new(4).unwrap(), alloc, |b| b.clear());
+        for _ in 0..N {
+            drop(pool.take_scoped());
+        }
+        assert_eq!(ALLOCS.get(), 1);
+        for _ in 0..N {
+            let a = pool.take();
+            pool.put(a);
+        }
+        assert_eq!(ALLOCS.get(), 1);
+        assert_eq!(pool.shard_lens().iter().sum::<usize>(), 1);
+    }
+
+    /// Four times as many threads as cores hammering a few shards
+    #[test]
+    fn test_oversubscribed() {
+        const SHARDS: NonZeroUsize = NonZeroUsize::new(2).unwrap();
+        const N: usize = 1 << 12;
+
+        let threads = thread::available_parallelism().map_or(1, |n| n.get()) * 4;
+        for mode in [
+            ArcPoolMode::Sharded(SHARDS),
+            ArcPoolMode::HybridSharded(SHARDS),
+        ] {
+            let pool = arc_buf_pool_with_mode::<u8>(Some(N), mode);
+            let start = std::time::Instant::now();
+            thread::scope(|s| {
+                for _ in 0..threads {
+                    s.spawn(|| {
+                        for i in 0..N {
+                            let mut a = pool.take();
+                            assert!(a.is_empty());
+                            a.push(1);
+                            let b = pool.take_scoped();
+                            pool.put(a);
+                            if i % 2 == 0 {
+                                drop(b);
+                            } else {
+                                core::mem::forget(b);
+                            }
+                        }
+                    });
+                }
+            });
+            println!("{mode:?}: {threads} threads: {:?}", start.elapsed());
+            let lens = pool.shard_lens();
+            assert_eq!(lens.len(), SHARDS.get());
+            assert!(lens.iter().sum::<usize>() <= threads * 2, "{lens:?}");
+        }
+    }
+}
+
 #[cfg(feature = "nightly")]
 #[cfg(test)]
 mod benches {
@@ -218,6 +487,22 @@ mod benches {
         });
     }
 
+    #[bench]
+    fn bench_arc_pool_lock_free_scoped(bencher: &mut test::Bencher) {
+        let mut in_use = vec![];
+        let pool = ArcObjPool::with_mode(None, ArcPoolMode::LockFree, Vec::new, |b| b.clear());
+        bencher.iter(|| {
+            for _ in 0..N {
+                let mut buf = pool.take_scoped();
+                buf.push(Data::default());
+                in_use.push(buf);
+            }
+            for _ in 0..N {
+                in_use.pop().unwrap();
+            }
+        });
+    }
+
     #[bench]
     fn bench_arc_pool(bencher: &mut test::Bencher) {
         let mut in_use = vec![];
@@ -6,7 +6,10 @@ use core::{
 };
 use std::sync::Arc;
 
-use crate::ops::{clear::Clear, len::Len};
+use crate::ops::{
+    clear::Clear,
+    len::{items_heap_size, HeapSize, Len},
+};
 
 #[derive(Debug)]
 pub struct StableVec<T, const CHUNK_SIZE: usize> {
@@ -14,8 +17,18 @@ pub struct StableVec<T, const CHUNK_SIZE: usize> {
     size: usize,
 }
 impl<T, const CHUNK_SIZE: usize> StableVec<T, CHUNK_SIZE> {
+    /// Fails to compile if `CHUNK_SIZE` is zero or odd:
+    ///
+    /// ```compile_fail
+    /// use primitive::arena::stable_vec::StableVec;
+    ///
+    /// let _ = StableVec::<u8, 3>::new();
+    /// ```
     pub fn new() -> Self {
-        assert_eq!(CHUNK_SIZE % 2, 0);
+        const {
+            assert!(CHUNK_SIZE != 0);
+            assert!(CHUNK_SIZE.is_multiple_of(2));
+        }
         Self {
             chunks: vec![],
             size: 0,
@@ -25,6 +38,15 @@ impl<T, const CHUNK_SIZE: usize> StableVec<T, CHUNK_SIZE> {
     fn indices(index: usize) -> (usize, usize) {
         (index / CHUNK_SIZE, index % CHUNK_SIZE)
     }
+    /// No reference over the chunk; it would invalidate the pointers handed out to the other entries in it
+    ///
+    /// # Safety
+    ///
+    /// `chunk` exists and `offset` is less than `CHUNK_SIZE`
+    unsafe fn slot(&mut self, chunk: usize, offset: usize) -> *mut T {
+        let chunk = core::ptr::addr_of_mut!(*self.chunks[chunk]);
+        unsafe { chunk.cast::<T>().add(offset) }
+    }
     pub fn push(&mut self, value: T) -> NonNull<T> {
         let (chunk, offset) = Self::indices(self.size);
         self.size += 1;
@@ -32,20 +54,17 @@ impl<T, const CHUNK_SIZE: usize> StableVec<T, CHUNK_SIZE> {
             self.chunks
                 .push(Box::new([const { MaybeUninit::uninit() }; CHUNK_SIZE]));
         }
-        let chunk = &mut self.chunks[chunk];
-        chunk[offset] = MaybeUninit::new(value);
-        let ptr = unsafe { chunk[offset].assume_init_mut() };
-        NonNull::from(ptr)
+        let ptr = unsafe { self.slot(chunk, offset) };
+        unsafe { ptr.write(value) };
+        unsafe { NonNull::new_unchecked(ptr) }
     }
     pub fn pop(&mut self) -> Option<T> {
         if self.size == 0 {
             return None;
         }
-        let (chunk, offset) = Self::indices(self.size);
         self.size -= 1;
-        let chunk = &mut self.chunks[chunk];
-        let item = core::mem::replace(&mut chunk[offset], MaybeUninit::uninit());
-        Some(unsafe { item.assume_init() })
+        let (chunk, offset) = Self::indices(self.size);
+        Some(unsafe { self.slot(chunk, offset).read() })
     }
     pub fn iter(&self) -> impl Iterator<Item = &T> {
         (0..self.size)
@@ -63,6 +82,14 @@ impl<T, const CHUNK_SIZE: usize> Len for StableVec<T, CHUNK_SIZE> {
         self.size
     }
 }
+impl<T: HeapSize, const CHUNK_SIZE: usize> HeapSize for StableVec<T, CHUNK_SIZE> {
+    fn heap_size(&self) -> usize {
+        let chunk = core::mem::size_of::<[MaybeUninit<T>; CHUNK_SIZE]>();
+        self.chunks.capacity() * core::mem::size_of::<Box<[MaybeUninit<T>; CHUNK_SIZE]>>()
+            + self.chunks.len() * chunk
+            + items_heap_size(self.iter())
+    }
+}
 impl<T, const CHUNK_SIZE: usize> Clear for StableVec<T, CHUNK_SIZE> {
     fn clear(&mut self) {
         self.chunks.clear();
@@ -86,6 +113,9 @@ pub struct SafePtrMut16<T, const CHUNK_SIZE: usize> {
     ptr: NonNull<T>,
     _store: StorePtr<T, CHUNK_SIZE>,
 }
+// Unique access to one entry; the last pointer to go drops every entry
+unsafe impl<T: Send, const CHUNK_SIZE: usize> Send for SafePtrMut16<T, CHUNK_SIZE> {}
+unsafe impl<T: Sync, const CHUNK_SIZE: usize> Sync for SafePtrMut16<T, CHUNK_SIZE> {}
 impl<T, const CHUNK_SIZE: usize> SafePtrMut16<T, CHUNK_SIZE> {
     pub fn into_ref(self) -> SafePtr16<T, CHUNK_SIZE> {
         SafePtr16 {
@@ -110,6 +140,9 @@ pub struct SafePtr16<T, const CHUNK_SIZE: usize> {
     ptr: NonNull<T>,
     _store: StorePtr<T, CHUNK_SIZE>,
 }
+// Clones share `&T` across threads
+unsafe impl<T: Send + Sync, const CHUNK_SIZE: usize> Send for SafePtr16<T, CHUNK_SIZE> {}
+unsafe impl<T: Send + Sync, const CHUNK_SIZE: usize> Sync for SafePtr16<T, CHUNK_SIZE> {}
 impl<T, const CHUNK_SIZE: usize> Clone for SafePtr16<T, CHUNK_SIZE> {
     fn clone(&self) -> Self {
         Self {
@@ -129,6 +162,9 @@ pub struct SafePtrMut24<T: 'static> {
     ptr: NonNull<T>,
     _store: Arc<dyn core::any::Any>,
 }
+// Same as `SafePtrMut16`
+unsafe impl<T: Send> Send for SafePtrMut24<T> {}
+unsafe impl<T: Sync> Sync for SafePtrMut24<T> {}
 impl<T> SafePtrMut24<T> {
     pub fn into_ref(self) -> SafePtr24<T> {
         SafePtr24 {
@@ -153,6 +189,9 @@ pub struct SafePtr24<T: 'static> {
     ptr: NonNull<T>,
     _store: Arc<dyn core::any::Any>,
 }
+// Same as `SafePtr16`
+unsafe impl<T: Send + Sync> Send for SafePtr24<T> {}
+unsafe impl<T: Send + Sync> Sync for SafePtr24<T> {}
 impl<T> Clone for SafePtr24<T> {
     fn clone(&self) -> Self {
         Self {
@@ -172,6 +211,8 @@ impl<T> Deref for SafePtr24<T> {
 pub struct SafeStableVec<T, const CHUNK_SIZE: usize> {
     vec: StorePtr<T, CHUNK_SIZE>,
 }
+// Pushes only touch the slots no pointer has been handed out for
+unsafe impl<T: Send, const CHUNK_SIZE: usize> Send for SafeStableVec<T, CHUNK_SIZE> {}
 impl<T, const CHUNK_SIZE: usize> SafeStableVec<T, CHUNK_SIZE> {
     pub fn new() -> Self {
         let vec = Arc::new(UnsafeCell::new(StableVec::<T, CHUNK_SIZE>::new()));
@@ -203,6 +244,20 @@ impl<T, const CHUNK_SIZE: usize> Len for SafeStableVec<T, CHUNK_SIZE> {
 }
 #[cfg(test)]
 #[test]
+fn test_stable_vec_pop() {
+    let mut vec = StableVec::<_, 2>::new();
+    assert_eq!(vec.pop(), None);
+    let ptrs = (0..5).map(|i| vec.push(i.to_string())).collect::<Vec<_>>();
+    assert_eq!(unsafe { ptrs[0].as_ref() }, "0");
+    assert_eq!(vec.pop().as_deref(), Some("4"));
+    assert_eq!(vec.pop().as_deref(), Some("3"));
+    vec.push(String::from("5"));
+    assert_eq!(unsafe { ptrs[2].as_ref() }, "2");
+    assert_eq!(vec.len(), 4);
+    assert_eq!(vec.iter().collect::<Vec<_>>(), ["0", "1", "2", "5"]);
+}
+#[cfg(test)]
+#[test]
 fn test_safe_stable_vec() {
     let mut vec = SafeStableVec::<_, 2>::new();
     let p0 = vec.push24(0);
@@ -223,3 +278,26 @@ fn test_safe_stable_vec() {
     let p1 = p1.clone();
     assert_eq!(*p1, 2);
 }
+#[cfg(test)]
+#[test]
+fn test_safe_ptr_across_threads() {
+    let mut vec = SafeStableVec::<_, 2>::new();
+    let p0 = vec.push16(String::from("a")).into_ref();
+    let mut p1 = vec.push24(String::from("b"));
+    let reader = std::thread::spawn({
+        let p0 = p0.clone();
+        move || p0.len()
+    });
+    let writer = std::thread::spawn(move || {
+        p1.push('c');
+        p1.into_ref()
+    });
+    let pusher = std::thread::spawn(move || {
+        vec.push16(String::from("d"));
+        vec.len()
+    });
+    assert_eq!(reader.join().unwrap(), 1);
+    assert_eq!(*writer.join().unwrap(), "bc");
+    assert_eq!(pusher.join().unwrap(), 3);
+    assert_eq!(*p0, "a");
+}
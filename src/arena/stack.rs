@@ -3,10 +3,13 @@ use core::{
     ops::{Index, IndexMut},
 };
 
-use crate::ops::{
-    len::{Capacity, Full, Len, LenExt},
-    list::{List, ListMut},
-    slice::{AsSlice, AsSliceMut},
+use crate::{
+    error::IndexOutOfBounds,
+    ops::{
+        len::{Capacity, Full, Len, LenExt},
+        list::{List, ListMut},
+        slice::{assume_init_slice, assume_init_slice_mut, AsSlice, AsSliceMut},
+    },
 };
 
 pub trait Stack<T> {
@@ -69,7 +72,11 @@ impl<T> IndexMut<usize> for DynCappedStack<T> {
     }
 }
 impl<T> List<T> for DynCappedStack<T> {}
-impl<T> ListMut<T> for DynCappedStack<T> {}
+impl<T> ListMut<T> for DynCappedStack<T> {
+    fn swap(&mut self, i: usize, j: usize) {
+        self.as_slice_mut().swap(i, j);
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum DynStack<T> {
@@ -137,7 +144,11 @@ impl<T> Len for DynStack<T> {
     }
 }
 impl<T> List<T> for DynStack<T> {}
-impl<T> ListMut<T> for DynStack<T> {}
+impl<T> ListMut<T> for DynStack<T> {
+    fn swap(&mut self, i: usize, j: usize) {
+        self.as_slice_mut().swap(i, j);
+    }
+}
 
 #[derive(Debug)]
 pub struct StaticStack<T, const N: usize> {
@@ -145,6 +156,7 @@ pub struct StaticStack<T, const N: usize> {
     len: usize,
 }
 impl<T, const N: usize> StaticStack<T, N> {
+    pub const CAPACITY: usize = N;
     #[must_use]
     pub const fn new() -> Self {
         Self {
@@ -152,27 +164,62 @@ impl<T, const N: usize> StaticStack<T, N> {
             len: 0,
         }
     }
+    /// # Panic
+    ///
+    /// `index` is not less than [`Len::len()`]
     pub fn swap_remove(&mut self, index: usize) -> T {
         assert!(index < self.len());
+        match self.try_swap_remove(index) {
+            Ok(removed) => removed,
+            Err(_) => unreachable!(),
+        }
+    }
+    pub fn try_swap_remove(&mut self, index: usize) -> Result<T, IndexOutOfBounds> {
+        self.check_index(index, self.len())?;
         let removed = core::mem::replace(&mut self.array[index], MaybeUninit::uninit());
         let last = core::mem::replace(&mut self.array[self.len - 1], MaybeUninit::uninit());
         self.array[index] = last;
         self.len -= 1;
-        unsafe { removed.assume_init() }
+        Ok(unsafe { removed.assume_init() })
     }
+    /// # Panic
+    ///
+    /// `index` is not less than [`Len::len()`]
     pub fn remove(&mut self, index: usize) -> T {
         assert!(index < self.len());
+        match self.try_remove(index) {
+            Ok(removed) => removed,
+            Err(_) => unreachable!(),
+        }
+    }
+    pub fn try_remove(&mut self, index: usize) -> Result<T, IndexOutOfBounds> {
+        self.check_index(index, self.len())?;
         let removed = core::mem::replace(&mut self.array[index], MaybeUninit::uninit());
         for i in index..self.len - 1 {
             let next = core::mem::replace(&mut self.array[i + 1], MaybeUninit::uninit());
             self.array[i] = next;
         }
         self.len -= 1;
-        unsafe { removed.assume_init() }
+        Ok(unsafe { removed.assume_init() })
     }
+    /// Return the last item pushed out if full
+    ///
+    /// # Panic
+    ///
+    /// `index` is greater than [`Len::len()`] or not less than [`Capacity::capacity()`]
     pub fn insert(&mut self, index: usize, value: T) -> Option<T> {
         assert!(index <= self.len());
         assert!(index < self.capacity());
+        match self.try_insert(index, value) {
+            Ok(last) => last,
+            Err(_) => unreachable!(),
+        }
+    }
+    pub fn try_insert(&mut self, index: usize, value: T) -> Result<Option<T>, IndexOutOfBounds<T>> {
+        let end = (self.len() + 1).min(self.capacity());
+        if let Err(e) = self.check_index(index, end) {
+            return Err(e.with_value(value));
+        }
         let last = if self.is_full() {
             let last = core::mem::replace(&mut self.array[self.len - 1], MaybeUninit::uninit());
             self.len -= 1;
@@ -186,7 +233,13 @@ impl<T, const N: usize> StaticStack<T, N> {
         }
         self.array[index] = MaybeUninit::new(value);
         self.len += 1;
-        last
+        Ok(last)
+    }
+    fn check_index(&self, index: usize, end: usize) -> Result<(), IndexOutOfBounds> {
+        if end <= index {
+            return Err(IndexOutOfBounds::new(index, end));
+        }
+        Ok(())
     }
 }
 #[cfg(test)]
@@ -196,6 +249,7 @@ fn test_static_stack() {
     assert_eq!(s.push(2).unwrap(), 2);
 
     let mut s: StaticStack<usize, 5> = StaticStack::new();
+    assert_eq!(s.capacity(), StaticStack::<usize, 5>::CAPACITY);
     assert_eq!(s.as_slice(), []);
     s.push(3);
     assert_eq!(s.as_slice(), [3]);
@@ -214,6 +268,59 @@ fn test_static_stack() {
     s.swap_remove(0);
     assert_eq!(s.as_slice(), [5, 3, 4]);
 }
+#[cfg(test)]
+#[test]
+fn test_static_stack_try() {
+    let mut s: StaticStack<usize, 3> = StaticStack::new();
+    assert_eq!(
+        s.try_insert(1, 7),
+        Err(IndexOutOfBounds {
+            index: 1,
+            end: 1,
+            value: 7
+        })
+    );
+    assert_eq!(s.try_remove(0), Err(IndexOutOfBounds::new(0, 0)));
+    assert_eq!(s.try_swap_remove(0), Err(IndexOutOfBounds::new(0, 0)));
+    for i in 0..3 {
+        assert_eq!(s.try_insert(i, i), Ok(None));
+    }
+    // Full: the last slot is the end
+    assert_eq!(s.try_insert(3, 3).unwrap_err().end, 3);
+    assert_eq!(s.try_insert(2, 9), Ok(Some(2)));
+    assert_eq!(s.try_remove(3), Err(IndexOutOfBounds::new(3, 3)));
+    assert_eq!(s.try_swap_remove(0), Ok(0));
+    assert_eq!(s.as_slice(), [9, 1]);
+
+    let mut empty: StaticStack<usize, 0> = StaticStack::new();
+    assert_eq!(empty.try_insert(0, 1).unwrap_err().end, 0);
+}
+#[cfg(test)]
+#[test]
+#[should_panic(expected = "assertion failed: index <= self.len()")]
+fn test_static_stack_insert_panic() {
+    let mut s: StaticStack<usize, 3> = StaticStack::new();
+    s.insert(2, 0);
+}
+#[cfg(test)]
+#[test]
+fn test_static_stack_zst() {
+    let mut s: StaticStack<(), 3> = StaticStack::new();
+    assert_eq!(s.capacity(), 3);
+    assert_eq!(s.pop(), None);
+    for _ in 0..3 {
+        assert!(s.push(()).is_none());
+    }
+    assert!(s.is_full());
+    assert_eq!(s.push(()), Some(()));
+    assert_eq!(s.as_slice(), [(); 3]);
+    assert_eq!(s.swap_remove(1), ());
+    assert_eq!(s.len(), 2);
+    assert_eq!(s.pop(), Some(()));
+    assert_eq!(s.pop(), Some(()));
+    assert_eq!(s.pop(), None);
+    assert!(s.is_empty());
+}
 impl<T, const N: usize> Stack<T> for StaticStack<T, N> {
     fn push(&mut self, obj: T) -> Option<T> {
         if self.is_full() {
@@ -244,12 +351,12 @@ impl<T, const N: usize> Capacity for StaticStack<T, N> {
 }
 impl<T, const N: usize> AsSlice<T> for StaticStack<T, N> {
     fn as_slice(&self) -> &[T] {
-        unsafe { core::mem::transmute(&self.array[..self.len]) }
+        unsafe { assume_init_slice(&self.array[..self.len]) }
     }
 }
 impl<T, const N: usize> AsSliceMut<T> for StaticStack<T, N> {
     fn as_slice_mut(&mut self) -> &mut [T] {
-        unsafe { core::mem::transmute(&mut self.array[..self.len]) }
+        unsafe { assume_init_slice_mut(&mut self.array[..self.len]) }
     }
 }
 impl<T, const N: usize> Index<usize> for StaticStack<T, N> {
@@ -278,7 +385,11 @@ impl<T: Clone, const N: usize> Clone for StaticStack<T, N> {
     }
 }
 impl<T, const N: usize> List<T> for StaticStack<T, N> {}
-impl<T, const N: usize> ListMut<T> for StaticStack<T, N> {}
+impl<T, const N: usize> ListMut<T> for StaticStack<T, N> {
+    fn swap(&mut self, i: usize, j: usize) {
+        self.as_slice_mut().swap(i, j);
+    }
+}
 impl<T, const N: usize> Drop for StaticStack<T, N> {
     fn drop(&mut self) {
         for i in 0..self.len {
@@ -293,6 +404,7 @@ pub struct StaticRevStack<T, const N: usize> {
     array: [MaybeUninit<T>; N],
 }
 impl<T, const N: usize> StaticRevStack<T, N> {
+    pub const CAPACITY: usize = N;
     #[must_use]
     pub const fn new() -> Self {
         Self {
@@ -368,6 +480,13 @@ fn test_static_rev_stack() {
     assert_eq!(s.as_slice(), [1, 2, 3, 4]);
     s.remove(1);
     assert_eq!(s.as_slice(), [1, 3, 4]);
+    assert_eq!(s.pop(), Some(4));
+    assert_eq!(s.push(5), None);
+    assert_eq!(s.as_slice(), [1, 3, 5]);
+    assert_eq!(s.pop(), Some(5));
+    assert_eq!(s.pop(), Some(3));
+    assert_eq!(s.pop(), Some(1));
+    assert_eq!(s.pop(), None);
 }
 impl<T, const N: usize> Stack<T> for StaticRevStack<T, N> {
     fn push(&mut self, obj: T) -> Option<T> {
@@ -381,7 +500,7 @@ impl<T, const N: usize> Stack<T> for StaticRevStack<T, N> {
         if self.is_empty() {
             return None;
         }
-        Some(self.remove(self.len()))
+        Some(self.remove(self.len() - 1))
     }
 }
 impl<T, const N: usize> Len for StaticRevStack<T, N> {
@@ -396,13 +515,13 @@ impl<T, const N: usize> Capacity for StaticRevStack<T, N> {
 }
 impl<T, const N: usize> AsSlice<T> for StaticRevStack<T, N> {
     fn as_slice(&self) -> &[T] {
-        unsafe { core::mem::transmute(&self.array[self.start()..]) }
+        unsafe { assume_init_slice(&self.array[self.start()..]) }
     }
 }
 impl<T, const N: usize> AsSliceMut<T> for StaticRevStack<T, N> {
     fn as_slice_mut(&mut self) -> &mut [T] {
         let start = self.start();
-        unsafe { core::mem::transmute(&mut self.array[start..]) }
+        unsafe { assume_init_slice_mut(&mut self.array[start..]) }
     }
 }
 impl<T, const N: usize> Index<usize> for StaticRevStack<T, N> {
@@ -431,7 +550,11 @@ impl<T: Clone, const N: usize> Clone for StaticRevStack<T, N> {
     }
 }
 impl<T, const N: usize> List<T> for StaticRevStack<T, N> {}
-impl<T, const N: usize> ListMut<T> for StaticRevStack<T, N> {}
+impl<T, const N: usize> ListMut<T> for StaticRevStack<T, N> {
+    fn swap(&mut self, i: usize, j: usize) {
+        self.as_slice_mut().swap(i, j);
+    }
+}
 impl<T, const N: usize> Drop for StaticRevStack<T, N> {
     fn drop(&mut self) {
         for i in self.start()..self.array.len() {
@@ -0,0 +1,46 @@
+use thiserror::Error;
+
+pub use crate::{
+    io::{codec::DecodeFloatError, inner_buf::NotEnoughBytes},
+    map::weak_lru::WeakLruConfigError,
+    ops::{float::OutOfRange, non_max::IsMax, range::SpanInsertError},
+    queue::{grow_queue::WouldExceed, req_queue::PushError},
+    sync::{
+        mcast::LayoutError,
+        mpsc_ring::{Empty, Full},
+    },
+};
+
+/// No slot left; the rejected item is handed back
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("out of buffer space")]
+pub struct QueueFull<T = ()>(pub T);
+
+/// `index` is not in `0..end`; the rejected item is handed back
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("index {index} is out of bounds 0..{end}")]
+pub struct IndexOutOfBounds<T = ()> {
+    pub index: usize,
+    pub end: usize,
+    pub value: T,
+}
+impl IndexOutOfBounds {
+    #[must_use]
+    pub const fn new(index: usize, end: usize) -> Self {
+        Self {
+            index,
+            end,
+            value: (),
+        }
+    }
+}
+impl<T> IndexOutOfBounds<T> {
+    #[must_use]
+    pub fn with_value<U>(self, value: U) -> IndexOutOfBounds<U> {
+        IndexOutOfBounds {
+            index: self.index,
+            end: self.end,
+            value,
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Fixed-width big-endian encoding
+
+use thiserror::Error;
+
+use crate::ops::{
+    float::{OutOfRange, UnitR},
+    unsigned::{U24, U40, U48, U56},
+};
+
+use super::inner_buf::{self, InnerBuf, NotEnoughBytes};
+
+macro_rules! define_unsigned_codec {
+    ($ty: ident, $primitive: ident, $bytes: expr, $put: ident, $get: ident, $get_buf: ident) => {
+        pub fn $put(buf: &mut impl Extend<u8>, value: $ty) {
+            let bytes = $primitive::from(value).to_be_bytes();
+            buf.extend(bytes[bytes.len() - $bytes..].iter().copied());
+        }
+        pub fn $get(bytes: &mut &[u8]) -> Result<$ty, NotEnoughBytes> {
+            let array = inner_buf::read_array::<$bytes>(bytes)?;
+            Ok(decode_unsigned!($ty, $primitive, array))
+        }
+        pub fn $get_buf(buf: &mut InnerBuf, additional: &mut &[u8]) -> Result<$ty, NotEnoughBytes> {
+            let array = buf.read_array::<$bytes>(additional)?;
+            Ok(decode_unsigned!($ty, $primitive, array))
+        }
+    };
+}
+macro_rules! decode_unsigned {
+    ($ty: ident, $primitive: ident, $array: expr) => {{
+        let mut bytes = [0; core::mem::size_of::<$primitive>()];
+        let start = bytes.len() - $array.len();
+        bytes[start..].copy_from_slice(&$array);
+        $ty::new($primitive::from_be_bytes(bytes)).unwrap()
+    }};
+}
+define_unsigned_codec!(U24, u32, 3, put_u24_be, get_u24_be, get_u24_be_buf);
+define_unsigned_codec!(U40, u64, 5, put_u40_be, get_u40_be, get_u40_be_buf);
+define_unsigned_codec!(U48, u64, 6, put_u48_be, get_u48_be, get_u48_be_buf);
+define_unsigned_codec!(U56, u64, 7, put_u56_be, get_u56_be, get_u56_be_buf);
+
+pub fn put_unit_f64(buf: &mut impl Extend<u8>, value: UnitR<f64>) {
+    buf.extend(value.get().to_be_bytes());
+}
+pub fn get_unit_f64(bytes: &mut &[u8]) -> Result<UnitR<f64>, DecodeFloatError> {
+    let array = inner_buf::copy_array::<8>(bytes)?;
+    let value = UnitR::try_from(f64::from_be_bytes(array))?;
+    inner_buf::advance(bytes, array.len());
+    Ok(value)
+}
+/// Nothing is consumed on error
+pub fn get_unit_f64_buf(
+    buf: &mut InnerBuf,
+    additional: &mut &[u8],
+) -> Result<UnitR<f64>, DecodeFloatError> {
+    let array = buf.copy_array::<8>(additional)?;
+    let value = UnitR::try_from(f64::from_be_bytes(array))?;
+    buf.advance(array.len(), additional);
+    Ok(value)
+}
+#[derive(Debug, Clone, Error)]
+pub enum DecodeFloatError {
+    #[error("{0}")]
+    NotEnoughBytes(#[from] NotEnoughBytes),
+    #[error("{0}")]
+    OutOfRange(#[from] OutOfRange<f64>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! test_round_trip {
+        ($ty: ident, $bytes: expr, $put: ident, $get: ident, $get_buf: ident) => {
+            for value in [$ty::MIN, $ty::MAX] {
+                let mut encoded = vec![];
+                $put(&mut encoded, value);
+                assert_eq!(encoded.len(), $bytes);
+                encoded.push(0xff);
+                let mut bytes = &encoded[..];
+                assert_eq!($get(&mut bytes).unwrap(), value);
+                assert_eq!(bytes, [0xff]);
+
+                let mut buf = InnerBuf::new();
+                buf.batch_enqueue(&encoded[..1]);
+                let mut additional = &encoded[1..];
+                assert_eq!($get_buf(&mut buf, &mut additional).unwrap(), value);
+                assert_eq!(additional, [0xff]);
+
+                let mut truncated = &encoded[..$bytes - 1];
+                assert!($get(&mut truncated).is_err());
+                assert_eq!(truncated.len(), $bytes - 1);
+                let mut buf = InnerBuf::new();
+                let mut additional = &encoded[..$bytes - 1];
+                assert!($get_buf(&mut buf, &mut additional).is_err());
+                assert_eq!(additional.len(), $bytes - 1);
+            }
+        };
+    }
+
+    #[test]
+    fn test_unsigned_round_trip() {
+        test_round_trip!(U24, 3, put_u24_be, get_u24_be, get_u24_be_buf);
+        test_round_trip!(U40, 5, put_u40_be, get_u40_be, get_u40_be_buf);
+        test_round_trip!(U48, 6, put_u48_be, get_u48_be, get_u48_be_buf);
+        test_round_trip!(U56, 7, put_u56_be, get_u56_be, get_u56_be_buf);
+    }
+
+    #[test]
+    fn test_big_endian() {
+        let mut encoded = vec![];
+        put_u24_be(&mut encoded, U24::new(0x010203).unwrap());
+        put_u48_be(&mut encoded, U48::new(0x0a0b0c0d0e0f).unwrap());
+        assert_eq!(encoded, [1, 2, 3, 0xa, 0xb, 0xc, 0xd, 0xe, 0xf]);
+    }
+
+    #[test]
+    fn test_unit_f64() {
+        for value in [0., 0.25, 1.] {
+            let value = UnitR::new(value).unwrap();
+            let mut encoded = vec![];
+            put_unit_f64(&mut encoded, value);
+            let mut bytes = &encoded[..];
+            assert_eq!(get_unit_f64(&mut bytes).unwrap(), value);
+            assert!(bytes.is_empty());
+
+            let mut buf = InnerBuf::new();
+            let mut additional = &encoded[..];
+            assert_eq!(get_unit_f64_buf(&mut buf, &mut additional).unwrap(), value);
+            assert!(additional.is_empty());
+        }
+        for invalid in [-0.5, 1.5, f64::NAN] {
+            let encoded = invalid.to_be_bytes();
+            let mut bytes = &encoded[..];
+            assert!(matches!(
+                get_unit_f64(&mut bytes),
+                Err(DecodeFloatError::OutOfRange(_))
+            ));
+            assert_eq!(bytes.len(), 8);
+        }
+        let mut bytes = &[0; 7][..];
+        assert!(matches!(
+            get_unit_f64(&mut bytes),
+            Err(DecodeFloatError::NotEnoughBytes(_))
+        ));
+    }
+}
@@ -1,26 +1,93 @@
+use core::num::NonZeroUsize;
+use std::io::{IoSlice, IoSliceMut};
+
 use thiserror::Error;
 
-use crate::{ops::len::Len, queue::grow_queue::GrowQueue};
+use crate::{
+    ops::len::Len,
+    queue::grow_queue::{GrowQueue, WouldExceed, START_UP_SIZE},
+};
 
 /// [I/O-Free (Sans-I/O)](https://sans-io.readthedocs.io/how-to-sans-io.html)
 #[derive(Debug)]
 pub struct InnerBuf {
     buf: GrowQueue<u8>,
+    /// Bytes ever consumed from both the buffer and the `additional` slices
+    consumed: u64,
 }
 impl InnerBuf {
     #[must_use]
     pub const fn new() -> Self {
         Self {
             buf: GrowQueue::new(),
+            consumed: 0,
+        }
+    }
+    /// Never buffer more than `max` bytes so a peer cannot grow the memory without bound
+    #[must_use]
+    pub const fn with_max_buffered(max: NonZeroUsize) -> Self {
+        Self {
+            buf: GrowQueue::with_limits(START_UP_SIZE, Some(max)),
+            consumed: 0,
+        }
+    }
+    #[must_use]
+    pub fn mark(&self) -> BufMark {
+        BufMark {
+            consumed: self.consumed,
+        }
+    }
+    #[must_use]
+    pub fn consumed_since(&self, mark: BufMark) -> usize {
+        usize::try_from(self.consumed - mark.consumed).unwrap()
+    }
+    /// Read without consuming until [`BufTxn::commit()`]
+    #[must_use]
+    pub fn begin(&mut self) -> BufTxn<'_> {
+        BufTxn {
+            buf: self,
+            pending: 0,
         }
     }
+    /// # Panic
+    ///
+    /// The bytes would exceed the max of [`Self::with_max_buffered()`]
     pub fn batch_enqueue(&mut self, bytes: &[u8]) {
         self.buf.batch_enqueue(bytes);
     }
+    /// Buffer none of the bytes if they would exceed the max of [`Self::with_max_buffered()`]
+    pub fn try_batch_enqueue(&mut self, bytes: &[u8]) -> Result<(), WouldExceed> {
+        self.buf.try_batch_enqueue(bytes)
+    }
+    /// # Panic
+    ///
+    /// The bytes would exceed the max of [`Self::with_max_buffered()`]
+    pub fn batch_enqueue_vectored(&mut self, bufs: &[IoSlice<'_>]) {
+        self.buf.batch_enqueue_vectored(bufs);
+    }
+    /// Buffer none of the bytes if they would exceed the max of [`Self::with_max_buffered()`]
+    pub fn try_batch_enqueue_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<(), WouldExceed> {
+        self.buf.try_batch_enqueue_vectored(bufs)
+    }
+    /// Append the buffered bytes as at most two slices for a vectored write
+    ///
+    /// [`Self::advance()`] by what the write took.
+    pub fn as_io_slices<'a>(&'a self, out: &mut Vec<IoSlice<'a>>) {
+        self.buf.as_io_slices(out);
+    }
     #[must_use]
     pub fn available(&self, additional: usize) -> usize {
         self.buf.len() + additional
     }
+    /// The most bytes ever buffered at once
+    #[must_use]
+    pub const fn high_water_mark(&self) -> usize {
+        self.buf.high_water_mark()
+    }
+    /// Release the memory of a past burst; see [`GrowQueue::shrink_to()`]
+    pub fn shrink_to(&mut self, target: usize) {
+        self.buf.shrink_to(target);
+    }
     pub fn read_array<const N: usize>(
         &mut self,
         additional: &mut &[u8],
@@ -38,23 +105,61 @@ impl InnerBuf {
         Ok(array)
     }
     pub fn copy_exact(&mut self, buf: &mut [u8], additional: &[u8]) -> Result<(), NotEnoughBytes> {
-        if self.available(additional.len()) < buf.len() {
+        self.copy_exact_at(0, buf, additional)
+    }
+    /// Consume as many bytes as fit in `bufs` and return the count
+    pub fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>], additional: &mut &[u8]) -> usize {
+        let n = self.copy_into_vectored(bufs, additional);
+        self.advance(n, additional);
+        n
+    }
+    /// Copy as many bytes as fit in `bufs` without consuming them and return the count
+    pub fn copy_into_vectored(&mut self, bufs: &mut [IoSliceMut<'_>], additional: &[u8]) -> usize {
+        let (a, b) = self.buf.as_slices().unwrap_or((&[], None));
+        let mut srcs = [a, b.unwrap_or(&[]), additional].into_iter();
+        let mut src: &[u8] = &[];
+        let mut copied = 0;
+        for buf in bufs.iter_mut() {
+            let mut buf = &mut buf[..];
+            while !buf.is_empty() {
+                if src.is_empty() {
+                    match srcs.next() {
+                        Some(next) => src = next,
+                        None => return copied,
+                    }
+                    continue;
+                }
+                let len = src.len().min(buf.len());
+                buf[..len].copy_from_slice(&src[..len]);
+                src = &src[len..];
+                buf = &mut buf[len..];
+                copied += len;
+            }
+        }
+        copied
+    }
+    /// Copy starting from `offset` bytes after the read position
+    fn copy_exact_at(
+        &self,
+        offset: usize,
+        buf: &mut [u8],
+        additional: &[u8],
+    ) -> Result<(), NotEnoughBytes> {
+        if self.available(additional.len()) < offset + buf.len() {
             return Err(NotEnoughBytes);
         }
         let (a, b) = self.buf.as_slices().unwrap_or((&[], None));
         let b = b.unwrap_or(&[]);
-        let mut remaining = buf.len();
-        let a_len = a.len().min(remaining);
-        remaining -= a_len;
-        let b_len = b.len().min(remaining);
-        remaining -= b_len;
-        let c_len = additional.len().min(remaining);
+        let mut skip = offset;
         let mut start = 0;
-        buf[start..start + a_len].copy_from_slice(&a[..a_len]);
-        start += a_len;
-        buf[start..start + b_len].copy_from_slice(&b[..b_len]);
-        start += b_len;
-        buf[start..].copy_from_slice(&additional[..c_len]);
+        for src in [a, b, additional] {
+            let skipped = skip.min(src.len());
+            skip -= skipped;
+            let src = &src[skipped..];
+            let len = src.len().min(buf.len() - start);
+            buf[start..start + len].copy_from_slice(&src[..len]);
+            start += len;
+        }
         Ok(())
     }
     /// # Panic
@@ -68,6 +173,7 @@ impl InnerBuf {
         self.buf.batch_dequeue(buf_len);
         let slice_len = additional.len().min(remaining);
         *additional = &additional[slice_len..];
+        self.consumed += n as u64;
     }
 }
 impl Default for InnerBuf {
@@ -79,6 +185,62 @@ impl Default for InnerBuf {
 #[error("not enough bytes")]
 pub struct NotEnoughBytes;
 
+/// A read position of [`InnerBuf`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BufMark {
+    consumed: u64,
+}
+
+/// Reads on [`InnerBuf`] that only take effect on [`Self::commit()`]
+///
+/// `additional` must be the same slice on every call within the transaction, including the one passed to [`Self::commit()`].
+/// It is only advanced on [`Self::commit()`].
+///
+/// Dropping the transaction without committing rolls back all the reads.
+#[derive(Debug)]
+pub struct BufTxn<'a> {
+    buf: &'a mut InnerBuf,
+    pending: usize,
+}
+impl BufTxn<'_> {
+    /// Bytes read so far in this transaction
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+    #[must_use]
+    pub fn available(&self, additional: usize) -> usize {
+        self.buf.available(additional) - self.pending
+    }
+    pub fn read_array<const N: usize>(
+        &mut self,
+        additional: &[u8],
+    ) -> Result<[u8; N], NotEnoughBytes> {
+        let array = self.copy_array(additional)?;
+        self.advance(N, additional);
+        Ok(array)
+    }
+    pub fn copy_array<const N: usize>(&self, additional: &[u8]) -> Result<[u8; N], NotEnoughBytes> {
+        let mut array = [0; N];
+        self.copy_exact(&mut array, additional)?;
+        Ok(array)
+    }
+    pub fn copy_exact(&self, buf: &mut [u8], additional: &[u8]) -> Result<(), NotEnoughBytes> {
+        self.buf.copy_exact_at(self.pending, buf, additional)
+    }
+    /// # Panic
+    ///
+    /// `n` is more than `self.available(additional)`
+    pub fn advance(&mut self, n: usize, additional: &[u8]) {
+        assert!(n <= self.available(additional.len()));
+        self.pending += n;
+    }
+    /// Consume all the bytes read in this transaction
+    pub fn commit(self, additional: &mut &[u8]) {
+        self.buf.advance(self.pending, additional);
+    }
+}
+
 pub fn read_array<const N: usize>(bytes: &mut &[u8]) -> Result<[u8; N], NotEnoughBytes> {
     let array = copy_array(bytes)?;
     advance(bytes, N);
@@ -100,7 +262,7 @@ pub fn copy_exact(buf: &mut [u8], bytes: &[u8]) -> Result<(), NotEnoughBytes> {
 ///
 /// `n` is more than `buf.len()`
 pub fn advance(buf: &mut &[u8], n: usize) {
-    assert!(buf.len() <= n);
+    assert!(n <= buf.len());
     *buf = &buf[n..];
 }
 
@@ -110,7 +272,11 @@ pub mod tests {
     use std::time::Instant;
 
     use crate::{
-        ops::unit::{DurationExt, HumanDuration},
+        io::tests::writev,
+        ops::{
+            len::Capacity,
+            unit::{DurationExt, HumanDuration},
+        },
         time::{stopwatch::Stopwatch, timer::Timer},
     };
 
@@ -150,6 +316,173 @@ pub mod tests {
         }
     }
 
+    /// Length-prefixed messages
+    fn decode(txn: &mut BufTxn<'_>, additional: &[u8]) -> Result<Vec<u8>, NotEnoughBytes> {
+        let [len] = txn.read_array(additional)?;
+        let mut msg = vec![0; len as usize];
+        txn.copy_exact(&mut msg, additional)?;
+        txn.advance(msg.len(), additional);
+        Ok(msg)
+    }
+
+    #[test]
+    fn test_txn_rollback() {
+        let mut buf = InnerBuf::new();
+        let mark = buf.mark();
+        buf.batch_enqueue(&[3, 1]);
+        {
+            let mut txn = buf.begin();
+            assert!(decode(&mut txn, &[]).is_err());
+            assert_eq!(txn.pending(), 1);
+        }
+        assert_eq!(buf.available(0), 2);
+        assert_eq!(buf.consumed_since(mark), 0);
+        buf.batch_enqueue(&[2, 3, 1]);
+        let mut txn = buf.begin();
+        assert_eq!(decode(&mut txn, &[]).unwrap(), [1, 2, 3]);
+        txn.commit(&mut &[][..]);
+        assert_eq!(buf.available(0), 1);
+        assert_eq!(buf.consumed_since(mark), 4);
+        {
+            let mut txn = buf.begin();
+            assert!(decode(&mut txn, &[]).is_err());
+        }
+        assert_eq!(buf.available(0), 1);
+        assert_eq!(buf.consumed_since(mark), 4);
+    }
+
+    #[test]
+    fn test_txn_additional() {
+        let mut buf = InnerBuf::new();
+        buf.batch_enqueue(&[2]);
+        let mark = buf.mark();
+        let additional = &[5][..];
+        {
+            let mut txn = buf.begin();
+            assert!(decode(&mut txn, additional).is_err());
+        }
+        assert_eq!(additional, [5]);
+        buf.batch_enqueue(additional);
+        let mut additional = &[6, 1, 9][..];
+        let mut txn = buf.begin();
+        assert_eq!(decode(&mut txn, additional).unwrap(), [5, 6]);
+        txn.commit(&mut additional);
+        assert_eq!(additional, [1, 9]);
+        assert_eq!(buf.available(0), 0);
+        let mut txn = buf.begin();
+        assert_eq!(decode(&mut txn, additional).unwrap(), [9]);
+        assert_eq!(txn.available(additional.len()), 0);
+        txn.commit(&mut additional);
+        assert!(additional.is_empty());
+        assert_eq!(buf.consumed_since(mark), 5);
+
+        buf.batch_enqueue(&[1]);
+        let mut additional = &[4, 2, 8][..];
+        let mut txn = buf.begin();
+        assert_eq!(decode(&mut txn, additional).unwrap(), [4]);
+        txn.commit(&mut additional);
+        assert_eq!(additional, [2, 8]);
+        assert_eq!(buf.consumed_since(mark), 7);
+    }
+
+    #[test]
+    fn test_max_buffered() {
+        let max = NonZeroUsize::new(4).unwrap();
+        let mut buf = InnerBuf::with_max_buffered(max);
+        buf.try_batch_enqueue(&[2, 7]).unwrap();
+        assert_eq!(
+            buf.try_batch_enqueue(&[8, 9, 1]).unwrap_err(),
+            WouldExceed { len: 5, max }
+        );
+        assert_eq!(buf.available(0), 2);
+        assert!(decode(&mut buf.begin(), &[]).is_err());
+        buf.try_batch_enqueue(&[8, 1]).unwrap();
+        let mut txn = buf.begin();
+        assert_eq!(decode(&mut txn, &[]).unwrap(), [7, 8]);
+        txn.commit(&mut &[][..]);
+        // Room again after consuming
+        buf.try_batch_enqueue(&[5, 6, 7]).unwrap();
+        assert_eq!(buf.available(0), 4);
+    }
+
+    #[test]
+    fn test_shrink_after_burst() {
+        let mut buf = InnerBuf::new();
+        buf.batch_enqueue(&[7; 1024]);
+        buf.batch_enqueue(&[1, 2]);
+        buf.advance(1024, &mut &[][..]);
+        assert_eq!(buf.high_water_mark(), 1026);
+        buf.shrink_to(0);
+        assert_eq!(buf.high_water_mark(), 1026);
+        assert_eq!(buf.buf.capacity(), START_UP_SIZE);
+        assert_eq!(buf.copy_array(&[]).unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn test_io_slices() {
+        let mut buf = InnerBuf::new();
+        let mark = buf.mark();
+        let mut out = vec![];
+        buf.as_io_slices(&mut out);
+        assert!(out.is_empty());
+
+        // Wrap the ring around
+        buf.batch_enqueue(&[0; START_UP_SIZE - 3]);
+        buf.advance(START_UP_SIZE - 3, &mut &[][..]);
+        let bytes = (1..=10).collect::<Vec<u8>>();
+        buf.batch_enqueue_vectored(&[IoSlice::new(&bytes[..4]), IoSlice::new(&bytes[4..])]);
+        let mut out = vec![];
+        buf.as_io_slices(&mut out);
+        assert_eq!(out.len(), 2);
+        assert_eq!(writev(&out), bytes);
+
+        // A vectored write that only takes part of the bytes
+        let written = out[0].len() + 1;
+        drop(out);
+        buf.advance(written, &mut &[][..]);
+        let mut out = vec![];
+        buf.as_io_slices(&mut out);
+        assert_eq!(writev(&out), bytes[written..]);
+        assert_eq!(buf.consumed_since(mark), START_UP_SIZE - 3 + written);
+    }
+
+    #[test]
+    fn test_read_vectored() {
+        let mut buf = InnerBuf::new();
+        buf.batch_enqueue(&[0; START_UP_SIZE - 2]);
+        buf.advance(START_UP_SIZE - 2, &mut &[][..]);
+        buf.batch_enqueue(&[1, 2, 3, 4]);
+        let mark = buf.mark();
+        let mut additional = &[5, 6, 7][..];
+
+        let (mut a, mut b) = ([0; 3], [0; 3]);
+        let mut bufs = [
+            IoSliceMut::new(&mut a),
+            IoSliceMut::new(&mut []),
+            IoSliceMut::new(&mut b),
+        ];
+        assert_eq!(buf.copy_into_vectored(&mut bufs, additional), 6);
+        assert_eq!(buf.consumed_since(mark), 0);
+        assert_eq!(buf.read_vectored(&mut bufs, &mut additional), 6);
+        assert_eq!((a, b), ([1, 2, 3], [4, 5, 6]));
+        assert_eq!(additional, [7]);
+        assert_eq!(buf.consumed_since(mark), 6);
+
+        // Fewer bytes than room
+        let mut c = [0; 4];
+        assert_eq!(
+            buf.read_vectored(&mut [IoSliceMut::new(&mut c)], &mut additional),
+            1
+        );
+        assert_eq!(c, [7, 0, 0, 0]);
+        assert!(additional.is_empty());
+        assert_eq!(
+            buf.read_vectored(&mut [IoSliceMut::new(&mut c)], &mut additional),
+            0
+        );
+        assert_eq!(buf.consumed_since(mark), 7);
+    }
+
     #[test]
     #[ignore]
     fn test_alloc() {
@@ -1,3 +1,18 @@
+pub mod codec;
 pub mod inner_buf;
+pub mod reassembly;
 pub mod ring_seq;
 pub mod token_bucket;
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::IoSlice;
+
+    /// What a vectored write of `bufs` would put on the wire
+    pub fn writev(bufs: &[IoSlice<'_>]) -> Vec<u8> {
+        let mut wire = vec![];
+        let n = std::io::Write::write_vectored(&mut wire, bufs).unwrap();
+        assert_eq!(n, wire.len());
+        wire
+    }
+}
@@ -0,0 +1,255 @@
+use core::num::NonZeroUsize;
+use std::io::IoSliceMut;
+
+use crate::{
+    arena::obj_pool::{buf_pool, ObjPool},
+    queue::{
+        seq_queue::{SeqInsertResult, SeqQueue},
+        waste::RecycleWaste,
+    },
+};
+
+use super::inner_buf::InnerBuf;
+
+/// Puts the datagrams numbered from zero back into one contiguous byte stream
+///
+/// The payloads are copied into pooled buffers, and every buffer the queue drops goes back to the pool.
+///
+/// Backpressure: the datagrams that do not fit in the output stage stay queued until [`Self::read()`] makes room.
+#[derive(Debug)]
+pub struct DatagramReassembler {
+    queue: SeqQueue<u64, Vec<u8>>,
+    pool: ObjPool<Vec<u8>>,
+    out: InnerBuf,
+    max_buffered: NonZeroUsize,
+}
+impl DatagramReassembler {
+    /// - `window`: how far ahead of the next expected datagram one is still kept
+    /// - `max_buffered`: the most contiguous bytes waiting for [`Self::read()`]
+    #[must_use]
+    pub fn new(window: NonZeroUsize, max_buffered: NonZeroUsize) -> Self {
+        let mut queue = SeqQueue::new(window);
+        queue.set_next(0, |_, _| {});
+        Self {
+            queue,
+            pool: buf_pool(Some(window.get())),
+            out: InnerBuf::with_max_buffered(max_buffered),
+            max_buffered,
+        }
+    }
+    /// A `payload` longer than `max_buffered` of [`Self::new()`] is dropped as [`ReassemblyEvent::TooLarge`]
+    pub fn on_datagram(&mut self, seq: u64, payload: &[u8]) -> ReassemblyEvent {
+        if self.max_buffered.get() < payload.len() {
+            return ReassemblyEvent::TooLarge;
+        }
+        let mut buf = self.pool.take();
+        buf.extend_from_slice(payload);
+        let pool = &mut self.pool;
+        let waste = RecycleWaste::new(|buf| pool.put(buf));
+        match self.queue.insert(seq, buf, waste) {
+            SeqInsertResult::Stale | SeqInsertResult::Duplicate => {
+                return ReassemblyEvent::Duplicate
+            }
+            SeqInsertResult::OutOfWindow => return ReassemblyEvent::OutOfWindow,
+            SeqInsertResult::Stalled | SeqInsertResult::InOrder | SeqInsertResult::OutOfOrder => (),
+        }
+        let (datagrams, bytes) = self.deliver();
+        if datagrams == 0 {
+            return ReassemblyEvent::Buffered;
+        }
+        ReassemblyEvent::Delivered { bytes }
+    }
+    /// Consume the contiguous bytes into `out` and return the count
+    pub fn read(&mut self, out: &mut [u8]) -> usize {
+        let mut n = 0;
+        loop {
+            n += self
+                .out
+                .read_vectored(&mut [IoSliceMut::new(&mut out[n..])], &mut &[][..]);
+            // Room might have been made for the datagrams held back
+            let (datagrams, _) = self.deliver();
+            if datagrams == 0 || n == out.len() {
+                return n;
+            }
+        }
+    }
+    /// Bytes ready for [`Self::read()`]
+    #[must_use]
+    pub fn readable(&self) -> usize {
+        self.out.available(0)
+    }
+    /// The sequence number of the next datagram to deliver
+    #[must_use]
+    pub fn next_seq(&self) -> Option<u64> {
+        self.queue.next().copied()
+    }
+    /// Move the contiguous datagrams into the output stage while they fit
+    ///
+    /// Return the number of the datagrams and the bytes moved.
+    fn deliver(&mut self) -> (usize, usize) {
+        let mut datagrams = 0;
+        let mut bytes = 0;
+        while let Some((_, payload)) = self.queue.peek() {
+            if self.out.try_batch_enqueue(payload).is_err() {
+                break;
+            }
+            datagrams += 1;
+            bytes += payload.len();
+            let pool = &mut self.pool;
+            let (_, buf) = self
+                .queue
+                .pop(RecycleWaste::new(|buf| pool.put(buf)))
+                .unwrap();
+            self.pool.put(buf);
+        }
+        (datagrams, bytes)
+    }
+}
+
+/// What [`DatagramReassembler::on_datagram()`] did with a datagram
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReassemblyEvent {
+    /// The datagram and the ones queued right after it became readable
+    Delivered { bytes: usize },
+    /// Kept until the datagrams before it arrive or the output stage has room
+    Buffered,
+    /// Already delivered or queued
+    Duplicate,
+    /// Too far ahead of the next expected datagram
+    OutOfWindow,
+    /// Longer than the output stage could ever hold; the stream cannot get past it
+    TooLarge,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::rng::{RngLite, SplitMix64};
+
+    use super::*;
+
+    const MTU: usize = 16;
+
+    fn datagrams(rng: &mut SplitMix64, count: usize) -> Vec<Vec<u8>> {
+        (0..count)
+            .map(|_| {
+                let mut payload = vec![0; rng.next_usize_below(MTU + 1)];
+                rng.fill_bytes(&mut payload);
+                payload
+            })
+            .collect()
+    }
+
+    fn read_all(r: &mut DatagramReassembler, stream: &mut Vec<u8>) {
+        let mut buf = [0; 7];
+        loop {
+            let n = r.read(&mut buf);
+            if n == 0 {
+                return;
+            }
+            stream.extend_from_slice(&buf[..n]);
+        }
+    }
+
+    #[test]
+    fn test_in_order() {
+        let mut r = DatagramReassembler::new(
+            NonZeroUsize::new(4).unwrap(),
+            NonZeroUsize::new(64).unwrap(),
+        );
+        assert_eq!(
+            r.on_datagram(0, b"hello"),
+            ReassemblyEvent::Delivered { bytes: 5 }
+        );
+        assert_eq!(r.on_datagram(2, b"!"), ReassemblyEvent::Buffered);
+        assert_eq!(r.on_datagram(2, b"!"), ReassemblyEvent::Duplicate);
+        assert_eq!(r.on_datagram(0, b"hello"), ReassemblyEvent::Duplicate);
+        assert_eq!(r.on_datagram(1 << 10, b"?"), ReassemblyEvent::OutOfWindow);
+        assert_eq!(
+            r.on_datagram(1, b" world"),
+            ReassemblyEvent::Delivered { bytes: 7 }
+        );
+        assert_eq!(r.next_seq(), Some(3));
+        assert_eq!(
+            r.on_datagram(3, b""),
+            ReassemblyEvent::Delivered { bytes: 0 }
+        );
+        let mut stream = vec![];
+        read_all(&mut r, &mut stream);
+        assert_eq!(stream, b"hello world!");
+    }
+
+    #[test]
+    fn test_backpressure() {
+        let mut r =
+            DatagramReassembler::new(NonZeroUsize::new(8).unwrap(), NonZeroUsize::new(4).unwrap());
+        assert_eq!(
+            r.on_datagram(0, b"abc"),
+            ReassemblyEvent::Delivered { bytes: 3 }
+        );
+        // No room for it before a read
+        assert_eq!(r.on_datagram(1, b"de"), ReassemblyEvent::Buffered);
+        assert_eq!(r.on_datagram(2, b"fg"), ReassemblyEvent::Buffered);
+        assert_eq!(r.readable(), 3);
+        let mut buf = [0; 16];
+        // Each read makes room for the next held back datagram
+        assert_eq!(r.read(&mut buf), 7);
+        assert_eq!(&buf[..7], b"abcdefg");
+        assert_eq!(r.readable(), 0);
+        assert_eq!(r.next_seq(), Some(3));
+    }
+
+    #[test]
+    fn test_too_large() {
+        let mut r =
+            DatagramReassembler::new(NonZeroUsize::new(4).unwrap(), NonZeroUsize::new(4).unwrap());
+        assert_eq!(r.on_datagram(0, b"abcde"), ReassemblyEvent::TooLarge);
+        assert_eq!(r.on_datagram(1, b"fghij"), ReassemblyEvent::TooLarge);
+        assert_eq!(r.readable(), 0);
+        assert_eq!(r.next_seq(), Some(0));
+        // Neither took up the sequence number
+        assert_eq!(
+            r.on_datagram(0, b"abcd"),
+            ReassemblyEvent::Delivered { bytes: 4 }
+        );
+        assert_eq!(r.on_datagram(1, b"e"), ReassemblyEvent::Buffered);
+    }
+
+    #[test]
+    fn test_lossy_link() {
+        let mut rng = SplitMix64::new(0);
+        for _ in 0..64 {
+            let sent = datagrams(&mut rng, 128);
+            let mut r = DatagramReassembler::new(
+                NonZeroUsize::new(sent.len()).unwrap(),
+                NonZeroUsize::new(MTU * 4).unwrap(),
+            );
+            let mut pending = (0..sent.len()).collect::<Vec<_>>();
+            let mut stream = vec![];
+            while !pending.is_empty() {
+                rng.shuffle(&mut pending);
+                for &seq in &pending {
+                    match rng.next_usize_below(8) {
+                        // Dropped; retransmitted in the next round
+                        0 => continue,
+                        // Duplicated on the way
+                        1 => {
+                            let _ = r.on_datagram(seq as u64, &sent[seq]);
+                        }
+                        _ => (),
+                    }
+                    let event = r.on_datagram(seq as u64, &sent[seq]);
+                    assert_ne!(event, ReassemblyEvent::OutOfWindow);
+                    if rng.next_usize_below(4) == 0 {
+                        read_all(&mut r, &mut stream);
+                    }
+                }
+                read_all(&mut r, &mut stream);
+                // Retransmit everything not yet delivered; the ones still queued come out as duplicates
+                let next = r.next_seq().unwrap() as usize;
+                pending = (next..sent.len()).collect();
+            }
+            assert_eq!(stream, sent.concat());
+            assert_eq!(r.next_seq(), Some(sent.len() as u64));
+        }
+    }
+}
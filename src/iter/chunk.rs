@@ -1,6 +1,6 @@
-use core::mem::MaybeUninit;
+use core::{convert::Infallible, mem::MaybeUninit, ops::ControlFlow};
 
-use crate::ops::slice::dyn_vec_init;
+use crate::ops::slice::{assume_init_slice_mut, dyn_vec_init};
 
 pub trait Chunks: Iterator + Sized {
     fn static_chunks<T, const CHUNK_SIZE: usize>(self, for_each: impl FnMut(&mut [T]))
@@ -17,38 +17,109 @@ pub trait Chunks: Iterator + Sized {
         let mut tray = dyn_vec_init(chunk_size, || MaybeUninit::uninit());
         self.chunks(&mut tray, for_each);
     }
-    fn chunks<T>(mut self, tray: &mut [MaybeUninit<T>], mut for_each: impl FnMut(&mut [T]))
+    fn chunks<T>(self, tray: &mut [MaybeUninit<T>], mut for_each: impl FnMut(&mut [T]))
     where
         Self: Iterator<Item = T>,
     {
-        let mut i = 0;
-        loop {
-            let v = self.next();
-            let is_end = v.is_none();
-            if let Some(v) = v {
-                tray[i] = MaybeUninit::new(v);
-                i += 1;
-                if i < tray.len() {
-                    continue;
-                }
+        let Ok(()) = self.try_chunks(tray, |chunk| {
+            for_each(chunk);
+            Ok::<_, Infallible>(())
+        });
+    }
+    /// [`Self::chunks()`] but stop at the first error
+    ///
+    /// On an error, the failed chunk and the items not yet pulled from the iterator are dropped;
+    /// [`Self::try_chunks_buffered()`] hands them back instead.
+    fn try_chunks<T, E>(
+        mut self,
+        tray: &mut [MaybeUninit<T>],
+        for_each: impl FnMut(&mut [T]) -> Result<(), E>,
+    ) -> Result<(), E>
+    where
+        Self: Iterator<Item = T>,
+    {
+        let Err((e, len)) = fill_chunks(&mut self, tray, for_each) else {
+            return Ok(());
+        };
+        for v in &mut tray[..len] {
+            unsafe { v.assume_init_drop() };
+        }
+        Err(e)
+    }
+    /// [`Self::try_chunks()`] but return the failed chunk followed by the rest of the iterator along with the error
+    ///
+    /// The rest of the iterator is drained on an error.
+    fn try_chunks_buffered<T, E>(
+        mut self,
+        tray: &mut [MaybeUninit<T>],
+        for_each: impl FnMut(&mut [T]) -> Result<(), E>,
+    ) -> Result<(), (E, Vec<T>)>
+    where
+        Self: Iterator<Item = T>,
+    {
+        let Err((e, len)) = fill_chunks(&mut self, tray, for_each) else {
+            return Ok(());
+        };
+        let mut leftover = Vec::with_capacity(len + self.size_hint().0);
+        leftover.extend(tray[..len].iter().map(|v| unsafe { v.assume_init_read() }));
+        leftover.extend(self);
+        Err((e, leftover))
+    }
+    /// [`Self::chunks()`] until `for_each` breaks and return the break value
+    ///
+    /// The rest is dropped as in [`Self::try_chunks()`].
+    fn chunks_until<T, B>(
+        self,
+        tray: &mut [MaybeUninit<T>],
+        mut for_each: impl FnMut(&mut [T]) -> ControlFlow<B>,
+    ) -> Option<B>
+    where
+        Self: Iterator<Item = T>,
+    {
+        self.try_chunks(tray, |chunk| match for_each(chunk) {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(b) => Err(b),
+        })
+        .err()
+    }
+}
+impl<T> Chunks for T where T: Iterator {}
+
+/// Hand the chunks to `for_each` until it fails
+///
+/// On an error, the `len` items of the failed chunk are left initialized at the front of `tray` along with the error.
+fn fill_chunks<T, E>(
+    iter: &mut impl Iterator<Item = T>,
+    tray: &mut [MaybeUninit<T>],
+    mut for_each: impl FnMut(&mut [T]) -> Result<(), E>,
+) -> Result<(), (E, usize)> {
+    let mut i = 0;
+    loop {
+        let v = iter.next();
+        let is_end = v.is_none();
+        if let Some(v) = v {
+            tray[i] = MaybeUninit::new(v);
+            i += 1;
+            if i < tray.len() {
+                continue;
             }
-            if i != 0 {
-                let raw_chunk = &mut tray[..i];
-                let chunk =
-                    unsafe { core::mem::transmute::<&mut [MaybeUninit<T>], &mut [T]>(raw_chunk) };
-                for_each(chunk);
-                for v in raw_chunk {
-                    unsafe { v.assume_init_drop() };
-                }
-                i = 0;
+        }
+        if i != 0 {
+            let raw_chunk = &mut tray[..i];
+            let chunk = unsafe { assume_init_slice_mut(raw_chunk) };
+            if let Err(e) = for_each(chunk) {
+                return Err((e, i));
             }
-            if is_end {
-                break;
+            for v in raw_chunk {
+                unsafe { v.assume_init_drop() };
             }
+            i = 0;
+        }
+        if is_end {
+            return Ok(());
         }
     }
 }
-impl<T> Chunks for T where T: Iterator {}
 #[cfg(test)]
 #[test]
 fn test_chunks() {
@@ -89,3 +160,89 @@ fn test_chunks() {
         assert_eq!(access_times, 1);
     }
 }
+#[cfg(test)]
+#[test]
+fn test_try_chunks() {
+    let mut tray = [const { MaybeUninit::uninit() }; 3];
+    let mut seen = vec![];
+    let res = (0..10)
+        .map(|i| i.to_string())
+        .try_chunks(&mut tray, |chunk| {
+            seen.push(chunk.join(","));
+            if chunk.contains(&String::from("4")) {
+                Err(seen.len())
+            } else {
+                Ok(())
+            }
+        });
+    // Stops at the second chunk
+    assert_eq!(res, Err(2));
+    assert_eq!(seen, ["0,1,2", "3,4,5"]);
+
+    let mut tray = [const { MaybeUninit::uninit() }; 3];
+    let res = (0..10).try_chunks_buffered(&mut tray, |chunk| {
+        if chunk.contains(&7) {
+            Err(chunk[0])
+        } else {
+            Ok(())
+        }
+    });
+    let (e, leftover) = res.unwrap_err();
+    assert_eq!(e, 6);
+    assert_eq!(leftover, [6, 7, 8, 9]);
+
+    // Fails on the last partial chunk
+    let res = (0..7).try_chunks_buffered(
+        &mut tray,
+        |chunk| if chunk.len() < 3 { Err(()) } else { Ok(()) },
+    );
+    assert_eq!(res, Err(((), vec![6])));
+    assert_eq!(
+        (0..7).try_chunks_buffered(&mut tray, |_| Err::<(), ()>(())),
+        Err(((), (0..7).collect()))
+    );
+    assert_eq!(
+        (0..7).try_chunks_buffered(&mut tray, |_| Ok::<(), ()>(())),
+        Ok(())
+    );
+
+    let mut sums = vec![];
+    let res = (0..10).chunks_until(&mut tray, |chunk| {
+        let sum = chunk.iter().sum::<i32>();
+        sums.push(sum);
+        if 10 < sum {
+            ControlFlow::Break(sum)
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+    assert_eq!(res, Some(12));
+    assert_eq!(sums, [3, 12]);
+    assert_eq!(
+        (0..10).chunks_until(&mut tray, |_| ControlFlow::<()>::Continue(())),
+        None
+    );
+}
+#[cfg(test)]
+#[test]
+fn test_try_chunks_drop() {
+    use std::rc::Rc;
+
+    let counter = Rc::new(());
+    let items = (0..10).map(|_| Rc::clone(&counter)).collect::<Vec<_>>();
+    let mut tray = [const { MaybeUninit::uninit() }; 4];
+    let res = items.into_iter().try_chunks(&mut tray, |_| Err(()));
+    assert_eq!(res, Err(()));
+    // The failed chunk and the rest are all dropped
+    assert_eq!(Rc::strong_count(&counter), 1);
+
+    let items = (0..10).map(|_| Rc::clone(&counter)).collect::<Vec<_>>();
+    let (_, leftover) = items
+        .into_iter()
+        .try_chunks_buffered(&mut tray, |_| Err(()))
+        .unwrap_err();
+    assert_eq!(leftover.len(), 10);
+    assert_eq!(Rc::strong_count(&counter), 11);
+    drop(leftover);
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
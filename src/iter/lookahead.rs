@@ -21,6 +21,14 @@ where
         let next = self.iter.next();
         core::mem::replace(&mut self.next, next)
     }
+    /// Of the items left including the peeked one
+    #[must_use]
+    pub fn size_hint(&self) -> (usize, Option<usize>) {
+        let peeked = usize::from(self.next.is_some());
+        let (lower, upper) = self.iter.size_hint();
+        let upper = upper.and_then(|upper| upper.checked_add(peeked));
+        (lower.saturating_add(peeked), upper)
+    }
 }
 
 #[derive(Debug)]
@@ -52,10 +60,7 @@ where
 fn test_lookahead1_mut() {
     let mut vec = vec![1, 2, 3];
     let mut iter = Lookahead1Mut::new(vec.iter_mut());
-    loop {
-        let Some(int) = iter.peek() else {
-            break;
-        };
+    while let Some(int) = iter.peek() {
         *int = 0;
         iter.pop();
     }
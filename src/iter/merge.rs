@@ -1,3 +1,5 @@
+use core::cmp::Ordering;
+
 use super::lookahead::Lookahead1;
 
 /// # Example
@@ -29,6 +31,23 @@ impl<I, T, F> VecZipLookahead1<I, T, F> {
         }
     }
 }
+impl<I: Iterator, F> VecZipLookahead1<I, I::Item, F>
+where
+    F: FnMut(&I::Item, &I::Item) -> bool,
+{
+    /// Pop only the chosen head and return it with the index of its column
+    ///
+    /// Unlike [`Iterator::next()`], the heads are neither copied out nor required to be [`Copy`].
+    pub fn next_chosen(&mut self) -> Option<(usize, I::Item)> {
+        if self.iterators.iter().any(|x| x.peek().is_none()) {
+            return None;
+        }
+        let heads = self.iterators.iter().map(|x| x.peek().unwrap());
+        let i = choose_one(heads, &mut self.choose_left)?;
+        let x = self.iterators[i].pop().unwrap();
+        Some((i, x))
+    }
+}
 impl<I: Iterator, F> Iterator for VecZipLookahead1<I, I::Item, F>
 where
     F: FnMut(I::Item, I::Item) -> bool,
@@ -51,6 +70,8 @@ where
     }
 }
 
+/// K-way merge by `cmp`; ties go to the lower column
+///
 /// # Example
 ///
 /// ```rust
@@ -58,7 +79,7 @@ where
 ///
 /// let iterators: [Vec<i32>; 2] = [vec![1, 4, 6], vec![2, 3, 5]];
 /// let iterators = iterators.map(|x| Lookahead1::new(x.into_iter()));
-/// let mut iter = VecLookahead1::new(iterators.to_vec(), |x: &i32, y: &i32| *x <= *y);
+/// let mut iter = VecLookahead1::new(iterators.to_vec(), |x: &i32, y: &i32| x.cmp(y));
 /// assert_eq!(iter.next().unwrap(), 1);
 /// assert_eq!(iter.next().unwrap(), 2);
 /// assert_eq!(iter.next().unwrap(), 3);
@@ -70,26 +91,33 @@ where
 #[derive(Debug, Clone)]
 pub struct VecLookahead1<I, T, F> {
     iterators: Vec<Lookahead1<I, T>>,
-    choose_left: F,
+    cmp: F,
 }
 impl<I, T, F> VecLookahead1<I, T, F> {
     #[must_use]
-    pub const fn new(iterators: Vec<Lookahead1<I, T>>, choose_left: F) -> Self {
-        Self {
-            iterators,
-            choose_left,
-        }
+    pub const fn new(iterators: Vec<Lookahead1<I, T>>, cmp: F) -> Self {
+        Self { iterators, cmp }
+    }
+}
+impl<T: Ord> VecLookahead1<std::vec::IntoIter<T>, T, fn(&T, &T) -> Ordering> {
+    #[must_use]
+    pub fn from_sorted_vecs(vecs: Vec<Vec<T>>) -> Self {
+        let iterators = vecs
+            .into_iter()
+            .map(|vec| Lookahead1::new(vec.into_iter()))
+            .collect();
+        Self::new(iterators, T::cmp)
     }
 }
 impl<I: Iterator, F> Iterator for VecLookahead1<I, I::Item, F>
 where
-    F: FnMut(&I::Item, &I::Item) -> bool,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
 {
     type Item = I::Item;
 
     fn next(&mut self) -> Option<Self::Item> {
         let iter = self.iterators.iter().filter_map(|x| x.peek());
-        let i = choose_one(iter, &mut self.choose_left)?;
+        let i = choose_one(iter, |x, so_far| (self.cmp)(x, so_far).is_lt())?;
         let x = self
             .iterators
             .iter_mut()
@@ -100,6 +128,19 @@ where
             .unwrap();
         Some(x)
     }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iterators
+            .iter()
+            .map(|x| x.size_hint())
+            .fold((0, Some(0)), |(lower, upper), (l, u)| {
+                let upper = upper.zip(u).and_then(|(upper, u)| upper.checked_add(u));
+                (lower.saturating_add(l), upper)
+            })
+    }
+}
+impl<I: ExactSizeIterator, F> ExactSizeIterator for VecLookahead1<I, I::Item, F> where
+    F: FnMut(&I::Item, &I::Item) -> Ordering
+{
 }
 
 /// `choose_left`: choose first arg if true
@@ -120,3 +161,75 @@ fn choose_one<T: Copy>(
     let (i, _) = next?;
     Some(i)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(s: &[&str]) -> Vec<String> {
+        s.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_next_chosen() {
+        let iterators = [strings(&["a", "d"]), strings(&["b", "c", "e"])];
+        let iterators = iterators.map(|x| Lookahead1::new(x.into_iter()));
+        let mut iter = VecZipLookahead1::new(iterators.to_vec(), |x: &String, y: &String| x <= y);
+        let mut out = vec![];
+        while let Some(chosen) = iter.next_chosen() {
+            out.push(chosen);
+        }
+        let expected = [(0, "a"), (1, "b"), (1, "c"), (0, "d")];
+        assert_eq!(out.len(), expected.len());
+        for ((i, x), (j, y)) in out.iter().zip(expected) {
+            assert_eq!((*i, x.as_str()), (j, y));
+        }
+    }
+
+    #[test]
+    fn test_merge_strings() {
+        let vecs = vec![
+            strings(&["apple", "kiwi", "plum"]),
+            strings(&[]),
+            strings(&["banana", "cherry", "kiwi", "zucchini"]),
+            strings(&["date"]),
+        ];
+        let mut expected: Vec<String> = vecs.iter().flatten().cloned().collect();
+        expected.sort();
+        let merge = VecLookahead1::from_sorted_vecs(vecs);
+        assert_eq!(merge.len(), expected.len());
+        assert_eq!(merge.collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_merge_stable() {
+        // Ordered by the key only; the column tags which input an item came from
+        let vecs = [
+            vec![(1, 0), (2, 0), (2, 0), (5, 0)],
+            vec![(0, 1), (2, 1), (5, 1)],
+            vec![(2, 2), (3, 2)],
+        ];
+        let iterators = vecs.map(|x| Lookahead1::new(x.into_iter()));
+        let mut merge =
+            VecLookahead1::new(iterators.to_vec(), |x: &(u8, usize), y: &(u8, usize)| {
+                x.0.cmp(&y.0)
+            });
+        assert_eq!(merge.size_hint(), (9, Some(9)));
+        merge.next();
+        assert_eq!(merge.len(), 8);
+        let out: Vec<_> = merge.collect();
+        assert_eq!(
+            out,
+            [
+                (1, 0),
+                (2, 0),
+                (2, 0),
+                (2, 1),
+                (2, 2),
+                (3, 2),
+                (5, 0),
+                (5, 1)
+            ]
+        );
+    }
+}
@@ -5,10 +5,12 @@ extern crate test;
 pub mod analysis;
 pub mod arena;
 pub mod dep_inj;
+pub mod error;
 pub mod io;
 pub mod iter;
 pub mod map;
 pub mod ops;
+pub mod prelude;
 pub mod queue;
 pub mod set;
 pub mod sync;
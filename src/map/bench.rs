@@ -1,12 +1,12 @@
 #[cfg(feature = "nightly")]
 #[cfg(test)]
 mod benches {
-    use std::{collections::HashMap, hint::black_box};
+    use std::collections::HashMap;
 
     use indexmap::IndexMap;
 
     use crate::{
-        analysis::bench::HeapRandomizer,
+        analysis::bench::map_bench::{self, BenchValue, MapForEach},
         map::{
             dense_hash_map::DenseHashMap,
             grow_dense_map::GrowDenseMap,
@@ -16,212 +16,133 @@ mod benches {
         ops::clear::Clear,
     };
 
-    const N: usize = 2 << 16;
-    const VALUE_SIZE: usize = 2 << 5;
+    const N: usize = map_bench::DEFAULT_N;
     const GROW_DENSE_MAP_CHUNK_SIZE: usize = 2 << 5;
 
-    struct Value {
-        #[allow(dead_code)]
-        buf: [u8; VALUE_SIZE],
+    type StdMap = HashMap<usize, BenchValue>;
+    type DenseMap = DenseHashMap<usize, BenchValue>;
+    type IndexMapImpl = IndexMap<usize, BenchValue>;
+    type GrowMap = GrowDenseMap<usize, BenchValue, GROW_DENSE_MAP_CHUNK_SIZE>;
+
+    impl MapInsert<usize, BenchValue> for IndexMapImpl {
+        type Out = Option<BenchValue>;
+        fn insert(&mut self, key: usize, value: BenchValue) -> Self::Out {
+            IndexMap::insert(self, key, value)
+        }
     }
-    impl Value {
-        pub const fn new() -> Self {
-            Self {
-                buf: [0; VALUE_SIZE],
-            }
+    impl HashGet<usize, BenchValue> for IndexMapImpl {
+        fn get<Q>(&self, key: &Q) -> Option<&BenchValue>
+        where
+            Q: Eq + core::hash::Hash + ?Sized,
+            usize: core::borrow::Borrow<Q>,
+        {
+            IndexMap::get(self, key)
+        }
+    }
+    impl HashRemove<usize, BenchValue> for IndexMapImpl {
+        fn remove<Q>(&mut self, key: &Q) -> Option<BenchValue>
+        where
+            Q: Eq + core::hash::Hash + ?Sized,
+            usize: core::borrow::Borrow<Q>,
+        {
+            IndexMap::swap_remove(self, key)
+        }
+    }
+    impl MapForEach<usize, BenchValue> for IndexMapImpl {
+        fn for_each(&self, mut f: impl FnMut(&usize, &BenchValue)) {
+            self.iter().for_each(|(k, v)| f(k, v));
+        }
+        fn for_each_value(&self, f: impl FnMut(&BenchValue)) {
+            self.values().for_each(f);
+        }
+    }
+    impl Clear for IndexMapImpl {
+        fn clear(&mut self) {
+            IndexMap::clear(self);
         }
     }
 
     macro_rules! get {
-        ($m: ident, $bencher: ident) => {
-            let mut heap = HeapRandomizer::new();
-            for i in 0..N {
-                heap.randomize();
-                $m.insert(i, Value::new());
+        ($name: ident, $map: ty) => {
+            #[bench]
+            fn $name(bencher: &mut test::Bencher) {
+                let mut m = <$map>::default();
+                map_bench::fill_scattered(&mut m, N);
+                bencher.iter(|| map_bench::get(&m, N));
             }
-            $bencher.iter(|| {
-                let mut reverse = false;
-                for i in 0..N {
-                    let i = if reverse { N - 1 - i } else { i };
-                    reverse = !reverse;
-                    black_box($m.get(&i));
-                }
-            });
         };
     }
-    #[bench]
-    fn bench_get_std(bencher: &mut test::Bencher) {
-        let mut m = HashMap::new();
-        get!(m, bencher);
-    }
-    #[bench]
-    fn bench_get_dense(bencher: &mut test::Bencher) {
-        let mut m = DenseHashMap::new();
-        get!(m, bencher);
-    }
-    #[bench]
-    fn bench_get_index_map(bencher: &mut test::Bencher) {
-        let mut m = IndexMap::new();
-        get!(m, bencher);
-    }
-    #[bench]
-    fn bench_get_grow(bencher: &mut test::Bencher) {
-        let mut m = GrowDenseMap::<_, _, GROW_DENSE_MAP_CHUNK_SIZE>::new();
-        get!(m, bencher);
-    }
+    get!(bench_get_std, StdMap);
+    get!(bench_get_dense, DenseMap);
+    get!(bench_get_index_map, IndexMapImpl);
+    get!(bench_get_grow, GrowMap);
 
     macro_rules! iter {
-        ($m: ident, $bencher: ident) => {
-            for i in 0..N {
-                $m.insert(i, Value::new());
+        ($name: ident, $map: ty) => {
+            #[bench]
+            fn $name(bencher: &mut test::Bencher) {
+                let mut m = <$map>::default();
+                map_bench::fill(&mut m, N);
+                assert_eq!(m.iter().count(), N);
+                bencher.iter(|| map_bench::iter(&m));
             }
-            $bencher.iter(|| {
-                for (k, v) in $m.iter() {
-                    black_box((k, v));
-                }
-            });
         };
     }
-    #[bench]
-    fn bench_iter_std(bencher: &mut test::Bencher) {
-        let mut m = HashMap::new();
-        iter!(m, bencher);
-    }
-    #[bench]
-    fn bench_iter_dense(bencher: &mut test::Bencher) {
-        let mut m = DenseHashMap::new();
-        iter!(m, bencher);
-    }
-    #[bench]
-    fn bench_iter_index_map(bencher: &mut test::Bencher) {
-        let mut m = IndexMap::new();
-        iter!(m, bencher);
-    }
+    iter!(bench_iter_std, StdMap);
+    iter!(bench_iter_dense, DenseMap);
+    iter!(bench_iter_index_map, IndexMapImpl);
 
     macro_rules! insert_remove {
-        ($m: ident, $bencher: ident) => {
-            $bencher.iter(|| {
-                for i in 0..N {
-                    $m.insert(i, Value::new());
-                }
-                let mut reverse = false;
-                for i in 0..N {
-                    let i = if reverse { N - 1 - i } else { i };
-                    reverse = !reverse;
-                    #[allow(deprecated)]
-                    $m.remove(&i);
-                }
-            });
+        ($name: ident, $map: ty) => {
+            #[bench]
+            fn $name(bencher: &mut test::Bencher) {
+                let mut m = <$map>::default();
+                bencher.iter(|| map_bench::insert_remove(&mut m, N));
+            }
         };
     }
-    #[bench]
-    fn bench_insert_remove_std(bencher: &mut test::Bencher) {
-        let mut m = HashMap::new();
-        insert_remove!(m, bencher);
-    }
-    #[bench]
-    fn bench_insert_remove_dense(bencher: &mut test::Bencher) {
-        let mut m = DenseHashMap::new();
-        insert_remove!(m, bencher);
-    }
-    #[bench]
-    fn bench_insert_remove_index_map(bencher: &mut test::Bencher) {
-        let mut m = IndexMap::new();
-        insert_remove!(m, bencher);
-    }
+    insert_remove!(bench_insert_remove_std, StdMap);
+    insert_remove!(bench_insert_remove_dense, DenseMap);
+    insert_remove!(bench_insert_remove_index_map, IndexMapImpl);
 
     macro_rules! insert_iter_remove {
-        ($m: ident, $bencher: ident) => {
-            let n = (N as f64).sqrt().round() as usize;
-            $bencher.iter(|| {
-                for i in 0..n {
-                    $m.insert(i, Value::new());
-                }
-                let mut reverse = false;
-                for i in 0..n {
-                    for v in $m.iter() {
-                        black_box(v);
-                    }
-                    let i = if reverse { n - 1 - i } else { i };
-                    reverse = !reverse;
-                    #[allow(deprecated)]
-                    $m.remove(&i);
-                }
-            });
+        ($name: ident, $map: ty) => {
+            #[bench]
+            fn $name(bencher: &mut test::Bencher) {
+                let mut m = <$map>::default();
+                bencher.iter(|| map_bench::insert_iter_remove(&mut m, N));
+            }
         };
     }
-    #[bench]
-    fn bench_insert_iter_remove_std(bencher: &mut test::Bencher) {
-        let mut m = HashMap::new();
-        insert_iter_remove!(m, bencher);
-    }
-    #[bench]
-    fn bench_insert_iter_remove_dense(bencher: &mut test::Bencher) {
-        let mut m = DenseHashMap::new();
-        insert_iter_remove!(m, bencher);
-    }
-    #[bench]
-    fn bench_insert_iter_remove_index_map(bencher: &mut test::Bencher) {
-        let mut m = IndexMap::new();
-        insert_iter_remove!(m, bencher);
-    }
+    insert_iter_remove!(bench_insert_iter_remove_std, StdMap);
+    insert_iter_remove!(bench_insert_iter_remove_dense, DenseMap);
+    insert_iter_remove!(bench_insert_iter_remove_index_map, IndexMapImpl);
 
     macro_rules! insert_clear {
-        ($m: ident, $bencher: ident) => {
-            $bencher.iter(|| {
-                for i in 0..N {
-                    $m.insert(i, Value::new());
-                }
-                $m.clear();
-            });
+        ($name: ident, $map: ty) => {
+            #[bench]
+            fn $name(bencher: &mut test::Bencher) {
+                let mut m = <$map>::default();
+                bencher.iter(|| map_bench::insert_clear(&mut m, N));
+            }
         };
     }
-    #[bench]
-    fn bench_insert_clear_std(bencher: &mut test::Bencher) {
-        let mut m = HashMap::new();
-        insert_clear!(m, bencher);
-    }
-    #[bench]
-    fn bench_insert_clear_dense(bencher: &mut test::Bencher) {
-        let mut m = DenseHashMap::new();
-        insert_clear!(m, bencher);
-    }
-    #[bench]
-    fn bench_insert_clear_index_map(bencher: &mut test::Bencher) {
-        let mut m = IndexMap::new();
-        insert_clear!(m, bencher);
-    }
-    #[bench]
-    fn bench_insert_clear_grow(bencher: &mut test::Bencher) {
-        let mut m = GrowDenseMap::<_, _, GROW_DENSE_MAP_CHUNK_SIZE>::new();
-        insert_clear!(m, bencher);
-    }
+    insert_clear!(bench_insert_clear_std, StdMap);
+    insert_clear!(bench_insert_clear_dense, DenseMap);
+    insert_clear!(bench_insert_clear_index_map, IndexMapImpl);
+    insert_clear!(bench_insert_clear_grow, GrowMap);
 
     macro_rules! values {
-        ($m: ident, $bencher: ident) => {
-            for i in 0..N {
-                $m.insert(i, Value::new());
+        ($name: ident, $map: ty) => {
+            #[bench]
+            fn $name(bencher: &mut test::Bencher) {
+                let mut m = <$map>::default();
+                map_bench::fill(&mut m, N);
+                bencher.iter(|| map_bench::values(&m));
             }
-            $bencher.iter(|| {
-                for v in $m.values() {
-                    black_box(v);
-                }
-            });
         };
     }
-    #[bench]
-    fn bench_values_std(bencher: &mut test::Bencher) {
-        let mut m = HashMap::new();
-        values!(m, bencher);
-    }
-    #[bench]
-    fn bench_values_dense(bencher: &mut test::Bencher) {
-        let mut m = DenseHashMap::new();
-        values!(m, bencher);
-    }
-    #[bench]
-    fn bench_values_index_map(bencher: &mut test::Bencher) {
-        let mut m = IndexMap::new();
-        values!(m, bencher);
-    }
+    values!(bench_values_std, StdMap);
+    values!(bench_values_dense, DenseMap);
+    values!(bench_values_index_map, IndexMapImpl);
 }
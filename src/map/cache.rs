@@ -0,0 +1,212 @@
+use core::{
+    borrow::Borrow,
+    hash::{BuildHasher, Hash},
+};
+use std::hash::RandomState;
+
+use crate::ops::{clear::Clear, len::Len};
+
+use super::{
+    expiring_map::TravelForwardInTime,
+    hash_map::{HashGetMut, HashRemove},
+    weak_lru::WeakLru,
+    MapInsert,
+};
+
+/// Map that drops entries by its own policy, be it capacity or expiry
+pub trait Cache<K, V>: Len + Clear {
+    /// Handed to the expiry checks; `()` for caches that never expire
+    type Time;
+    /// Counts as an access
+    #[must_use]
+    fn get<Q>(&mut self, key: &Q, now: Self::Time) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq + Hash;
+    fn insert(&mut self, key: K, value: V, now: Self::Time);
+    fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq + Hash;
+    /// Remove the entry the policy would drop next
+    fn evict_one(&mut self) -> Option<(K, V)>;
+}
+
+/// [`WeakLru`] whose entries also expire `ttl` after their last insertion, or never if that overflows `Time`
+///
+/// An expired entry is only reclaimed once looked up; until then it counts towards [`Len::len()`] and competes for the capacity like any other.
+#[derive(Debug, Clone)]
+pub struct ExpiringLru<K, V, const N: usize, Time, Duration, H = RandomState> {
+    lru: WeakLru<K, (V, Option<Time>), N, H>,
+    ttl: Duration,
+}
+impl<K, V, const N: usize, Time, Duration> ExpiringLru<K, V, N, Time, Duration, RandomState> {
+    #[must_use]
+    pub fn new(ttl: Duration) -> Self {
+        Self::with_hasher(ttl, RandomState::new())
+    }
+}
+impl<K, V, const N: usize, Time, Duration, H> ExpiringLru<K, V, N, Time, Duration, H> {
+    #[must_use]
+    pub fn with_hasher(ttl: Duration, hasher: H) -> Self {
+        Self {
+            lru: WeakLru::with_hasher(hasher),
+            ttl,
+        }
+    }
+    #[must_use]
+    pub fn ttl(&self) -> &Duration {
+        &self.ttl
+    }
+}
+impl<K, V, const N: usize, Time, Duration, H> Len for ExpiringLru<K, V, N, Time, Duration, H> {
+    fn len(&self) -> usize {
+        self.lru.len()
+    }
+}
+impl<K, V, const N: usize, Time, Duration, H> Clear for ExpiringLru<K, V, N, Time, Duration, H> {
+    fn clear(&mut self) {
+        self.lru.clear();
+    }
+}
+impl<K, V, const N: usize, Time, Duration, H> Cache<K, V>
+    for ExpiringLru<K, V, N, Time, Duration, H>
+where
+    K: Eq + Hash,
+    H: BuildHasher,
+    Time: TravelForwardInTime<Duration = Duration> + Ord,
+{
+    type Time = Time;
+    /// Remove the entry and its key if its deadline is not after `now`
+    fn get<Q>(&mut self, key: &Q, now: Time) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq + Hash,
+    {
+        // Only the hit counts as an access
+        let (_, deadline) = self.lru.peek(key)?;
+        if deadline.as_ref().is_some_and(|deadline| *deadline <= now) {
+            HashRemove::remove(&mut self.lru, key);
+            return None;
+        }
+        let (value, _) = self.lru.get_mut(key).unwrap();
+        Some(value)
+    }
+    fn insert(&mut self, key: K, value: V, now: Time) {
+        let deadline = now.travel_forward_for(&self.ttl);
+        MapInsert::insert(&mut self.lru, key, (value, deadline));
+    }
+    fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq + Hash,
+    {
+        HashRemove::remove(&mut self.lru, key).map(|(value, _)| value)
+    }
+    fn evict_one(&mut self) -> Option<(K, V)> {
+        self.lru.evict_one().map(|(key, (value, _))| (key, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::{expiring_map::ExpiringHashMap, hash_map::BuildFxHasher};
+
+    use super::*;
+
+    type MockLru<const N: usize> = ExpiringLru<&'static str, u32, N, u64, u64, BuildFxHasher>;
+
+    #[test]
+    fn test_expired_reclaimed() {
+        let mut lru = MockLru::<4>::with_hasher(10, BuildFxHasher::default());
+        lru.insert("a", 1, 0);
+        lru.insert("b", 2, 5);
+        assert_eq!(lru.get("a", 9), Some(&mut 1));
+        // Expired but not yet looked up
+        assert_eq!(lru.len(), 2);
+        assert_eq!(lru.get("a", 10), None);
+        assert_eq!(lru.len(), 1);
+        // The key is gone too, so a lookup before the old deadline cannot revive it
+        assert_eq!(lru.get("a", 0), None);
+
+        // The freed slot takes a new key without evicting a live one
+        for (i, key) in ["c", "d", "e"].into_iter().enumerate() {
+            lru.insert(key, i as u32, 10);
+        }
+        assert_eq!(lru.len(), 4);
+        assert_eq!(lru.get("b", 14), Some(&mut 2));
+        for key in ["c", "d", "e"] {
+            assert!(lru.get(key, 14).is_some());
+        }
+        assert_eq!(lru.get("b", 15), None);
+        assert_eq!(lru.len(), 3);
+
+        // Re-insertion pushes the deadline back
+        lru.insert("c", 7, 19);
+        assert_eq!(lru.get("c", 28), Some(&mut 7));
+        assert_eq!(lru.get("d", 28), None);
+    }
+
+    #[test]
+    fn test_never_expire() {
+        let mut lru = MockLru::<4>::with_hasher(u64::MAX, BuildFxHasher::default());
+        lru.insert("a", 1, 1);
+        assert_eq!(lru.get("a", u64::MAX), Some(&mut 1));
+        // No overflow, so it does expire
+        lru.insert("b", 2, 0);
+        assert_eq!(lru.get("b", u64::MAX), None);
+    }
+
+    #[test]
+    fn test_capacity_eviction() {
+        const N: usize = 4;
+        let mut lru = MockLru::<N>::with_hasher(u64::MAX / 2, BuildFxHasher::default());
+        let keys = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+        for (i, key) in keys.into_iter().enumerate() {
+            lru.insert(key, i as u32, i as u64);
+            assert!(lru.len() <= N);
+            assert_eq!(lru.get(key, i as u64), Some(&mut (i as u32)));
+        }
+        assert_eq!(lru.len(), N);
+        let mut evicted = vec![];
+        while let Some((key, value)) = lru.evict_one() {
+            assert_eq!(keys[value as usize], key);
+            evicted.push(key);
+            assert_eq!(lru.get(key, 0), None);
+        }
+        assert_eq!(evicted.len(), N);
+        assert_eq!(lru.len(), 0);
+    }
+
+    fn fill_and_drain<C: Cache<u32, u32>>(cache: &mut C, now: impl Fn() -> C::Time) {
+        for i in 0..8 {
+            cache.insert(i, i * 2, now());
+        }
+        let len = cache.len();
+        assert!(0 < len);
+        let (key, value) = cache.evict_one().unwrap();
+        assert_eq!(value, key * 2);
+        assert_eq!(cache.get(&key, now()), None);
+        assert_eq!(cache.len(), len - 1);
+        let (key, _) = cache.evict_one().unwrap();
+        cache.insert(key, 1, now());
+        assert_eq!(cache.remove(&key), Some(1));
+        cache.clear();
+        assert!(cache.evict_one().is_none());
+    }
+
+    #[test]
+    fn test_policies() {
+        fill_and_drain(&mut WeakLru::<_, _, 4>::new(), || ());
+        fill_and_drain(&mut ExpiringHashMap::<_, _, u64, u64>::new(10), || 0);
+        fill_and_drain(&mut ExpiringLru::<_, _, 4, u64, u64>::new(10), || 0);
+
+        // The earliest deadline goes first
+        let mut map = ExpiringHashMap::<_, _, u64, u64>::new(10);
+        map.insert("a", 0, 0);
+        map.insert("b", 0, 1);
+        map.insert("a", 0, 2);
+        assert_eq!(Cache::evict_one(&mut map), Some(("b", 0)));
+        assert_eq!(Cache::evict_one(&mut map), Some(("a", 0)));
+    }
+}
@@ -5,21 +5,53 @@ use core::{
 };
 use std::hash::RandomState;
 
-use crate::ops::ring::RingSpace;
+use crate::ops::{
+    clear::Clear, index::define_index, len::Len, prefetch::prefetch_read, ring::RingSpace,
+};
 
 use super::{
-    hash_map::{HashGet, HashGetMut, HashRemove},
+    hash_map::{
+        BuildFxHasher, BuildIdentityHasher, HashGet, HashGetMut, HashRemove, IsEnabledNoHash,
+        NoHashBuilder, SeededState,
+    },
     MapInsert,
 };
 
+define_index!(
+    /// Slot of an entry in a [`CapHashMap`], reused by whichever key lands there next
+    SlotIndex
+);
+
 #[derive(Debug, Clone)]
 pub struct CapHashMap<K, V, H = RandomState> {
     entries: Vec<Option<(K, V)>>,
+    /// One byte per slot in [`Self::entries`]: [`EMPTY_META`] or the [`meta_tag()`] of the hash of the key
+    ///
+    /// Probed before the keys so that most of the full key comparisons are skipped.
+    meta: Vec<u8>,
     direct_sets: NonZeroUsize,
     assoc_ways: NonZeroUsize,
     next_way_index: usize,
+    /// Occupied slots
+    len: usize,
     hash_builder: H,
 }
+/// [`CapHashMap`] keyed by integer ids that are used as their own hashes
+///
+/// Sequential ids land in sequential sets, so `direct_sets` of them fill every set before any set sees a second one.
+/// Their metadata tags are all the same though, so every occupied way gets a full key comparison, which is only an integer compare.
+///
+/// Only for [`IsEnabledNoHash`] keys:
+///
+/// ```compile_fail
+/// use core::num::NonZeroUsize;
+///
+/// use primitive::map::{cap_map::IntCapHashMap, hash_map::NoHashBuilder};
+///
+/// let one = NonZeroUsize::new(1).unwrap();
+/// let _: IntCapHashMap<String, u32> = IntCapHashMap::with_hasher(one, one, NoHashBuilder::default());
+/// ```
+pub type IntCapHashMap<K, V> = CapHashMap<K, V, NoHashBuilder<K>>;
 impl<K, V, H> CapHashMap<K, V, H> {
     #[must_use]
     pub fn with_hasher(direct_sets: NonZeroUsize, assoc_ways: NonZeroUsize, hasher: H) -> Self {
@@ -27,9 +59,11 @@ impl<K, V, H> CapHashMap<K, V, H> {
             entries: (0..direct_sets.get() * assoc_ways.get())
                 .map(|_| None)
                 .collect(),
+            meta: vec![EMPTY_META; direct_sets.get() * assoc_ways.get()],
             direct_sets,
             assoc_ways,
             next_way_index: 0,
+            len: 0,
             hash_builder: hasher,
         }
     }
@@ -40,6 +74,24 @@ impl<K, V> CapHashMap<K, V, RandomState> {
         Self::with_hasher(direct_sets, assoc_ways, RandomState::new())
     }
 }
+impl<K, V> CapHashMap<K, V, BuildFxHasher> {
+    #[must_use]
+    pub fn with_fx_hasher(direct_sets: NonZeroUsize, assoc_ways: NonZeroUsize) -> Self {
+        Self::with_hasher(direct_sets, assoc_ways, BuildFxHasher::default())
+    }
+}
+impl<K: IsEnabledNoHash, V> CapHashMap<K, V, BuildIdentityHasher> {
+    #[must_use]
+    pub fn with_identity_hasher(direct_sets: NonZeroUsize, assoc_ways: NonZeroUsize) -> Self {
+        Self::with_hasher(direct_sets, assoc_ways, BuildIdentityHasher::default())
+    }
+}
+impl<K, V> CapHashMap<K, V, SeededState> {
+    #[must_use]
+    pub fn with_seed(direct_sets: NonZeroUsize, assoc_ways: NonZeroUsize, seed: u64) -> Self {
+        Self::with_hasher(direct_sets, assoc_ways, SeededState::new(seed))
+    }
+}
 impl<K, V, H> CapHashMap<K, V, H>
 where
     K: Eq + Hash,
@@ -48,99 +100,154 @@ where
     pub fn get_or_insert(
         &mut self,
         key: K,
-        value: impl FnOnce(usize) -> V,
+        value: impl FnOnce(SlotIndex) -> V,
     ) -> GetOrInsert<'_, K, V> {
         let hash = self.hash_builder.hash_one(&key);
-        let set_index = self.set_index(hash);
-        if let Some(index) = self.get_index_pre_hashed(&key, set_index) {
-            let (_, v) = self.entries[index].as_ref().unwrap();
+        if let Some(index) = self.get_index_prehashed(hash, |k| *k == key) {
+            let (_, v) = self.entries[index.as_usize()].as_ref().unwrap();
             return GetOrInsert::Get(v);
         }
-        GetOrInsert::Insert(self.force_insert_pre_hashed(key, set_index, value))
+        GetOrInsert::Insert(self.force_insert_prehashed(hash, key, value))
     }
-    pub fn insert_2(&mut self, key: K, value: impl FnOnce(usize) -> V) -> (usize, Option<(K, V)>) {
+    pub fn insert_2(
+        &mut self,
+        key: K,
+        value: impl FnOnce(SlotIndex) -> V,
+    ) -> (SlotIndex, Option<(K, V)>) {
         let hash = self.hash_builder.hash_one(&key);
-        let set_index = self.set_index(hash);
-        if let Some(index) = self.get_index_pre_hashed(&key, set_index) {
-            let old = self.entries[index].take().unwrap();
-            self.entries[index] = Some((key, value(index)));
-            return (index, Some(old));
+        self.insert_prehashed(hash, key, value)
+    }
+    #[must_use]
+    pub fn get_index<Q>(&self, key: &Q) -> Option<SlotIndex>
+    where
+        Q: Eq + Hash + ?Sized,
+        K: Borrow<Q>,
+    {
+        let hash = self.hash_builder.hash_one(key);
+        self.get_index_prehashed(hash, |k| k.borrow() == key)
+    }
+    /// Same as [`HashGet::get()`] on each of `keys` but with the memory accesses of the whole batch overlapped
+    ///
+    /// All the keys are hashed and their sets prefetched before any set is probed.
+    #[must_use]
+    pub fn get_batch<'a, Q, const B: usize>(&'a self, keys: [&Q; B]) -> [Option<&'a V>; B]
+    where
+        Q: Eq + Hash + ?Sized,
+        K: Borrow<Q>,
+    {
+        let hashes = keys.map(|key| self.hash_builder.hash_one(key));
+        for &hash in &hashes {
+            self.prefetch_set(hash);
         }
-        self.force_insert_pre_hashed(key, set_index, value)
+        core::array::from_fn(|i| {
+            let index = self.get_index_prehashed(hashes[i], |k| k.borrow() == keys[i])?;
+            let (_, v) = self.entries[index.as_usize()].as_ref()?;
+            Some(v)
+        })
     }
-    fn force_insert_pre_hashed(
+}
+impl<K, V, H> CapHashMap<K, V, H>
+where
+    K: Eq,
+{
+    /// Same as [`Self::insert_2()`] but with the `hash` of `key` supplied by the caller
+    ///
+    /// `hash` must be consistent across all the `*_prehashed` calls for the same key.
+    pub fn insert_prehashed(
         &mut self,
+        hash: u64,
         key: K,
-        set_index: usize,
-        value: impl FnOnce(usize) -> V,
-    ) -> (usize, Option<(K, V)>) {
-        let ways = &self.entries[self.ways(set_index)];
-        let way_index = ways.iter().position(|entry| entry.is_none());
+        value: impl FnOnce(SlotIndex) -> V,
+    ) -> (SlotIndex, Option<(K, V)>) {
+        if let Some(index) = self.get_index_prehashed(hash, |k| *k == key) {
+            let value = value(index);
+            let old = self.entries[index.as_usize()].replace((key, value));
+            return (index, old);
+        }
+        self.force_insert_prehashed(hash, key, value)
+    }
+    fn force_insert_prehashed(
+        &mut self,
+        hash: u64,
+        key: K,
+        value: impl FnOnce(SlotIndex) -> V,
+    ) -> (SlotIndex, Option<(K, V)>) {
+        let set_index = self.set_index(hash);
+        let ways = &self.meta[self.ways(set_index)];
+        let way_index = ways.iter().position(|&meta| meta == EMPTY_META);
         let way_index = way_index.unwrap_or(self.next_way_index);
         if self.assoc_ways.get() != 1 {
             self.next_way_index = self.next_way_index.ring_add(1, self.assoc_ways.get() - 1);
         }
         let index = self.index(set_index, way_index);
+        let slot = SlotIndex::new(index);
+        // Leave the slot untouched if `value()` panics
+        let value = value(slot);
+        self.meta[index] = meta_tag(hash);
         let ejected = match &mut self.entries[index] {
             Some((k, v)) => {
                 // Faster than mem replacing the entry all at once if the key can be simply loaded in the register
                 let k = core::mem::replace(k, key);
-                let v = core::mem::replace(v, value(index));
+                let v = core::mem::replace(v, value);
                 Some((k, v))
             }
             None => {
-                self.entries[index] = Some((key, value(index)));
+                self.entries[index] = Some((key, value));
+                self.len += 1;
                 None
             }
         };
-        (index, ejected)
+        (slot, ejected)
     }
-    pub fn remove_entry(&mut self, index: usize) -> Option<(K, V)> {
-        self.entries[index].take()
+}
+impl<K, V, H> CapHashMap<K, V, H> {
+    pub fn remove_entry(&mut self, index: SlotIndex) -> Option<(K, V)> {
+        self.meta[index.as_usize()] = EMPTY_META;
+        let entry = self.entries[index.as_usize()].take();
+        self.len -= usize::from(entry.is_some());
+        entry
     }
     #[must_use]
-    pub fn entry(&self, index: usize) -> Option<(&K, &V)> {
-        let (k, v) = self.entries[index].as_ref()?;
+    pub fn entry(&self, index: SlotIndex) -> Option<(&K, &V)> {
+        let (k, v) = self.entries[index.as_usize()].as_ref()?;
         Some((k, v))
     }
     #[must_use]
-    pub fn entry_mut(&mut self, index: usize) -> Option<(&K, &mut V)> {
-        let (k, v) = self.entries[index].as_mut()?;
+    pub fn entry_mut(&mut self, index: SlotIndex) -> Option<(&K, &mut V)> {
+        let (k, v) = self.entries[index.as_usize()].as_mut()?;
         Some((k, v))
     }
+    /// Same as [`Self::get_index()`] but with the `hash` supplied by the caller and the key matched by `eq`
     #[must_use]
-    pub fn get_index<Q>(&self, key: &Q) -> Option<usize>
-    where
-        Q: Eq + Hash + ?Sized,
-        K: Borrow<Q>,
-    {
-        let hash = self.hash_builder.hash_one(key);
-        self.get_index_pre_hashed(key, self.set_index(hash))
-    }
-    #[must_use]
-    fn get_index_pre_hashed<Q>(&self, key: &Q, set_index: usize) -> Option<usize>
-    where
-        Q: Eq + ?Sized,
-        K: Borrow<Q>,
-    {
-        let ways = &self.entries[self.ways(set_index)];
-        let predicate = |entry: &Option<(K, V)>| {
-            let Some((k, _)) = entry else {
-                return false;
+    pub fn get_index_prehashed(
+        &self,
+        hash: u64,
+        mut eq: impl FnMut(&K) -> bool,
+    ) -> Option<SlotIndex> {
+        let set_index = self.set_index(hash);
+        let ways = self.ways(set_index);
+        let tag = meta_tag(hash);
+        for (index, &meta) in ways.clone().zip(&self.meta[ways]) {
+            if meta != tag {
+                continue;
+            }
+            let Some((k, _)) = &self.entries[index] else {
+                continue;
             };
-            k.borrow() == key
-        };
-        let way_index = if ways.len() == 1 {
-            if predicate(&ways[0]) {
-                Some(0)
-            } else {
-                None
+            if eq(k) {
+                return Some(SlotIndex::new(index));
             }
-        } else {
-            ways.iter().position(predicate)
-        };
-        let index = self.index(set_index, way_index?);
-        Some(index)
+        }
+        None
+    }
+    pub fn remove_prehashed(&mut self, hash: u64, eq: impl FnMut(&K) -> bool) -> Option<(K, V)> {
+        let index = self.get_index_prehashed(hash, eq)?;
+        self.remove_entry(index)
+    }
+    fn prefetch_set(&self, hash: u64) {
+        let index = self.index(self.set_index(hash), 0);
+        prefetch_read(self.meta.as_ptr().wrapping_add(index));
+        prefetch_read(self.entries.as_ptr().wrapping_add(index));
     }
     #[must_use]
     fn index(&self, set_index: usize, way_index: usize) -> usize {
@@ -152,15 +259,35 @@ where
         let end = start + self.assoc_ways.get();
         start..end
     }
+    /// Modulo rather than masking so that `direct_sets` needs not be a power of two and identity hashes of sequential ids are spread evenly
     #[must_use]
     fn set_index(&self, hash: u64) -> usize {
         hash as usize % self.direct_sets.get()
     }
 }
+const EMPTY_META: u8 = 0;
+/// The occupied bit and the top 7 bits of `hash`, which are independent of the set picked by the low bits
+#[must_use]
+const fn meta_tag(hash: u64) -> u8 {
+    0x80 | (hash >> 57) as u8
+}
 #[derive(Debug, Clone, Copy)]
 pub enum GetOrInsert<'a, K, V> {
     Get(&'a V),
-    Insert((usize, Option<(K, V)>)),
+    Insert((SlotIndex, Option<(K, V)>)),
+}
+impl<K, V, H> Clear for CapHashMap<K, V, H> {
+    fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+        self.meta.fill(EMPTY_META);
+        self.next_way_index = 0;
+        self.len = 0;
+    }
+}
+impl<K, V, H> Len for CapHashMap<K, V, H> {
+    fn len(&self) -> usize {
+        self.len
+    }
 }
 impl<K, V, H> HashGet<K, V> for CapHashMap<K, V, H>
 where
@@ -173,7 +300,7 @@ where
         K: Borrow<Q>,
     {
         let index = self.get_index(key)?;
-        let (_, v) = self.entries[index].as_ref()?;
+        let (_, v) = self.entries[index.as_usize()].as_ref()?;
         Some(v)
     }
 }
@@ -188,7 +315,7 @@ where
         K: Borrow<Q>,
     {
         let index = self.get_index(key)?;
-        let (_, v) = self.entries[index].as_mut()?;
+        let (_, v) = self.entries[index.as_usize()].as_mut()?;
         Some(v)
     }
 }
@@ -203,7 +330,7 @@ where
         K: Borrow<Q>,
     {
         let index = self.get_index(key)?;
-        self.entries[index].take().map(|(_, v)| v)
+        self.remove_entry(index).map(|(_, v)| v)
     }
 }
 impl<K, V, H> MapInsert<K, V> for CapHashMap<K, V, H>
@@ -216,12 +343,86 @@ where
         let (_, out) = self.insert_2(key, |_| value);
         out
     }
+    /// Hash the whole batch in a tight loop and prefetch the set of each next entry
+    fn insert_many(&mut self, iter: impl IntoIterator<Item = (K, V)>) {
+        let batch: Vec<(u64, K, V)> = iter
+            .into_iter()
+            .map(|(key, value)| (self.hash_builder.hash_one(&key), key, value))
+            .collect();
+        if let Some(&(hash, _, _)) = batch.first() {
+            self.prefetch_set(hash);
+        }
+        let mut batch = batch.into_iter().peekable();
+        while let Some((hash, key, value)) = batch.next() {
+            if let Some(&(next, _, _)) = batch.peek() {
+                self.prefetch_set(next);
+            }
+            let _ = self.insert_prehashed(hash, key, |_| value);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_meta_collisions() {
+        // Small integers under the identity hash share the top 7 bits and thus the tag
+        let sets = NonZeroUsize::new(2).unwrap();
+        let ways = NonZeroUsize::new(4).unwrap();
+        let mut map = CapHashMap::with_identity_hasher(sets, ways);
+        let assert_meta_sync = |map: &CapHashMap<u64, u64, BuildIdentityHasher>| {
+            for (entry, &meta) in map.entries.iter().zip(&map.meta) {
+                assert_eq!(entry.is_some(), meta != EMPTY_META);
+            }
+            assert_eq!(map.len(), map.entries.iter().flatten().count());
+        };
+        for k in 0..8 {
+            assert!(map.insert(k, k * 10).is_none());
+        }
+        assert!(map.meta.iter().all(|&meta| meta == meta_tag(0)));
+        for k in 0..8 {
+            assert_eq!(map.get(&k), Some(&(k * 10)));
+        }
+        assert_eq!(map.get(&8), None);
+        assert_eq!(map.remove(&2), Some(20));
+        assert_eq!(map.get(&2), None);
+        assert_eq!(map.get(&4), Some(&40));
+        assert_meta_sync(&map);
+        // Into the slot freed by the removal
+        assert!(map.insert(10, 100).is_none());
+        assert_eq!(map.len(), 8);
+        // Eject from a full set
+        let ejected = map.insert(12, 120).unwrap();
+        assert_eq!(map.get(&ejected.0), None);
+        assert_eq!(map.get(&12), Some(&120));
+        assert_meta_sync(&map);
+        // A different tag in the same set
+        let far = 1 << 60;
+        assert!(map.insert(far, 1).is_some());
+        assert_ne!(meta_tag(far), meta_tag(0));
+        assert_eq!(map.get(&far), Some(&1));
+        assert_meta_sync(&map);
+        map.clear();
+        assert_meta_sync(&map);
+        assert_eq!(map.len(), 0);
+    }
+
+    #[test]
+    fn test_insert_many() {
+        let batch = || (0..300_usize).map(|i| (i * 31 % 101, i));
+        let sets = NonZeroUsize::new(16).unwrap();
+        let ways = NonZeroUsize::new(4).unwrap();
+        let mut bulk = CapHashMap::with_seed(sets, ways, 7);
+        let mut naive = CapHashMap::with_seed(sets, ways, 7);
+        bulk.insert_many(batch());
+        for (k, v) in batch() {
+            naive.insert(k, v);
+        }
+        assert_eq!(bulk.entries, naive.entries);
+    }
+
     #[test]
     fn test_cap_map() {
         const N: usize = 1 << 10;
@@ -245,6 +446,145 @@ mod tests {
         dbg!(&map);
     }
 
+    #[test]
+    fn test_prehashed() {
+        const N: usize = 1 << 10;
+
+        let direct_sets = NonZeroUsize::new(5).unwrap();
+        let assoc_ways = NonZeroUsize::new(2).unwrap();
+        let hasher = RandomState::new();
+        let mut map = CapHashMap::with_hasher(direct_sets, assoc_ways, hasher.clone());
+        let mut prehashed = CapHashMap::with_hasher(direct_sets, assoc_ways, hasher.clone());
+        for i in 0..N {
+            let hash = hasher.hash_one(i);
+            let (index, ejected) = map.insert_2(i, |_| i);
+            let (prehashed_index, prehashed_ejected) = prehashed.insert_prehashed(hash, i, |_| i);
+            assert_eq!(index, prehashed_index);
+            assert_eq!(ejected.map(|(k, _)| k), prehashed_ejected.map(|(k, _)| k));
+            assert_eq!(
+                prehashed.get_index_prehashed(hash, |k| *k == i),
+                Some(prehashed_index)
+            );
+            assert_eq!(map.get_index(&i), Some(index));
+            let (_, ejected) = prehashed.insert_prehashed(hash, i, |_| i + 1);
+            assert_eq!(ejected, Some((i, i)));
+            assert_eq!(HashGet::get(&prehashed, &i), Some(&(i + 1)));
+        }
+        for i in 0..N {
+            let hash = hasher.hash_one(i);
+            let removed = prehashed.remove_prehashed(hash, |k| *k == i);
+            assert_eq!(removed.map(|(_, v)| v - 1), map.remove(&i));
+            assert!(prehashed.get_index_prehashed(hash, |k| *k == i).is_none());
+        }
+        assert!(prehashed.entries.iter().all(|entry| entry.is_none()));
+    }
+
+    #[test]
+    fn test_value_panics() {
+        let sets = NonZeroUsize::new(1).unwrap();
+        let ways = NonZeroUsize::new(2).unwrap();
+        let mut map = CapHashMap::with_identity_hasher(sets, ways);
+        let insert_panicking = |map: &mut CapHashMap<u64, u64, _>, key| {
+            let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                map.insert_2(key, |_| panic!("no value"))
+            }));
+            assert!(res.is_err());
+            for (entry, &meta) in map.entries.iter().zip(&map.meta) {
+                assert_eq!(entry.is_some(), meta != EMPTY_META);
+            }
+            assert_eq!(map.len(), map.entries.iter().flatten().count());
+        };
+        map.insert(0, 0);
+        map.insert(1, 10);
+        // Replacing
+        insert_panicking(&mut map, 0);
+        assert_eq!(map.get(&0), Some(&0));
+        // Ejecting, under a different tag
+        let far = 1 << 60;
+        insert_panicking(&mut map, far);
+        assert_eq!(map.get(&far), None);
+        assert_eq!(map.get(&0), Some(&0));
+        assert_eq!(map.get(&1), Some(&10));
+        // Into an empty slot
+        map.remove(&1);
+        insert_panicking(&mut map, 2);
+        assert_eq!(map.get(&2), None);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_seeded() {
+        const N: usize = 1 << 10;
+
+        let direct_sets = NonZeroUsize::new(1 << 6).unwrap();
+        let assoc_ways = NonZeroUsize::new(2).unwrap();
+        let indices = |map: &mut CapHashMap<usize, usize, _>| {
+            (0..N)
+                .map(|i| map.insert_2(i, |_| i).0)
+                .collect::<Vec<SlotIndex>>()
+        };
+        let a = indices(&mut CapHashMap::with_seed(direct_sets, assoc_ways, 1));
+        let b = indices(&mut CapHashMap::with_seed(direct_sets, assoc_ways, 1));
+        let c = indices(&mut CapHashMap::with_seed(direct_sets, assoc_ways, 2));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+
+        let mut fx = CapHashMap::with_fx_hasher(direct_sets, assoc_ways);
+        fx.insert_2(1, |_| 1);
+        assert_eq!(fx.get(&1), Some(&1));
+
+        let mut identity = CapHashMap::with_identity_hasher(direct_sets, assoc_ways);
+        for i in 0..direct_sets.get() {
+            let (index, _) = identity.insert_2(i, |_| i);
+            assert_eq!(index.as_usize(), i * assoc_ways.get());
+        }
+    }
+
+    #[test]
+    fn test_int_sequential_keys() {
+        let sets = NonZeroUsize::new(37).unwrap();
+        let ways = NonZeroUsize::new(4).unwrap();
+        let mut int: IntCapHashMap<u32, u32> =
+            IntCapHashMap::with_hasher(sets, ways, NoHashBuilder::new());
+        let mut reference = std::collections::HashMap::new();
+        let n = (sets.get() * ways.get()) as u32;
+        for key in 0..n {
+            let (_, ejected) = int.insert_2(key, |_| key * 2);
+            assert!(ejected.is_none());
+            reference.insert(key, key * 2);
+        }
+        assert_eq!(int.len(), reference.len());
+        for key in 0..n {
+            assert_eq!(int.get(&key), reference.get(&key));
+        }
+        for key in (0..n).step_by(3) {
+            assert_eq!(int.remove(&key), reference.remove(&key));
+        }
+        for key in 0..n {
+            assert_eq!(int.get(&key), reference.get(&key));
+        }
+        assert_eq!(int.len(), reference.len());
+    }
+
+    #[test]
+    fn test_get_batch() {
+        let sets = NonZeroUsize::new(1 << 4).unwrap();
+        let ways = NonZeroUsize::new(4).unwrap();
+        let mut map = CapHashMap::new(sets, ways);
+        for i in 0..1 << 6 {
+            map.insert_2(format!("key/{i}"), |_| i);
+        }
+        // Every other key misses, some of which have been evicted
+        let keys = (0..1 << 7).map(|i| format!("key/{i}")).collect::<Vec<_>>();
+        for batch in keys.chunks_exact(8) {
+            let batch: [&str; 8] = core::array::from_fn(|i| batch[i].as_str());
+            let scalar = batch.map(|key| map.get(key));
+            assert_eq!(map.get_batch(batch), scalar);
+        }
+        assert_eq!(map.get_batch(["key/0", "key/0"]), [map.get("key/0"); 2]);
+        assert_eq!(map.get_batch::<str, 0>([]), []);
+    }
+
     #[test]
     #[ignore]
     fn test_load_factors() {
@@ -304,7 +644,11 @@ mod benches {
 
     use test::Bencher;
 
-    use crate::sync::tests::RepeatedData;
+    use crate::{
+        analysis::rng::{RngLite, SplitMix64},
+        map::hash_map::NoHashBuilder,
+        sync::tests::RepeatedData,
+    };
 
     use super::*;
 
@@ -327,6 +671,87 @@ mod benches {
             }
         });
     }
+    /// String keys sharing a long prefix so that every full comparison is costly
+    #[bench]
+    fn bench_get_string_8_ways(bencher: &mut Bencher) {
+        let ways = NonZeroUsize::new(8).unwrap();
+        let sets = NonZeroUsize::new(N / 8).unwrap();
+        let keys = (0..N)
+            .map(|i| format!("some/long/shared/prefix/{i}"))
+            .collect::<Vec<_>>();
+        let mut map = CapHashMap::with_fx_hasher(sets, ways);
+        for (i, key) in keys.iter().enumerate() {
+            map.insert_2(key.clone(), |_| i);
+        }
+        bencher.iter(|| {
+            let mut sum = 0;
+            for key in &keys {
+                sum += map.get(key.as_str()).copied().unwrap_or(0);
+            }
+            test::black_box(sum)
+        });
+    }
+    fn bench_get_u64<H: BuildHasher>(bencher: &mut Bencher, hasher: H) {
+        let mut map = CapHashMap::with_hasher(DIRECT_SETS, ASSOC_WAYS, hasher);
+        for i in 0..N as u64 {
+            map.insert_2(i, |_| i);
+        }
+        bencher.iter(|| {
+            let mut sum = 0;
+            for i in 0..N as u64 {
+                sum += map.get(&i).copied().unwrap_or(0);
+            }
+            test::black_box(sum)
+        });
+    }
+    #[bench]
+    fn bench_get_u64_random_state(bencher: &mut Bencher) {
+        bench_get_u64(bencher, RandomState::new());
+    }
+    #[bench]
+    fn bench_get_u64_no_hash(bencher: &mut Bencher) {
+        bench_get_u64(bencher, NoHashBuilder::<u64>::new());
+    }
+    const BATCH: usize = 16;
+    /// Random keys, half of them misses, over a map much larger than the caches
+    fn big_map_and_keys() -> (CapHashMap<u64, u64>, Vec<u64>) {
+        let sets = NonZeroUsize::new(1 << 20).unwrap();
+        let capacity = (sets.get() * ASSOC_WAYS.get()) as u64;
+        let mut map = CapHashMap::new(sets, ASSOC_WAYS);
+        for i in 0..capacity {
+            map.insert_2(i, |_| i);
+        }
+        let mut rng = SplitMix64::new(0);
+        let keys = (0..1 << 12)
+            .map(|_| rng.next_usize_below(capacity as usize * 2) as u64)
+            .collect();
+        (map, keys)
+    }
+    #[bench]
+    fn bench_get_big_scalar(bencher: &mut Bencher) {
+        let (map, keys) = big_map_and_keys();
+        bencher.iter(|| {
+            let mut sum = 0;
+            for key in &keys {
+                sum += map.get(key).copied().unwrap_or(0);
+            }
+            test::black_box(sum)
+        });
+    }
+    #[bench]
+    fn bench_get_big_batch(bencher: &mut Bencher) {
+        let (map, keys) = big_map_and_keys();
+        bencher.iter(|| {
+            let mut sum = 0;
+            for batch in keys.chunks_exact(BATCH) {
+                let batch: [&u64; BATCH] = core::array::from_fn(|i| &batch[i]);
+                for v in map.get_batch(batch) {
+                    sum += v.copied().unwrap_or(0);
+                }
+            }
+            test::black_box(sum)
+        });
+    }
     #[bench]
     fn bench_hash_map(bencher: &mut Bencher) {
         let mut map: HashMap<usize, RepeatedData<u8, DATA_SIZE>> = HashMap::new();
@@ -0,0 +1,366 @@
+use core::{
+    borrow::Borrow,
+    cell::Cell,
+    hash::{BuildHasher, Hash},
+    num::NonZeroUsize,
+};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    analysis::bench::ExpMovVar,
+    ops::{clear::Clear, len::Len},
+    time::stopwatch::Stopwatch,
+};
+
+use super::{
+    cap_map::CapHashMap,
+    dense_hash_map::DenseHashMap,
+    hash_map::{HashGet, HashGetMut, HashRemove},
+    linear_front_btree::LinearFrontBTreeMap,
+    strict_lru::StrictLru,
+    MapInsert,
+};
+
+/// Forward every operation to the inner map and count them
+///
+/// Gets through `&self` are counted too, so the counters are [`Cell`]s and the map is not [`Sync`].
+#[derive(Debug, Clone)]
+pub struct CountingMap<M> {
+    inner: M,
+    counts: Cell<MapCounts>,
+    timing: Option<Timing>,
+}
+#[derive(Debug, Clone)]
+struct Timing {
+    periods: NonZeroUsize,
+    times: Cell<MapTimes>,
+}
+impl<M> CountingMap<M> {
+    #[must_use]
+    pub fn new(inner: M) -> Self {
+        Self {
+            inner,
+            counts: Cell::new(MapCounts::default()),
+            timing: None,
+        }
+    }
+    /// Also time each operation into an EMA over `periods` operations of the same kind
+    #[must_use]
+    pub fn with_timing(inner: M, periods: NonZeroUsize) -> Self {
+        Self {
+            inner,
+            counts: Cell::new(MapCounts::default()),
+            timing: Some(Timing {
+                periods,
+                times: Cell::new(MapTimes::new(periods)),
+            }),
+        }
+    }
+    #[must_use]
+    pub fn metrics(&self) -> MapMetrics {
+        MapMetrics {
+            counts: self.counts.get(),
+            times: self.timing.as_ref().map(|timing| timing.times.get()),
+        }
+    }
+    pub fn reset_metrics(&mut self) {
+        self.counts.set(MapCounts::default());
+        if let Some(timing) = &self.timing {
+            timing.times.set(MapTimes::new(timing.periods));
+        }
+    }
+    #[must_use]
+    pub fn inner(&self) -> &M {
+        &self.inner
+    }
+    #[must_use]
+    pub fn into_inner(self) -> M {
+        self.inner
+    }
+}
+fn count(counts: &Cell<MapCounts>, f: impl FnOnce(&mut MapCounts)) {
+    let mut c = counts.get();
+    f(&mut c);
+    counts.set(c);
+}
+fn count_get(counts: &Cell<MapCounts>, hit: bool) {
+    count(counts, |c| {
+        c.gets += 1;
+        *if hit { &mut c.hits } else { &mut c.misses } += 1;
+    });
+}
+/// Run `op` and record its duration in seconds into the EMA picked by `pick`
+fn timed<T>(
+    timing: &Option<Timing>,
+    pick: fn(&mut MapTimes) -> &mut ExpMovVar<f64>,
+    op: impl FnOnce() -> T,
+) -> T {
+    let Some(timing) = timing else {
+        return op();
+    };
+    let mut watch = Stopwatch::default();
+    watch.start();
+    let out = op();
+    watch.pause();
+    let mut times = timing.times.get();
+    pick(&mut times).update(watch.elapsed().as_secs_f64());
+    timing.times.set(times);
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MapCounts {
+    /// Both [`HashGet::get()`] and [`HashGetMut::get_mut()`]
+    pub gets: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub inserts: u64,
+    /// Inserts of keys already present
+    pub replacements: u64,
+    /// Other entries dropped by inserts, e.g. by a capacity-bound map
+    pub ejections: u64,
+    pub removes: u64,
+}
+/// EMAs of the durations of the operations in seconds
+#[derive(Debug, Clone, Copy)]
+pub struct MapTimes {
+    pub get: ExpMovVar<f64>,
+    pub insert: ExpMovVar<f64>,
+    pub remove: ExpMovVar<f64>,
+}
+impl MapTimes {
+    fn new(periods: NonZeroUsize) -> Self {
+        Self {
+            get: ExpMovVar::from_periods(periods),
+            insert: ExpMovVar::from_periods(periods),
+            remove: ExpMovVar::from_periods(periods),
+        }
+    }
+}
+#[derive(Debug, Clone, Copy)]
+pub struct MapMetrics {
+    pub counts: MapCounts,
+    /// [`None`] unless built with [`CountingMap::with_timing()`]
+    pub times: Option<MapTimes>,
+}
+
+impl<K, V, M> HashGet<K, V> for CountingMap<M>
+where
+    M: HashGet<K, V>,
+{
+    fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        Q: Eq + Hash + ?Sized,
+        K: Borrow<Q>,
+    {
+        let value = timed(&self.timing, |t| &mut t.get, || self.inner.get(key));
+        count_get(&self.counts, value.is_some());
+        value
+    }
+}
+impl<K, V, M> HashGetMut<K, V> for CountingMap<M>
+where
+    M: HashGetMut<K, V>,
+{
+    fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        Q: Eq + Hash + ?Sized,
+        K: Borrow<Q>,
+    {
+        let Self {
+            inner,
+            counts,
+            timing,
+        } = self;
+        let value = timed(timing, |t| &mut t.get, || inner.get_mut(key));
+        count_get(counts, value.is_some());
+        value
+    }
+}
+impl<K, V, M> HashRemove<K, V> for CountingMap<M>
+where
+    M: HashRemove<K, V>,
+{
+    fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        Q: Eq + Hash + ?Sized,
+        K: Borrow<Q>,
+    {
+        count(&self.counts, |c| c.removes += 1);
+        timed(&self.timing, |t| &mut t.remove, || self.inner.remove(key))
+    }
+}
+/// Tell replacements from ejections by the output and the change in length
+impl<K, V, M> MapInsert<K, V> for CountingMap<M>
+where
+    M: CountInsert<K, V> + Len,
+{
+    type Out = M::Out;
+    fn insert(&mut self, key: K, value: V) -> Self::Out {
+        let len = self.inner.len();
+        let out = timed(
+            &self.timing,
+            |t| &mut t.insert,
+            || self.inner.insert(key, value),
+        );
+        let replaced = self.inner.replaced(&out);
+        let expected_len = len + usize::from(!replaced);
+        let ejected = expected_len.saturating_sub(self.inner.len());
+        count(&self.counts, |c| {
+            c.inserts += 1;
+            c.replacements += u64::from(replaced);
+            c.ejections += ejected as u64;
+        });
+        out
+    }
+}
+/// Maps whose insert output tells whether the value of the inserted key was replaced
+///
+/// Spares [`CountingMap`] a lookup before each insert.
+pub trait CountInsert<K, V>: MapInsert<K, V> {
+    /// `out` is what the last insert returned
+    fn replaced(&self, out: &Self::Out) -> bool;
+}
+impl<K, V, S> CountInsert<K, V> for HashMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    fn replaced(&self, out: &Self::Out) -> bool {
+        out.is_some()
+    }
+}
+impl<K: Ord, V> CountInsert<K, V> for BTreeMap<K, V> {
+    fn replaced(&self, out: &Self::Out) -> bool {
+        out.is_some()
+    }
+}
+impl<K, V, const N: usize> CountInsert<K, V> for LinearFrontBTreeMap<K, V, N>
+where
+    K: Ord + Clone,
+{
+    fn replaced(&self, out: &Self::Out) -> bool {
+        out.is_some()
+    }
+}
+impl<K, V, H> CountInsert<K, V> for DenseHashMap<K, V, H>
+where
+    K: Eq + Hash + Clone,
+    H: BuildHasher,
+{
+    fn replaced(&self, out: &Self::Out) -> bool {
+        out.is_some()
+    }
+}
+/// An ejected key is gone while a replaced one is back in; only looked up if anything was dropped
+impl<K, V, H> CountInsert<K, V> for CapHashMap<K, V, H>
+where
+    K: Eq + Hash,
+    H: BuildHasher,
+{
+    fn replaced(&self, out: &Self::Out) -> bool {
+        out.as_ref().is_some_and(|(k, _)| self.get(k).is_some())
+    }
+}
+/// Same as [`CapHashMap`]
+impl<K, V, H> CountInsert<K, V> for StrictLru<K, V, H>
+where
+    K: Eq + Hash + Clone,
+    H: BuildHasher,
+{
+    fn replaced(&self, out: &Self::Out) -> bool {
+        out.as_ref().is_some_and(|(k, _)| self.peek(k).is_some())
+    }
+}
+impl<M: Len> Len for CountingMap<M> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+/// Does not reset the metrics
+impl<M: Clear> Clear for CountingMap<M> {
+    fn clear(&mut self) {
+        self.inner.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workload<M>(map: &mut CountingMap<M>)
+    where
+        M: CountInsert<u32, u32> + HashGet<u32, u32> + HashGetMut<u32, u32> + HashRemove<u32, u32>,
+        M: Len,
+    {
+        for i in 0..4 {
+            map.insert(i, i);
+        }
+        map.insert(0, 10);
+        assert_eq!(map.get(&0), Some(&10));
+        assert_eq!(map.get(&9), None);
+        *map.get_mut(&1).unwrap() += 10;
+        assert_eq!(map.get_mut(&8), None);
+        assert_eq!(map.remove(&2), Some(2));
+        assert_eq!(map.remove(&2), None);
+    }
+
+    const WORKLOAD_COUNTS: MapCounts = MapCounts {
+        gets: 4,
+        hits: 2,
+        misses: 2,
+        inserts: 5,
+        replacements: 1,
+        ejections: 0,
+        removes: 2,
+    };
+
+    #[test]
+    fn test_hash_map() {
+        let mut map = CountingMap::new(HashMap::new());
+        workload(&mut map);
+        assert_eq!(map.metrics().counts, WORKLOAD_COUNTS);
+        assert!(map.metrics().times.is_none());
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.inner().get(&1), Some(&11));
+        map.reset_metrics();
+        assert_eq!(map.metrics().counts, MapCounts::default());
+    }
+
+    #[test]
+    fn test_dense_hash_map() {
+        let periods = NonZeroUsize::new(8).unwrap();
+        let mut map = CountingMap::with_timing(DenseHashMap::new(), periods);
+        workload(&mut map);
+        let metrics = map.metrics();
+        assert_eq!(metrics.counts, WORKLOAD_COUNTS);
+        let times = metrics.times.unwrap();
+        for time in [times.get, times.insert, times.remove] {
+            assert!(time.mean().get().unwrap() >= 0.);
+        }
+        map.clear();
+        assert_eq!(map.len(), 0);
+        assert_eq!(map.metrics().counts, WORKLOAD_COUNTS);
+        map.reset_metrics();
+        assert!(map.metrics().times.unwrap().get.mean().get().is_none());
+    }
+
+    #[test]
+    fn test_ejections() {
+        let one = NonZeroUsize::new(1).unwrap();
+        let mut map = CountingMap::new(CapHashMap::new(one, one));
+        map.insert(1, 1);
+        map.insert(2, 2);
+        map.insert(2, 3);
+        let counts = map.metrics().counts;
+        assert_eq!(counts.inserts, 3);
+        assert_eq!(counts.replacements, 1);
+        assert_eq!(counts.ejections, 1);
+
+        let mut map = CountingMap::new(StrictLru::new(one));
+        map.insert(1, 1);
+        map.insert(2, 2);
+        map.insert(2, 3);
+        assert_eq!(map.metrics().counts, counts);
+    }
+}
@@ -1,55 +1,202 @@
-use std::collections::HashMap;
+use core::hash::BuildHasher;
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    sync::Arc,
+};
 
-use crate::ops::{clear::Clear, len::Len};
+use crate::ops::{
+    clear::Clear,
+    len::{HeapSize, Len},
+};
 
 use super::{
-    free_list::{DenseFreeList, FreeList},
-    hash_map::{HashGet, HashGetMut, HashRemove},
+    free_list::{DenseFreeList, FreeIndex, FreeList},
+    hash_map::{
+        BuildFxHasher, BuildIdentityHasher, HashGet, HashGetMut, HashRemove, IsEnabledNoHash,
+        NoHashBuilder, SeededState,
+    },
     MapInsert,
 };
 
 /// vs. [`indexmap::IndexMap`]:
 /// - [`Self::values()`]: basically the same
+/// - [`Self::iter()`]: basically the same
+///
+/// Each key is stored twice, once in the index and once alongside its value.
+#[derive(Debug)]
+pub struct DenseHashMap<K, V, H = RandomState> {
+    inner: Arc<Inner<K, V, H>>,
+    /// Set by [`Self::snapshot()`] where `K`, `V` and `H` are known to be [`Clone`]
+    unshare: Option<Unshare<K, V, H>>,
+}
+// Same as without the `Arc`: the storage is only shared with snapshots, which require `K`, `V` and `H` to be `Sync`
+unsafe impl<K: Send, V: Send, H: Send> Send for DenseHashMap<K, V, H> {}
+unsafe impl<K: Sync, V: Sync, H: Sync> Sync for DenseHashMap<K, V, H> {}
+/// [`DenseHashMap`] keyed by integer ids that are used as their own hashes
+///
+/// Only for [`IsEnabledNoHash`] keys:
+///
+/// ```compile_fail
+/// use primitive::map::dense_hash_map::IntDenseHashMap;
+///
+/// let _ = IntDenseHashMap::<String, u32>::default();
+/// ```
+pub type IntDenseHashMap<K, V> = DenseHashMap<K, V, NoHashBuilder<K>>;
+type NewInner<K, V, H> = fn(&Inner<K, V, H>) -> Inner<K, V, H>;
+#[derive(Debug)]
+struct Unshare<K, V, H> {
+    copy: NewInner<K, V, H>,
+    /// No entries but the same hasher
+    empty: NewInner<K, V, H>,
+}
+impl<K, V, H> Clone for Unshare<K, V, H> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<K, V, H> Copy for Unshare<K, V, H> {}
 #[derive(Debug, Clone)]
-pub struct DenseHashMap<K, V> {
-    data: DenseFreeList<V>,
-    index: HashMap<K, usize>,
+struct Inner<K, V, H> {
+    data: DenseFreeList<(K, V)>,
+    index: HashMap<K, FreeIndex, H>,
 }
 impl<K, V> DenseHashMap<K, V> {
     #[must_use]
     pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+impl<K, V> DenseHashMap<K, V, BuildFxHasher> {
+    #[must_use]
+    pub fn with_fx_hasher() -> Self {
+        Self::with_hasher(BuildFxHasher::default())
+    }
+}
+impl<K: IsEnabledNoHash, V> DenseHashMap<K, V, BuildIdentityHasher> {
+    #[must_use]
+    pub fn with_identity_hasher() -> Self {
+        Self::with_hasher(BuildIdentityHasher::default())
+    }
+}
+impl<K, V> DenseHashMap<K, V, SeededState> {
+    #[must_use]
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_hasher(SeededState::new(seed))
+    }
+}
+impl<K, V, H> DenseHashMap<K, V, H> {
+    #[must_use]
+    pub fn with_hasher(hasher: H) -> Self {
         Self {
-            data: DenseFreeList::new(),
-            index: HashMap::new(),
+            inner: Arc::new(Inner {
+                data: DenseFreeList::new(),
+                index: HashMap::with_hasher(hasher),
+            }),
+            unshare: None,
+        }
+    }
+
+    /// Copy the storage first if a [`DenseSnapshot`] still shares it
+    fn inner_mut(&mut self) -> &mut Inner<K, V, H> {
+        if Arc::get_mut(&mut self.inner).is_none() {
+            let unshare = self.unshare.expect("only snapshots share the storage");
+            self.inner = Arc::new((unshare.copy)(&self.inner));
+        }
+        Arc::get_mut(&mut self.inner).unwrap()
+    }
+}
+impl<K, V, H> DenseHashMap<K, V, H>
+where
+    K: Clone + Sync,
+    V: Clone + Sync,
+    H: Clone + Sync,
+{
+    /// Read-only view of the current entries in O(1)
+    ///
+    /// The next mutation of `self` copies the whole storage if the snapshot is still alive by then.
+    ///
+    /// `Sync` is required since `self` can be sent away while the snapshot still reads the same storage:
+    ///
+    /// ```compile_fail
+    /// use std::cell::Cell;
+    /// use primitive::map::dense_hash_map::DenseHashMap;
+    ///
+    /// let mut m = DenseHashMap::<u32, Cell<u32>>::new();
+    /// let _snapshot = m.snapshot();
+    /// ```
+    #[must_use]
+    pub fn snapshot(&mut self) -> DenseSnapshot<K, V, H> {
+        self.unshare = Some(Unshare {
+            copy: Inner::clone,
+            empty: Inner::empty,
+        });
+        DenseSnapshot {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+impl<K, V, H> Clone for DenseHashMap<K, V, H>
+where
+    K: Clone,
+    V: Clone,
+    H: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::new(Inner::clone(&self.inner)),
+            unshare: self.unshare,
         }
     }
 }
-impl<K, V> Default for DenseHashMap<K, V> {
+impl<K, V, H: Default> Default for DenseHashMap<K, V, H> {
     fn default() -> Self {
-        Self::new()
+        Self::with_hasher(H::default())
     }
 }
-impl<K, V> MapInsert<K, V> for DenseHashMap<K, V>
+impl<K, V, H> FromIterator<(K, V)> for DenseHashMap<K, V, H>
 where
-    K: Eq + core::hash::Hash,
+    K: Eq + core::hash::Hash + Clone,
+    H: BuildHasher + Default,
+{
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut map = Self::with_hasher(H::default());
+        map.insert_many(iter);
+        map
+    }
+}
+impl<K, V, H> MapInsert<K, V> for DenseHashMap<K, V, H>
+where
+    K: Eq + core::hash::Hash + Clone,
+    H: BuildHasher,
 {
     type Out = Option<V>;
     /// slower than [`std::collections::HashMap::insert()`]
     fn insert(&mut self, key: K, value: V) -> Option<V> {
-        let Some(&index) = self.index.get(&key) else {
-            let index = self.data.insert(value);
-            self.index.insert(key, index);
+        let inner = self.inner_mut();
+        let Some(&index) = inner.index.get(&key) else {
+            let index = inner.data.insert((key.clone(), value));
+            inner.index.insert(key, index);
             return None;
         };
-        let prev = self.data.remove(index).unwrap();
-        let index = self.data.insert(value);
-        self.index.insert(key, index);
-        Some(prev)
+        let (_, prev) = inner.data.get_mut(index).unwrap();
+        Some(core::mem::replace(prev, value))
+    }
+    /// Reserve both the index and the data for the lower size hint first
+    fn insert_many(&mut self, iter: impl IntoIterator<Item = (K, V)>) {
+        let iter = iter.into_iter();
+        let (additional, _) = iter.size_hint();
+        let inner = self.inner_mut();
+        inner.index.reserve(additional);
+        inner.data.reserve(additional);
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
     }
 }
-impl<K, V> HashRemove<K, V> for DenseHashMap<K, V>
+impl<K, V, H> HashRemove<K, V> for DenseHashMap<K, V, H>
 where
     K: Eq + core::hash::Hash,
+    H: BuildHasher,
 {
     /// slower than [`std::collections::HashMap::remove()`]:
     fn remove<Q>(&mut self, key: &Q) -> Option<V>
@@ -57,82 +204,289 @@ where
         Q: ?Sized + core::hash::Hash + Eq,
         K: core::borrow::Borrow<Q>,
     {
-        let index = self.index.remove(key)?;
-        self.data.remove(index)
+        if !self.inner.index.contains_key(key) {
+            return None;
+        }
+        let inner = self.inner_mut();
+        let index = inner.index.remove(key).unwrap();
+        let (_, value) = inner.data.remove(index).unwrap();
+        Some(value)
     }
 }
-impl<K, V> HashGet<K, V> for DenseHashMap<K, V>
+impl<K, V, H> HashGet<K, V> for DenseHashMap<K, V, H>
 where
     K: Eq + core::hash::Hash,
+    H: BuildHasher,
 {
     /// slower than [`std::collections::HashMap::get()`]:
-    #[must_use]
     fn get<Q>(&self, key: &Q) -> Option<&V>
     where
         Q: ?Sized + core::hash::Hash + Eq,
         K: core::borrow::Borrow<Q>,
     {
-        let index = *self.index.get(key)?;
-        Some(self.data.get(index).unwrap())
+        let index = *self.inner.index.get(key)?;
+        Some(&self.inner.data.get(index).unwrap().1)
     }
 }
-impl<K, V> HashGetMut<K, V> for DenseHashMap<K, V>
+impl<K, V, H> HashGetMut<K, V> for DenseHashMap<K, V, H>
 where
     K: Eq + core::hash::Hash,
+    H: BuildHasher,
 {
     /// slower than [`std::collections::HashMap::get_mut()`]:
-    #[must_use]
     fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
     where
         Q: ?Sized + core::hash::Hash + Eq,
         K: core::borrow::Borrow<Q>,
     {
-        let index = *self.index.get(key)?;
-        Some(self.data.get_mut(index).unwrap())
+        let index = *self.inner.index.get(key)?;
+        Some(&mut self.inner_mut().data.get_mut(index).unwrap().1)
     }
 }
-impl<K, V> DenseHashMap<K, V>
+impl<K, V, H> DenseHashMap<K, V, H>
+where
+    K: Eq + core::hash::Hash + Clone,
+    H: BuildHasher,
+{
+    /// Only build the owned key if `key` is absent
+    ///
+    /// A miss hashes `key` twice, once for the lookup and once for the insertion.
+    /// The owned key is also cloned once since it is stored both in the index and alongside the value.
+    pub fn get_or_insert_with_key<Q>(
+        &mut self,
+        key: &Q,
+        make_key: impl FnOnce(&Q) -> K,
+        make_value: impl FnOnce() -> V,
+    ) -> &mut V
+    where
+        Q: ?Sized + core::hash::Hash + Eq,
+        K: core::borrow::Borrow<Q>,
+    {
+        let index = self.inner.index.get(key).copied();
+        let inner = self.inner_mut();
+        let index = match index {
+            Some(index) => index,
+            None => {
+                let key = make_key(key);
+                let index = inner.data.insert((key.clone(), make_value()));
+                inner.index.insert(key, index);
+                index
+            }
+        };
+        &mut inner.data.get_mut(index).unwrap().1
+    }
+    /// Return the input back if `key` is present
+    pub fn insert_if_absent(&mut self, key: K, value: V) -> Option<(K, V)> {
+        if self.inner.index.contains_key(&key) {
+            return Some((key, value));
+        }
+        let inner = self.inner_mut();
+        let index = inner.data.insert((key.clone(), value));
+        inner.index.insert(key, index);
+        None
+    }
+}
+impl<K, V, H> DenseHashMap<K, V, H>
 where
     K: Eq + core::hash::Hash,
+    H: BuildHasher,
 {
+    /// Same as [`HashGet::get()`] on each of `keys` but with the memory accesses of the whole batch overlapped
+    ///
+    /// The index lookups of all the keys go first, then the entries are prefetched in two rounds before any is read.
+    #[must_use]
+    pub fn get_batch<'a, Q, const B: usize>(&'a self, keys: [&Q; B]) -> [Option<&'a V>; B]
+    where
+        Q: ?Sized + core::hash::Hash + Eq,
+        K: core::borrow::Borrow<Q>,
+    {
+        let inner = &*self.inner;
+        let indices = keys.map(|key| inner.index.get(key).copied());
+        for &index in indices.iter().flatten() {
+            inner.data.prefetch_slot(index);
+        }
+        for &index in indices.iter().flatten() {
+            inner.data.prefetch(index);
+        }
+        indices.map(|index| Some(&inner.data.get(index?).unwrap().1))
+    }
+    /// Keep only the entries for which `pred` returns `true` in a single pass
+    pub fn retain(&mut self, mut pred: impl FnMut(&K, &mut V) -> bool) {
+        let inner = self.inner_mut();
+        let mut cursor = inner.data.cursor_front();
+        while let Some((_, (key, value))) = cursor.current_mut() {
+            if pred(key, value) {
+                cursor.advance();
+                continue;
+            }
+            let (key, _) = cursor.remove_current().unwrap();
+            inner.index.remove(&key).unwrap();
+        }
+    }
     /// always faster than [`std::collections::HashMap::values()`]
     pub fn values(&self) -> impl Iterator<Item = &V> {
-        self.data.iter().map(|(_, value)| value)
+        self.inner.data.iter().map(|(_, (_, value))| value)
     }
     /// always faster than [`std::collections::HashMap::values_mut()`]
     pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
-        self.data.iter_mut().map(|(_, value)| value)
+        self.inner_mut()
+            .data
+            .iter_mut()
+            .map(|(_, (_, value))| value)
     }
     pub fn keys(&self) -> impl Iterator<Item = &K> + Clone {
-        self.index.keys()
+        self.inner.data.iter().map(|(_, (key, _))| key)
     }
-    /// always slower than [`std::collections::HashMap::iter()`]
-    ///
-    /// Consider using [`Self::values()`] instead.
+    /// always faster than [`std::collections::HashMap::iter()`]
     pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + Clone {
-        let indices = self.index.iter();
-        indices.map(|(k, &index)| (k, self.data.get(index).unwrap()))
+        self.inner.data.iter().map(|(_, (key, value))| (key, value))
+    }
+    /// always faster than [`std::collections::HashMap::iter_mut()`]
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        self.inner_mut()
+            .data
+            .iter_mut()
+            .map(|(_, (key, value))| (&*key, value))
     }
 }
-impl<K, V> Len for DenseHashMap<K, V> {
+impl<K, V, H> Len for DenseHashMap<K, V, H> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+impl<K, V, H> Clear for DenseHashMap<K, V, H> {
+    fn clear(&mut self) {
+        match Arc::get_mut(&mut self.inner) {
+            Some(inner) => {
+                inner.data.clear();
+                inner.index.clear();
+            }
+            // Leave the storage to the snapshots rather than copying it only to clear the copy
+            None => {
+                let unshare = self.unshare.expect("only snapshots share the storage");
+                self.inner = Arc::new((unshare.empty)(&self.inner));
+            }
+        }
+    }
+}
+/// Includes the storage shared with the snapshots
+impl<K: HeapSize, V: HeapSize, H> HeapSize for DenseHashMap<K, V, H> {
+    fn heap_size(&self) -> usize {
+        let arc = 2 * core::mem::size_of::<usize>() + core::mem::size_of::<Inner<K, V, H>>();
+        arc + self.inner.data.heap_size() + self.inner.index.heap_size()
+    }
+}
+impl<K, V, H: Clone> Inner<K, V, H> {
+    fn empty(&self) -> Self {
+        Self {
+            data: DenseFreeList::new(),
+            index: HashMap::with_hasher(self.index.hasher().clone()),
+        }
+    }
+}
+impl<K, V, H> Len for Inner<K, V, H> {
     fn len(&self) -> usize {
         assert_eq!(self.data.len(), self.index.len());
         self.data.len()
     }
 }
-impl<K, V> Clear for DenseHashMap<K, V> {
-    fn clear(&mut self) {
-        self.data.clear();
-        self.index.clear();
+
+/// Read-only view taken by [`DenseHashMap::snapshot()`]
+#[derive(Debug)]
+pub struct DenseSnapshot<K, V, H = RandomState> {
+    inner: Arc<Inner<K, V, H>>,
+}
+impl<K, V, H> DenseSnapshot<K, V, H>
+where
+    K: Eq + core::hash::Hash,
+    H: BuildHasher,
+{
+    #[must_use]
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        Q: ?Sized + core::hash::Hash + Eq,
+        K: core::borrow::Borrow<Q>,
+    {
+        let index = *self.inner.index.get(key)?;
+        Some(&self.inner.data.get(index).unwrap().1)
+    }
+}
+impl<K, V, H> DenseSnapshot<K, V, H> {
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.inner.data.iter().map(|(_, (_, value))| value)
+    }
+    pub fn keys(&self) -> impl Iterator<Item = &K> + Clone {
+        self.inner.data.iter().map(|(_, (key, _))| key)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + Clone {
+        self.inner.data.iter().map(|(_, (key, value))| (key, value))
+    }
+}
+impl<K, V, H> Clone for DenseSnapshot<K, V, H> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+impl<K, V, H> Len for DenseSnapshot<K, V, H> {
+    fn len(&self) -> usize {
+        self.inner.len()
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use core::cell::Cell;
+
     use crate::ops::len::LenExt;
 
     use super::*;
 
+    thread_local! {
+        static KEY_ALLOCS: Cell<usize> = const { Cell::new(0) };
+    }
+    /// Hashes the same as [`str`]
+    #[derive(Debug, PartialEq, Eq, Hash)]
+    struct CountedKey(String);
+    impl From<&str> for CountedKey {
+        fn from(value: &str) -> Self {
+            KEY_ALLOCS.set(KEY_ALLOCS.get() + 1);
+            Self(value.to_owned())
+        }
+    }
+    impl Clone for CountedKey {
+        fn clone(&self) -> Self {
+            KEY_ALLOCS.set(KEY_ALLOCS.get() + 1);
+            Self(self.0.clone())
+        }
+    }
+    impl core::borrow::Borrow<str> for CountedKey {
+        fn borrow(&self) -> &str {
+            &self.0
+        }
+    }
+
+    #[test]
+    fn test_get_or_insert_with_key() {
+        let mut m: DenseHashMap<CountedKey, usize> = DenseHashMap::new();
+        *m.get_or_insert_with_key("a", |k| CountedKey::from(k), || 0) += 1;
+        let miss_allocs = KEY_ALLOCS.get();
+        assert_eq!(miss_allocs, 2);
+        for _ in 0..16 {
+            *m.get_or_insert_with_key("a", |k| CountedKey::from(k), || 0) += 1;
+        }
+        assert_eq!(KEY_ALLOCS.get(), miss_allocs);
+        assert_eq!(m.get("a"), Some(&17));
+
+        let rejected = m.insert_if_absent(CountedKey::from("a"), 0);
+        assert_eq!(rejected, Some((CountedKey(String::from("a")), 0)));
+        assert_eq!(m.get("a"), Some(&17));
+        assert_eq!(m.insert_if_absent(CountedKey::from("b"), 1), None);
+        assert_eq!(m.get("b"), Some(&1));
+        assert_eq!(m.len(), 2);
+    }
+
     #[test]
     fn test_dense_hash_map() {
         let mut m = DenseHashMap::new();
@@ -143,4 +497,282 @@ mod tests {
         assert_eq!(m.remove(&0).unwrap(), 1);
         assert!(m.is_empty());
     }
+
+    #[test]
+    fn test_insert_many() {
+        let batch = || (0..200_usize).map(|i| (i * 13 % 71, i));
+        let mut naive = DenseHashMap::with_seed(3);
+        for (k, v) in batch() {
+            naive.insert(k, v);
+        }
+        let mut bulk = DenseHashMap::with_seed(3);
+        bulk.insert_many(batch());
+        assert_eq!(bulk.len(), naive.len());
+        assert!(bulk.iter().eq(naive.iter()));
+        let collected: DenseHashMap<_, _, BuildFxHasher> = batch().collect();
+        assert!(collected.iter().eq(naive.iter()));
+
+        // Copy on write still holds
+        let snapshot = bulk.snapshot();
+        bulk.insert_many([(0, 0), (100, 100)]);
+        assert_eq!(bulk.get(&100), Some(&100));
+        assert_eq!(bulk.get(&0), Some(&0));
+        assert_eq!(snapshot.get(&100), None);
+        assert_eq!(snapshot.get(&0), naive.get(&0));
+    }
+
+    #[test]
+    fn test_remove_then_iter() {
+        const N: usize = 1 << 6;
+
+        let mut m = DenseHashMap::new();
+        for i in 0..N {
+            m.insert(i, i * 2);
+        }
+        for i in (0..N).step_by(3) {
+            assert_eq!(m.remove(&i), Some(i * 2));
+        }
+        m.insert(1, 0);
+        for (k, v) in m.iter_mut() {
+            *v += k;
+        }
+        let mut pairs = m.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>();
+        pairs.sort_unstable();
+        let expected = (0..N)
+            .filter(|i| i % 3 != 0)
+            .map(|i| if i == 1 { (1, 1) } else { (i, i * 3) })
+            .collect::<Vec<_>>();
+        assert_eq!(pairs, expected);
+        assert_eq!(m.keys().count(), m.len());
+        assert_eq!(m.values().count(), m.len());
+        for (k, v) in m.iter() {
+            assert_eq!(m.get(k), Some(v));
+        }
+    }
+
+    #[test]
+    fn test_retain() {
+        const N: usize = 1 << 6;
+
+        let mut m = DenseHashMap::new();
+        for i in 0..N {
+            m.insert(i, i);
+        }
+        let snapshot = m.snapshot();
+        let mut visits = 0;
+        m.retain(|&k, v| {
+            visits += 1;
+            *v *= 2;
+            k % 2 == 1
+        });
+        assert_eq!(visits, N);
+        assert_eq!(m.len(), N / 2);
+        for i in 0..N {
+            let expected = (i % 2 == 1).then_some(i * 2);
+            assert_eq!(m.get(&i).copied(), expected);
+        }
+        assert_eq!(snapshot.len(), N);
+
+        m.retain(|_, _| false);
+        assert!(m.is_empty());
+        m.insert(0, 0);
+        assert_eq!(m.get(&0), Some(&0));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        const N: usize = 1 << 6;
+
+        let mut m = DenseHashMap::new();
+        for i in 0..N {
+            m.insert(i, i);
+        }
+        // no copy without an outstanding snapshot
+        let storage = Arc::as_ptr(&m.inner);
+        m.insert(N, N);
+        m.remove(&N);
+        *m.get_mut(&0).unwrap() += 1;
+        assert_eq!(Arc::as_ptr(&m.inner), storage);
+
+        let snapshot = m.snapshot();
+        assert_eq!(Arc::as_ptr(&snapshot.inner), storage);
+        // reads do not copy
+        assert_eq!(m.get(&0), Some(&1));
+        assert_eq!(m.remove(&N), None);
+        assert_eq!(Arc::as_ptr(&m.inner), storage);
+        // the first mutation copies once
+        m.insert(0, 0);
+        let copied = Arc::as_ptr(&m.inner);
+        assert_ne!(copied, storage);
+        m.remove(&1);
+        m.values_mut().for_each(|v| *v *= 2);
+        assert_eq!(Arc::as_ptr(&m.inner), copied);
+
+        assert_eq!(snapshot.len(), N);
+        assert_eq!(snapshot.get(&0), Some(&1));
+        assert_eq!(snapshot.get(&1), Some(&1));
+        let mut pairs = snapshot.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>();
+        pairs.sort_unstable();
+        let expected = (0..N)
+            .map(|i| (i, i + usize::from(i == 0)))
+            .collect::<Vec<_>>();
+        assert_eq!(pairs, expected);
+        assert_eq!(snapshot.values().sum::<usize>(), (0..N).sum::<usize>() + 1);
+        assert_eq!(m.len(), N - 1);
+        assert_eq!(m.get(&2), Some(&4));
+
+        let snapshot = m.snapshot();
+        m.clear();
+        assert!(m.is_empty());
+        assert_eq!(snapshot.len(), N - 1);
+        // Fresh storage rather than a cleared copy with all the capacity
+        assert_eq!(
+            m.heap_size(),
+            DenseHashMap::<usize, usize>::new().heap_size()
+        );
+        drop(snapshot);
+        let storage = Arc::as_ptr(&m.inner);
+        m.insert(0, 0);
+        assert_eq!(Arc::as_ptr(&m.inner), storage);
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send<T: Send>() {}
+        fn assert_sync<T: Sync>() {}
+        // Send but not Sync
+        assert_send::<DenseHashMap<u32, core::cell::Cell<u32>>>();
+        assert_sync::<DenseHashMap<u32, u32>>();
+        assert_send::<DenseSnapshot<u32, u32>>();
+    }
+
+    #[test]
+    fn test_dense_hash_map_seeded() {
+        const N: usize = 1 << 6;
+
+        let keys = |seed| {
+            let mut m = DenseHashMap::with_seed(seed);
+            for i in 0..N {
+                m.insert(i, i);
+            }
+            m.inner.index.keys().copied().collect::<Vec<_>>()
+        };
+        assert_eq!(keys(1), keys(1));
+        assert_ne!(keys(1), keys(2));
+
+        let mut m = DenseHashMap::with_identity_hasher();
+        m.insert(1, 1);
+        assert_eq!(*m.get(&1).unwrap(), 1);
+        let mut m = DenseHashMap::with_fx_hasher();
+        m.insert(1, 1);
+        assert_eq!(*m.get(&1).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_int_parity() {
+        let mut int: IntDenseHashMap<u32, u32> = IntDenseHashMap::default();
+        let mut random = DenseHashMap::new();
+        let keys = (0..1024_u32).map(|i| i.wrapping_mul(0x9e37_79b9) % 300);
+        for (i, key) in keys.enumerate() {
+            if i % 3 == 0 {
+                assert_eq!(int.remove(&key), random.remove(&key));
+            } else {
+                assert_eq!(int.insert(key, i as u32), random.insert(key, i as u32));
+            }
+            assert_eq!(int.len(), random.len());
+        }
+        for key in 0..300 {
+            assert_eq!(int.get(&key), random.get(&key));
+        }
+        let mut a = int.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
+        let mut b = random.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
+        a.sort_unstable();
+        b.sort_unstable();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_heap_size() {
+        let mut map = DenseHashMap::new();
+        let mut prev = map.heap_size();
+        for i in 0..256_u32 {
+            map.insert(i, i.to_string());
+            assert!(prev <= map.heap_size());
+            prev = map.heap_size();
+        }
+        let strings = (0..256_u32)
+            .map(|i| map.get(&i).unwrap().capacity())
+            .sum::<usize>();
+        assert!(prev >= 256 * (4 + 24 + 4) + strings);
+    }
+
+    #[test]
+    fn test_get_batch() {
+        let mut map = DenseHashMap::new();
+        for i in 0..1 << 6 {
+            map.insert(format!("key/{i}"), i);
+        }
+        for i in (0..1 << 6).step_by(3) {
+            map.remove(format!("key/{i}").as_str());
+        }
+        // Hits, removed keys and never inserted keys
+        let keys = (0..1 << 7).map(|i| format!("key/{i}")).collect::<Vec<_>>();
+        for batch in keys.chunks_exact(8) {
+            let batch: [&str; 8] = core::array::from_fn(|i| batch[i].as_str());
+            let scalar = batch.map(|key| map.get(key));
+            assert_eq!(map.get_batch(batch), scalar);
+        }
+        assert_eq!(map.get_batch(["key/1", "key/1"]), [Some(&1); 2]);
+        assert_eq!(map.get_batch::<str, 0>([]), []);
+    }
+}
+
+#[cfg(feature = "nightly")]
+#[cfg(test)]
+mod benches {
+    use test::Bencher;
+
+    use crate::analysis::rng::{RngLite, SplitMix64};
+
+    use super::*;
+
+    const BATCH: usize = 16;
+    /// Random keys, half of them misses, over a map much larger than the caches
+    fn big_map_and_keys() -> (DenseHashMap<u64, u64>, Vec<u64>) {
+        const LEN: u64 = 1 << 22;
+        let mut map = DenseHashMap::new();
+        for i in 0..LEN {
+            map.insert(i, i);
+        }
+        let mut rng = SplitMix64::new(0);
+        let keys = (0..1 << 12)
+            .map(|_| rng.next_usize_below(LEN as usize * 2) as u64)
+            .collect();
+        (map, keys)
+    }
+    #[bench]
+    fn bench_get_big_scalar(bencher: &mut Bencher) {
+        let (map, keys) = big_map_and_keys();
+        bencher.iter(|| {
+            let mut sum = 0;
+            for key in &keys {
+                sum += map.get(key).copied().unwrap_or(0);
+            }
+            test::black_box(sum)
+        });
+    }
+    #[bench]
+    fn bench_get_big_batch(bencher: &mut Bencher) {
+        let (map, keys) = big_map_and_keys();
+        bencher.iter(|| {
+            let mut sum = 0;
+            for batch in keys.chunks_exact(BATCH) {
+                let batch: [&u64; BATCH] = core::array::from_fn(|i| &batch[i]);
+                for v in map.get_batch(batch) {
+                    sum += v.copied().unwrap_or(0);
+                }
+            }
+            test::black_box(sum)
+        });
+    }
 }
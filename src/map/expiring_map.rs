@@ -2,81 +2,220 @@ use core::{borrow::Borrow, hash::Hash, time::Duration};
 use std::{collections::HashMap, time::Instant};
 
 use crate::{
-    ops::{len::Len, ord_entry::OrdEntry},
+    ops::{clear::Clear, len::Len, ord_entry::OrdEntry},
     queue::ord_queue::OrdQueue,
 };
 
+/// When the deadline of an entry is pushed back to `now + ttl`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExpiryMode {
+    /// Only on the first insertion of the key
+    Absolute,
+    /// On the first insertion of the key and on every [`ExpiringHashMap::get_mut()`]
+    SlidingOnGet,
+    /// On every insertion and every [`ExpiringHashMap::get_mut()`]
+    #[default]
+    SlidingOnGetOrInsert,
+}
+
+/// The `Time` handed back with a value is its deadline; `None` if `now + ttl` overflows `Time` so that the entry never expires
+///
+/// Refreshing a deadline does not touch the expiry queue.
+/// Instead, a queued deadline is re-checked against the stored one at [`Self::cleanup()`] and re-queued if it has been pushed back.
 #[derive(Debug, Clone)]
 pub struct ExpiringHashMap<K, V, Time, Duration> {
-    hash_map: HashMap<K, (Time, V)>,
-    ord_queue: OrdQueue<OrdEntry<Time, K>>,
+    hash_map: HashMap<K, Entry<V, Time, Duration>>,
+    ord_queue: OrdQueue<OrdEntry<Deadline<Time>, K>>,
     duration: Duration,
+    mode: ExpiryMode,
+}
+#[derive(Debug, Clone)]
+struct Entry<V, Time, Duration> {
+    value: V,
+    deadline: Deadline<Time>,
+    /// The deadline this key is queued under; other queued deadlines of the key are obsolete
+    queued: Deadline<Time>,
+    /// Overrides [`ExpiringHashMap::duration`]
+    ttl: Option<Duration>,
 }
 impl<K, V, Time: Ord, Duration> ExpiringHashMap<K, V, Time, Duration> {
     pub fn new(duration: Duration) -> Self {
+        Self::with_mode(duration, ExpiryMode::default())
+    }
+    pub fn with_mode(duration: Duration, mode: ExpiryMode) -> Self {
         Self {
             hash_map: HashMap::new(),
             ord_queue: OrdQueue::new(),
             duration,
+            mode,
         }
     }
-    pub fn insert(&mut self, key: K, value: V, now: Time) -> Option<(V, Time)>
-    where
-        Time: Copy,
-        K: Eq + Hash + Clone,
-    {
-        match self.hash_map.insert(key.clone(), (now, value)) {
-            Some((time, v)) => Some((v, time)),
-            None => {
+    pub fn mode(&self) -> ExpiryMode {
+        self.mode
+    }
+}
+impl<K, V, Time, Duration> ExpiringHashMap<K, V, Time, Duration>
+where
+    K: Eq + Hash + Clone,
+    Time: TravelForwardInTime<Duration = Duration> + Ord + Copy,
+{
+    /// Return the replaced value and its deadline
+    pub fn insert(&mut self, key: K, value: V, now: Time) -> Option<(V, Option<Time>)> {
+        self.insert_entry(key, value, None, now)
+    }
+    /// [`Self::insert()`] with `ttl` instead of the map-wide duration for this entry
+    ///
+    /// An explicit `ttl` always resets the deadline regardless of [`Self::mode()`].
+    pub fn insert_with_ttl(
+        &mut self,
+        key: K,
+        value: V,
+        ttl: Duration,
+        now: Time,
+    ) -> Option<(V, Option<Time>)> {
+        self.insert_entry(key, value, Some(ttl), now)
+    }
+    fn insert_entry(
+        &mut self,
+        key: K,
+        value: V,
+        ttl: Option<Duration>,
+        now: Time,
+    ) -> Option<(V, Option<Time>)> {
+        let Some(entry) = self.hash_map.get_mut(&key) else {
+            let deadline = deadline(now, ttl.as_ref().unwrap_or(&self.duration));
+            self.ord_queue.push(OrdEntry {
+                key: deadline,
+                value: key.clone(),
+            });
+            let entry = Entry {
+                value,
+                deadline,
+                queued: deadline,
+                ttl,
+            };
+            self.hash_map.insert(key, entry);
+            return None;
+        };
+        let prev_deadline = entry.deadline;
+        let reset = ttl.is_some() || self.mode == ExpiryMode::SlidingOnGetOrInsert;
+        if ttl.is_some() {
+            entry.ttl = ttl;
+        }
+        if reset {
+            entry.deadline = deadline(now, entry.ttl.as_ref().unwrap_or(&self.duration));
+        }
+        if entry.deadline < entry.queued {
+            // The queued deadline would be too late to expire the entry in time
+            entry.queued = entry.deadline;
+            self.ord_queue.push(OrdEntry {
+                key: entry.deadline,
+                value: key,
+            });
+        }
+        let prev = core::mem::replace(&mut entry.value, value);
+        Some((prev, prev_deadline.time()))
+    }
+    /// Drop the removed and obsolete keys at the head of the queue and re-queue the pushed-back ones
+    /// until the head is the live entry with the earliest deadline
+    fn settle_head(&mut self) -> Option<Deadline<Time>> {
+        while let Some(OrdEntry {
+            key: queued,
+            value: key,
+        }) = self.ord_queue.peek()
+        {
+            let queued = *queued;
+            let Some(entry) = self.hash_map.get_mut(key) else {
+                // Removed
+                self.ord_queue.pop();
+                continue;
+            };
+            if entry.queued != queued {
+                // Obsolete
+                self.ord_queue.pop();
+                continue;
+            }
+            if queued < entry.deadline {
+                entry.queued = entry.deadline;
+                let OrdEntry { value: key, .. } = self.ord_queue.pop().unwrap();
                 self.ord_queue.push(OrdEntry {
-                    key: now,
+                    key: entry.deadline,
                     value: key,
                 });
-                None
+                continue;
             }
+            return Some(queued);
         }
+        None
     }
-    pub fn cleanup(&mut self, now: Time, mut waste: impl FnMut(K, V, Time))
-    where
-        K: Eq + Hash + Clone,
-        Time: TravelBackInTime<Duration = Duration> + Copy,
-    {
-        let Some(deadline) = now.travel_back_for(&self.duration) else {
-            return;
-        };
-        while let Some(OrdEntry { key: instant, .. }) = self.ord_queue.peek() {
-            if deadline < *instant {
+    /// Pop the head settled by [`Self::settle_head()`]
+    fn pop_head(&mut self) -> (K, V, Deadline<Time>) {
+        let OrdEntry { value: key, .. } = self.ord_queue.pop().unwrap();
+        let entry = self.hash_map.remove(&key).unwrap();
+        (key, entry.value, entry.deadline)
+    }
+    /// Hand the entries whose deadlines are not after `now` to `waste`
+    pub fn cleanup(&mut self, now: Time, mut waste: impl FnMut(K, V, Time)) {
+        while let Some(Deadline::At(deadline)) = self.settle_head() {
+            if now < deadline {
                 return;
             }
-
-            let key = self
-                .ord_queue
-                .pop()
-                .expect("We know it is not empty.")
-                .value;
-
-            let real_instant = self.hash_map[&key].0;
-
-            if deadline < real_instant {
-                self.ord_queue.push(OrdEntry {
-                    key: real_instant,
-                    value: key,
-                });
-            } else {
-                let (_, value) = self.hash_map.remove(&key).unwrap();
-                waste(key, value, real_instant);
-            }
+            let (key, value, _) = self.pop_head();
+            waste(key, value, deadline);
         }
     }
+    /// Remove the entry with the earliest deadline whether or not it has passed
+    ///
+    /// The entries that never expire go last.
+    pub fn pop_earliest(&mut self) -> Option<(K, V, Option<Time>)> {
+        self.settle_head()?;
+        let (key, value, deadline) = self.pop_head();
+        Some((key, value, deadline.time()))
+    }
+    /// The earliest deadline of all the entries, e.g. for when a poll loop should wake up next
+    ///
+    /// Takes `&mut self` to repair the head of the lazily updated expiry queue in amortized O(log n).
+    pub fn next_expiry(&mut self) -> Option<Time> {
+        self.settle_head()?.time()
+    }
+    /// Number of the entries whose deadlines are not after `now + window` in O(n)
+    ///
+    /// Includes the entries already due but not yet cleaned up.
+    pub fn expires_within(&self, window: &Duration, now: Time) -> usize {
+        let end = deadline(now, window);
+        self.hash_map
+            .values()
+            .filter(|entry| entry.deadline != Deadline::Never && entry.deadline <= end)
+            .count()
+    }
+    /// All the entries with their deadlines in ascending order in O(n log n)
+    ///
+    /// Equal deadlines are in key order.
+    ///
+    /// The entries that never expire go last.
+    pub fn iter_by_deadline(&self) -> impl Iterator<Item = (&K, &V, Option<Time>)> + '_
+    where
+        K: Ord,
+    {
+        let mut entries = self
+            .hash_map
+            .iter()
+            .map(|(key, entry)| (key, &entry.value, entry.deadline))
+            .collect::<Vec<_>>();
+        entries.sort_unstable_by(|a, b| a.2.cmp(&b.2).then_with(|| a.0.cmp(b.0)));
+        entries
+            .into_iter()
+            .map(|(key, value, deadline)| (key, value, deadline.time()))
+    }
     pub fn contains_key<Q>(&mut self, key: &Q, now: Time, waste: impl FnMut(K, V, Time)) -> bool
     where
-        K: Borrow<Q> + Eq + Hash + Clone,
-        Q: ?Sized + Eq + core::hash::Hash,
-        Time: TravelBackInTime<Duration = Duration> + Copy,
+        K: Borrow<Q>,
+        Q: ?Sized + Eq + Hash,
     {
         self.cleanup(now, waste);
         self.hash_map.contains_key(key)
     }
+    /// Push the deadline back unless in [`ExpiryMode::Absolute`]
     pub fn get_mut<Q>(
         &mut self,
         key: &Q,
@@ -84,25 +223,26 @@ impl<K, V, Time: Ord, Duration> ExpiringHashMap<K, V, Time, Duration> {
         waste: impl FnMut(K, V, Time),
     ) -> Option<&mut V>
     where
-        K: Borrow<Q> + Eq + Hash + Clone,
-        Q: ?Sized + Eq + core::hash::Hash,
-        Time: TravelBackInTime<Duration = Duration> + Copy,
+        K: Borrow<Q>,
+        Q: ?Sized + Eq + Hash,
     {
         self.cleanup(now, waste);
-        match self.hash_map.get_mut(key) {
-            Some((time, value)) => {
-                *time = now;
-                Some(value)
-            }
-            None => None,
+        let entry = self.hash_map.get_mut(key)?;
+        if self.mode != ExpiryMode::Absolute {
+            let refreshed = deadline(now, entry.ttl.as_ref().unwrap_or(&self.duration));
+            entry.deadline = entry.deadline.max(refreshed);
         }
+        Some(&mut entry.value)
     }
-    pub fn remove<Q>(&mut self, k: &Q) -> Option<(V, Time)>
+    /// Return the value and its deadline
+    pub fn remove<Q>(&mut self, k: &Q) -> Option<(V, Option<Time>)>
     where
-        K: Borrow<Q> + Eq + Hash + Clone,
-        Q: ?Sized + Eq + core::hash::Hash,
+        K: Borrow<Q>,
+        Q: ?Sized + Eq + Hash,
     {
-        self.hash_map.remove(k).map(|(t, v)| (v, t))
+        self.hash_map
+            .remove(k)
+            .map(|entry| (entry.value, entry.deadline.time()))
     }
 }
 impl<K, V, Time, Duration> Len for ExpiringHashMap<K, V, Time, Duration> {
@@ -110,12 +250,76 @@ impl<K, V, Time, Duration> Len for ExpiringHashMap<K, V, Time, Duration> {
         self.hash_map.len()
     }
 }
+impl<K, V, Time, Duration> Clear for ExpiringHashMap<K, V, Time, Duration> {
+    fn clear(&mut self) {
+        self.hash_map.clear();
+        self.ord_queue.clear();
+    }
+}
+/// Expired entries are dropped instead of wasted
+impl<K, V, Time, Duration> super::cache::Cache<K, V> for ExpiringHashMap<K, V, Time, Duration>
+where
+    K: Eq + Hash + Clone,
+    Time: TravelForwardInTime<Duration = Duration> + Ord + Copy,
+{
+    type Time = Time;
+    fn get<Q>(&mut self, key: &Q, now: Time) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq + Hash,
+    {
+        self.get_mut(key, now, |_, _, _| ())
+    }
+    fn insert(&mut self, key: K, value: V, now: Time) {
+        let _ = ExpiringHashMap::insert(self, key, value, now);
+    }
+    fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq + Hash,
+    {
+        ExpiringHashMap::remove(self, key).map(|(value, _)| value)
+    }
+    fn evict_one(&mut self) -> Option<(K, V)> {
+        self.pop_earliest().map(|(key, value, _)| (key, value))
+    }
+}
+/// Orders [`Self::Never`] after any time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Deadline<Time> {
+    At(Time),
+    /// `now + ttl` overflows `Time`, e.g. a TTL of the max duration
+    Never,
+}
+impl<Time> Deadline<Time> {
+    fn time(self) -> Option<Time> {
+        match self {
+            Deadline::At(time) => Some(time),
+            Deadline::Never => None,
+        }
+    }
+}
+fn deadline<Time, Duration>(now: Time, ttl: &Duration) -> Deadline<Time>
+where
+    Time: TravelForwardInTime<Duration = Duration>,
+{
+    now.travel_forward_for(ttl)
+        .map_or(Deadline::Never, Deadline::At)
+}
 
 pub trait TravelBackInTime: Sized {
     type Duration;
     fn travel_back_for(&self, duration: &Self::Duration) -> Option<Self>;
 }
 
+/// What [`ExpiringHashMap`] and [`super::cache::ExpiringLru`] need of `Time` to compute the deadlines
+///
+/// They required [`TravelBackInTime`] before they kept deadlines instead of the access times.
+pub trait TravelForwardInTime: Sized {
+    type Duration;
+    fn travel_forward_for(&self, duration: &Self::Duration) -> Option<Self>;
+}
+
 macro_rules! impl_travel_back_for {
     () => {
         fn travel_back_for(&self, duration: &Self::Duration) -> Option<Self> {
@@ -123,16 +327,31 @@ macro_rules! impl_travel_back_for {
         }
     };
 }
+macro_rules! impl_travel_forward_for {
+    () => {
+        fn travel_forward_for(&self, duration: &Self::Duration) -> Option<Self> {
+            self.checked_add(*duration)
+        }
+    };
+}
 impl TravelBackInTime for Instant {
     type Duration = Duration;
     impl_travel_back_for!();
 }
+impl TravelForwardInTime for Instant {
+    type Duration = Duration;
+    impl_travel_forward_for!();
+}
 macro_rules! same_type_impl_travel_back_in_time {
     ($ty: ident) => {
         impl TravelBackInTime for $ty {
             type Duration = $ty;
             impl_travel_back_for!();
         }
+        impl TravelForwardInTime for $ty {
+            type Duration = $ty;
+            impl_travel_forward_for!();
+        }
     };
 }
 same_type_impl_travel_back_in_time!(Duration);
@@ -146,3 +365,170 @@ same_type_impl_travel_back_in_time!(i16);
 same_type_impl_travel_back_in_time!(i32);
 same_type_impl_travel_back_in_time!(i64);
 same_type_impl_travel_back_in_time!(i128);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type MockMap = ExpiringHashMap<&'static str, u32, u64, u64>;
+
+    fn expired(map: &mut MockMap, now: u64) -> Vec<(&'static str, u64)> {
+        let mut expired = vec![];
+        map.cleanup(now, |k, _, deadline| expired.push((k, deadline)));
+        expired
+    }
+
+    #[test]
+    fn test_absolute() {
+        let mut map = MockMap::with_mode(10, ExpiryMode::Absolute);
+        map.insert("a", 1, 0);
+        assert_eq!(*map.get_mut("a", 9, |_, _, _| ()).unwrap(), 1);
+        // Neither access nor re-insertion extends it
+        assert_eq!(map.insert("a", 2, 9), Some((1, Some(10))));
+        assert_eq!(expired(&mut map, 9), []);
+        assert!(!map.contains_key("a", 10, |_, _, _| ()));
+        assert_eq!(map.len(), 0);
+        map.insert("b", 1, 10);
+        assert_eq!(expired(&mut map, 20), [("b", 20)]);
+    }
+
+    #[test]
+    fn test_sliding() {
+        let mut map = MockMap::with_mode(10, ExpiryMode::SlidingOnGet);
+        map.insert("a", 1, 0);
+        map.insert("b", 1, 0);
+        for now in (5..50).step_by(5) {
+            assert!(map.get_mut("a", now, |_, _, _| ()).is_some());
+        }
+        // Not accessed
+        assert!(!map.contains_key("b", 45, |_, _, _| ()));
+        assert_eq!(map.insert("a", 2, 45), Some((1, Some(55))));
+        assert_eq!(expired(&mut map, 54), []);
+        assert_eq!(expired(&mut map, 55), [("a", 55)]);
+
+        let mut map = MockMap::with_mode(10, ExpiryMode::SlidingOnGetOrInsert);
+        map.insert("a", 1, 0);
+        assert_eq!(map.insert("a", 2, 8), Some((1, Some(10))));
+        assert_eq!(expired(&mut map, 17), []);
+        assert!(map.get_mut("a", 17, |_, _, _| ()).is_some());
+        assert_eq!(expired(&mut map, 26), []);
+        assert_eq!(expired(&mut map, 27), [("a", 27)]);
+    }
+
+    #[test]
+    fn test_ttl_override() {
+        let mut map = MockMap::with_mode(100, ExpiryMode::SlidingOnGet);
+        map.insert("default", 0, 0);
+        map.insert_with_ttl("short", 0, 5, 0);
+        map.insert_with_ttl("long", 0, 200, 0);
+        assert_eq!(expired(&mut map, 5), [("short", 5)]);
+        // Refreshed with its own TTL
+        assert!(map.get_mut("long", 50, |_, _, _| ()).is_some());
+        assert_eq!(expired(&mut map, 100), [("default", 100)]);
+        assert_eq!(expired(&mut map, 249), []);
+        // Shortening the deadline of a queued key still expires it in time
+        assert_eq!(
+            map.insert_with_ttl("long", 1, 10, 100),
+            Some((0, Some(250)))
+        );
+        assert_eq!(expired(&mut map, 110), [("long", 110)]);
+        assert_eq!(map.len(), 0);
+
+        map.insert("a", 0, 0);
+        assert_eq!(map.remove("a"), Some((0, Some(100))));
+        map.insert_with_ttl("a", 0, 300, 10);
+        assert_eq!(expired(&mut map, 200), []);
+        assert_eq!(expired(&mut map, 310), [("a", 310)]);
+    }
+
+    #[test]
+    fn test_next_expiry() {
+        let mut map = MockMap::with_mode(10, ExpiryMode::SlidingOnGet);
+        assert_eq!(map.next_expiry(), None);
+        map.insert("a", 0, 0);
+        map.insert("b", 0, 2);
+        map.insert("c", 0, 2);
+        map.insert_with_ttl("d", 0, 30, 0);
+        assert_eq!(map.next_expiry(), Some(10));
+
+        // Removing the earliest entry leaves its queued deadline behind
+        map.remove("a");
+        assert_eq!(map.next_expiry(), Some(12));
+        map.remove("b");
+        assert_eq!(map.next_expiry(), Some(12));
+        // Pushing back the earliest deadline does not re-queue it until repaired
+        assert!(map.get_mut("c", 5, |_, _, _| ()).is_some());
+        assert_eq!(map.next_expiry(), Some(15));
+        map.insert("e", 0, 4);
+        assert_eq!(map.next_expiry(), Some(14));
+        // Shortening a deadline queues it again
+        map.insert_with_ttl("d", 1, 1, 5);
+        assert_eq!(map.next_expiry(), Some(6));
+        assert_eq!(expired(&mut map, 6), [("d", 6)]);
+        assert_eq!(map.next_expiry(), Some(14));
+        assert_eq!(map.pop_earliest(), Some(("e", 0, Some(14))));
+        assert_eq!(map.pop_earliest(), Some(("c", 0, Some(15))));
+        assert_eq!(map.next_expiry(), None);
+    }
+
+    #[test]
+    fn test_iter_by_deadline() {
+        let mut map = MockMap::with_mode(10, ExpiryMode::SlidingOnGet);
+        for (key, now) in [("z", 3), ("b", 0), ("y", 3), ("a", 5), ("x", 3)] {
+            map.insert(key, 0, now);
+        }
+        assert!(map.get_mut("b", 4, |_, _, _| ()).is_some());
+        // Ties in key order
+        let order = map
+            .iter_by_deadline()
+            .map(|(&k, _, deadline)| (k, deadline))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            [
+                ("x", Some(13)),
+                ("y", Some(13)),
+                ("z", Some(13)),
+                ("b", Some(14)),
+                ("a", Some(15))
+            ]
+        );
+        assert_eq!(map.next_expiry(), Some(13));
+        // A tied earliest entry removed leaves the rest of the tie
+        map.remove("x");
+        assert_eq!(map.next_expiry(), Some(13));
+        map.remove("y");
+        map.remove("z");
+        assert_eq!(map.next_expiry(), Some(14));
+
+        assert_eq!(map.expires_within(&0, 13), 0);
+        assert_eq!(map.expires_within(&1, 13), 1);
+        assert_eq!(map.expires_within(&2, 13), 2);
+        // Already due but not cleaned up
+        assert_eq!(map.expires_within(&0, 20), 2);
+        assert_eq!(map.expires_within(&u64::MAX, 1), 2);
+    }
+
+    #[test]
+    fn test_never_expire() {
+        let mut map = MockMap::with_mode(u64::MAX, ExpiryMode::SlidingOnGet);
+        map.insert("never", 0, 1);
+        map.insert_with_ttl("soon", 0, 10, 1);
+        assert_eq!(map.next_expiry(), Some(11));
+        assert_eq!(expired(&mut map, u64::MAX), [("soon", 11)]);
+        assert_eq!(map.next_expiry(), None);
+        assert!(map.get_mut("never", u64::MAX, |_, _, _| ()).is_some());
+        assert_eq!(map.expires_within(&u64::MAX, 0), 0);
+        assert_eq!(
+            map.iter_by_deadline().collect::<Vec<_>>(),
+            [(&"never", &0, None)]
+        );
+        assert_eq!(map.insert("never", 1, 2), Some((0, None)));
+        // A finite TTL makes it expire again
+        assert_eq!(map.insert_with_ttl("never", 2, 5, 2), Some((1, None)));
+        assert_eq!(map.next_expiry(), Some(7));
+        map.insert("last", 0, 3);
+        assert_eq!(map.pop_earliest(), Some(("never", 2, Some(7))));
+        assert_eq!(map.pop_earliest(), Some(("last", 0, None)));
+    }
+}
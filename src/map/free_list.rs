@@ -1,9 +1,25 @@
-use crate::ops::{clear::Clear, len::Len};
+use core::ops::ControlFlow;
 
+use crate::ops::{
+    clear::Clear,
+    index::define_index,
+    len::{items_heap_size, HeapSize, Len},
+    non_max::NonMaxU32,
+    opt::OptionExt,
+    prefetch::prefetch_read,
+};
+
+define_index!(
+    /// Handed out by a [`FreeList`] and valid until removed from it
+    FreeIndex
+);
+
+/// Holds at most `u32::MAX - 1` (about 4 billion) entries to keep the indices in each entry and slot 4 bytes
 #[derive(Debug, Clone)]
 pub struct DenseFreeList<T> {
     data: Vec<DenseFreeListData<T>>,
-    index: SparseFreeList<usize>,
+    /// `Option<NonMaxU32>` in the sparse slots is a quarter the size of `Option<usize>`
+    index: SparseFreeList<NonMaxU32>,
 }
 impl<T> DenseFreeList<T> {
     #[must_use]
@@ -14,61 +30,91 @@ impl<T> DenseFreeList<T> {
         }
     }
 }
+impl<T> DenseFreeList<T> {
+    /// Make room for at least `additional` more entries
+    pub fn reserve(&mut self, additional: usize) {
+        self.data.reserve(additional);
+        self.index.reserve(additional);
+    }
+}
 impl<T> Default for DenseFreeList<T> {
     fn default() -> Self {
         Self::new()
     }
 }
 impl<T> FreeList<T> for DenseFreeList<T> {
-    #[must_use]
-    fn get(&self, index: usize) -> Option<&T> {
+    fn get(&self, index: FreeIndex) -> Option<&T> {
         let index = self.local_index(index)?;
         Some(&self.data[index].value)
     }
-    #[must_use]
-    fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+    fn get_mut(&mut self, index: FreeIndex) -> Option<&mut T> {
         let index = self.local_index(index)?;
         Some(&mut self.data[index].value)
     }
-    fn iter<'a>(&'a self) -> impl Iterator<Item = (usize, &'a T)> + Clone
+    fn iter<'a>(&'a self) -> impl Iterator<Item = (FreeIndex, &'a T)> + Clone
     where
         T: 'a,
     {
-        self.data.iter().map(|data| (data.user_index, &data.value))
+        self.data
+            .iter()
+            .map(|data| (data.user_index(), &data.value))
     }
-    fn iter_mut<'a>(&'a mut self) -> impl Iterator<Item = (usize, &'a mut T)>
+    fn iter_mut<'a>(&'a mut self) -> impl Iterator<Item = (FreeIndex, &'a mut T)>
     where
         T: 'a,
     {
         self.data
             .iter_mut()
-            .map(|data| (data.user_index, &mut data.value))
+            .map(|data| (data.user_index(), &mut data.value))
     }
 
-    #[must_use]
-    fn insert(&mut self, value: T) -> usize {
-        let index = self.data.len();
+    /// # Panic
+    ///
+    /// The list already holds `u32::MAX - 1` entries
+    fn insert(&mut self, value: T) -> FreeIndex {
+        let index = u32::try_from(self.data.len()).ok().and_then(NonMaxU32::new);
+        let index = index.expect("dense free list is full");
         let user_index = self.index.insert(index);
-        let data = DenseFreeListData { value, user_index };
+        let data = DenseFreeListData {
+            value,
+            // No more slots than entries
+            user_index: user_index.as_usize() as u32,
+        };
         self.data.push(data);
         user_index
     }
-    fn remove(&mut self, index: usize) -> Option<T> {
+    fn remove(&mut self, index: FreeIndex) -> Option<T> {
         let local_index = self.local_index(index)?;
         self.index.remove(index).unwrap();
-        let DenseFreeListData { value, user_index } = self.data.swap_remove(local_index);
-        assert_eq!(user_index, index);
+        let data = self.data.swap_remove(local_index);
+        assert_eq!(data.user_index(), index);
         if let Some(data) = self.data.get(local_index) {
-            let i = self.index.get_mut(data.user_index).unwrap();
-            *i = local_index;
+            let i = self.index.get_mut(data.user_index()).unwrap();
+            *i = NonMaxU32::new(local_index as u32).unwrap();
         }
-        Some(value)
+        Some(data.value)
     }
 }
 impl<T> DenseFreeList<T> {
+    /// Prefetch the slot mapping `index` to its entry
+    ///
+    /// Followed by [`Self::prefetch()`] for the same `index` once the slot is likely in cache.
+    pub fn prefetch_slot(&self, index: FreeIndex) {
+        prefetch_read(self.index.data.as_ptr().wrapping_add(index.as_usize()));
+    }
+    /// Prefetch the entry at `index`; a no-op if `index` is vacant
+    pub fn prefetch(&self, index: FreeIndex) {
+        if let Some(index) = self.local_index(index) {
+            prefetch_read(self.data.as_ptr().wrapping_add(index));
+        }
+    }
+    /// Walk the entries in [`FreeList::iter()`] order while removing some of them
+    pub fn cursor_front(&mut self) -> FreeListCursor<'_, Self> {
+        FreeListCursor { list: self, pos: 0 }
+    }
     #[must_use]
-    fn local_index(&self, index: usize) -> Option<usize> {
-        Some(*self.index.get(index)?)
+    fn local_index(&self, index: FreeIndex) -> Option<usize> {
+        Some(self.index.get(index)?.get() as usize)
     }
 }
 impl<T> Len for DenseFreeList<T> {
@@ -82,10 +128,23 @@ impl<T> Clear for DenseFreeList<T> {
         self.index.clear();
     }
 }
+impl<T: HeapSize> HeapSize for DenseFreeList<T> {
+    fn heap_size(&self) -> usize {
+        self.data.capacity() * core::mem::size_of::<DenseFreeListData<T>>()
+            + items_heap_size(self.data.iter().map(|data| &data.value))
+            + self.index.heap_size()
+    }
+}
 #[derive(Debug, Clone)]
 struct DenseFreeListData<T> {
     pub value: T,
-    pub user_index: usize,
+    pub user_index: u32,
+}
+impl<T> DenseFreeListData<T> {
+    #[must_use]
+    pub fn user_index(&self) -> FreeIndex {
+        FreeIndex::new(self.user_index as usize)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -104,30 +163,47 @@ impl<T> SparseFreeList<T> {
         }
     }
 }
+impl<T> SparseFreeList<T> {
+    /// Make room for at least `additional` more entries
+    pub fn reserve(&mut self, additional: usize) {
+        self.data
+            .reserve(additional.saturating_sub(self.free.len()));
+    }
+}
+impl<T> SparseFreeList<T> {
+    /// Walk the entries in [`FreeList::iter()`] order while removing some of them
+    pub fn cursor_front(&mut self) -> FreeListCursor<'_, Self> {
+        let mut cursor = FreeListCursor { list: self, pos: 0 };
+        cursor.seek();
+        cursor
+    }
+}
 impl<T> Default for SparseFreeList<T> {
     fn default() -> Self {
         Self::new()
     }
 }
 impl<T> FreeList<T> for SparseFreeList<T> {
-    #[must_use]
-    fn get(&self, index: usize) -> Option<&T> {
-        self.data.get(index).and_then(|data| data.as_ref())
+    fn get(&self, index: FreeIndex) -> Option<&T> {
+        self.data
+            .get(index.as_usize())
+            .and_then(|data| data.as_ref())
     }
-    #[must_use]
-    fn get_mut(&mut self, index: usize) -> Option<&mut T> {
-        self.data.get_mut(index).and_then(|data| data.as_mut())
+    fn get_mut(&mut self, index: FreeIndex) -> Option<&mut T> {
+        self.data
+            .get_mut(index.as_usize())
+            .and_then(|data| data.as_mut())
     }
-    fn iter<'a>(&'a self) -> impl Iterator<Item = (usize, &'a T)> + Clone
+    fn iter<'a>(&'a self) -> impl Iterator<Item = (FreeIndex, &'a T)> + Clone
     where
         T: 'a,
     {
         self.data.iter().enumerate().filter_map(|(index, data)| {
             let data = data.as_ref()?;
-            Some((index, data))
+            Some((FreeIndex::new(index), data))
         })
     }
-    fn iter_mut<'a>(&'a mut self) -> impl Iterator<Item = (usize, &'a mut T)>
+    fn iter_mut<'a>(&'a mut self) -> impl Iterator<Item = (FreeIndex, &'a mut T)>
     where
         T: 'a,
     {
@@ -136,25 +212,25 @@ impl<T> FreeList<T> for SparseFreeList<T> {
             .enumerate()
             .filter_map(|(index, data)| {
                 let data = data.as_mut()?;
-                Some((index, data))
+                Some((FreeIndex::new(index), data))
             })
     }
 
-    #[must_use]
-    fn insert(&mut self, value: T) -> usize {
+    fn insert(&mut self, value: T) -> FreeIndex {
         self.count += 1;
         let Some(index) = self.free.pop() else {
             let index = self.data.len();
             self.data.push(Some(value));
-            return index;
+            return FreeIndex::new(index);
         };
-        self.data[index] = Some(value);
-        index
+        let filled = self.data[index].replace_if_none(|| value);
+        debug_assert!(filled);
+        FreeIndex::new(index)
     }
-    fn remove(&mut self, index: usize) -> Option<T> {
-        let value = self.data.get_mut(index)?.take()?;
+    fn remove(&mut self, index: FreeIndex) -> Option<T> {
+        let value = self.data.get_mut(index.as_usize())?.take()?;
         self.count -= 1;
-        self.free.push(index);
+        self.free.push(index.as_usize());
         Some(value)
     }
 }
@@ -170,22 +246,118 @@ impl<T> Clear for SparseFreeList<T> {
         self.count = 0;
     }
 }
+impl<T: HeapSize> HeapSize for SparseFreeList<T> {
+    fn heap_size(&self) -> usize {
+        self.free.heap_size() + self.data.heap_size()
+    }
+}
+
+/// Points at an entry of the list `L` or past its end
+///
+/// On [`DenseFreeList`], [`Self::remove_current()`] moves the last entry into the current position and the cursor visits it next.
+#[derive(Debug)]
+pub struct FreeListCursor<'a, L> {
+    list: &'a mut L,
+    /// Occupied or at the end
+    pos: usize,
+}
+impl<T> FreeListCursor<'_, SparseFreeList<T>> {
+    #[must_use]
+    pub fn current(&self) -> Option<(FreeIndex, &T)> {
+        let value = self.list.data.get(self.pos)?.as_ref().unwrap();
+        Some((FreeIndex::new(self.pos), value))
+    }
+    #[must_use]
+    pub fn current_mut(&mut self) -> Option<(FreeIndex, &mut T)> {
+        let value = self.list.data.get_mut(self.pos)?.as_mut().unwrap();
+        Some((FreeIndex::new(self.pos), value))
+    }
+    /// Remove the current entry and move on to the next one
+    pub fn remove_current(&mut self) -> Option<T> {
+        let value = self.list.remove(FreeIndex::new(self.pos))?;
+        self.advance();
+        Some(value)
+    }
+    /// A no-op at the end
+    pub fn advance(&mut self) {
+        if self.pos < self.list.data.len() {
+            self.pos += 1;
+            self.seek();
+        }
+    }
+    fn seek(&mut self) {
+        while self.list.data.get(self.pos).is_some_and(Option::is_none) {
+            self.pos += 1;
+        }
+    }
+}
+impl<T> FreeListCursor<'_, DenseFreeList<T>> {
+    #[must_use]
+    pub fn current(&self) -> Option<(FreeIndex, &T)> {
+        let data = self.list.data.get(self.pos)?;
+        Some((data.user_index(), &data.value))
+    }
+    #[must_use]
+    pub fn current_mut(&mut self) -> Option<(FreeIndex, &mut T)> {
+        let data = self.list.data.get_mut(self.pos)?;
+        Some((data.user_index(), &mut data.value))
+    }
+    /// Remove the current entry; the last entry takes its place and becomes the current one
+    pub fn remove_current(&mut self) -> Option<T> {
+        let index = self.list.data.get(self.pos)?.user_index();
+        self.list.remove(index)
+    }
+    /// A no-op at the end
+    pub fn advance(&mut self) {
+        if self.pos < self.list.data.len() {
+            self.pos += 1;
+        }
+    }
+}
 
 pub trait FreeList<T>: Len + Clear {
     #[must_use]
-    fn get(&self, index: usize) -> Option<&T>;
+    fn get(&self, index: FreeIndex) -> Option<&T>;
     #[must_use]
-    fn get_mut(&mut self, index: usize) -> Option<&mut T>;
-    fn iter<'a>(&'a self) -> impl Iterator<Item = (usize, &'a T)> + Clone
+    fn get_mut(&mut self, index: FreeIndex) -> Option<&mut T>;
+    fn iter<'a>(&'a self) -> impl Iterator<Item = (FreeIndex, &'a T)> + Clone
     where
         T: 'a;
-    fn iter_mut<'a>(&'a mut self) -> impl Iterator<Item = (usize, &'a mut T)>
+    fn iter_mut<'a>(&'a mut self) -> impl Iterator<Item = (FreeIndex, &'a mut T)>
     where
         T: 'a;
 
     #[must_use]
-    fn insert(&mut self, value: T) -> usize;
-    fn remove(&mut self, index: usize) -> Option<T>;
+    fn insert(&mut self, value: T) -> FreeIndex;
+    fn remove(&mut self, index: FreeIndex) -> Option<T>;
+
+    /// Visit the entries in [`Self::iter()`] order until `f` fails
+    fn try_for_each<E>(&self, mut f: impl FnMut(FreeIndex, &T) -> Result<(), E>) -> Result<(), E> {
+        self.iter().try_for_each(|(index, value)| f(index, value))
+    }
+    /// [`Self::try_for_each()`] over [`Self::iter_mut()`]
+    fn try_for_each_mut<E>(
+        &mut self,
+        mut f: impl FnMut(FreeIndex, &mut T) -> Result<(), E>,
+    ) -> Result<(), E> {
+        self.iter_mut()
+            .try_for_each(|(index, value)| f(index, value))
+    }
+    /// Visit the entries in [`Self::iter()`] order until `f` breaks and return the break value
+    fn for_each_until<B>(&self, mut f: impl FnMut(FreeIndex, &T) -> ControlFlow<B>) -> Option<B> {
+        self.iter()
+            .try_for_each(|(index, value)| f(index, value))
+            .break_value()
+    }
+    /// [`Self::for_each_until()`] over [`Self::iter_mut()`]
+    fn for_each_mut_until<B>(
+        &mut self,
+        mut f: impl FnMut(FreeIndex, &mut T) -> ControlFlow<B>,
+    ) -> Option<B> {
+        self.iter_mut()
+            .try_for_each(|(index, value)| f(index, value))
+            .break_value()
+    }
 }
 
 #[cfg(test)]
@@ -200,6 +372,31 @@ mod tests {
         test_free_list(l);
     }
     #[test]
+    fn test_sparse_slot_size() {
+        assert_eq!(
+            core::mem::size_of::<Option<NonMaxU32>>(),
+            core::mem::size_of::<u32>()
+        );
+        assert_eq!(
+            core::mem::size_of::<DenseFreeListData<u32>>(),
+            2 * core::mem::size_of::<u32>()
+        );
+    }
+    #[test]
+    #[allow(deprecated)]
+    fn test_free_index() {
+        let mut l = SparseFreeList::new();
+        let a = l.insert(1);
+        let b = l.insert(2);
+        assert!(a < b);
+        assert_eq!(b.as_usize(), 1);
+        assert_eq!(usize::from(b), 1);
+        assert_eq!(b.to_string(), "1");
+        assert_eq!(format!("{b:?}"), "FreeIndex(1)");
+        assert_eq!(FreeIndex::from_usize(1), b);
+        assert_eq!(l.get(FreeIndex::from_usize(0)), Some(&1));
+    }
+    #[test]
     fn test_dense() {
         let l = DenseFreeList::new();
         test_free_list(l);
@@ -223,6 +420,200 @@ mod tests {
         assert!(l.get(i_1).is_none());
         assert!(l.is_empty());
     }
+
+    #[test]
+    fn test_try_for_each() {
+        let mut sparse = SparseFreeList::new();
+        let mut dense = DenseFreeList::new();
+        for i in 0..6 {
+            let _ = sparse.insert(i);
+            let _ = dense.insert(i);
+        }
+        sparse.remove(FreeIndex::new(1));
+        dense.remove(FreeIndex::new(1));
+        try_for_each_in(sparse);
+        try_for_each_in(dense);
+    }
+    fn try_for_each_in(mut l: impl FreeList<usize>) {
+        let order = l.iter().map(|(_, &v)| v).collect::<Vec<_>>();
+        let mut seen = vec![];
+        let res = l.try_for_each(|index, &value| {
+            seen.push(value);
+            if value == 3 {
+                Err(index)
+            } else {
+                Ok(())
+            }
+        });
+        // Stops right at the failing entry
+        assert_eq!(l.get(res.unwrap_err()), Some(&3));
+        let stop = order.iter().position(|&v| v == 3).unwrap();
+        assert_eq!(seen, order[..=stop]);
+        assert_eq!(l.try_for_each(|_, _| Ok::<_, ()>(())), Ok(()));
+
+        let res = l.try_for_each_mut(|_, value| {
+            if *value == 4 {
+                return Err(*value);
+            }
+            *value *= 10;
+            Ok(())
+        });
+        assert_eq!(res, Err(4));
+        let stop = order.iter().position(|&v| v == 4).unwrap();
+        let expected = order
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| if i < stop { v * 10 } else { v })
+            .collect::<Vec<_>>();
+        assert!(l.iter().map(|(_, &v)| v).eq(expected));
+
+        let mut visited = 0;
+        let found = l.for_each_until(|index, &value| {
+            visited += 1;
+            if value == 4 {
+                ControlFlow::Break(index)
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert_eq!(l.get(found.unwrap()), Some(&4));
+        assert_eq!(visited, stop + 1);
+        let res = l.for_each_mut_until(|_, value| {
+            *value += 1;
+            ControlFlow::<()>::Continue(())
+        });
+        assert_eq!(res, None);
+        assert!(l.iter().all(|(_, &v)| v != 4));
+    }
+
+    #[test]
+    fn test_cursor_every_other() {
+        let mut sparse = SparseFreeList::new();
+        let mut dense = DenseFreeList::new();
+        for i in 0..8 {
+            let _ = sparse.insert(i);
+            let _ = dense.insert(i);
+        }
+        // A hole up front
+        sparse.remove(FreeIndex::new(0));
+        dense.remove(FreeIndex::new(0));
+
+        let mut cursor = sparse.cursor_front();
+        let mut visited = vec![];
+        while let Some((index, &value)) = cursor.current() {
+            assert_eq!(index.as_usize(), value);
+            visited.push(value);
+            if value % 2 == 0 {
+                assert_eq!(cursor.remove_current(), Some(value));
+            } else {
+                *cursor.current_mut().unwrap().1 *= 10;
+                cursor.advance();
+            }
+        }
+        assert_eq!(visited, [1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(cursor.remove_current(), None);
+        let left = sparse.iter().map(|(_, &v)| v).collect::<Vec<_>>();
+        assert_eq!(left, [10, 30, 50, 70]);
+        assert_eq!(sparse.len(), 4);
+
+        let mut cursor = dense.cursor_front();
+        let mut visited = vec![];
+        while let Some((index, &value)) = cursor.current() {
+            assert_eq!(index.as_usize(), value);
+            visited.push(value);
+            if value % 2 == 0 {
+                assert_eq!(cursor.remove_current(), Some(value));
+            } else {
+                *cursor.current_mut().unwrap().1 *= 10;
+                cursor.advance();
+            }
+        }
+        // Removing 0 moved 7 to the front; removing 2 moved 6 in, then 5 after 6 left
+        assert_eq!(visited, [7, 1, 2, 6, 5, 3, 4]);
+        visited.sort_unstable();
+        assert_eq!(visited, [1, 2, 3, 4, 5, 6, 7]);
+        let mut left = dense
+            .iter()
+            .map(|(i, &v)| (i.as_usize(), v))
+            .collect::<Vec<_>>();
+        left.sort_unstable();
+        assert_eq!(left, [(1, 10), (3, 30), (5, 50), (7, 70)]);
+        assert_eq!(dense.len(), 4);
+    }
+    #[test]
+    fn test_cursor_remove_last() {
+        let mut sparse = SparseFreeList::new();
+        let mut dense = DenseFreeList::new();
+        for i in 0..3 {
+            let _ = sparse.insert(i);
+            let _ = dense.insert(i);
+        }
+
+        let mut cursor = sparse.cursor_front();
+        cursor.advance();
+        cursor.advance();
+        assert_eq!(cursor.remove_current(), Some(2));
+        assert!(cursor.current().is_none());
+        cursor.advance();
+        assert!(cursor.current().is_none());
+        assert_eq!(sparse.len(), 2);
+
+        let mut cursor = dense.cursor_front();
+        cursor.advance();
+        cursor.advance();
+        assert_eq!(cursor.remove_current(), Some(2));
+        assert!(cursor.current().is_none());
+        cursor.advance();
+        assert!(cursor.current().is_none());
+        assert_eq!(dense.len(), 2);
+        assert_eq!(dense.insert(3), FreeIndex::new(2));
+    }
+    #[test]
+    fn test_cursor_remove_first() {
+        let mut sparse = SparseFreeList::new();
+        let mut dense = DenseFreeList::new();
+        for i in 0..3 {
+            let _ = sparse.insert(i);
+            let _ = dense.insert(i);
+        }
+
+        let mut cursor = sparse.cursor_front();
+        assert_eq!(cursor.remove_current(), Some(0));
+        assert_eq!(cursor.current(), Some((FreeIndex::new(1), &1)));
+        assert_eq!(cursor.remove_current(), Some(1));
+        assert_eq!(cursor.remove_current(), Some(2));
+        assert_eq!(cursor.remove_current(), None);
+        assert!(sparse.is_empty());
+        assert!(sparse.cursor_front().current().is_none());
+
+        let mut cursor = dense.cursor_front();
+        assert_eq!(cursor.remove_current(), Some(0));
+        assert_eq!(cursor.current(), Some((FreeIndex::new(2), &2)));
+        assert_eq!(cursor.remove_current(), Some(2));
+        assert_eq!(cursor.remove_current(), Some(1));
+        assert_eq!(cursor.remove_current(), None);
+        assert!(dense.is_empty());
+        assert!(dense.cursor_front().current().is_none());
+    }
+
+    #[test]
+    fn test_heap_size() {
+        let mut sparse = SparseFreeList::new();
+        let a = sparse.insert(String::with_capacity(4));
+        let _ = sparse.insert(String::new());
+        sparse.remove(a);
+        let expected = sparse.free.capacity() * 8 + sparse.data.capacity() * 24;
+        assert_eq!(sparse.heap_size(), expected);
+
+        let mut dense = DenseFreeList::new();
+        let mut prev = dense.heap_size();
+        for i in 0..64_u64 {
+            let _ = dense.insert(i);
+            assert!(prev <= dense.heap_size());
+            prev = dense.heap_size();
+        }
+        assert!(prev >= 64 * (16 + 4));
+    }
 }
 
 #[cfg(feature = "nightly")]
@@ -232,10 +623,28 @@ mod benches {
 
     use slotmap::SlotMap;
 
+    use crate::analysis::rng::{RngLite, SplitMix64};
+
     use super::*;
 
     const N: usize = 2 << 16;
     const VALUE_SIZE: usize = 2 << 5;
+    /// Visit the indices in a seeded shuffle rather than alternating between both ends
+    const RANDOM_ACCESS: bool = true;
+
+    fn access_order(len: usize) -> Vec<usize> {
+        let mut order = (0..len).collect::<Vec<_>>();
+        if RANDOM_ACCESS {
+            SplitMix64::new(0).shuffle(&mut order);
+            return order;
+        }
+        let mut reverse = false;
+        for (i, slot) in order.iter_mut().enumerate() {
+            *slot = if reverse { len - 1 - i } else { i };
+            reverse = !reverse;
+        }
+        order
+    }
 
     struct Value {
         #[allow(dead_code)]
@@ -251,18 +660,15 @@ mod benches {
 
     macro_rules! insert_remove {
         ($bencher: ident, $l: ident) => {
+            let order = access_order(N);
             $bencher.iter(|| {
                 let mut indices = vec![];
                 for _ in 0..N {
                     let index = $l.insert(Value::new());
                     indices.push(index);
                 }
-                let mut reverse = false;
-                for i in 0..indices.len() {
-                    let i = if reverse { indices.len() - 1 - i } else { i };
-                    reverse = !reverse;
-                    let index = indices[i];
-                    $l.remove(index);
+                for &i in &order {
+                    $l.remove(indices[i]);
                 }
             });
         };
@@ -286,21 +692,18 @@ mod benches {
     macro_rules! insert_iter_remove {
         ($bencher: ident, $l: ident) => {
             let n = ((N as f64).sqrt() / 2.).round() as usize;
+            let order = access_order(n);
             $bencher.iter(|| {
                 let mut indices = vec![];
                 for _ in 0..n {
                     let index = $l.insert(Value::new());
                     indices.push(index);
                 }
-                let mut reverse = false;
-                for i in 0..indices.len() {
+                for &i in &order {
                     for v in $l.iter() {
                         black_box(v);
                     }
-                    let i = if reverse { indices.len() - 1 - i } else { i };
-                    reverse = !reverse;
-                    let index = indices[i];
-                    $l.remove(index);
+                    $l.remove(indices[i]);
                 }
             });
         };
@@ -356,13 +759,10 @@ mod benches {
                 let index = $l.insert(Value::new());
                 indices.push(index);
             }
+            let order = access_order(indices.len());
             $bencher.iter(|| {
-                let mut reverse = false;
-                for i in 0..indices.len() {
-                    let i = if reverse { indices.len() - 1 - i } else { i };
-                    reverse = !reverse;
-                    let index = indices[i];
-                    black_box($l.get(index));
+                for &i in &order {
+                    black_box($l.get(indices[i]));
                 }
             });
         };
@@ -72,6 +72,16 @@ where
         self.lookup.insert(key, ptr);
     }
 }
+impl<K, V, const CHUNK_SIZE: usize> FromIterator<(K, V)> for GrowDenseMap<K, V, CHUNK_SIZE>
+where
+    K: core::hash::Hash + Eq,
+{
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut map = Self::new();
+        map.insert_many(iter);
+        map
+    }
+}
 impl<K, V, const CHUNK_SIZE: usize> Len for GrowDenseMap<K, V, CHUNK_SIZE> {
     fn len(&self) -> usize {
         self.lookup.len()
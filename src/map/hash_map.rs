@@ -1,5 +1,9 @@
-use core::{borrow::Borrow, hash::Hash};
-use std::collections::HashMap;
+use core::{
+    borrow::Borrow,
+    hash::{BuildHasher, BuildHasherDefault, Hash, Hasher},
+    marker::PhantomData,
+};
+use std::{collections::HashMap, hash::DefaultHasher};
 
 use super::MapInsert;
 
@@ -38,9 +42,10 @@ pub trait HashEnsure<K, V>: HashGetMut<K, V> + MapInsert<K, V> {
 }
 impl<K, V, T> HashEnsure<K, V> for T where T: HashGetMut<K, V> + MapInsert<K, V> {}
 
-impl<K, V> HashGet<K, V> for HashMap<K, V>
+impl<K, V, S> HashGet<K, V> for HashMap<K, V, S>
 where
     K: Eq + Hash,
+    S: BuildHasher,
 {
     fn get<Q>(&self, key: &Q) -> Option<&V>
     where
@@ -50,9 +55,10 @@ where
         HashMap::get(self, key)
     }
 }
-impl<K, V> HashGetMut<K, V> for HashMap<K, V>
+impl<K, V, S> HashGetMut<K, V> for HashMap<K, V, S>
 where
     K: Eq + Hash,
+    S: BuildHasher,
 {
     fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
     where
@@ -62,9 +68,10 @@ where
         HashMap::get_mut(self, key)
     }
 }
-impl<K, V> HashRemove<K, V> for HashMap<K, V>
+impl<K, V, S> HashRemove<K, V> for HashMap<K, V, S>
 where
     K: Eq + Hash,
+    S: BuildHasher,
 {
     fn remove<Q>(&mut self, key: &Q) -> Option<V>
     where
@@ -74,12 +81,213 @@ where
         HashMap::remove(self, key)
     }
 }
-impl<K, V> MapInsert<K, V> for HashMap<K, V>
+impl<K, V, S> MapInsert<K, V> for HashMap<K, V, S>
 where
     K: Eq + Hash,
+    S: BuildHasher,
 {
     type Out = Option<V>;
     fn insert(&mut self, key: K, value: V) -> Self::Out {
         HashMap::insert(self, key, value)
     }
+    fn insert_many(&mut self, iter: impl IntoIterator<Item = (K, V)>) {
+        self.extend(iter);
+    }
+}
+
+/// Use the integer key itself as the hash
+///
+/// Only valid for integer keys; panics on any other kind of write.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IdentityHasher {
+    hash: u64,
+}
+impl Hasher for IdentityHasher {
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+    fn write(&mut self, _bytes: &[u8]) {
+        panic!("identity hasher only supports integer keys");
+    }
+    fn write_u8(&mut self, i: u8) {
+        self.hash = i.into();
+    }
+    fn write_u16(&mut self, i: u16) {
+        self.hash = i.into();
+    }
+    fn write_u32(&mut self, i: u32) {
+        self.hash = i.into();
+    }
+    fn write_u64(&mut self, i: u64) {
+        self.hash = i;
+    }
+    fn write_u128(&mut self, i: u128) {
+        self.hash = i as u64;
+    }
+    fn write_usize(&mut self, i: usize) {
+        self.hash = i as u64;
+    }
+}
+pub type BuildIdentityHasher = BuildHasherDefault<IdentityHasher>;
+/// [`BuildIdentityHasher`] that only builds for the keys it works on
+///
+/// Neither [`BuildHasher`] nor [`Default`] is there for other keys, so a map keyed by them fails to compile instead of panicking on the first insert:
+///
+/// ```compile_fail
+/// use std::collections::HashMap;
+///
+/// use primitive::map::hash_map::NoHashBuilder;
+///
+/// let _: HashMap<String, u32, _> = HashMap::with_hasher(NoHashBuilder::<String>::default());
+/// ```
+pub struct NoHashBuilder<K> {
+    _key: PhantomData<fn(&K)>,
+}
+impl<K: IsEnabledNoHash> NoHashBuilder<K> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { _key: PhantomData }
+    }
+}
+impl<K: IsEnabledNoHash> Default for NoHashBuilder<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<K: IsEnabledNoHash> BuildHasher for NoHashBuilder<K> {
+    type Hasher = IdentityHasher;
+    fn build_hasher(&self) -> IdentityHasher {
+        IdentityHasher::default()
+    }
+}
+impl<K> Clone for NoHashBuilder<K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<K> Copy for NoHashBuilder<K> {}
+impl<K> core::fmt::Debug for NoHashBuilder<K> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("NoHashBuilder")
+    }
+}
+
+/// Keys whose [`Hash`] only ever writes a single integer so that [`IdentityHasher`] never panics on them
+///
+/// Bounds the `with_identity_hasher()` constructors and [`NoHashBuilder`] so that a `String` key is rejected at compile time.
+pub trait IsEnabledNoHash: Eq + Hash {}
+macro_rules! impl_no_hash {
+    ($($ty: ty),*) => {
+        $(
+            impl IsEnabledNoHash for $ty {}
+        )*
+    };
+}
+impl_no_hash!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+impl_no_hash!(
+    core::num::NonZeroU8,
+    core::num::NonZeroU16,
+    core::num::NonZeroU32,
+    core::num::NonZeroU64,
+    core::num::NonZeroU128,
+    core::num::NonZeroUsize
+);
+
+/// The non-cryptographic hash used in rustc
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FxHasher {
+    hash: u64,
+}
+impl FxHasher {
+    const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+    fn add_to_hash(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(Self::SEED);
+    }
+}
+impl Hasher for FxHasher {
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.add_to_hash(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        for &byte in chunks.remainder() {
+            self.add_to_hash(byte.into());
+        }
+    }
+    fn write_u8(&mut self, i: u8) {
+        self.add_to_hash(i.into());
+    }
+    fn write_u16(&mut self, i: u16) {
+        self.add_to_hash(i.into());
+    }
+    fn write_u32(&mut self, i: u32) {
+        self.add_to_hash(i.into());
+    }
+    fn write_u64(&mut self, i: u64) {
+        self.add_to_hash(i);
+    }
+    fn write_usize(&mut self, i: usize) {
+        self.add_to_hash(i as u64);
+    }
+}
+pub type BuildFxHasher = BuildHasherDefault<FxHasher>;
+
+/// SipHash seeded by a user-defined value
+///
+/// Deterministic across runs of the same build, unlike [`std::hash::RandomState`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SeededState {
+    seed: u64,
+}
+impl SeededState {
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+impl BuildHasher for SeededState {
+    type Hasher = DefaultHasher;
+    fn build_hasher(&self) -> Self::Hasher {
+        let mut hasher = DefaultHasher::new();
+        hasher.write_u64(self.seed);
+        hasher
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_hasher() {
+        let h = BuildIdentityHasher::default();
+        assert_eq!(h.hash_one(3_u64), 3);
+        assert_eq!(h.hash_one(3_usize), 3);
+        assert_eq!(h.hash_one(3_u8), 3);
+        assert_eq!(h.hash_one(3_u128), 3);
+        assert_eq!(h.hash_one(-1_i32), u64::from(u32::MAX));
+        assert_eq!(h.hash_one(crate::ops::unsigned::U5::new(3).unwrap()), 3);
+        let non_max = crate::ops::non_max::NonMax::new(3_u32).unwrap();
+        assert_eq!(h.hash_one(non_max), 3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_identity_hasher_non_integer() {
+        let h = BuildIdentityHasher::default();
+        let _ = h.hash_one("a");
+    }
+
+    #[test]
+    fn test_deterministic_hashers() {
+        let fx = BuildFxHasher::default();
+        assert_eq!(fx.hash_one("abc"), BuildFxHasher::default().hash_one("abc"));
+        assert_ne!(fx.hash_one(1), fx.hash_one(2));
+        let a = SeededState::new(1);
+        assert_eq!(a.hash_one(3), SeededState::new(1).hash_one(3));
+        assert_ne!(a.hash_one(3), SeededState::new(2).hash_one(3));
+    }
 }
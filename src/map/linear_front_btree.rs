@@ -1,16 +1,17 @@
-use core::borrow::Borrow;
+use core::{borrow::Borrow, ops::RangeBounds};
 use std::collections::BTreeMap;
 
 use crate::{
     arena::stack::{Stack, StaticRevStack},
+    iter::{lookahead::Lookahead1, merge::VecLookahead1},
     ops::{
-        len::{Full, Len, LenExt},
+        len::{items_heap_size, Full, HeapSize, Len, LenExt},
         ord_entry::OrdEntry,
         slice::{AsSlice, AsSliceMut, LinearSearch},
     },
 };
 
-use super::MapInsert;
+use super::{merge::merge_ordered, MapInsert};
 
 pub type LinearFrontBTreeMap11<K, V> = LinearFrontBTreeMap<K, V, 11>;
 
@@ -24,6 +25,9 @@ where
     fn insert(&mut self, key: K, value: V) -> Self::Out {
         BTreeMap::insert(self, key, value)
     }
+    fn insert_many(&mut self, iter: impl IntoIterator<Item = (K, V)>) {
+        self.extend(iter);
+    }
 }
 
 /// It is optimal if:
@@ -74,6 +78,23 @@ where
         }
         None
     }
+    /// Sort the batch and build the part past the linear front in bulk
+    fn insert_many(&mut self, iter: impl IntoIterator<Item = (K, V)>) {
+        let mut batch: Vec<(K, V)> = iter.into_iter().collect();
+        // Stable so that the last of the equal keys still wins
+        batch.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let mut batch = batch.into_iter().peekable();
+        while let Some((key, _)) = batch.peek() {
+            if self.btree_first.as_ref().is_some_and(|first| first <= key) {
+                break;
+            }
+            let (key, value) = batch.next().unwrap();
+            self.insert(key, value);
+        }
+        // The rest are all past the linear front
+        let mut rest = BTreeMap::from_iter(batch);
+        self.btree.append(&mut rest);
+    }
 }
 impl<K, V, const N: usize> LinearFrontBTreeMap<K, V, N>
 where
@@ -105,6 +126,20 @@ where
         }
         self.btree.get_mut(key)
     }
+    /// Entries with keys in `range` in ascending key order
+    pub fn range<R>(&self, range: R) -> impl DoubleEndedIterator<Item = (&K, &V)> + '_
+    where
+        R: RangeBounds<K>,
+    {
+        let bounds = (range.start_bound().cloned(), range.end_bound().cloned());
+        let linear_bounds = bounds.clone();
+        self.linear
+            .as_slice()
+            .iter()
+            .filter(move |entry| linear_bounds.contains(&entry.key))
+            .map(|entry| (&entry.key, &entry.value))
+            .chain(self.btree.range(bounds))
+    }
     pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
     where
         Q: Ord + ?Sized,
@@ -151,6 +186,90 @@ where
         }
         self.linear.pop().map(|entry| (entry.key, entry.value))
     }
+    /// Keep only the entries for which `pred` returns `true`
+    pub fn retain(&mut self, mut pred: impl FnMut(&K, &mut V) -> bool) {
+        self.remove_if(|key, value| !pred(key, value), |_| {});
+    }
+    /// Remove and return the entries for which `pred` returns `true` in ascending key order
+    pub fn extract_if(&mut self, pred: impl FnMut(&K, &mut V) -> bool) -> Vec<(K, V)> {
+        let mut extracted = vec![];
+        self.remove_if(pred, |entry| extracted.push(entry));
+        extracted
+    }
+    fn remove_if(
+        &mut self,
+        mut pred: impl FnMut(&K, &mut V) -> bool,
+        mut removed: impl FnMut((K, V)),
+    ) {
+        let mut any_removed = false;
+        let mut i = 0;
+        while i < self.linear.len() {
+            let entry = &mut self.linear.as_slice_mut()[i];
+            if !pred(&entry.key, &mut entry.value) {
+                i += 1;
+                continue;
+            }
+            let entry = self.linear.remove(i);
+            removed((entry.key, entry.value));
+            any_removed = true;
+        }
+        let mut btree_removed = vec![];
+        for (key, value) in self.btree.iter_mut() {
+            if pred(key, value) {
+                btree_removed.push(key.clone());
+            }
+        }
+        for key in btree_removed {
+            let value = self.btree.remove(&key).unwrap();
+            removed((key, value));
+            any_removed = true;
+        }
+        if !any_removed {
+            return;
+        }
+        self.reset_btree_first();
+        self.refill_linear();
+    }
+    /// Entries of both maps in ascending key order; on equal keys, the one of `self` comes first
+    pub fn merge_iter<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = (&'a K, &'a V)> + 'a {
+        merge_ordered(vec![self.iter(), other.iter()])
+    }
+    /// Move all the entries of `other` in
+    ///
+    /// `on_conflict(key, ours, theirs)` makes the value of a key in both maps.
+    ///
+    /// Rebuilds the map in one sorted pass: `O(n + m)`.
+    ///
+    /// If `on_conflict` panics, `self` keeps only the entries merged before the panic; the rest of both maps are dropped.
+    pub fn merge_into(&mut self, other: Self, mut on_conflict: impl FnMut(&K, V, V) -> V) {
+        let ours = Lookahead1::new(core::mem::take(self).into_sorted_entries());
+        let theirs = Lookahead1::new(other.into_sorted_entries());
+        // Ties go to the lower column: ours comes right before theirs
+        let mut merged =
+            VecLookahead1::new(vec![ours, theirs], |a: &(K, V), b: &(K, V)| a.0.cmp(&b.0))
+                .peekable();
+        let mut rest = vec![];
+        while let Some((key, mut value)) = merged.next() {
+            // Each map holds a key at most once
+            if let Some((_, theirs)) = merged.next_if(|(next, _)| *next == key) {
+                value = on_conflict(&key, value, theirs);
+            }
+            if let Some(entry) = self.linear.push(OrdEntry { key, value }) {
+                rest.push((entry.key, entry.value));
+            }
+        }
+        // Already sorted
+        self.btree = BTreeMap::from_iter(rest);
+        self.reset_btree_first();
+    }
+    fn into_sorted_entries(mut self) -> impl Iterator<Item = (K, V)> {
+        let mut linear = Vec::with_capacity(self.linear.len());
+        while let Some(entry) = self.linear.pop() {
+            linear.push((entry.key, entry.value));
+        }
+        linear.reverse();
+        linear.into_iter().chain(self.btree)
+    }
     fn refill_linear(&mut self) {
         let refill_len = (N as f64 * REFILL_RATIO) as usize;
         if refill_len < self.linear.len() {
@@ -206,14 +325,36 @@ impl<K, V, const N: usize> Len for LinearFrontBTreeMap<K, V, N> {
         self.linear.len() + self.btree.len()
     }
 }
+/// The linear part is inline; the B-tree part is approximate as in [`BTreeMap`]'s [`HeapSize`]
+impl<K: HeapSize, V: HeapSize, const N: usize> HeapSize for LinearFrontBTreeMap<K, V, N> {
+    fn heap_size(&self) -> usize {
+        let linear = self.linear.as_slice();
+        self.btree_first.heap_size()
+            + items_heap_size(linear.iter().map(|entry| &entry.key))
+            + items_heap_size(linear.iter().map(|entry| &entry.value))
+            + self.btree.heap_size()
+    }
+}
 impl<K, V, const N: usize> Default for LinearFrontBTreeMap<K, V, N> {
     fn default() -> Self {
         Self::new()
     }
 }
+impl<K, V, const N: usize> FromIterator<(K, V)> for LinearFrontBTreeMap<K, V, N>
+where
+    K: Ord + Clone,
+{
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut map = Self::new();
+        map.insert_many(iter);
+        map
+    }
+}
 
 #[cfg(test)]
 mod tests {
+    use core::ops::Bound;
+
     use super::*;
 
     #[test]
@@ -234,6 +375,193 @@ mod tests {
         }
         assert!(tree.is_empty());
     }
+
+    #[test]
+    fn test_retain_refill() {
+        let end = 21;
+        let mut tree = LinearFrontBTreeMap11::new();
+        for i in 0..end {
+            tree.insert(i, i);
+        }
+        assert_eq!(tree.linear.len(), 11);
+        tree.retain(|k, _| 11 <= *k);
+        assert_eq!(tree.len(), end - 11);
+        assert_eq!(tree.linear.len(), end - 11);
+        assert!(tree.btree.is_empty());
+        assert!(tree.btree_first.is_none());
+        let keys = tree.iter().map(|(k, _)| *k).collect::<Vec<_>>();
+        assert_eq!(keys, (11..end).collect::<Vec<_>>());
+        tree.insert(0, 0);
+        assert_eq!(tree.iter().next(), Some((&0, &0)));
+    }
+
+    #[test]
+    fn test_retain_nothing() {
+        let end = 21;
+        let mut tree = LinearFrontBTreeMap11::new();
+        for i in 0..end {
+            tree.insert(i, i);
+        }
+        tree.retain(|_, _| true);
+        assert_eq!(tree.len(), end);
+        assert_eq!(tree.linear.len(), 11);
+        assert_eq!(tree.btree_first, Some(11));
+        let keys = tree.iter().map(|(k, _)| *k).collect::<Vec<_>>();
+        assert_eq!(keys, (0..end).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_range() {
+        let end = 21;
+        let mut tree = LinearFrontBTreeMap11::new();
+        for i in (0..end).step_by(2) {
+            tree.insert(i, i);
+        }
+        for i in (1..end).step_by(2) {
+            tree.insert(i, i);
+        }
+        let keys =
+            |r: (Bound<usize>, Bound<usize>)| tree.range(r).map(|(k, _)| *k).collect::<Vec<_>>();
+        let expected = |r: (Bound<usize>, Bound<usize>)| {
+            (0..end).filter(|k| r.contains(k)).collect::<Vec<_>>()
+        };
+        for start in 0..end + 2 {
+            for stop in start..end + 2 {
+                for r in [
+                    (Bound::Included(start), Bound::Excluded(stop)),
+                    (Bound::Excluded(start), Bound::Included(stop)),
+                    (Bound::Unbounded, Bound::Included(stop)),
+                    (Bound::Included(start), Bound::Unbounded),
+                ] {
+                    assert_eq!(keys(r), expected(r));
+                }
+            }
+        }
+        assert_eq!(tree.range(..=10).next_back(), Some((&10, &10)));
+        assert_eq!(tree.range(..15).next_back(), Some((&14, &14)));
+    }
+
+    #[test]
+    fn test_extract_if() {
+        let end = 21;
+        let mut tree = LinearFrontBTreeMap11::new();
+        for i in 0..end {
+            tree.insert(i, i);
+        }
+        let extracted = tree.extract_if(|k, _| k % 2 == 0);
+        assert_eq!(
+            extracted,
+            (0..end).step_by(2).map(|i| (i, i)).collect::<Vec<_>>()
+        );
+        assert_eq!(tree.len(), end / 2);
+        let keys = tree.iter().map(|(k, _)| *k).collect::<Vec<_>>();
+        assert_eq!(keys, (1..end).step_by(2).collect::<Vec<_>>());
+        for i in (1..end).step_by(2) {
+            assert_eq!(tree.remove(&i), Some(i));
+        }
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn test_insert_many() {
+        let scattered =
+            |n: usize, seed: usize| (0..n).map(move |i| ((i * 7919 + seed) % 97, i + seed));
+        for prefill in [0, 5, 11, 30] {
+            for batch in [0, 3, 11, 12, 200] {
+                let mut bulk = LinearFrontBTreeMap11::new();
+                let mut naive = LinearFrontBTreeMap11::new();
+                for (k, v) in scattered(prefill, 1) {
+                    bulk.insert(k, v);
+                    naive.insert(k, v);
+                }
+                bulk.insert_many(scattered(batch, 2));
+                for (k, v) in scattered(batch, 2) {
+                    naive.insert(k, v);
+                }
+                assert_eq!(bulk.len(), naive.len());
+                assert!(bulk.iter().eq(naive.iter()));
+                assert!(bulk.linear.is_full() || bulk.btree.is_empty());
+                let linear = bulk.linear.as_slice();
+                assert!(linear.windows(2).all(|w| w[0].key < w[1].key));
+                assert_eq!(bulk.btree_first, bulk.btree.keys().next().copied());
+                if let (Some(last), Some(first)) = (linear.last(), &bulk.btree_first) {
+                    assert!(last.key < *first);
+                }
+            }
+        }
+        let map: LinearFrontBTreeMap11<_, _> = scattered(50, 3).collect();
+        let naive = scattered(50, 3).collect::<BTreeMap<_, _>>();
+        assert!(map.iter().eq(naive.iter()));
+    }
+
+    #[test]
+    fn test_merge_into() {
+        type Map = LinearFrontBTreeMap<u32, String, 4>;
+        // Both maps spill into their B-trees, and the keys interleave across the boundaries
+        let mut ours = (0..10).map(|i| (i * 2, format!("a{i}"))).collect::<Map>();
+        let theirs = (0..10).map(|i| (i * 3, format!("b{i}"))).collect::<Map>();
+        let mut model = ours
+            .iter()
+            .map(|(k, v)| (*k, v.clone()))
+            .collect::<BTreeMap<_, _>>();
+        for (k, v) in theirs.iter() {
+            model
+                .entry(*k)
+                .and_modify(|ours| *ours = format!("{ours}+{v}"))
+                .or_insert_with(|| v.clone());
+        }
+        let merged_view = ours
+            .merge_iter(&theirs)
+            .map(|(k, _)| *k)
+            .collect::<Vec<_>>();
+        assert!(merged_view.is_sorted());
+        assert_eq!(merged_view.len(), 20);
+
+        let mut conflicts = vec![];
+        ours.merge_into(theirs, |k, a, b| {
+            conflicts.push(*k);
+            format!("{a}+{b}")
+        });
+        assert_eq!(conflicts, [0, 6, 12, 18]);
+        assert!(ours.iter().map(|(k, v)| (*k, v.clone())).eq(model.clone()));
+        assert_eq!(ours.len(), model.len());
+        assert!(ours.linear.is_full());
+        assert_eq!(ours.btree_first, ours.btree.keys().next().copied());
+        let linear_last = ours.linear.as_slice().last().unwrap().key;
+        assert!(linear_last < ours.btree_first.unwrap());
+        for (k, v) in &model {
+            assert_eq!(ours.get(k), Some(v));
+        }
+        // Still behaves after the rebuild
+        assert_eq!(ours.remove(&0).as_deref(), Some("a0+b0"));
+        ours.insert(1, String::from("new"));
+        assert_eq!(ours.pop_first(), Some((1, String::from("new"))));
+        assert_eq!(ours.pop_last(), Some((27, String::from("b9"))));
+
+        // Merging into an empty map keeps everything on the linear front if it fits
+        let mut empty = Map::new();
+        empty.merge_into(
+            Map::from_iter([(2, String::new()), (1, String::new())]),
+            |_, _, _| unreachable!(),
+        );
+        assert_eq!(empty.linear.len(), 2);
+        assert!(empty.btree_first.is_none());
+        assert!(empty.iter().map(|(k, _)| *k).eq([1, 2]));
+    }
+
+    #[test]
+    fn test_heap_size() {
+        let mut map = LinearFrontBTreeMap11::new();
+        map.insert(0_u32, String::with_capacity(5));
+        assert_eq!(map.heap_size(), 5);
+        let mut prev = map.heap_size();
+        for i in 1..64 {
+            map.insert(i, String::new());
+            assert!(prev <= map.heap_size());
+            prev = map.heap_size();
+        }
+        assert!(prev > 5);
+    }
 }
 
 #[cfg(feature = "nightly")]
@@ -273,6 +601,31 @@ mod benches {
         insert_remove!(bencher, b);
     }
 
+    const BATCH: usize = 1 << 12;
+    fn scattered_batch() -> Vec<(usize, usize)> {
+        (0..BATCH).map(|i| (i * 7919 % BATCH, i)).collect()
+    }
+    #[bench]
+    fn bench_insert_many_linear_front_btree(bencher: &mut Bencher) {
+        let batch = scattered_batch();
+        bencher.iter(|| {
+            let mut b: LinearFrontBTreeMap<usize, usize, LINEAR> = LinearFrontBTreeMap::new();
+            b.insert_many(batch.iter().copied());
+            black_box(b)
+        });
+    }
+    #[bench]
+    fn bench_insert_loop_linear_front_btree(bencher: &mut Bencher) {
+        let batch = scattered_batch();
+        bencher.iter(|| {
+            let mut b: LinearFrontBTreeMap<usize, usize, LINEAR> = LinearFrontBTreeMap::new();
+            for &(k, v) in &batch {
+                b.insert(k, v);
+            }
+            black_box(b)
+        });
+    }
+
     macro_rules! iter {
         ($bencher: ident, $b: ident) => {
             for i in 0..(LINEAR * 2) {
@@ -0,0 +1,171 @@
+use crate::ops::{clear::Clear, len::Len};
+
+const NIL: u32 = u32::MAX;
+
+/// Doubly-linked list threaded through externally allocated indices
+///
+/// The indices are expected to be dense, e.g. the ones handed out by a [`super::free_list::SparseFreeList`], since the links are stored in a [`Vec`] indexed by them.
+#[derive(Debug, Clone)]
+pub struct IndexLinkedList {
+    nodes: Vec<Option<Node>>,
+    head: u32,
+    tail: u32,
+    len: usize,
+}
+impl IndexLinkedList {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            nodes: vec![],
+            head: NIL,
+            tail: NIL,
+            len: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn contains(&self, index: usize) -> bool {
+        self.node(index).is_some()
+    }
+    #[must_use]
+    pub fn front(&self) -> Option<usize> {
+        link(self.head)
+    }
+    #[must_use]
+    pub fn back(&self) -> Option<usize> {
+        link(self.tail)
+    }
+
+    /// # Panic
+    ///
+    /// If `index` is already in the list
+    pub fn push_front(&mut self, index: usize) {
+        let i = u32::try_from(index).unwrap();
+        assert_ne!(i, NIL);
+        if self.nodes.len() <= index {
+            self.nodes.resize(index + 1, None);
+        }
+        assert!(self.nodes[index].is_none());
+        self.nodes[index] = Some(Node {
+            prev: NIL,
+            next: self.head,
+        });
+        match link(self.head) {
+            Some(head) => self.node_mut(head).prev = i,
+            None => self.tail = i,
+        }
+        self.head = i;
+        self.len += 1;
+    }
+    /// Return `false` if `index` is not in the list
+    pub fn move_to_front(&mut self, index: usize) -> bool {
+        if !self.remove(index) {
+            return false;
+        }
+        self.push_front(index);
+        true
+    }
+    pub fn pop_back(&mut self) -> Option<usize> {
+        let back = self.back()?;
+        self.remove(back);
+        Some(back)
+    }
+    /// Return `false` if `index` is not in the list
+    pub fn remove(&mut self, index: usize) -> bool {
+        let Some(node) = self.nodes.get_mut(index).and_then(|node| node.take()) else {
+            return false;
+        };
+        match link(node.prev) {
+            Some(prev) => self.node_mut(prev).next = node.next,
+            None => self.head = node.next,
+        }
+        match link(node.next) {
+            Some(next) => self.node_mut(next).prev = node.prev,
+            None => self.tail = node.prev,
+        }
+        self.len -= 1;
+        true
+    }
+
+    /// From front to back
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        let mut curr = self.head;
+        core::iter::from_fn(move || {
+            let index = link(curr)?;
+            curr = self.node(index).unwrap().next;
+            Some(index)
+        })
+    }
+
+    fn node(&self, index: usize) -> Option<&Node> {
+        self.nodes.get(index)?.as_ref()
+    }
+    fn node_mut(&mut self, index: usize) -> &mut Node {
+        self.nodes[index].as_mut().unwrap()
+    }
+}
+impl Default for IndexLinkedList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Len for IndexLinkedList {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+impl Clear for IndexLinkedList {
+    fn clear(&mut self) {
+        self.nodes.clear();
+        self.head = NIL;
+        self.tail = NIL;
+        self.len = 0;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    prev: u32,
+    next: u32,
+}
+
+fn link(i: u32) -> Option<usize> {
+    if i == NIL {
+        return None;
+    }
+    Some(i as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_linked_list() {
+        let mut l = IndexLinkedList::new();
+        assert_eq!(l.pop_back(), None);
+        for i in [3, 0, 5] {
+            l.push_front(i);
+        }
+        assert_eq!(l.iter().collect::<Vec<_>>(), [5, 0, 3]);
+        assert_eq!((l.front(), l.back(), l.len()), (Some(5), Some(3), 3));
+        assert!(l.move_to_front(3));
+        assert!(l.move_to_front(3));
+        assert!(!l.move_to_front(1));
+        assert_eq!(l.iter().collect::<Vec<_>>(), [3, 5, 0]);
+        assert!(l.remove(5));
+        assert!(!l.remove(5));
+        assert!(!l.contains(5));
+        assert_eq!(l.iter().collect::<Vec<_>>(), [3, 0]);
+        assert_eq!(l.pop_back(), Some(0));
+        assert_eq!(l.pop_back(), Some(3));
+        assert_eq!(l.pop_back(), None);
+        assert_eq!((l.front(), l.back(), l.len()), (None, None, 0));
+        l.push_front(5);
+        l.push_front(1);
+        l.clear();
+        assert_eq!(l.iter().count(), 0);
+        l.push_front(1);
+        assert_eq!(l.iter().collect::<Vec<_>>(), [1]);
+    }
+}
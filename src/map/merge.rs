@@ -0,0 +1,83 @@
+use crate::iter::{lookahead::Lookahead1, merge::VecLookahead1};
+
+/// Ordered view over sorted `(key, value)` iterators, e.g. of the shards of a map, without collecting them
+///
+/// Keys found in several iterators are all kept; ties go to the lower index in `iters`.
+pub fn merge_ordered<'a, K, V, I>(iters: Vec<I>) -> impl Iterator<Item = (&'a K, &'a V)>
+where
+    K: Ord + 'a,
+    V: 'a,
+    I: Iterator<Item = (&'a K, &'a V)>,
+{
+    let iterators = iters.into_iter().map(Lookahead1::new).collect();
+    VecLookahead1::new(iterators, |a: &(&K, &V), b: &(&K, &V)| a.0.cmp(b.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        map::{linear_front_btree::LinearFrontBTreeMap, MapInsert},
+        queue::seq_queue::BTreeSeqQueue,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_merge_shards() {
+        let mut shards = vec![];
+        for (shard, keys) in [[1, 4, 6, 9].as_slice(), &[2, 4, 5], &[0, 4, 6, 7, 8, 10]]
+            .into_iter()
+            .enumerate()
+        {
+            // Small linear front so that every shard also has a B-tree part
+            let mut map = LinearFrontBTreeMap::<u32, usize, 2>::new();
+            for &key in keys {
+                map.insert(key, shard);
+            }
+            shards.push(map);
+        }
+        let merged = merge_ordered(shards.iter().map(|map| map.iter()).collect())
+            .map(|(&key, &shard)| (key, shard))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            merged,
+            [
+                (0, 2),
+                (1, 0),
+                (2, 1),
+                (4, 0),
+                (4, 1),
+                (4, 2),
+                (5, 1),
+                (6, 0),
+                (6, 2),
+                (7, 2),
+                (8, 2),
+                (9, 0),
+                (10, 2),
+            ]
+        );
+
+        let empty: Vec<core::iter::Empty<(&u32, &usize)>> = vec![];
+        assert_eq!(merge_ordered(empty).count(), 0);
+    }
+
+    #[test]
+    fn test_merge_seq_queue() {
+        let mut map = LinearFrontBTreeMap::<u32, char, 2>::new();
+        for (key, value) in [(1, 'a'), (4, 'b'), (7, 'c')] {
+            map.insert(key, value);
+        }
+        let queue: BTreeSeqQueue<u32, char> = [(5, 'x'), (2, 'y'), (4, 'z')].into_iter().collect();
+        let merged = merge_ordered(vec![
+            Box::new(map.iter()) as Box<dyn Iterator<Item = (&u32, &char)>>,
+            Box::new(queue.iter()),
+        ])
+        .map(|(&key, &value)| (key, value))
+        .collect::<Vec<_>>();
+        assert_eq!(
+            merged,
+            [(1, 'a'), (2, 'y'), (4, 'b'), (4, 'z'), (5, 'x'), (7, 'c')]
+        );
+    }
+}
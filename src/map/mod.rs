@@ -1,14 +1,28 @@
 mod bench;
+pub mod cache;
 pub mod cap_map;
+pub mod counting_map;
 pub mod dense_hash_map;
 pub mod expiring_map;
 pub mod free_list;
 pub mod grow_dense_map;
 pub mod hash_map;
 pub mod linear_front_btree;
+pub mod linked_list;
+pub mod merge;
+pub mod strict_lru;
 pub mod weak_lru;
 
 pub trait MapInsert<K, V> {
     type Out;
     fn insert(&mut self, key: K, value: V) -> Self::Out;
+    /// [`Self::insert()`] the entries in order and drop the outputs
+    fn insert_many(&mut self, iter: impl IntoIterator<Item = (K, V)>)
+    where
+        Self: Sized,
+    {
+        for (key, value) in iter {
+            let _ = self.insert(key, value);
+        }
+    }
 }
@@ -0,0 +1,224 @@
+use core::{
+    borrow::Borrow,
+    hash::{BuildHasher, Hash},
+    num::NonZeroUsize,
+};
+use std::collections::{hash_map::RandomState, HashMap};
+
+use crate::ops::{clear::Clear, len::Len};
+
+use super::{
+    free_list::{FreeIndex, FreeList, SparseFreeList},
+    hash_map::HashGetMut,
+    linked_list::IndexLinkedList,
+    MapInsert,
+};
+
+/// Exact LRU eviction
+///
+/// vs. [`super::weak_lru::WeakLru`]:
+/// - always evicts the least recently used entry
+/// - no wrongful key eviction
+/// - heap allocated
+#[derive(Debug, Clone)]
+pub struct StrictLru<K, V, H = RandomState> {
+    capacity: NonZeroUsize,
+    index: HashMap<K, FreeIndex, H>,
+    data: SparseFreeList<(K, V)>,
+    /// Front is the most recently used
+    order: IndexLinkedList,
+}
+impl<K, V> StrictLru<K, V> {
+    #[must_use]
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self::with_hasher(capacity, RandomState::new())
+    }
+}
+impl<K, V, H> StrictLru<K, V, H> {
+    #[must_use]
+    pub fn with_hasher(capacity: NonZeroUsize, hasher: H) -> Self {
+        Self {
+            capacity,
+            index: HashMap::with_hasher(hasher),
+            data: SparseFreeList::new(),
+            order: IndexLinkedList::new(),
+        }
+    }
+
+    /// From the most to the least recently used; does not count as an access
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.order.iter().map(|i| {
+            let (k, v) = self.data.get(FreeIndex::new(i)).unwrap();
+            (k, v)
+        })
+    }
+}
+impl<K, V, H> StrictLru<K, V, H>
+where
+    K: Eq + Hash + Clone,
+    H: BuildHasher,
+{
+    /// Return the entry pushed out: the previous one under `key` or else the least recently used one if full
+    pub fn insert(&mut self, key: K, value: V) -> Option<(K, V)> {
+        if let Some(&i) = self.index.get(&key) {
+            self.order.move_to_front(i.as_usize());
+            let entry = self.data.get_mut(i).unwrap();
+            let old = core::mem::replace(entry, (key, value));
+            return Some(old);
+        }
+        let evicted = if self.len() == self.capacity.get() {
+            self.pop_lru()
+        } else {
+            None
+        };
+        let i = self.data.insert((key.clone(), value));
+        self.index.insert(key, i);
+        self.order.push_front(i.as_usize());
+        evicted
+    }
+    /// Count as an access
+    #[must_use]
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get_mut(key).map(|v| &*v)
+    }
+    /// Does not count as an access
+    #[must_use]
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let &i = self.index.get(key)?;
+        Some(&self.data.get(i).unwrap().1)
+    }
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        let i = self.order.pop_back()?;
+        let (k, v) = self.data.remove(FreeIndex::new(i)).unwrap();
+        self.index.remove(&k).unwrap();
+        Some((k, v))
+    }
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let i = self.index.remove(key)?;
+        self.order.remove(i.as_usize());
+        Some(self.data.remove(i).unwrap().1)
+    }
+}
+impl<K, V, H> HashGetMut<K, V> for StrictLru<K, V, H>
+where
+    K: Eq + Hash + Clone,
+    H: BuildHasher,
+{
+    /// Count as an access
+    fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let &i = self.index.get(key)?;
+        self.order.move_to_front(i.as_usize());
+        Some(&mut self.data.get_mut(i).unwrap().1)
+    }
+}
+impl<K, V, H> MapInsert<K, V> for StrictLru<K, V, H>
+where
+    K: Eq + Hash + Clone,
+    H: BuildHasher,
+{
+    type Out = Option<(K, V)>;
+    fn insert(&mut self, key: K, value: V) -> Self::Out {
+        StrictLru::insert(self, key, value)
+    }
+}
+impl<K, V, H> Len for StrictLru<K, V, H> {
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+}
+impl<K, V, H> Clear for StrictLru<K, V, H> {
+    fn clear(&mut self) {
+        self.index.clear();
+        self.data.clear();
+        self.order.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::rng::{RngLite, SplitMix64};
+
+    use super::*;
+
+    #[test]
+    fn test_eviction_order() {
+        let mut lru = StrictLru::new(NonZeroUsize::new(3).unwrap());
+        assert_eq!(lru.insert('a', 1), None);
+        assert_eq!(lru.insert('b', 2), None);
+        assert_eq!(lru.insert('c', 3), None);
+        // a is promoted; b is now the least recently used
+        assert_eq!(lru.get(&'a'), Some(&1));
+        assert_eq!(lru.insert('d', 4), Some(('b', 2)));
+        // peek does not promote c
+        assert_eq!(lru.peek(&'c'), Some(&3));
+        assert_eq!(lru.insert('e', 5), Some(('c', 3)));
+        // replacing promotes a
+        assert_eq!(lru.insert('a', 10), Some(('a', 1)));
+        assert_eq!(
+            lru.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>(),
+            [('a', 10), ('e', 5), ('d', 4)]
+        );
+        *lru.get_mut(&'d').unwrap() += 1;
+        assert_eq!(lru.insert('f', 6), Some(('e', 5)));
+        assert_eq!(lru.remove(&'a'), Some(10));
+        assert_eq!(lru.remove(&'a'), None);
+        assert_eq!(lru.len(), 2);
+        assert_eq!(lru.pop_lru(), Some(('d', 5)));
+        assert_eq!(lru.pop_lru(), Some(('f', 6)));
+        assert_eq!(lru.pop_lru(), None);
+        assert!(lru.get(&'f').is_none());
+    }
+
+    #[test]
+    fn test_against_reference() {
+        const CAP: usize = 8;
+        let mut lru = StrictLru::new(NonZeroUsize::new(CAP).unwrap());
+        // Front is the most recently used
+        let mut reference: Vec<(u32, u32)> = vec![];
+        let mut rng = SplitMix64::new(11);
+        for i in 0..1 << 12 {
+            let key = rng.next_usize_below(CAP * 2) as u32;
+            if rng.next_usize_below(2) == 0 {
+                let pos = reference.iter().position(|(k, _)| *k == key);
+                let expected = match pos {
+                    Some(pos) => Some(reference.remove(pos)),
+                    None if reference.len() == CAP => reference.pop(),
+                    None => None,
+                };
+                reference.insert(0, (key, i));
+                assert_eq!(lru.insert(key, i), expected);
+            } else {
+                let pos = reference.iter().position(|(k, _)| *k == key);
+                let expected = pos.map(|pos| {
+                    let entry = reference.remove(pos);
+                    reference.insert(0, entry);
+                    entry.1
+                });
+                assert_eq!(lru.get(&key).copied(), expected);
+            }
+            assert!(lru
+                .iter()
+                .map(|(&k, &v)| (k, v))
+                .eq(reference.iter().copied()));
+        }
+        lru.clear();
+        assert_eq!(lru.len(), 0);
+        assert_eq!(lru.iter().count(), 0);
+    }
+}
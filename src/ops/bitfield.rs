@@ -0,0 +1,217 @@
+/// Transparent wrapper over an unsigned integer with typed accessors to its bit fields
+///
+/// Fields are packed from the LSB in the order they are listed.
+/// A field of width `1` is a [`bool`]; any other field is one of the [`crate::ops::unsigned`] types, which has to be in scope.
+/// Each field names its getter and its setter since `macro_rules!` cannot build the setter name.
+///
+/// ```
+/// use primitive::ops::unsigned::U4;
+///
+/// primitive::bitfield! {
+///     pub struct Flags(u8) {
+///         syn, set_syn: 1,
+///         ack, set_ack: 1,
+///         window, set_window: U4,
+///     }
+/// }
+///
+/// let mut f = Flags::from_bits(0b0010_1001);
+/// assert!(f.syn());
+/// assert!(!f.ack());
+/// assert_eq!(f.window(), U4::new(0b1010).unwrap());
+/// f.set_ack(true);
+/// assert_eq!(f.to_bits(), 0b0010_1011);
+/// ```
+///
+/// The fields must fit in the backing integer:
+///
+/// ```compile_fail
+/// use primitive::ops::unsigned::U4;
+///
+/// primitive::bitfield! {
+///     struct TooWide(u8) {
+///         a, set_a: U4,
+///         b, set_b: U4,
+///         c, set_c: 1,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! bitfield {
+    (
+        $(#[$meta: meta])*
+        $vis: vis struct $name: ident($backing: ty) {
+            $($get: ident, $set: ident: $width: tt),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+        #[repr(transparent)]
+        $vis struct $name($backing);
+        impl $name {
+            #[must_use]
+            pub const fn from_bits(bits: $backing) -> Self {
+                Self(bits)
+            }
+            #[must_use]
+            pub const fn to_bits(self) -> $backing {
+                self.0
+            }
+        }
+        impl core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.debug_struct(stringify!($name))
+                    $(.field(stringify!($get), &$crate::bitfield!(@debug $width, self.$get())))*
+                    .finish()
+            }
+        }
+        $crate::bitfield!(@accessors $name, $backing, 0, $($get, $set: $width,)*);
+    };
+    (@accessors $name: ident, $backing: ty, $offset: expr,) => {
+        const _: () = assert!(
+            $offset <= <$backing>::BITS,
+            "bit fields are wider than the backing integer"
+        );
+    };
+    (@accessors $name: ident, $backing: ty, $offset: expr, $get: ident, $set: ident: 1, $($rest: tt)*) => {
+        impl $name {
+            #[must_use]
+            pub const fn $get(self) -> bool {
+                (self.0 >> $offset) & 1 == 1
+            }
+            pub fn $set(&mut self, value: bool) {
+                let mask: $backing = 1 << $offset;
+                self.0 = (self.0 & !mask) | (<$backing>::from(value) << $offset);
+            }
+        }
+        $crate::bitfield!(@accessors $name, $backing, ($offset + 1), $($rest)*);
+    };
+    (@accessors $name: ident, $backing: ty, $offset: expr, $get: ident, $set: ident: $ty: ident, $($rest: tt)*) => {
+        impl $name {
+            #[must_use]
+            pub const fn $get(self) -> $ty {
+                let mask = <$backing>::MAX >> (<$backing>::BITS - <$ty>::BITS);
+                match <$ty>::new(((self.0 >> $offset) & mask) as _) {
+                    Some(value) => value,
+                    None => unreachable!(),
+                }
+            }
+            pub fn $set(&mut self, value: $ty) {
+                let mask = (<$backing>::MAX >> (<$backing>::BITS - <$ty>::BITS)) << $offset;
+                let value = u128::from(value) as $backing;
+                self.0 = (self.0 & !mask) | (value << $offset);
+            }
+        }
+        $crate::bitfield!(@accessors $name, $backing, ($offset + <$ty>::BITS), $($rest)*);
+    };
+    (@debug 1, $value: expr) => {
+        $value
+    };
+    (@debug $ty: ident, $value: expr) => {
+        u128::from($value)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ops::unsigned::{U3, U4, U9};
+
+    bitfield! {
+        /// The flags word of a TCP header without the data offset
+        struct TcpFlags(u16) {
+            fin, set_fin: 1,
+            syn, set_syn: 1,
+            rst, set_rst: 1,
+            psh, set_psh: 1,
+            ack, set_ack: 1,
+            urg, set_urg: 1,
+            ece, set_ece: 1,
+            cwr, set_cwr: 1,
+            ns, set_ns: 1,
+            reserved, set_reserved: U3,
+            window_scale, set_window_scale: U4,
+        }
+    }
+    bitfield! {
+        struct Full(u16) {
+            low, set_low: U9,
+            mid, set_mid: U4,
+            high, set_high: U3,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut f = TcpFlags::default();
+        f.set_window_scale(U4::MAX);
+        assert_eq!(f.to_bits(), 0xf000);
+        f.set_ns(true);
+        f.set_fin(true);
+        assert_eq!(f.to_bits(), 0xf101);
+        f.set_reserved(U3::MAX);
+        assert_eq!(f.to_bits(), 0xff01);
+        f.set_window_scale(U4::MIN);
+        f.set_reserved(U3::new(0b010).unwrap());
+        assert_eq!(f.to_bits(), 0x0501);
+        assert_eq!(f.reserved(), U3::new(0b010).unwrap());
+        f.set_fin(false);
+        assert!(!f.fin());
+        assert!(f.ns());
+
+        let setters: [fn(&mut TcpFlags, bool); 9] = [
+            TcpFlags::set_fin,
+            TcpFlags::set_syn,
+            TcpFlags::set_rst,
+            TcpFlags::set_psh,
+            TcpFlags::set_ack,
+            TcpFlags::set_urg,
+            TcpFlags::set_ece,
+            TcpFlags::set_cwr,
+            TcpFlags::set_ns,
+        ];
+        for (i, set) in setters.iter().enumerate() {
+            let mut f = TcpFlags::from_bits(0);
+            set(&mut f, true);
+            assert_eq!(f.to_bits(), 1 << i);
+            let mut f = TcpFlags::from_bits(u16::MAX);
+            set(&mut f, false);
+            assert_eq!(f.to_bits(), !(1 << i));
+        }
+
+        let all = TcpFlags::from_bits(u16::MAX);
+        assert!(all.fin() && all.syn() && all.rst() && all.psh() && all.ack());
+        assert!(all.urg() && all.ece() && all.cwr() && all.ns());
+        assert_eq!(all.reserved(), U3::MAX);
+        assert_eq!(all.window_scale(), U4::MAX);
+
+        let mut full = Full::from_bits(0);
+        for (low, mid, high) in [
+            (U9::MAX, U4::MIN, U3::MAX),
+            (U9::MIN, U4::MAX, U3::MIN),
+            (
+                U9::new(0x155).unwrap(),
+                U4::new(0b1010).unwrap(),
+                U3::new(0b101).unwrap(),
+            ),
+        ] {
+            full.set_low(low);
+            full.set_mid(mid);
+            full.set_high(high);
+            assert_eq!((full.low(), full.mid(), full.high()), (low, mid, high));
+            assert_eq!(Full::from_bits(full.to_bits()), full);
+        }
+        assert_eq!(full.to_bits(), 0b1011_0101_0101_0101);
+    }
+
+    #[test]
+    fn test_debug() {
+        let mut f = TcpFlags::from_bits(0);
+        f.set_syn(true);
+        f.set_ack(true);
+        f.set_window_scale(U4::new(7).unwrap());
+        assert_eq!(
+            format!("{f:?}"),
+            "TcpFlags { fin: false, syn: true, rst: false, psh: false, ack: true, urg: false, ece: false, cwr: false, ns: false, reserved: 0, window_scale: 7 }"
+        );
+    }
+}
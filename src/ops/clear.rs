@@ -1,3 +1,5 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
 pub trait Clear {
     fn clear(&mut self);
 }
@@ -6,3 +8,170 @@ impl<T> Clear for Vec<T> {
         self.clear();
     }
 }
+impl Clear for String {
+    fn clear(&mut self) {
+        self.clear();
+    }
+}
+impl<K, V, S> Clear for HashMap<K, V, S> {
+    fn clear(&mut self) {
+        self.clear();
+    }
+}
+impl<K, V> Clear for BTreeMap<K, V> {
+    fn clear(&mut self) {
+        self.clear();
+    }
+}
+impl<T> Clear for VecDeque<T> {
+    fn clear(&mut self) {
+        self.clear();
+    }
+}
+
+/// Clear every element but keep it, so the allocations of both the container and the elements are reused
+pub trait DeepClear {
+    fn deep_clear(&mut self);
+}
+impl<T: Clear> DeepClear for [T] {
+    fn deep_clear(&mut self) {
+        self.iter_mut().for_each(Clear::clear);
+    }
+}
+impl<T: Clear, const N: usize> DeepClear for [T; N] {
+    fn deep_clear(&mut self) {
+        self.as_mut_slice().deep_clear();
+    }
+}
+impl<T: Clear> DeepClear for Vec<T> {
+    fn deep_clear(&mut self) {
+        self.as_mut_slice().deep_clear();
+    }
+}
+impl<T: Clear> DeepClear for Option<T> {
+    fn deep_clear(&mut self) {
+        if let Some(x) = self {
+            x.clear();
+        }
+    }
+}
+impl<T: Clear + ?Sized> DeepClear for Box<T> {
+    fn deep_clear(&mut self) {
+        self.as_mut().clear();
+    }
+}
+macro_rules! impl_deep_clear_tuple {
+    ($($ty: ident $i: tt),*) => {
+        impl<$($ty: Clear),*> DeepClear for ($($ty,)*) {
+            fn deep_clear(&mut self) {
+                $(self.$i.clear();)*
+            }
+        }
+    };
+}
+impl_deep_clear_tuple!(A 0);
+impl_deep_clear_tuple!(A 0, B 1);
+impl_deep_clear_tuple!(A 0, B 1, C 2);
+impl_deep_clear_tuple!(A 0, B 1, C 2, D 3);
+
+/// Implement [`Clear`] by clearing the listed fields
+///
+/// ```
+/// use primitive::ops::clear::Clear;
+///
+/// struct Buffers {
+///     bytes: Vec<u8>,
+///     text: String,
+///     id: u32,
+/// }
+/// primitive::impl_clear_fields!(Buffers { bytes, text });
+///
+/// let mut b = Buffers { bytes: vec![1], text: "a".into(), id: 1 };
+/// b.clear();
+/// assert!(b.bytes.is_empty() && b.text.is_empty());
+/// assert_eq!(b.id, 1);
+/// ```
+#[macro_export]
+macro_rules! impl_clear_fields {
+    ($ty: ty { $($field: ident),* $(,)? }) => {
+        impl $crate::ops::clear::Clear for $ty {
+            fn clear(&mut self) {
+                $($crate::ops::clear::Clear::clear(&mut self.$field);)*
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        map::{dense_hash_map::DenseHashMap, MapInsert},
+        ops::len::LenExt,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_capacity_preserved() {
+        let mut v = Vec::with_capacity(16);
+        v.extend([1, 2, 3]);
+        Clear::clear(&mut v);
+        assert!(v.is_empty());
+        assert_eq!(v.capacity(), 16);
+
+        let mut s = String::with_capacity(16);
+        s.push_str("abc");
+        Clear::clear(&mut s);
+        assert!(s.is_empty());
+        assert_eq!(s.capacity(), 16);
+    }
+
+    #[test]
+    fn test_deep_clear() {
+        let mut maps = vec![DenseHashMap::new(), DenseHashMap::new()];
+        maps[0].insert(1, 'a');
+        maps[1].insert(2, 'b');
+        maps.deep_clear();
+        assert_eq!(maps.len(), 2);
+        assert!(maps.iter().all(LenExt::is_empty));
+
+        let mut bufs = [String::with_capacity(8), String::from("a")];
+        bufs[0].push('b');
+        bufs.deep_clear();
+        assert!(bufs.iter().all(String::is_empty));
+        assert_eq!(bufs[0].capacity(), 8);
+
+        let mut tuple = (vec![1], String::from("a"), VecDeque::from([3]));
+        tuple.deep_clear();
+        assert!(tuple.0.is_empty() && tuple.1.is_empty() && tuple.2.is_empty());
+
+        let mut some = Some(vec![2]);
+        some.deep_clear();
+        assert_eq!(some, Some(vec![]));
+
+        let mut boxed = Box::new(BTreeMap::from([(1, 2)]));
+        boxed.deep_clear();
+        assert!(boxed.is_empty());
+    }
+
+    struct Fields {
+        a: Vec<u8>,
+        b: HashMap<u8, u8>,
+        c: u8,
+    }
+    impl_clear_fields!(Fields { a, b });
+
+    #[test]
+    fn test_impl_clear_fields() {
+        let mut f = Fields {
+            a: Vec::with_capacity(4),
+            b: HashMap::from([(1, 1)]),
+            c: 1,
+        };
+        f.a.push(1);
+        f.clear();
+        assert!(f.a.is_empty() && f.b.is_empty());
+        assert_eq!(f.a.capacity(), 4);
+        assert_eq!(f.c, 1);
+    }
+}
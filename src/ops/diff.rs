@@ -1,6 +1,12 @@
 use num_traits::{CheckedAdd, CheckedSub};
 
-use super::wrap::{Map, TransposeOption, TransposeResult};
+use crate::set::bit_set::StaticBitSet;
+
+use super::{
+    clear::Clear,
+    len::Len,
+    wrap::{Map, TransposeOption, TransposeResult},
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Diff<U> {
@@ -68,6 +74,107 @@ pub trait DiffExt: CheckedAdd + CheckedSub + Ord {
 }
 impl<T> DiffExt for T where T: CheckedAdd + CheckedSub + Ord {}
 
+/// Value that remembers whether it has been mutated since the last [`Self::take_dirty()`]
+///
+/// Only [`Self::get_mut()`] and [`Self::set()`] mark it dirty; there is no [`core::ops::DerefMut`] so that a read never does.
+#[derive(Debug, Clone, Default)]
+pub struct Tracked<T> {
+    value: T,
+    dirty: bool,
+}
+impl<T> Tracked<T> {
+    /// Start clean
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            value,
+            dirty: false,
+        }
+    }
+    #[must_use]
+    pub const fn get(&self) -> &T {
+        &self.value
+    }
+    /// Mark dirty even if nothing ends up written
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.dirty = true;
+        &mut self.value
+    }
+    pub fn set(&mut self, value: T) {
+        self.dirty = true;
+        self.value = value;
+    }
+    pub fn mark(&mut self) {
+        self.dirty = true;
+    }
+    #[must_use]
+    pub const fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+    /// The value if it is dirty, which clears the flag
+    pub fn take_dirty(&mut self) -> Option<&T> {
+        if !core::mem::take(&mut self.dirty) {
+            return None;
+        }
+        Some(&self.value)
+    }
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+impl<T> core::ops::Deref for Tracked<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+/// Dirty flags of up to `WORDS * usize::BITS` fields, usually indexed by a field enum convertible into [`usize`]
+#[derive(Debug, Clone, Default)]
+pub struct DirtySet<const WORDS: usize> {
+    flags: StaticBitSet<WORDS>,
+}
+impl<const WORDS: usize> DirtySet<WORDS> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            flags: StaticBitSet::new(),
+        }
+    }
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.flags.capacity()
+    }
+    /// # Panic
+    ///
+    /// `field` is out of [`Self::capacity()`]
+    pub fn mark(&mut self, field: impl Into<usize>) {
+        self.flags.set(field.into());
+    }
+    #[must_use]
+    pub fn is_marked(&self, field: impl Into<usize>) -> bool {
+        self.flags.get(field.into())
+    }
+    /// Indices of the marked fields in ascending order
+    ///
+    /// All the flags are cleared right away, even if the iterator is not run to the end.
+    pub fn drain(&mut self) -> impl Iterator<Item = usize> {
+        let flags = core::mem::take(&mut self.flags);
+        (0..flags.capacity()).filter(move |&i| flags.get(i))
+    }
+}
+impl<const WORDS: usize> Len for DirtySet<WORDS> {
+    fn len(&self) -> usize {
+        self.flags.len()
+    }
+}
+impl<const WORDS: usize> Clear for DirtySet<WORDS> {
+    fn clear(&mut self) {
+        self.flags.clear();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -79,4 +186,63 @@ mod tests {
         let c = a.sub_diff(b);
         assert_eq!(b.add_diff(c).unwrap(), a);
     }
+
+    #[test]
+    fn test_tracked() {
+        let mut t = Tracked::new(vec![1]);
+        assert_eq!(t.len(), 1);
+        assert_eq!(t.get(), &[1]);
+        assert!(!t.is_dirty());
+        assert_eq!(t.take_dirty(), None);
+
+        t.get_mut().push(2);
+        assert!(t.is_dirty());
+        assert_eq!(t.take_dirty().map(Vec::as_slice), Some(&[1, 2][..]));
+        assert!(!t.is_dirty());
+        assert_eq!(t.take_dirty(), None);
+
+        t.set(vec![]);
+        assert_eq!(t.take_dirty().map(Vec::len), Some(0));
+        t.mark();
+        assert!(t.take_dirty().is_some());
+        assert_eq!(t.into_inner(), []);
+    }
+
+    #[test]
+    fn test_dirty_set() {
+        #[derive(Debug, Clone, Copy)]
+        enum Field {
+            Position = 0,
+            Velocity = 1,
+            // In the second word on every target
+            Health = usize::BITS as isize + 6,
+        }
+        impl From<Field> for usize {
+            fn from(value: Field) -> Self {
+                value as usize
+            }
+        }
+
+        let mut d = DirtySet::<2>::new();
+        assert_eq!(d.capacity(), 2 * usize::BITS as usize);
+        assert!(!d.is_marked(Field::Position));
+        assert_eq!(d.drain().count(), 0);
+
+        d.mark(Field::Health);
+        d.mark(Field::Position);
+        d.mark(Field::Health);
+        assert_eq!(d.len(), 2);
+        assert!(d.is_marked(Field::Health));
+        assert!(!d.is_marked(Field::Velocity));
+        assert_eq!(d.drain().collect::<Vec<_>>(), [0, Field::Health.into()]);
+        assert_eq!(d.len(), 0);
+        assert_eq!(d.drain().count(), 0);
+
+        d.mark(Field::Velocity);
+        drop(d.drain());
+        assert_eq!(d.len(), 0);
+        d.mark(Field::Velocity);
+        d.clear();
+        assert!(!d.is_marked(Field::Velocity));
+    }
 }
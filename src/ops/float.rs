@@ -1,6 +1,7 @@
 use core::{fmt::Debug, marker::PhantomData};
 
 use num_traits::Float;
+use thiserror::Error;
 
 use super::opt::Opt;
 
@@ -52,6 +53,37 @@ impl<F: Float, W: WrapNonNan<F>> Opt<W> for OptR<F, W> {
         res
     }
 }
+impl<F: Float, W: WrapNonNan<F>> OptR<F, W> {
+    #[must_use]
+    pub fn is_none(&self) -> bool {
+        self.v.is_nan()
+    }
+    #[must_use]
+    pub fn is_some(&self) -> bool {
+        !self.is_none()
+    }
+    #[must_use]
+    pub fn unwrap_or(&self, default: W) -> W {
+        self.get().unwrap_or(default)
+    }
+    /// Same as [`Option::and_then()`]
+    #[must_use]
+    pub fn map_or_none(&self, f: impl FnOnce(W) -> Option<W>) -> Self {
+        self.get().and_then(f).into()
+    }
+    /// Add `w` to the value or take `w` as the value if there is none
+    ///
+    /// # Panic
+    ///
+    /// The sum is out of the range of `W`
+    pub fn add_assign_some(&mut self, w: W) {
+        let sum = match self.get() {
+            Some(v) => W::new(v.get() + w.get()).expect("sum out of range"),
+            None => w,
+        };
+        *self = Self::some(sum);
+    }
+}
 impl<F: Float, W: WrapNonNan<F>> From<Option<W>> for OptR<F, W> {
     fn from(value: Option<W>) -> Self {
         value.map(|v| Self::some(v)).unwrap_or(Self::none())
@@ -129,6 +161,19 @@ impl<F: Copy> UnitR<F> {
         self.v.get()
     }
 }
+impl<F: Float> UnitR<F> {
+    /// `1 - x`
+    #[must_use]
+    pub fn complement(self) -> Self {
+        unsafe { Self::new_unchecked(F::one() - self.get()) }
+    }
+}
+impl<F: Float> core::ops::Mul for UnitR<F> {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self::Output {
+        unsafe { Self::new_unchecked(self.get() * rhs.get()) }
+    }
+}
 impl_ord_traits!(UnitR, v);
 impl_fmt_traits!(UnitR, v);
 impl<F: Float> WrapNonNan<F> for UnitR<F> {
@@ -169,6 +214,17 @@ impl<F: Copy> NonNegR<F> {
         self.v.get()
     }
 }
+impl<F: Float> NonNegR<F> {
+    /// Return [`None`] if the sum overflows to infinity
+    #[must_use]
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        Self::new(self.get() + other.get())
+    }
+    #[must_use]
+    pub fn sqrt(self) -> Self {
+        unsafe { Self::new_unchecked(self.get().sqrt()) }
+    }
+}
 impl_ord_traits!(NonNegR, v);
 impl_fmt_traits!(NonNegR, v);
 impl<F: Float> WrapNonNan<F> for NonNegR<F> {
@@ -209,6 +265,13 @@ impl<F: Copy> PosR<F> {
         self.v.get()
     }
 }
+impl<F: Float> PosR<F> {
+    /// Return [`None`] if the product overflows to infinity or underflows to zero
+    #[must_use]
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        Self::new(self.get() * other.get())
+    }
+}
 impl_ord_traits!(PosR, v);
 impl_fmt_traits!(PosR, v);
 impl<F: Float> WrapNonNan<F> for PosR<F> {
@@ -294,6 +357,55 @@ impl<F: Float> WrapNonNan<F> for NonNanF<F> {
     }
 }
 
+/// Linear interpolation from `a` to `b`
+///
+/// The result is clamped between `a` and `b` so rounding never escapes the finite range.
+#[must_use]
+pub fn lerp<F: Float>(a: R<F>, b: R<F>, t: UnitR<F>) -> R<F> {
+    let (a, b, t) = (a.get(), b.get(), t.get());
+    let v = a * (F::one() - t) + b * t;
+    let v = v.max(a.min(b)).min(a.max(b));
+    unsafe { R::new_unchecked(v) }
+}
+
+/// Sort by [`f64::total_cmp()`]: negative NaNs first, positive NaNs last and `-0.` before `0.`
+pub fn sort_floats(floats: &mut [f64]) {
+    floats.sort_unstable_by(f64::total_cmp);
+}
+/// Stable sort by the float `key` in the order of [`sort_floats()`]
+pub fn sort_by_float_key<T>(slice: &mut [T], mut key: impl FnMut(&T) -> f64) {
+    slice.sort_by(|a, b| key(a).total_cmp(&key(b)));
+}
+
+#[derive(Debug, Clone, Copy, Error)]
+#[error("{value} is out of the range {range}")]
+pub struct OutOfRange<F> {
+    pub value: F,
+    pub range: &'static str,
+}
+macro_rules! wrapper_try_from {
+    ($wrapper: ident, $range: literal, $float: ty) => {
+        impl TryFrom<$float> for $wrapper<$float> {
+            type Error = OutOfRange<$float>;
+            fn try_from(value: $float) -> Result<Self, Self::Error> {
+                Self::new(value).ok_or(OutOfRange {
+                    value,
+                    range: $range,
+                })
+            }
+        }
+    };
+    ($wrapper: ident, $range: literal) => {
+        wrapper_try_from!($wrapper, $range, f32);
+        wrapper_try_from!($wrapper, $range, f64);
+    };
+}
+wrapper_try_from!(UnitR, "[0, 1]");
+wrapper_try_from!(NonNegR, "[0, inf)");
+wrapper_try_from!(PosR, "(0, inf)");
+wrapper_try_from!(R, "(-inf, inf)");
+wrapper_try_from!(NonNanF, "non-NAN");
+
 macro_rules! wrapper_from_to {
     ($from: ident, $to: ident) => {
         impl<F: Float> From<$from<F>> for $to<F> {
@@ -329,6 +441,66 @@ mod tests {
         assert_eq!(a.map(|x| x.get()), [0., 0.1, 0.1, 1.]);
     }
 
+    #[test]
+    fn test_closed_ops() {
+        let zero = UnitR::new(0.).unwrap();
+        let one = UnitR::new(1.).unwrap();
+        let half = UnitR::new(0.5).unwrap();
+        let sub = UnitR::new(f64::MIN_POSITIVE / 2.).unwrap();
+        assert_eq!((half * half).get(), 0.25);
+        assert_eq!((one * one).get(), 1.);
+        assert_eq!((zero * one).get(), 0.);
+        assert_eq!((sub * sub).get(), 0.);
+        assert_eq!(zero.complement(), one);
+        assert_eq!(one.complement(), zero);
+        assert_eq!(sub.complement(), one);
+
+        let max = NonNegR::new(f64::MAX).unwrap();
+        let two = NonNegR::new(2.).unwrap();
+        assert!(max.checked_add(max).is_none());
+        assert_eq!(max.checked_add(NonNegR::new(0.).unwrap()).unwrap(), max);
+        assert_eq!(two.checked_add(two).unwrap().get(), 4.);
+        assert_eq!(NonNegR::new(4.).unwrap().sqrt(), two);
+        assert_eq!(NonNegR::new(0.).unwrap().sqrt().get(), 0.);
+        assert!(max.sqrt().get().is_finite());
+
+        let max = PosR::new(f64::MAX).unwrap();
+        let sub = PosR::new(f64::MIN_POSITIVE / 2.).unwrap();
+        let two = PosR::new(2.).unwrap();
+        assert!(max.checked_mul(two).is_none());
+        assert!(sub.checked_mul(sub).is_none());
+        assert_eq!(two.checked_mul(two).unwrap().get(), 4.);
+    }
+
+    #[test]
+    fn test_lerp() {
+        let a = R::new(-1.).unwrap();
+        let b = R::new(3.).unwrap();
+        assert_eq!(lerp(a, b, UnitR::new(0.).unwrap()), a);
+        assert_eq!(lerp(a, b, UnitR::new(1.).unwrap()), b);
+        assert_eq!(lerp(a, b, UnitR::new(0.5).unwrap()).get(), 1.);
+        let max = R::new(f64::MAX).unwrap();
+        let min = R::new(f64::MIN).unwrap();
+        for t in [0., 0.1, 0.3, 0.5, 0.7, 1.] {
+            let t = UnitR::new(t).unwrap();
+            assert_eq!(lerp(max, max, t), max);
+            assert!(lerp(min, max, t).get().is_finite());
+        }
+    }
+
+    #[test]
+    fn test_try_from() {
+        assert_eq!(UnitR::try_from(0.5).unwrap().get(), 0.5);
+        let err = UnitR::try_from(1.5_f64).unwrap_err();
+        assert_eq!(err.to_string(), "1.5 is out of the range [0, 1]");
+        assert!(NonNegR::try_from(-0.1_f32).is_err());
+        assert!(NonNegR::try_from(f64::INFINITY).is_err());
+        assert!(PosR::try_from(0.).is_err());
+        assert!(PosR::try_from(f64::MIN_POSITIVE / 2.).is_ok());
+        assert!(R::try_from(f64::NAN).is_err());
+        assert!(NonNanF::try_from(f64::INFINITY).is_ok());
+    }
+
     #[test]
     fn test_opt() {
         let mut a = OptR::some(UnitR::new(1.).unwrap());
@@ -336,4 +508,41 @@ mod tests {
         assert_eq!(a.take().unwrap(), UnitR::new(1.).unwrap());
         assert!(a.get().is_none());
     }
+
+    #[test]
+    fn test_opt_combinators() {
+        let r = |v: f64| NonNegR::new(v).unwrap();
+        let mut ema: OptR<f64, NonNegR<f64>> = OptR::none();
+        assert!(ema.is_none());
+        assert_eq!(ema.unwrap_or(r(3.)), r(3.));
+        assert!(ema.map_or_none(|v| Some(v.sqrt())).is_none());
+        ema.add_assign_some(r(4.));
+        assert!(ema.is_some());
+        ema.add_assign_some(r(5.));
+        assert_eq!(ema.unwrap_or(r(0.)), r(9.));
+        assert_eq!(ema.map_or_none(|v| Some(v.sqrt())).get(), Some(r(3.)));
+        assert!(ema.map_or_none(|_| None).is_none());
+        // No value out of the range of the wrapper
+        assert!(ema.map_or_none(|v| NonNegR::new(v.get() - 10.)).is_none());
+
+        let mut unit = OptR::some(UnitR::new(0.75).unwrap());
+        let caught =
+            std::panic::catch_unwind(move || unit.add_assign_some(UnitR::new(0.5).unwrap()));
+        assert!(caught.is_err());
+    }
+
+    #[test]
+    fn test_sort_floats() {
+        let mut floats = [2., f64::NAN, -0., f64::NEG_INFINITY, -f64::NAN, 0., 1.];
+        sort_floats(&mut floats);
+        assert!(floats[0].is_nan() && floats[0].is_sign_negative());
+        assert_eq!(floats[1..6], [f64::NEG_INFINITY, -0., 0., 1., 2.]);
+        assert!(floats[2].is_sign_negative());
+        assert!(floats[6].is_nan() && floats[6].is_sign_positive());
+
+        let mut pairs = [("b", 1.), ("nan", f64::NAN), ("a", -1.), ("c", 1.)];
+        sort_by_float_key(&mut pairs, |&(_, f)| f);
+        let names = pairs.map(|(name, _)| name);
+        assert_eq!(names, ["a", "b", "c", "nan"]);
+    }
 }
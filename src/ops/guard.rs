@@ -0,0 +1,172 @@
+use core::{marker::PhantomData, mem::ManuallyDrop};
+use std::panic::AssertUnwindSafe;
+
+/// Run the closure on drop
+///
+/// If the closure panics while the thread is already unwinding, the second panic is caught and discarded instead of aborting the process.
+/// The panic hook still reports it.
+pub type DropGuard<F> = Guard<F, Always>;
+/// Run the closure on drop only if the thread is not panicking
+pub type ScopeSuccess<F> = Guard<F, OnSuccess>;
+/// Run the closure on drop only if the thread is panicking
+///
+/// A panic inside the closure is caught and discarded; see [`DropGuard`].
+pub type ScopeFailure<F> = Guard<F, OnFailure>;
+
+/// Decide whether a [`Guard`] runs its closure on drop
+pub trait GuardStrategy {
+    fn should_run(panicking: bool) -> bool;
+}
+#[derive(Debug)]
+pub enum Always {}
+impl GuardStrategy for Always {
+    fn should_run(_panicking: bool) -> bool {
+        true
+    }
+}
+#[derive(Debug)]
+pub enum OnSuccess {}
+impl GuardStrategy for OnSuccess {
+    fn should_run(panicking: bool) -> bool {
+        !panicking
+    }
+}
+#[derive(Debug)]
+pub enum OnFailure {}
+impl GuardStrategy for OnFailure {
+    fn should_run(panicking: bool) -> bool {
+        panicking
+    }
+}
+
+#[must_use]
+pub struct Guard<F: FnOnce(), S: GuardStrategy> {
+    f: ManuallyDrop<F>,
+    strategy: PhantomData<S>,
+}
+impl<F: FnOnce(), S: GuardStrategy> Guard<F, S> {
+    pub fn new(f: F) -> Self {
+        Self {
+            f: ManuallyDrop::new(f),
+            strategy: PhantomData,
+        }
+    }
+    /// Drop the guard without running the closure
+    pub fn dismiss(self) {
+        let mut this = ManuallyDrop::new(self);
+        // SAFETY: `this` is never dropped so `f` is never taken again
+        unsafe { ManuallyDrop::drop(&mut this.f) };
+    }
+}
+impl<F: FnOnce(), S: GuardStrategy> Drop for Guard<F, S> {
+    fn drop(&mut self) {
+        // SAFETY: `f` is not accessed after drop
+        let f = unsafe { ManuallyDrop::take(&mut self.f) };
+        let panicking = std::thread::panicking();
+        if !S::should_run(panicking) {
+            return;
+        }
+        if !panicking {
+            f();
+            return;
+        }
+        // A panic during unwinding would abort the process
+        let _ = std::panic::catch_unwind(AssertUnwindSafe(f));
+    }
+}
+impl<F: FnOnce(), S: GuardStrategy> core::fmt::Debug for Guard<F, S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Guard").finish_non_exhaustive()
+    }
+}
+
+/// Run the statements at the end of the enclosing scope
+///
+/// ```
+/// use std::cell::Cell;
+///
+/// let a = Cell::new(0);
+/// {
+///     primitive::defer! { a.set(1); }
+///     assert_eq!(a.get(), 0);
+/// }
+/// assert_eq!(a.get(), 1);
+/// ```
+#[macro_export]
+macro_rules! defer {
+    ($($body:tt)*) => {
+        let _guard = $crate::ops::guard::DropGuard::new(|| { $($body)* });
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use std::panic::catch_unwind;
+
+    use super::*;
+
+    #[test]
+    fn test_drop_guard() {
+        let a = Cell::new(0);
+        {
+            let _g = DropGuard::new(|| a.set(a.get() + 1));
+            assert_eq!(a.get(), 0);
+        }
+        assert_eq!(a.get(), 1);
+        DropGuard::new(|| a.set(a.get() + 1)).dismiss();
+        assert_eq!(a.get(), 1);
+        {
+            defer! { a.set(a.get() * 3); }
+            defer! { a.set(a.get() + 1); }
+        }
+        assert_eq!(a.get(), 6);
+    }
+
+    #[test]
+    fn test_dismiss_drops_closure() {
+        let rc = std::rc::Rc::new(());
+        let captured = std::rc::Rc::clone(&rc);
+        let g = DropGuard::new(move || drop(captured));
+        assert_eq!(std::rc::Rc::strong_count(&rc), 2);
+        g.dismiss();
+        assert_eq!(std::rc::Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    fn test_scope_success_failure() {
+        let success = Cell::new(false);
+        let failure = Cell::new(false);
+        {
+            let _s = ScopeSuccess::new(|| success.set(true));
+            let _f = ScopeFailure::new(|| failure.set(true));
+        }
+        assert!(success.get());
+        assert!(!failure.get());
+
+        let success = Cell::new(false);
+        let failure = Cell::new(false);
+        let res = catch_unwind(AssertUnwindSafe(|| {
+            let _s = ScopeSuccess::new(|| success.set(true));
+            let _f = ScopeFailure::new(|| failure.set(true));
+            panic!();
+        }));
+        assert!(res.is_err());
+        assert!(!success.get());
+        assert!(failure.get());
+    }
+
+    #[test]
+    fn test_double_panic() {
+        let ran = Cell::new(false);
+        let res = catch_unwind(AssertUnwindSafe(|| {
+            let _g = DropGuard::new(|| {
+                ran.set(true);
+                panic!("second");
+            });
+            panic!("first");
+        }));
+        assert!(res.is_err());
+        assert!(ran.get());
+    }
+}
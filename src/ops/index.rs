@@ -0,0 +1,43 @@
+/// Index newtype of one container so it cannot be fed to another one by mistake
+macro_rules! define_index {
+    ($(#[$meta: meta])* $ty: ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #[repr(transparent)]
+        pub struct $ty(usize);
+        impl $ty {
+            #[must_use]
+            pub(crate) const fn new(index: usize) -> Self {
+                Self(index)
+            }
+            /// Shim for the callers still holding raw `usize` indices
+            #[deprecated(note = "keep the index type returned by the container instead")]
+            #[must_use]
+            pub const fn from_usize(index: usize) -> Self {
+                Self(index)
+            }
+            #[must_use]
+            pub const fn as_usize(self) -> usize {
+                self.0
+            }
+        }
+        impl core::fmt::Display for $ty {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+        impl From<$ty> for usize {
+            fn from(index: $ty) -> Self {
+                index.0
+            }
+        }
+        impl $crate::map::hash_map::IsEnabledNoHash for $ty {}
+        impl $crate::ops::len::HeapSize for $ty {
+            const NO_HEAP: bool = true;
+            fn heap_size(&self) -> usize {
+                0
+            }
+        }
+    };
+}
+pub(crate) use define_index;
@@ -75,3 +75,167 @@ impl<T, const N: usize> Len for [T; N] {
         N
     }
 }
+
+/// Bytes owned on the heap, not counting `size_of::<Self>()` itself
+pub trait HeapSize {
+    /// [`Self::heap_size()`] is always zero, so containers can skip visiting the items
+    const NO_HEAP: bool = false;
+    #[must_use]
+    fn heap_size(&self) -> usize;
+}
+/// Sum of [`HeapSize::heap_size()`] of the items; O(1) if [`HeapSize::NO_HEAP`]
+#[must_use]
+pub fn items_heap_size<'a, T: HeapSize + 'a>(items: impl IntoIterator<Item = &'a T>) -> usize {
+    if T::NO_HEAP {
+        return 0;
+    }
+    items.into_iter().map(HeapSize::heap_size).sum()
+}
+
+macro_rules! impl_no_heap {
+    ($($ty: ty),*) => {
+        $(
+            impl HeapSize for $ty {
+                const NO_HEAP: bool = true;
+                fn heap_size(&self) -> usize {
+                    0
+                }
+            }
+        )*
+    };
+}
+impl_no_heap!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+impl_no_heap!(f32, f64, bool, char, ());
+impl_no_heap!(core::time::Duration, std::time::Instant);
+impl<T: HeapSize> HeapSize for Option<T> {
+    const NO_HEAP: bool = T::NO_HEAP;
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, HeapSize::heap_size)
+    }
+}
+impl<T: HeapSize, const N: usize> HeapSize for [T; N] {
+    const NO_HEAP: bool = T::NO_HEAP;
+    fn heap_size(&self) -> usize {
+        items_heap_size(self)
+    }
+}
+impl<T: HeapSize> HeapSize for Box<T> {
+    fn heap_size(&self) -> usize {
+        core::mem::size_of::<T>() + self.as_ref().heap_size()
+    }
+}
+impl<T: HeapSize> HeapSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * core::mem::size_of::<T>() + items_heap_size(self)
+    }
+}
+impl<T: HeapSize> HeapSize for VecDeque<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * core::mem::size_of::<T>() + items_heap_size(self)
+    }
+}
+impl HeapSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+/// Approximate: one control byte per slot and no rounding of the slot count
+impl<K: HeapSize, V: HeapSize, S> HeapSize for HashMap<K, V, S> {
+    fn heap_size(&self) -> usize {
+        let slot = core::mem::size_of::<(K, V)>() + 1;
+        self.capacity() * slot + items_heap_size(self.keys()) + items_heap_size(self.values())
+    }
+}
+/// Estimated bytes a [`BTreeMap`] node spends per entry on top of the key and the value
+///
+/// Nodes hold up to 11 entries and are about two thirds full, so the slots cost half an entry more;
+/// the node header and the child edges of the internal nodes are amortized into these bytes.
+pub const BTREE_ENTRY_OVERHEAD: usize = 16;
+/// Approximate; see [`BTREE_ENTRY_OVERHEAD`]
+impl<K: HeapSize, V: HeapSize> HeapSize for BTreeMap<K, V> {
+    fn heap_size(&self) -> usize {
+        let entry = core::mem::size_of::<(K, V)>();
+        self.len() * (entry + entry / 2 + BTREE_ENTRY_OVERHEAD)
+            + items_heap_size(self.keys())
+            + items_heap_size(self.values())
+    }
+}
+macro_rules! impl_heap_size_tuple {
+    ($($ty: ident $i: tt),*) => {
+        impl<$($ty: HeapSize),*> HeapSize for ($($ty,)*) {
+            const NO_HEAP: bool = $($ty::NO_HEAP)&&*;
+            fn heap_size(&self) -> usize {
+                0 $(+ self.$i.heap_size())*
+            }
+        }
+    };
+}
+impl_heap_size_tuple!(A 0);
+impl_heap_size_tuple!(A 0, B 1);
+impl_heap_size_tuple!(A 0, B 1, C 2);
+impl_heap_size_tuple!(A 0, B 1, C 2, D 3);
+
+/// Implement [`HeapSize`] by summing the listed fields
+///
+/// ```
+/// use primitive::ops::len::HeapSize;
+///
+/// struct Session {
+///     name: String,
+///     ids: Vec<u32>,
+///     port: u16,
+/// }
+/// primitive::impl_heap_size_fields!(Session { name, ids });
+///
+/// let s = Session { name: String::with_capacity(8), ids: Vec::with_capacity(2), port: 1 };
+/// assert_eq!(s.heap_size(), 8 + 2 * 4);
+/// ```
+#[macro_export]
+macro_rules! impl_heap_size_fields {
+    ($ty: ty { $($field: ident),* $(,)? }) => {
+        impl $crate::ops::len::HeapSize for $ty {
+            fn heap_size(&self) -> usize {
+                0 $(+ $crate::ops::len::HeapSize::heap_size(&self.$field))*
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heap_size() {
+        assert_eq!(7_u64.heap_size(), 0);
+        assert_eq!(Some(String::with_capacity(3)).heap_size(), 3);
+        let v: Vec<u32> = Vec::with_capacity(10);
+        assert_eq!(v.heap_size(), 40);
+        let nested = vec![String::with_capacity(5), String::with_capacity(7)];
+        assert_eq!(nested.heap_size(), nested.capacity() * 24 + 12);
+        assert_eq!(Box::new([1_u16; 4]).heap_size(), 8);
+        assert_eq!((1_u8, String::with_capacity(2)).heap_size(), 2);
+        const { assert!(<(u8, [u32; 2])>::NO_HEAP) };
+        const { assert!(!<(u8, String)>::NO_HEAP) };
+    }
+
+    #[test]
+    fn test_monotonic_under_insertion() {
+        let mut hash_map = HashMap::new();
+        let mut btree_map = BTreeMap::new();
+        let mut deque = VecDeque::new();
+        let (mut h, mut b, mut d) = (0, 0, 0);
+        for i in 0..256_u32 {
+            hash_map.insert(i, i.to_string());
+            btree_map.insert(i, i.to_string());
+            deque.push_back(i);
+            assert!(h <= hash_map.heap_size());
+            assert!(b < btree_map.heap_size());
+            assert!(d <= deque.heap_size());
+            h = hash_map.heap_size();
+            b = btree_map.heap_size();
+            d = deque.heap_size();
+        }
+        assert!(h >= 256 * (8 + 24));
+    }
+}
@@ -1,13 +1,233 @@
-use core::ops::{Index, IndexMut};
+use core::ops::{Index, IndexMut, Range};
 
-use super::len::Len;
+use super::{
+    len::Len,
+    slice::{AsSlice, AsSliceMut},
+};
 
 pub trait List<T>: Index<usize, Output = T> + Len {}
-pub trait ListMut<T>: IndexMut<usize, Output = T> + Len {}
+pub trait ListMut<T>: IndexMut<usize, Output = T> + Len {
+    /// Required since [`IndexMut`] alone cannot lend two items at once without moving them out by hand
+    ///
+    /// # Panic
+    ///
+    /// `i` or `j` is out of bounds
+    fn swap(&mut self, i: usize, j: usize);
+    /// # Panic
+    ///
+    /// `range` is out of bounds
+    fn reverse_range(&mut self, range: Range<usize>) {
+        assert!(range.start <= range.end);
+        assert!(range.end <= self.len());
+        let (mut start, mut end) = (range.start, range.end);
+        while start + 1 < end {
+            end -= 1;
+            self.swap(start, end);
+            start += 1;
+        }
+    }
+    /// Rotate in place so the item at `n` becomes the first
+    ///
+    /// # Panic
+    ///
+    /// `n` is greater than [`Len::len()`]
+    fn rotate_left(&mut self, n: usize) {
+        let len = self.len();
+        assert!(n <= len);
+        self.reverse_range(0..n);
+        self.reverse_range(n..len);
+        self.reverse_range(0..len);
+    }
+    /// # Panic
+    ///
+    /// `mid` is greater than [`Len::len()`]
+    fn split_at_mut(&mut self, mid: usize) -> (ListSliceMut<'_, T>, ListSliceMut<'_, T>)
+    where
+        Self: AsSliceMut<T>,
+    {
+        let (a, b) = self.as_slice_mut().split_at_mut(mid);
+        (ListSliceMut(a), ListSliceMut(b))
+    }
+    /// Disjoint views of `size` items each except maybe the last one
+    ///
+    /// # Panic
+    ///
+    /// `size` is zero
+    fn chunks_mut<'a>(&'a mut self, size: usize) -> impl Iterator<Item = ListSliceMut<'a, T>>
+    where
+        Self: AsSliceMut<T>,
+        T: 'a,
+    {
+        self.as_slice_mut().chunks_mut(size).map(ListSliceMut)
+    }
+    /// Visit every run of `size` consecutive items from the front
+    ///
+    /// The views overlap, so they are lent to `f` one at a time instead of iterated.
+    ///
+    /// # Panic
+    ///
+    /// `size` is zero
+    fn windows_mut(&mut self, size: usize, mut f: impl FnMut(ListSliceMut<'_, T>))
+    where
+        Self: AsSliceMut<T>,
+    {
+        assert_ne!(size, 0);
+        let slice = self.as_slice_mut();
+        let Some(count) = (slice.len() + 1).checked_sub(size) else {
+            return;
+        };
+        for start in 0..count {
+            f(ListSliceMut(&mut slice[start..start + size]));
+        }
+    }
+}
 
 impl<T> List<T> for Vec<T> {}
-impl<T> ListMut<T> for Vec<T> {}
+impl<T> ListMut<T> for Vec<T> {
+    fn swap(&mut self, i: usize, j: usize) {
+        <[T]>::swap(self, i, j);
+    }
+}
 impl<T> List<T> for [T] {}
-impl<T> ListMut<T> for [T] {}
+impl<T> ListMut<T> for [T] {
+    fn swap(&mut self, i: usize, j: usize) {
+        <[T]>::swap(self, i, j);
+    }
+}
 impl<T, const N: usize> List<T> for [T; N] {}
-impl<T, const N: usize> ListMut<T> for [T; N] {}
+impl<T, const N: usize> ListMut<T> for [T; N] {
+    fn swap(&mut self, i: usize, j: usize) {
+        <[T]>::swap(self, i, j);
+    }
+}
+
+/// Mutable view of a contiguous part of a list
+#[derive(Debug)]
+pub struct ListSliceMut<'a, T>(&'a mut [T]);
+impl<'a, T> ListSliceMut<'a, T> {
+    #[must_use]
+    pub fn new(slice: &'a mut [T]) -> Self {
+        Self(slice)
+    }
+    #[must_use]
+    pub fn into_slice(self) -> &'a mut [T] {
+        self.0
+    }
+}
+impl<T> Index<usize> for ListSliceMut<'_, T> {
+    type Output = T;
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+impl<T> IndexMut<usize> for ListSliceMut<'_, T> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+impl<T> Len for ListSliceMut<'_, T> {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+impl<T> AsSlice<T> for ListSliceMut<'_, T> {
+    fn as_slice(&self) -> &[T] {
+        self.0
+    }
+}
+impl<T> AsSliceMut<T> for ListSliceMut<'_, T> {
+    fn as_slice_mut(&mut self) -> &mut [T] {
+        self.0
+    }
+}
+impl<T> List<T> for ListSliceMut<'_, T> {}
+impl<T> ListMut<T> for ListSliceMut<'_, T> {
+    fn swap(&mut self, i: usize, j: usize) {
+        self.0.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arena::stack::{Stack, StaticStack};
+
+    use super::*;
+
+    fn exercise(list: &mut impl ListMut<u32>) {
+        assert_eq!(list.len(), 6);
+        list.swap(0, 5);
+        list.swap(2, 2);
+        assert_eq!([list[0], list[5]], [5, 0]);
+        list.swap(0, 5);
+        list.reverse_range(1..4);
+        assert_eq!(
+            (0..6).map(|i| list[i]).collect::<Vec<_>>(),
+            [0, 3, 2, 1, 4, 5]
+        );
+        list.reverse_range(1..4);
+        list.reverse_range(3..3);
+        list.rotate_left(2);
+        assert_eq!(
+            (0..6).map(|i| list[i]).collect::<Vec<_>>(),
+            [2, 3, 4, 5, 0, 1]
+        );
+        list.rotate_left(6);
+        list.rotate_left(0);
+        list.rotate_left(4);
+        assert_eq!(
+            (0..6).map(|i| list[i]).collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4, 5]
+        );
+    }
+
+    fn exercise_slices(list: &mut (impl ListMut<u32> + AsSliceMut<u32>)) {
+        let (mut a, mut b) = list.split_at_mut(2);
+        assert_eq!((a.len(), b.len()), (2, 4));
+        core::mem::swap(&mut a[0], &mut b[2]);
+        assert_eq!(list.as_slice(), [4, 1, 2, 3, 0, 5]);
+        list.swap(0, 4);
+
+        for (i, mut chunk) in list.chunks_mut(4).enumerate() {
+            chunk.rotate_left(1);
+            assert_eq!(chunk.len(), [4, 2][i]);
+        }
+        assert_eq!(list.as_slice(), [1, 2, 3, 0, 5, 4]);
+
+        let mut windows = vec![];
+        list.windows_mut(5, |mut w| {
+            w.swap(0, 4);
+            windows.push(w.as_slice().to_vec());
+        });
+        assert_eq!(windows, [vec![5, 2, 3, 0, 1], vec![4, 3, 0, 1, 2]]);
+        list.windows_mut(7, |_| unreachable!());
+    }
+
+    #[test]
+    fn test_vec() {
+        let mut v: Vec<u32> = (0..6).collect();
+        exercise(&mut v);
+        exercise_slices(&mut v);
+    }
+
+    #[test]
+    fn test_static_stack() {
+        let mut s = StaticStack::<u32, 8>::new();
+        for i in 0..6 {
+            assert!(s.push(i).is_none());
+        }
+        exercise(&mut s);
+        exercise_slices(&mut s);
+        s.as_slice_mut().sort_unstable();
+        let mut view = ListSliceMut::new(s.as_slice_mut());
+        exercise(&mut view);
+        assert_eq!(view.into_slice().len(), 6);
+    }
+
+    #[test]
+    fn test_drop_once() {
+        let mut v: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        ListMut::rotate_left(&mut v, 3);
+        ListMut::swap(&mut v, 0, 4);
+        assert_eq!(v, ["2", "4", "0", "1", "3"]);
+    }
+}
@@ -0,0 +1,181 @@
+use core::ops::{Add, Index, IndexMut, Mul};
+
+/// Column-major `R` by `C` matrix
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat<const R: usize, const C: usize> {
+    cols: [[f64; R]; C],
+}
+impl<const R: usize, const C: usize> Mat<R, C> {
+    #[must_use]
+    pub const fn zeros() -> Self {
+        Self { cols: [[0.; R]; C] }
+    }
+    #[must_use]
+    pub const fn from_cols(cols: [[f64; R]; C]) -> Self {
+        Self { cols }
+    }
+    #[must_use]
+    pub fn from_rows(rows: [[f64; C]; R]) -> Self {
+        Mat::<C, R>::from_cols(rows).transpose()
+    }
+    #[must_use]
+    pub const fn cols(&self) -> &[[f64; R]; C] {
+        &self.cols
+    }
+    #[must_use]
+    pub fn transpose(&self) -> Mat<C, R> {
+        let mut t = Mat::zeros();
+        for r in 0..R {
+            for c in 0..C {
+                t[(c, r)] = self[(r, c)];
+            }
+        }
+        t
+    }
+    #[must_use]
+    pub fn scale(mut self, k: f64) -> Self {
+        self.cols.iter_mut().flatten().for_each(|x| *x *= k);
+        self
+    }
+}
+impl<const N: usize> Mat<N, N> {
+    #[must_use]
+    pub fn identity() -> Self {
+        let mut m = Self::zeros();
+        for i in 0..N {
+            m[(i, i)] = 1.;
+        }
+        m
+    }
+}
+impl Mat<2, 2> {
+    #[must_use]
+    pub fn det(&self) -> f64 {
+        self[(0, 0)] * self[(1, 1)] - self[(0, 1)] * self[(1, 0)]
+    }
+    /// `x` in `self * x = b` by Cramer's rule
+    ///
+    /// [`None`] if `self` is singular.
+    #[must_use]
+    pub fn solve_2x2(&self, b: [f64; 2]) -> Option<[f64; 2]> {
+        let det = self.det();
+        if det == 0. || !det.is_finite() {
+            return None;
+        }
+        let x = core::array::from_fn(|i| {
+            let mut m = *self;
+            m.cols[i] = b;
+            m.det() / det
+        });
+        Some(x)
+    }
+}
+impl Mat<3, 3> {
+    #[must_use]
+    pub fn det(&self) -> f64 {
+        let [a, b, c] = self.cols;
+        a[0] * (b[1] * c[2] - b[2] * c[1]) - b[0] * (a[1] * c[2] - a[2] * c[1])
+            + c[0] * (a[1] * b[2] - a[2] * b[1])
+    }
+    /// `x` in `self * x = b` by Cramer's rule
+    ///
+    /// [`None`] if `self` is singular.
+    #[must_use]
+    pub fn solve_3x3(&self, b: [f64; 3]) -> Option<[f64; 3]> {
+        let det = self.det();
+        if det == 0. || !det.is_finite() {
+            return None;
+        }
+        let x = core::array::from_fn(|i| {
+            let mut m = *self;
+            m.cols[i] = b;
+            m.det() / det
+        });
+        Some(x)
+    }
+}
+impl<const R: usize, const C: usize> Default for Mat<R, C> {
+    fn default() -> Self {
+        Self::zeros()
+    }
+}
+/// `(row, column)`
+impl<const R: usize, const C: usize> Index<(usize, usize)> for Mat<R, C> {
+    type Output = f64;
+    fn index(&self, (r, c): (usize, usize)) -> &Self::Output {
+        &self.cols[c][r]
+    }
+}
+impl<const R: usize, const C: usize> IndexMut<(usize, usize)> for Mat<R, C> {
+    fn index_mut(&mut self, (r, c): (usize, usize)) -> &mut Self::Output {
+        &mut self.cols[c][r]
+    }
+}
+impl<const R: usize, const C: usize, const K: usize> Mul<Mat<C, K>> for Mat<R, C> {
+    type Output = Mat<R, K>;
+    fn mul(self, rhs: Mat<C, K>) -> Self::Output {
+        let mut m = Mat::zeros();
+        for k in 0..K {
+            for c in 0..C {
+                for r in 0..R {
+                    m[(r, k)] += self[(r, c)] * rhs[(c, k)];
+                }
+            }
+        }
+        m
+    }
+}
+impl<const R: usize, const C: usize> Add for Mat<R, C> {
+    type Output = Self;
+    fn add(mut self, rhs: Self) -> Self::Output {
+        for (a, b) in self
+            .cols
+            .iter_mut()
+            .flatten()
+            .zip(rhs.cols.iter().flatten())
+        {
+            *a += b;
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mat() {
+        let a = Mat::from_rows([[1., 2., 3.], [4., 5., 6.]]);
+        assert_eq!(a.cols(), &[[1., 4.], [2., 5.], [3., 6.]]);
+        assert_eq!(a[(1, 0)], 4.);
+        let t = a.transpose();
+        assert_eq!(t, Mat::from_rows([[1., 4.], [2., 5.], [3., 6.]]));
+        assert_eq!(a * t, Mat::from_rows([[14., 32.], [32., 77.]]));
+        assert_eq!(a * Mat::identity(), a);
+        assert_eq!(Mat::<2, 2>::identity() * a, a);
+        assert_eq!(a + a, a.scale(2.));
+        assert_eq!(Mat::<2, 3>::default(), Mat::zeros());
+    }
+
+    fn assert_close<const N: usize>(x: [f64; N], expected: [f64; N]) {
+        for (x, e) in x.iter().zip(expected) {
+            assert!((x - e).abs() < 1e-12, "{x}; {e}");
+        }
+    }
+
+    #[test]
+    fn test_solve() {
+        let m = Mat::from_rows([[2., 1.], [1., 3.]]);
+        assert_eq!(m.det(), 5.);
+        assert_close(m.solve_2x2([3., 5.]).unwrap(), [0.8, 1.4]);
+        let singular = Mat::from_rows([[1., 2.], [2., 4.]]);
+        assert_eq!(singular.solve_2x2([1., 1.]), None);
+
+        let m = Mat::from_rows([[2., 0., 1.], [1., 3., 2.], [1., 1., 2.]]);
+        assert_eq!(m.det(), 6.);
+        assert_close(m.solve_3x3([5., 13., 9.]).unwrap(), [1., 2., 3.]);
+        let singular = Mat::from_rows([[1., 2., 3.], [2., 4., 6.], [1., 1., 1.]]);
+        assert_eq!(singular.solve_3x3([1., 1., 1.]), None);
+    }
+}
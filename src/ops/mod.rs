@@ -1,19 +1,25 @@
 pub mod acc;
+pub mod bitfield;
 pub mod clear;
 pub mod diff;
 pub mod dyn_ref;
 pub mod float;
+pub mod guard;
+pub mod index;
 pub mod int_div;
 pub mod len;
 pub mod list;
+pub mod matrix;
 pub mod mut_cell;
 pub mod non_max;
 pub mod opt;
 pub mod opt_cmp;
 pub mod ord_entry;
+pub mod prefetch;
 pub mod range;
 pub mod ring;
 pub mod slice;
+pub mod small_str;
 pub mod stacked_state;
 pub mod unit;
 pub mod unsigned;
@@ -1,3 +1,7 @@
+use thiserror::Error;
+
+use crate::map::hash_map::IsEnabledNoHash;
+
 use super::opt::Opt;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -70,3 +74,136 @@ where
         self.v
     }
 }
+
+impl<T: IsEnabledNoHash> IsEnabledNoHash for NonMax<T> {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("value is the max of its type")]
+pub struct IsMax;
+
+/// Stored as `value ^ MAX` in a `NonZero*` so that `Option<Self>` is as big as the primitive
+macro_rules! define_non_max {
+    ($ty: ident, $primitive: ident, $nonzero_ty: ident) => {
+        #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+        #[repr(transparent)]
+        pub struct $ty {
+            inv: core::num::$nonzero_ty,
+        }
+        const _: () =
+            assert!(core::mem::size_of::<Option<$ty>>() == core::mem::size_of::<$primitive>());
+        impl $ty {
+            pub const MAX: Self = Self::new($primitive::MAX - 1).unwrap();
+            pub const MIN: Self = Self::new(0).unwrap();
+            pub const fn new(value: $primitive) -> Option<Self> {
+                match core::num::$nonzero_ty::new(value ^ $primitive::MAX) {
+                    Some(inv) => Some(Self { inv }),
+                    None => None,
+                }
+            }
+            /// # Safety
+            ///
+            /// Make sure input is not maxed out
+            pub const unsafe fn new_unchecked(value: $primitive) -> Self {
+                let inv = unsafe { core::num::$nonzero_ty::new_unchecked(value ^ $primitive::MAX) };
+                Self { inv }
+            }
+            pub const fn get(self) -> $primitive {
+                self.inv.get() ^ $primitive::MAX
+            }
+            pub const fn checked_add(self, other: Self) -> Option<Self> {
+                match self.get().checked_add(other.get()) {
+                    Some(value) => Self::new(value),
+                    None => None,
+                }
+            }
+            pub const fn checked_sub(self, other: Self) -> Option<Self> {
+                match self.get().checked_sub(other.get()) {
+                    Some(value) => Self::new(value),
+                    None => None,
+                }
+            }
+        }
+        impl From<$ty> for $primitive {
+            fn from(value: $ty) -> Self {
+                value.get()
+            }
+        }
+        impl TryFrom<$primitive> for $ty {
+            type Error = IsMax;
+            fn try_from(value: $primitive) -> Result<Self, Self::Error> {
+                Self::new(value).ok_or(IsMax)
+            }
+        }
+        impl PartialOrd for $ty {
+            fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for $ty {
+            fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+                self.get().cmp(&other.get())
+            }
+        }
+        impl core::fmt::Debug for $ty {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                core::fmt::Debug::fmt(&self.get(), f)
+            }
+        }
+        impl core::fmt::Display for $ty {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                core::fmt::Display::fmt(&self.get(), f)
+            }
+        }
+        impl $crate::map::hash_map::IsEnabledNoHash for $ty {}
+        impl $crate::ops::len::HeapSize for $ty {
+            const NO_HEAP: bool = true;
+            fn heap_size(&self) -> usize {
+                0
+            }
+        }
+    };
+}
+define_non_max!(NonMaxU8, u8, NonZeroU8);
+define_non_max!(NonMaxU16, u16, NonZeroU16);
+define_non_max!(NonMaxU32, u32, NonZeroU32);
+define_non_max!(NonMaxU64, u64, NonZeroU64);
+define_non_max!(NonMaxUsize, usize, NonZeroUsize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! test_non_max {
+        ($name: ident, $ty: ident, $primitive: ident) => {
+            #[test]
+            fn $name() {
+                assert_eq!(
+                    core::mem::size_of::<Option<$ty>>(),
+                    core::mem::size_of::<$primitive>()
+                );
+                for v in [0, 1, $primitive::MAX / 2, $primitive::MAX - 1] {
+                    let n = $ty::new(v).unwrap();
+                    assert_eq!(n.get(), v);
+                    assert_eq!($primitive::from(n), v);
+                    assert_eq!($ty::try_from(v), Ok(n));
+                    assert_eq!(unsafe { $ty::new_unchecked(v) }, n);
+                }
+                assert!($ty::new($primitive::MAX).is_none());
+                assert_eq!($ty::try_from($primitive::MAX), Err(IsMax));
+                assert_eq!($ty::MAX.get(), $primitive::MAX - 1);
+                assert!($ty::MIN < $ty::MAX);
+                let one = $ty::new(1).unwrap();
+                assert_eq!($ty::MIN.checked_add(one), Some(one));
+                assert!($ty::MAX.checked_add(one).is_none());
+                assert_eq!(one.checked_sub(one), Some($ty::MIN));
+                assert!($ty::MIN.checked_sub(one).is_none());
+                assert_eq!(format!("{one:?}"), "1");
+            }
+        };
+    }
+    test_non_max!(test_non_max_u8, NonMaxU8, u8);
+    test_non_max!(test_non_max_u16, NonMaxU16, u16);
+    test_non_max!(test_non_max_u32, NonMaxU32, u32);
+    test_non_max!(test_non_max_u64, NonMaxU64, u64);
+    test_non_max!(test_non_max_usize, NonMaxUsize, usize);
+}
@@ -9,3 +9,102 @@ pub trait Opt<T>: From<Option<T>> + Into<Option<T>> {
         o.map(f)
     }
 }
+
+/// Combinators on [`Option`] for slot-heavy container code
+///
+/// [`Option`] already has inherent methods named [`OptionExt::get_or_insert_default()`] and [`OptionExt::take_if()`], which method-call syntax picks first; call these through the trait to get the versions here.
+pub trait OptionExt<T> {
+    fn get_or_insert_default(&mut self) -> &mut T
+    where
+        T: Default;
+    /// Return `true` if `f` filled the empty slot
+    ///
+    /// `f` is not called if the slot is occupied.
+    fn replace_if_none(&mut self, f: impl FnOnce() -> T) -> bool;
+    /// Take the value out only if `pred` holds for it
+    ///
+    /// The slot is left as is if `pred` panics.
+    fn take_if(&mut self, pred: impl FnOnce(&T) -> bool) -> Option<T>;
+    fn inspect_none(&self, f: impl FnOnce()) -> &Self;
+}
+impl<T> OptionExt<T> for Option<T> {
+    fn get_or_insert_default(&mut self) -> &mut T
+    where
+        T: Default,
+    {
+        self.get_or_insert_with(T::default)
+    }
+    fn replace_if_none(&mut self, f: impl FnOnce() -> T) -> bool {
+        if self.is_some() {
+            return false;
+        }
+        *self = Some(f());
+        true
+    }
+    fn take_if(&mut self, pred: impl FnOnce(&T) -> bool) -> Option<T> {
+        if pred(self.as_ref()?) {
+            return self.take();
+        }
+        None
+    }
+    fn inspect_none(&self, f: impl FnOnce()) -> &Self {
+        if self.is_none() {
+            f();
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use super::*;
+
+    #[test]
+    fn test_get_or_insert_default() {
+        let mut o: Option<Vec<u8>> = None;
+        OptionExt::get_or_insert_default(&mut o).push(1);
+        OptionExt::get_or_insert_default(&mut o).push(2);
+        assert_eq!(o, Some(vec![1, 2]));
+    }
+
+    #[test]
+    fn test_replace_if_none() {
+        let mut o = None;
+        assert!(o.replace_if_none(|| 1));
+        assert!(!o.replace_if_none(|| unreachable!()));
+        assert_eq!(o, Some(1));
+
+        let mut o: Option<u8> = None;
+        let res = catch_unwind(AssertUnwindSafe(|| o.replace_if_none(|| panic!())));
+        assert!(res.is_err());
+        assert_eq!(o, None);
+    }
+
+    #[test]
+    fn test_take_if() {
+        let mut o = Some(3);
+        assert_eq!(OptionExt::take_if(&mut o, |&x| x % 2 == 0), None);
+        assert_eq!(o, Some(3));
+        assert_eq!(OptionExt::take_if(&mut o, |&x| x % 2 == 1), Some(3));
+        assert_eq!(o, None);
+        assert_eq!(OptionExt::take_if(&mut o, |_| unreachable!()), None);
+
+        let mut o = Some(3);
+        let res = catch_unwind(AssertUnwindSafe(|| {
+            OptionExt::take_if(&mut o, |_| panic!())
+        }));
+        assert!(res.is_err());
+        assert_eq!(o, Some(3));
+    }
+
+    #[test]
+    fn test_inspect_none() {
+        let mut calls = 0;
+        let o = None::<u8>;
+        assert_eq!(o.inspect_none(|| calls += 1), &None);
+        Some(1).inspect_none(|| unreachable!());
+        assert_eq!(calls, 1);
+    }
+}
@@ -0,0 +1,109 @@
+/// Hint the CPU to pull the cache line of `ptr` into L1 for a read soon
+///
+/// Never faults, so any address is fine, dangling or null included.
+/// A no-op on targets without a stable prefetch instruction or if built with `--cfg no_prefetch`.
+#[inline(always)]
+pub fn prefetch_read<T>(ptr: *const T) {
+    #[cfg(all(
+        not(no_prefetch),
+        any(
+            target_arch = "x86_64",
+            all(target_arch = "x86", target_feature = "sse")
+        )
+    ))]
+    {
+        #[cfg(target_arch = "x86")]
+        use core::arch::x86::{_mm_prefetch, _MM_HINT_T0};
+        #[cfg(target_arch = "x86_64")]
+        use core::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+        // SAFETY: SSE is enabled and a prefetch does not access the memory
+        unsafe { _mm_prefetch::<_MM_HINT_T0>(ptr.cast::<i8>()) };
+    }
+    #[cfg(all(not(no_prefetch), target_arch = "aarch64"))]
+    {
+        // SAFETY: a prefetch does not access the memory
+        unsafe {
+            core::arch::asm!(
+                "prfm pldl1keep, [{ptr}]",
+                ptr = in(reg) ptr,
+                options(nostack, preserves_flags, readonly)
+            );
+        }
+    }
+    #[cfg(any(
+        no_prefetch,
+        not(any(
+            target_arch = "x86_64",
+            all(target_arch = "x86", target_feature = "sse"),
+            target_arch = "aarch64"
+        ))
+    ))]
+    {
+        let _ = ptr;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefetch_any_address() {
+        let v = [1_u64; 4];
+        prefetch_read(v.as_ptr());
+        prefetch_read(v.as_ptr().wrapping_add(1 << 20));
+        prefetch_read(core::ptr::null::<u64>());
+        assert_eq!(v, [1; 4]);
+    }
+}
+
+#[cfg(feature = "nightly")]
+#[cfg(test)]
+mod benches {
+    use test::{black_box, Bencher};
+
+    use super::*;
+
+    /// Well past the last-level cache
+    const LEN: usize = 1 << 24;
+    const STEPS: usize = 1 << 20;
+    const AHEAD: usize = 16;
+
+    fn shuffled_walk() -> Vec<usize> {
+        let mut state = 0x9e37_79b9_7f4a_7c15_u64;
+        (0..STEPS)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as usize % LEN
+            })
+            .collect()
+    }
+
+    fn walk(bencher: &mut Bencher, prefetch: bool) {
+        let data = vec![1_u64; LEN];
+        let indices = shuffled_walk();
+        bencher.iter(|| {
+            let mut sum = 0;
+            for (i, &index) in indices.iter().enumerate() {
+                if prefetch {
+                    if let Some(&next) = indices.get(i + AHEAD) {
+                        prefetch_read(data.as_ptr().wrapping_add(next));
+                    }
+                }
+                // Each load waits for the previous one, as in a probe chain, but stays on the prefetched line
+                sum += data[index ^ (sum & 1) as usize];
+            }
+            black_box(sum)
+        });
+    }
+    #[bench]
+    fn bench_random_walk(bencher: &mut Bencher) {
+        walk(bencher, false);
+    }
+    #[bench]
+    fn bench_random_walk_prefetch(bencher: &mut Bencher) {
+        walk(bencher, true);
+    }
+}
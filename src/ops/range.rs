@@ -1,3 +1,11 @@
+use core::ops::Range;
+
+use thiserror::Error;
+
+use crate::map::{linear_front_btree::LinearFrontBTreeMap11, MapInsert};
+
+use super::{clear::Clear, len::Len};
+
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub struct RangeAny<T> {
     pub start: core::ops::Bound<T>,
@@ -53,6 +61,190 @@ impl<T> From<core::ops::RangeFull> for RangeAny<T> {
     }
 }
 
+/// What [`SpanMap::insert()`] does to the existing spans overlapping the new one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverlapPolicy {
+    /// Leave the map untouched and return [`SpanInsertError::Overlap`]
+    #[default]
+    Reject,
+    /// Trim the existing spans to the parts outside the new one, splitting a span in two if it encloses the new one
+    Split,
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum SpanInsertError<K, V> {
+    #[error("empty range")]
+    Empty(V),
+    #[error("range overlaps an existing span")]
+    Overlap { existing: Range<K>, value: V },
+}
+
+/// Disjoint half-open key ranges mapped to values
+#[derive(Debug, Clone)]
+pub struct SpanMap<K, V> {
+    policy: OverlapPolicy,
+    /// Keyed by range start
+    spans: LinearFrontBTreeMap11<K, Span<K, V>>,
+}
+#[derive(Debug, Clone)]
+struct Span<K, V> {
+    end: K,
+    value: V,
+}
+impl<K, V> SpanMap<K, V> {
+    #[must_use]
+    pub fn new(policy: OverlapPolicy) -> Self {
+        Self {
+            policy,
+            spans: LinearFrontBTreeMap11::new(),
+        }
+    }
+    #[must_use]
+    pub fn policy(&self) -> OverlapPolicy {
+        self.policy
+    }
+}
+impl<K, V> SpanMap<K, V>
+where
+    K: Ord + Copy,
+{
+    /// The span containing `key`
+    #[must_use]
+    pub fn get_range(&self, key: &K) -> Option<(Range<K>, &V)> {
+        let (&start, span) = self.spans.range(..=*key).next_back()?;
+        if span.end <= *key {
+            return None;
+        }
+        Some((start..span.end, &span.value))
+    }
+    #[must_use]
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.get_range(key).map(|(_, value)| value)
+    }
+    /// Remove the span of exactly `range`
+    pub fn remove_range(&mut self, range: &Range<K>) -> Option<V> {
+        let span = self.spans.get(&range.start)?;
+        if span.end != range.end {
+            return None;
+        }
+        self.spans.remove(&range.start).map(|span| span.value)
+    }
+    /// In ascending key order
+    pub fn iter(&self) -> impl Iterator<Item = (Range<K>, &V)> + '_ {
+        self.spans
+            .iter()
+            .map(|(&start, span)| (start..span.end, &span.value))
+    }
+    /// Parts of `within` not covered by any span in ascending key order
+    ///
+    /// None if `within` is empty or reversed.
+    #[must_use]
+    pub fn covering_gaps(&self, within: Range<K>) -> Vec<Range<K>> {
+        let mut gaps = vec![];
+        if within.end <= within.start {
+            return gaps;
+        }
+        let mut cursor = within.start;
+        if let Some((covered, _)) = self.get_range(&within.start) {
+            cursor = covered.end;
+        }
+        for (&start, span) in self.spans.range(within.start..within.end) {
+            if within.end <= cursor {
+                break;
+            }
+            if cursor < start {
+                gaps.push(cursor..start);
+            }
+            cursor = cursor.max(span.end);
+        }
+        if cursor < within.end {
+            gaps.push(cursor..within.end);
+        }
+        gaps
+    }
+    fn overlapping(&self, range: &Range<K>) -> Vec<Range<K>> {
+        let mut overlapping = vec![];
+        if let Some((covered, _)) = self.get_range(&range.start) {
+            overlapping.push(covered);
+        }
+        let rest = self
+            .spans
+            .range(range.start..range.end)
+            .filter(|(&start, _)| range.start != start)
+            .map(|(&start, span)| start..span.end);
+        overlapping.extend(rest);
+        overlapping
+    }
+}
+impl<K, V> SpanMap<K, V>
+where
+    K: Ord + Copy,
+    V: Clone,
+{
+    /// `V` is cloned only if [`OverlapPolicy::Split`] splits an enclosing span in two
+    pub fn insert(&mut self, range: Range<K>, value: V) -> Result<(), SpanInsertError<K, V>> {
+        if range.end <= range.start {
+            return Err(SpanInsertError::Empty(value));
+        }
+        let overlapping = self.overlapping(&range);
+        match self.policy {
+            OverlapPolicy::Reject => {
+                if let Some(existing) = overlapping.into_iter().next() {
+                    return Err(SpanInsertError::Overlap { existing, value });
+                }
+            }
+            OverlapPolicy::Split => {
+                for existing in overlapping {
+                    let span = self.spans.remove(&existing.start).unwrap();
+                    let left = existing.start < range.start;
+                    let right = range.end < existing.end;
+                    let (left_value, right_value) = match (left, right) {
+                        (true, true) => (Some(span.value.clone()), Some(span.value)),
+                        (true, false) => (Some(span.value), None),
+                        (false, true) => (None, Some(span.value)),
+                        (false, false) => (None, None),
+                    };
+                    if let Some(value) = left_value {
+                        let left = Span {
+                            end: range.start,
+                            value,
+                        };
+                        self.spans.insert(existing.start, left);
+                    }
+                    if let Some(value) = right_value {
+                        let right = Span {
+                            end: existing.end,
+                            value,
+                        };
+                        self.spans.insert(range.end, right);
+                    }
+                }
+            }
+        }
+        let span = Span {
+            end: range.end,
+            value,
+        };
+        self.spans.insert(range.start, span);
+        Ok(())
+    }
+}
+impl<K, V> Default for SpanMap<K, V> {
+    fn default() -> Self {
+        Self::new(OverlapPolicy::default())
+    }
+}
+impl<K, V> Len for SpanMap<K, V> {
+    fn len(&self) -> usize {
+        self.spans.len()
+    }
+}
+impl<K, V> Clear for SpanMap<K, V> {
+    fn clear(&mut self) {
+        self.spans = LinearFrontBTreeMap11::new();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -65,4 +257,72 @@ mod tests {
         };
         assert_eq!(range, (0..2).into());
     }
+
+    #[test]
+    fn test_span_map_adjacent() {
+        let mut m = SpanMap::new(OverlapPolicy::Reject);
+        m.insert(10..20, 'b').unwrap();
+        m.insert(0..10, 'a').unwrap();
+        m.insert(20..30, 'c').unwrap();
+        assert!(matches!(
+            m.insert(19..21, 'x'),
+            Err(SpanInsertError::Overlap { existing, value: 'x' }) if existing == (10..20)
+        ));
+        assert!(matches!(
+            m.insert(5..5, 'x'),
+            Err(SpanInsertError::Empty('x'))
+        ));
+        assert_eq!(m.len(), 3);
+        assert_eq!(m.get(&0), Some(&'a'));
+        assert_eq!(m.get(&9), Some(&'a'));
+        assert_eq!(m.get(&10), Some(&'b'));
+        assert_eq!(m.get_range(&29), Some((20..30, &'c')));
+        assert_eq!(m.get(&30), None);
+        assert_eq!(
+            m.iter().map(|(r, &v)| (r, v)).collect::<Vec<_>>(),
+            [(0..10, 'a'), (10..20, 'b'), (20..30, 'c')]
+        );
+        assert_eq!(m.remove_range(&(10..19)), None);
+        assert_eq!(m.remove_range(&(10..20)), Some('b'));
+        assert_eq!(m.get(&15), None);
+        assert_eq!(m.covering_gaps(0..40), [10..20, 30..40]);
+        assert_eq!(m.covering_gaps(5..25), vec![(10..20)]);
+        assert_eq!(m.covering_gaps(12..15), vec![(12..15)]);
+        assert!(m.covering_gaps(21..29).is_empty());
+        assert!(m.covering_gaps(35..35).is_empty());
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 35..5;
+        assert!(m.covering_gaps(reversed).is_empty());
+        m.clear();
+        assert_eq!(m.covering_gaps(0..5), vec![(0..5)]);
+    }
+
+    #[test]
+    fn test_span_map_split() {
+        let mut m = SpanMap::new(OverlapPolicy::Split);
+        m.insert(0..100, 'a').unwrap();
+        m.insert(40..60, 'b').unwrap();
+        m.insert(50..70, 'c').unwrap();
+        m.insert(90..110, 'd').unwrap();
+        m.insert(120..130, 'e').unwrap();
+        assert_eq!(
+            m.iter().map(|(r, &v)| (r, v)).collect::<Vec<_>>(),
+            [
+                (0..40, 'a'),
+                (40..50, 'b'),
+                (50..70, 'c'),
+                (70..90, 'a'),
+                (90..110, 'd'),
+                (120..130, 'e'),
+            ]
+        );
+        m.insert(30..125, 'f').unwrap();
+        assert_eq!(
+            m.iter().map(|(r, &v)| (r, v)).collect::<Vec<_>>(),
+            [(0..30, 'a'), (30..125, 'f'), (125..130, 'e')]
+        );
+        m.insert(30..125, 'g').unwrap();
+        assert_eq!(m.get_range(&30), Some((30..125, &'g')));
+        assert_eq!(m.len(), 3);
+    }
 }
@@ -2,6 +2,7 @@ pub trait RingSpace
 where
     Self: num_traits::Num + PartialOrd + Ord + Copy,
 {
+    #[inline]
     fn ring_add(self, other: Self, max: Self) -> Self {
         assert!(self <= max);
         assert!(other <= max);
@@ -12,6 +13,7 @@ where
         }
         other - self_til_end - Self::one()
     }
+    #[inline]
     fn ring_sub(self, other: Self, max: Self) -> Self {
         assert!(self <= max);
         assert!(other <= max);
@@ -1,3 +1,5 @@
+use core::mem::MaybeUninit;
+
 #[must_use]
 pub fn dyn_vec_init<T>(size: usize, new_value: impl Fn() -> T) -> Vec<T> {
     (0..size).map(|_| new_value()).collect()
@@ -98,6 +100,56 @@ pub trait LinearSearch<T>: AsSlice<T> {
 }
 impl<S, T> LinearSearch<T> for S where S: AsSlice<T> {}
 
+/// # Safety
+///
+/// All the elements in `slice` must be initialized
+#[must_use]
+pub unsafe fn assume_init_slice<T>(slice: &[MaybeUninit<T>]) -> &[T] {
+    // SAFETY: `MaybeUninit<T>` has the same layout as `T`
+    unsafe { &*(slice as *const [MaybeUninit<T>] as *const [T]) }
+}
+/// # Safety
+///
+/// All the elements in `slice` must be initialized
+#[must_use]
+pub unsafe fn assume_init_slice_mut<T>(slice: &mut [MaybeUninit<T>]) -> &mut [T] {
+    // SAFETY: `MaybeUninit<T>` has the same layout as `T`
+    unsafe { &mut *(slice as *mut [MaybeUninit<T>] as *mut [T]) }
+}
+/// View initialized elements as possibly uninitialized ones
+///
+/// There is no mutable version since writing [`MaybeUninit::uninit()`] through it would leave `T` uninitialized.
+#[must_use]
+pub fn as_uninit_slice<T>(slice: &[T]) -> &[MaybeUninit<T>] {
+    // SAFETY: `MaybeUninit<T>` has the same layout as `T`
+    unsafe { &*(slice as *const [T] as *const [MaybeUninit<T>]) }
+}
+/// Previous values in `dst` are overwritten without being dropped.
+///
+/// If a clone panics, the already cloned elements are leaked.
+///
+/// # Panic
+///
+/// `dst` and `src` have different lengths
+pub fn write_slice_cloned<'a, T: Clone>(dst: &'a mut [MaybeUninit<T>], src: &[T]) -> &'a mut [T] {
+    assert_eq!(dst.len(), src.len());
+    for (dst, src) in dst.iter_mut().zip(src) {
+        dst.write(src.clone());
+    }
+    // SAFETY: all the elements are written above
+    unsafe { assume_init_slice_mut(dst) }
+}
+/// Previous values in `dst` are overwritten.
+///
+/// # Panic
+///
+/// `dst` and `src` have different lengths
+pub fn write_slice_copied<'a, T: Copy>(dst: &'a mut [MaybeUninit<T>], src: &[T]) -> &'a mut [T] {
+    dst.copy_from_slice(as_uninit_slice(src));
+    // SAFETY: all the elements are written above
+    unsafe { assume_init_slice_mut(dst) }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -127,6 +179,32 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_uninit_slice() {
+        let mut buf: [MaybeUninit<String>; 3] = [const { MaybeUninit::uninit() }; 3];
+        let src = [String::from("a"), String::from("b")];
+        let written = write_slice_cloned(&mut buf[1..], &src);
+        written[0].push('c');
+        assert_eq!(unsafe { assume_init_slice(&buf[1..]) }, ["ac", "b"]);
+        for v in &mut buf[1..] {
+            unsafe { v.assume_init_drop() };
+        }
+
+        let mut buf = [MaybeUninit::uninit(); 4];
+        assert_eq!(write_slice_copied(&mut buf[..2], &[1, 2]), [1, 2]);
+        let init = unsafe { assume_init_slice_mut(&mut buf[..2]) };
+        init[1] = 3;
+        assert_eq!(unsafe { assume_init_slice(&buf[..2]) }, [1, 3]);
+        assert_eq!(unsafe { as_uninit_slice(&[4, 5])[1].assume_init() }, 5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_write_slice_len_mismatch() {
+        let mut buf = [MaybeUninit::uninit(); 2];
+        write_slice_copied(&mut buf, &[1]);
+    }
 }
 
 #[cfg(feature = "nightly")]
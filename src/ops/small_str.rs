@@ -0,0 +1,285 @@
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    ops::Deref,
+};
+
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("{len} bytes do not fit in {cap}")]
+pub struct TooLong {
+    pub len: usize,
+    pub cap: usize,
+}
+
+/// String of at most `N` bytes stored inline
+///
+/// Compares and hashes the same as [`str`] so that maps keyed by it can be queried by `&str`.
+///
+/// Fails to compile if `N` is larger than `u8::MAX`.
+#[derive(Clone, Copy)]
+pub struct SmallStr<const N: usize> {
+    len: u8,
+    bytes: [u8; N],
+}
+impl<const N: usize> SmallStr<N> {
+    /// In bytes
+    pub const CAPACITY: usize = N;
+    #[must_use]
+    pub const fn new() -> Self {
+        const { assert!(N <= u8::MAX as usize) };
+        Self {
+            len: 0,
+            bytes: [0; N],
+        }
+    }
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        // Only ever copied from a `str`
+        unsafe { core::str::from_utf8_unchecked(&self.bytes[..usize::from(self.len)]) }
+    }
+}
+impl<const N: usize> Default for SmallStr<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<const N: usize> TryFrom<&str> for SmallStr<N> {
+    type Error = TooLong;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut s = Self::new();
+        let Some(bytes) = s.bytes.get_mut(..value.len()) else {
+            return Err(TooLong {
+                len: value.len(),
+                cap: N,
+            });
+        };
+        bytes.copy_from_slice(value.as_bytes());
+        s.len = value.len() as u8;
+        Ok(s)
+    }
+}
+impl<const N: usize> Deref for SmallStr<N> {
+    type Target = str;
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+impl<const N: usize> Borrow<str> for SmallStr<N> {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+impl<const N: usize> AsRef<str> for SmallStr<N> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+impl<const N: usize> PartialEq for SmallStr<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+impl<const N: usize> Eq for SmallStr<N> {}
+impl<const N: usize> PartialEq<str> for SmallStr<N> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+impl<const N: usize> PartialEq<&str> for SmallStr<N> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+impl<const N: usize> PartialOrd for SmallStr<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<const N: usize> Ord for SmallStr<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+/// Required by [`Borrow<str>`]
+impl<const N: usize> Hash for SmallStr<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+impl<const N: usize> core::fmt::Display for SmallStr<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(self.as_str(), f)
+    }
+}
+impl<const N: usize> core::fmt::Debug for SmallStr<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// [`SmallStr`] that spills to the heap instead of failing on long strings
+#[derive(Clone)]
+pub enum SmallStrOrHeap<const N: usize> {
+    Inline(SmallStr<N>),
+    Heap(Box<str>),
+}
+impl<const N: usize> SmallStrOrHeap<N> {
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Inline(s) => s.as_str(),
+            Self::Heap(s) => s,
+        }
+    }
+}
+impl<const N: usize> From<&str> for SmallStrOrHeap<N> {
+    fn from(value: &str) -> Self {
+        match SmallStr::try_from(value) {
+            Ok(s) => Self::Inline(s),
+            Err(_) => Self::Heap(value.into()),
+        }
+    }
+}
+impl<const N: usize> Deref for SmallStrOrHeap<N> {
+    type Target = str;
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+impl<const N: usize> Borrow<str> for SmallStrOrHeap<N> {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+impl<const N: usize> AsRef<str> for SmallStrOrHeap<N> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+impl<const N: usize> PartialEq for SmallStrOrHeap<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+impl<const N: usize> Eq for SmallStrOrHeap<N> {}
+impl<const N: usize> PartialOrd for SmallStrOrHeap<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<const N: usize> Ord for SmallStrOrHeap<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+impl<const N: usize> Hash for SmallStrOrHeap<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+impl<const N: usize> core::fmt::Display for SmallStrOrHeap<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(self.as_str(), f)
+    }
+}
+impl<const N: usize> core::fmt::Debug for SmallStrOrHeap<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{hash::BuildHasher, num::NonZeroUsize};
+    use std::collections::hash_map::RandomState;
+
+    use crate::{
+        analysis::rng::{RngLite, SplitMix64},
+        map::{
+            cap_map::CapHashMap,
+            dense_hash_map::DenseHashMap,
+            hash_map::{HashGet, HashRemove},
+            MapInsert,
+        },
+    };
+
+    use super::*;
+
+    type Key = SmallStr<23>;
+
+    #[test]
+    fn test_small_str() {
+        let s = Key::try_from("abc").unwrap();
+        assert_eq!(s.as_str(), "abc");
+        assert_eq!(s.len(), 3);
+        assert_eq!(s, "abc");
+        assert_eq!(s.to_string(), "abc");
+        assert_eq!(format!("{s:?}"), "\"abc\"");
+        assert!(s < Key::try_from("abd").unwrap());
+        assert!(Key::try_from("ab").unwrap() < s);
+        assert_eq!(Key::default(), "");
+        assert_eq!(Key::try_from("héllo").unwrap(), "héllo");
+        assert_eq!(core::mem::size_of::<Key>(), 24);
+    }
+
+    #[test]
+    fn test_too_long() {
+        let full = "a".repeat(23);
+        assert_eq!(Key::try_from(full.as_str()).unwrap(), full.as_str());
+        let over = "a".repeat(24);
+        let err = Key::try_from(over.as_str()).unwrap_err();
+        assert_eq!(err, TooLong { len: 24, cap: 23 });
+        assert_eq!(err.to_string(), "24 bytes do not fit in 23");
+        assert!(SmallStr::<0>::try_from("a").is_err());
+        assert_eq!(SmallStr::<0>::try_from("").unwrap(), "");
+
+        let spilled = SmallStrOrHeap::<23>::from(over.as_str());
+        assert!(matches!(spilled, SmallStrOrHeap::Heap(_)));
+        assert_eq!(spilled.as_str(), over);
+        let inline = SmallStrOrHeap::<23>::from(full.as_str());
+        assert!(matches!(inline, SmallStrOrHeap::Inline(_)));
+        assert_eq!(&*inline, full);
+    }
+
+    #[test]
+    fn test_hash_consistency() {
+        let state = RandomState::new();
+        let mut rng = SplitMix64::new(0);
+        for _ in 0..1 << 10 {
+            let len = rng.next_usize_below(24);
+            let s = (0..len)
+                .map(|_| char::from(b' ' + rng.next_usize_below(95) as u8))
+                .collect::<String>();
+            let small = Key::try_from(s.as_str()).unwrap();
+            let spill = SmallStrOrHeap::<4>::from(s.as_str());
+            assert_eq!(state.hash_one(small), state.hash_one(s.as_str()));
+            assert_eq!(state.hash_one(&spill), state.hash_one(s.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_map_lookup() {
+        let names = ["alpha", "beta", "a_rather_long_name_23ch"];
+
+        let mut dense = DenseHashMap::new();
+        let mut cap = CapHashMap::new(NonZeroUsize::new(8).unwrap(), NonZeroUsize::new(2).unwrap());
+        for (i, name) in names.into_iter().enumerate() {
+            let key = Key::try_from(name).unwrap();
+            dense.insert(key, i);
+            cap.insert(key, i);
+        }
+        for (i, name) in names.into_iter().enumerate() {
+            assert_eq!(dense.get(name), Some(&i));
+            assert_eq!(cap.get(name), Some(&i));
+        }
+        assert_eq!(dense.get("gamma"), None);
+        assert_eq!(cap.get("gamma"), None);
+        assert_eq!(dense.remove("beta"), Some(1));
+        assert_eq!(cap.remove("beta"), Some(1));
+        assert_eq!(dense.get("beta"), None);
+        assert_eq!(cap.get("beta"), None);
+    }
+}
@@ -58,32 +58,119 @@ pub const KB: u64 = INFO_SIZE_INTERVAL;
 pub const MB: u64 = KB * INFO_SIZE_INTERVAL;
 pub const GB: u64 = MB * INFO_SIZE_INTERVAL;
 pub const TB: u64 = GB * INFO_SIZE_INTERVAL;
+pub const PB: u64 = TB * INFO_SIZE_INTERVAL;
+pub const EB: u64 = PB * INFO_SIZE_INTERVAL;
+const BINARY_PREFIXES: [&str; 7] = ["", "Ki", "Mi", "Gi", "Ti", "Pi", "Ei"];
+const SI_INTERVAL: u64 = 1_000;
+const SI_PREFIXES: [&str; 7] = ["", "k", "M", "G", "T", "P", "E"];
+
+/// Write `value` scaled down by the largest `interval` power that keeps it at least `1`
+fn fmt_prefixed(
+    f: &mut fmt::Formatter<'_>,
+    value: f64,
+    interval: u64,
+    prefixes: &[&str],
+    unit: &str,
+) -> fmt::Result {
+    let mut scale = 1.;
+    let mut prefix = prefixes[0];
+    if value.is_finite() {
+        for (exp, next_prefix) in prefixes.iter().enumerate().skip(1) {
+            let next_scale = (interval as f64).powi(exp as i32);
+            if value.abs() < next_scale {
+                break;
+            }
+            scale = next_scale;
+            prefix = next_prefix;
+        }
+    }
+    fmt::Display::fmt(&(value / scale), f)?;
+    write!(f, " {prefix}{unit}")
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct HumanBytes(pub u64);
 impl fmt::Display for HumanBytes {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let bytes = self.0 as f64;
-        let kilobytes = bytes / INFO_SIZE_INTERVAL as f64;
-        let megabytes = kilobytes / INFO_SIZE_INTERVAL as f64;
-        let gigabytes = megabytes / INFO_SIZE_INTERVAL as f64;
-        let terabytes = gigabytes / INFO_SIZE_INTERVAL as f64;
-        if 1. <= terabytes {
-            terabytes.fmt(f)?;
-            return write!(f, " TB");
-        }
-        if 1. <= gigabytes {
-            gigabytes.fmt(f)?;
-            return write!(f, " GB");
-        }
-        if 1. <= megabytes {
-            megabytes.fmt(f)?;
-            return write!(f, " MB");
-        }
-        if 1. <= kilobytes {
-            kilobytes.fmt(f)?;
-            return write!(f, " KB");
-        }
-        bytes.fmt(f)?;
-        write!(f, " B")
+        fmt_prefixed(f, self.0 as f64, INFO_SIZE_INTERVAL, &BINARY_PREFIXES, "B")
+    }
+}
+
+/// Events per second
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct HumanRate(pub f64);
+impl fmt::Display for HumanRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_prefixed(f, self.0, SI_INTERVAL, &SI_PREFIXES, "/s")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HumanThroughput {
+    pub bytes: u64,
+    pub per: Duration,
+}
+impl HumanThroughput {
+    #[must_use]
+    pub fn bytes_per_sec(&self) -> f64 {
+        self.bytes as f64 / self.per.as_secs_f64()
+    }
+}
+impl fmt::Display for HumanThroughput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_prefixed(
+            f,
+            self.bytes_per_sec(),
+            INFO_SIZE_INTERVAL,
+            &BINARY_PREFIXES,
+            "B/s",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_human_bytes() {
+        assert_eq!(HumanBytes(0).to_string(), "0 B");
+        assert_eq!(HumanBytes(1023).to_string(), "1023 B");
+        assert_eq!(HumanBytes(1024).to_string(), "1 KiB");
+        assert_eq!(HumanBytes(1536).to_string(), "1.5 KiB");
+        assert_eq!(format!("{:.1}", HumanBytes(1536)), "1.5 KiB");
+        assert_eq!(format!("{:.2}", HumanBytes(MB * 3)), "3.00 MiB");
+        assert_eq!(format!("{:.1}", HumanBytes(EB)), "1.0 EiB");
+        assert_eq!(HumanBytes(u64::MAX).to_string(), "16 EiB");
+    }
+
+    #[test]
+    fn test_human_rate() {
+        assert_eq!(HumanRate(0.).to_string(), "0 /s");
+        assert_eq!(HumanRate(999.).to_string(), "999 /s");
+        assert_eq!(HumanRate(1_000.).to_string(), "1 k/s");
+        assert_eq!(HumanRate(3_200_000.).to_string(), "3.2 M/s");
+        assert_eq!(format!("{:.1}", HumanRate(3_249_999.)), "3.2 M/s");
+        assert_eq!(format!("{:.1}", HumanRate(-2_000.)), "-2.0 k/s");
+        assert_eq!(HumanRate(f64::INFINITY).to_string(), "inf /s");
+    }
+
+    #[test]
+    fn test_human_throughput() {
+        let t = HumanThroughput {
+            bytes: 3 * KB,
+            per: Duration::from_secs(2),
+        };
+        assert_eq!(t.to_string(), "1.5 KiB/s");
+        let t = HumanThroughput {
+            bytes: 0,
+            per: Duration::from_secs(1),
+        };
+        assert_eq!(t.to_string(), "0 B/s");
+        let t = HumanThroughput {
+            bytes: GB,
+            per: Duration::from_millis(500),
+        };
+        assert_eq!(format!("{:.1}", t), "2.0 GiB/s");
     }
 }
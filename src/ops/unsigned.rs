@@ -6,6 +6,7 @@ macro_rules! define_unsigned {
         }
         impl $ty {
             const MAX_MASK: $primitive = (1 << $size) - 1;
+            pub const BITS: u32 = $size;
             pub const MAX: Self = Self::new((1 << $size) - 1).unwrap();
             pub const MIN: Self = Self::new(0).unwrap();
             /// # Safety
@@ -45,14 +46,15 @@ macro_rules! define_unsigned {
                 let value = self.value.saturating_sub(other.value);
                 Self::new(value).unwrap()
             }
+            /// Wrap in the primitive first; the mask then wraps at `2^BITS` since it divides the primitive's modulus
             pub const fn wrapping_add(&self, other: Self) -> Self {
-                Self::new((self.value + other.value) & Self::MAX_MASK).unwrap()
+                Self::new(self.value.wrapping_add(other.value) & Self::MAX_MASK).unwrap()
             }
             pub const fn wrapping_sub(&self, other: Self) -> Self {
-                let a = self.value | (1 << $size);
-                Self::new((a - other.value) & Self::MAX_MASK).unwrap()
+                Self::new(self.value.wrapping_sub(other.value) & Self::MAX_MASK).unwrap()
             }
         }
+        impl $crate::map::hash_map::IsEnabledNoHash for $ty {}
         impl From<$ty> for $primitive {
             fn from(value: $ty) -> Self {
                 value.value
@@ -89,6 +91,7 @@ macro_rules! define_unsigned {
                 self.value
             }
         }
+        impl $crate::map::hash_map::IsEnabledNoHash for $nonzero_ty {}
     };
 }
 
@@ -257,4 +260,58 @@ mod tests {
         assert_eq!(a.wrapping_add(b), U2::new(0).unwrap());
         assert_eq!(a.wrapping_sub(b), U2::new(2).unwrap());
     }
+
+    #[test]
+    fn test_u7_exhaustive() {
+        const MOD: u16 = 1 << 7;
+        for a in 0..MOD as u8 {
+            for b in 0..MOD as u8 {
+                let (x, y) = (U7::new(a).unwrap(), U7::new(b).unwrap());
+                let (a, b) = (u16::from(a), u16::from(b));
+                let sum = (a + b) % MOD;
+                let diff = (a + MOD - b) % MOD;
+                assert_eq!(u16::from(x.wrapping_add(y)), sum);
+                assert_eq!(u16::from(x.wrapping_sub(y)), diff);
+                assert_eq!(x.wrapping_add(y).wrapping_sub(y), x);
+                assert_eq!(x.wrapping_sub(y).wrapping_add(y), x);
+
+                let checked_add = (a + b < MOD).then_some(sum);
+                let checked_sub = (b <= a).then_some(diff);
+                assert_eq!(x.checked_add(y).map(u16::from), checked_add);
+                assert_eq!(x.checked_sub(y).map(u16::from), checked_sub);
+                assert_eq!(u16::from(x.saturating_add(y)), (a + b).min(MOD - 1));
+                assert_eq!(u16::from(x.saturating_sub(y)), a.saturating_sub(b));
+            }
+        }
+    }
+
+    /// Also run by `cargo test --release`, where an unmasked overflow would wrap silently instead of panicking
+    macro_rules! test_boundary {
+        ($name: ident, $ty: ident) => {
+            #[test]
+            fn $name() {
+                let one = $ty::new(1).unwrap();
+                let max = $ty::MAX;
+                let min = $ty::MIN;
+                assert_eq!(max.wrapping_add(max), max.wrapping_sub(one));
+                assert_eq!(max.wrapping_add(one), min);
+                assert_eq!(min.wrapping_sub(max), one);
+                assert_eq!(min.wrapping_sub(one), max);
+                assert_eq!(max.wrapping_sub(max), min);
+                assert_eq!(max.wrapping_sub(min), max);
+                assert_eq!(max.checked_add(one), None);
+                assert_eq!(min.checked_sub(one), None);
+                assert_eq!(max.saturating_add(max), max);
+                assert_eq!(min.saturating_sub(max), min);
+                let half = $ty::new(1 << ($ty::BITS - 1)).unwrap();
+                assert_eq!(half.wrapping_add(half), min);
+                assert_eq!(min.wrapping_sub(half), half);
+            }
+        };
+    }
+    test_boundary!(test_u7_boundary, U7);
+    test_boundary!(test_u15_boundary, U15);
+    test_boundary!(test_u31_boundary, U31);
+    test_boundary!(test_u63_boundary, U63);
+    test_boundary!(test_u127_boundary, U127);
 }
@@ -0,0 +1,73 @@
+//! Extension and capability traits of this crate in one import
+//!
+//! ```
+//! use primitive::{arena::stack::StaticStack, prelude::*};
+//!
+//! let mut stack = StaticStack::<u8, 2>::new();
+//! assert!(stack.is_empty());
+//! assert!(stack.push(1).is_none());
+//! assert!(stack.push(2).is_none());
+//! assert!(stack.is_full());
+//! assert_eq!(stack.push(3), Some(3));
+//! assert_eq!(stack.pop(), Some(2));
+//! assert_eq!(stack.len(), 1);
+//! ```
+//!
+//! [`ListMut`](crate::ops::list::ListMut) is left out since its methods would shadow the inherent ones of [`Vec`]:
+//!
+//! ```
+//! use primitive::prelude::*;
+//!
+//! let mut v = vec![3, 2, 1, 0];
+//! v.swap(0, 3);
+//! for chunk in v.chunks_mut(2) {
+//!     chunk.reverse();
+//! }
+//! assert_eq!(v, [2, 0, 3, 1]);
+//! ```
+
+pub use crate::{
+    arena::stack::Stack,
+    iter::chunk::Chunks,
+    map::{
+        free_list::FreeList,
+        hash_map::{HashEnsure, HashGet, HashGetMut, HashRemove},
+        MapInsert,
+    },
+    ops::{
+        clear::Clear,
+        diff::DiffExt,
+        float::FloatExt,
+        len::{Capacity, Full, Len, LenExt},
+        list::List,
+        opt::OptionExt,
+        ring::RingSpace,
+        slice::{AsSlice, AsSliceMut, LinearSearch},
+        unit::DurationExt,
+    },
+};
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn canonical_len(x: &impl crate::ops::len::Len) -> usize {
+        x.len()
+    }
+    fn prelude_len(x: &impl Len) -> usize {
+        canonical_len(x)
+    }
+
+    #[test]
+    fn test_prelude() {
+        let mut m = HashMap::new();
+        MapInsert::insert(&mut m, 1, 2);
+        assert_eq!(HashGet::get(&m, &1), Some(&2));
+        *m.ensure(&3, || 0) += 4;
+        assert_eq!(prelude_len(&m), 2);
+        Clear::clear(&mut m);
+        assert!(LenExt::is_empty(&m));
+    }
+}
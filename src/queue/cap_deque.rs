@@ -0,0 +1,246 @@
+use core::{marker::PhantomData, mem::MaybeUninit};
+
+use crate::ops::{
+    clear::Clear,
+    len::{Capacity, Full, Len, LenExt},
+    list::ListMut,
+    ring::RingSpace,
+};
+
+use super::cap_queue::CapQueuePointer;
+
+pub type CapVecDeque<T> = CapDeque<Vec<MaybeUninit<T>>, T>;
+impl<T> CapVecDeque<T> {
+    pub fn new_vec(capacity: usize) -> Self {
+        let buf_len = capacity + 1;
+        let mut buf = Vec::with_capacity(buf_len);
+        buf.extend((0..buf_len).map(|_| MaybeUninit::uninit()));
+        Self::new(buf)
+    }
+}
+pub type CapArrayDeque<T, const N: usize> = CapDeque<[MaybeUninit<T>; N], T>;
+impl<T, const N: usize> CapArrayDeque<T, N> {
+    /// `N - 1` since one slot is kept empty; ask for `N + 1` to hold `N` items
+    pub const CAPACITY: usize = N - 1;
+    /// Capacity is actually [`Self::CAPACITY`]
+    ///
+    /// Fails to compile if `N` is zero:
+    ///
+    /// ```compile_fail
+    /// use primitive::queue::cap_deque::CapArrayDeque;
+    ///
+    /// let _ = CapArrayDeque::<u8, 0>::new_array();
+    /// ```
+    pub fn new_array() -> Self {
+        const { assert!(N != 0) };
+        let buf = [const { MaybeUninit::uninit() }; N];
+        Self::new(buf)
+    }
+}
+
+/// Fixed-capacity double-ended queue
+pub struct CapDeque<L: ListMut<MaybeUninit<T>>, T> {
+    buf: L,
+    item: PhantomData<T>,
+    pointer: CapQueuePointer,
+}
+impl<L, T> CapDeque<L, T>
+where
+    L: ListMut<MaybeUninit<T>>,
+{
+    #[must_use]
+    pub fn new(buf: L) -> Self {
+        assert!(!buf.is_empty());
+        let pointer;
+        #[cfg(debug_assertions)]
+        {
+            pointer = CapQueuePointer::new(buf.len() - 1);
+        }
+        #[cfg(not(debug_assertions))]
+        {
+            pointer = CapQueuePointer::new();
+        }
+        Self {
+            buf,
+            pointer,
+            item: PhantomData,
+        }
+    }
+    /// Return [`Some`] if the deque is in full capacity
+    pub fn push_back(&mut self, item: T) -> Option<T> {
+        if self.is_full() {
+            return Some(item);
+        }
+        let index = self.pointer.enqueue(self.capacity());
+        self.buf[index] = MaybeUninit::new(item);
+        None
+    }
+    /// Return [`Some`] if the deque is in full capacity
+    pub fn push_front(&mut self, item: T) -> Option<T> {
+        if self.is_full() {
+            return Some(item);
+        }
+        let index = self.pointer.enqueue_front(self.capacity());
+        self.buf[index] = MaybeUninit::new(item);
+        None
+    }
+    pub fn pop_front(&mut self) -> Option<T> {
+        let index = self.pointer.dequeue(self.capacity())?;
+        Some(self.take(index))
+    }
+    pub fn pop_back(&mut self) -> Option<T> {
+        let index = self.pointer.dequeue_back(self.capacity())?;
+        Some(self.take(index))
+    }
+    fn take(&mut self, index: usize) -> T {
+        let value = core::mem::replace(&mut self.buf[index], MaybeUninit::uninit());
+        unsafe { value.assume_init() }
+    }
+    #[must_use]
+    pub fn front(&self) -> Option<&T> {
+        if self.is_empty() {
+            return None;
+        }
+        let index = self.pointer.head(self.capacity());
+        Some(unsafe { self.buf[index].assume_init_ref() })
+    }
+    #[must_use]
+    pub fn back(&self) -> Option<&T> {
+        if self.is_empty() {
+            return None;
+        }
+        let index = self.pointer.tail(self.capacity());
+        Some(unsafe { self.buf[index].assume_init_ref() })
+    }
+    /// From front to back
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + '_ {
+        let head = self.pointer.head(self.capacity());
+        (0..self.len()).map(move |i| {
+            let i = head.ring_add(i, self.capacity());
+            unsafe { self.buf[i].assume_init_ref() }
+        })
+    }
+}
+impl<L, T> core::fmt::Debug for CapDeque<L, T>
+where
+    L: ListMut<MaybeUninit<T>>,
+    T: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+impl<L, T> Capacity for CapDeque<L, T>
+where
+    L: ListMut<MaybeUninit<T>>,
+{
+    fn capacity(&self) -> usize {
+        self.buf.len().checked_sub(1).unwrap()
+    }
+}
+impl<L, T> Len for CapDeque<L, T>
+where
+    L: ListMut<MaybeUninit<T>>,
+{
+    fn len(&self) -> usize {
+        self.pointer.len(self.capacity())
+    }
+}
+impl<L, T> Clear for CapDeque<L, T>
+where
+    L: ListMut<MaybeUninit<T>>,
+{
+    fn clear(&mut self) {
+        while let Some(item) = self.pop_front() {
+            drop(item);
+        }
+    }
+}
+impl<L, T> Drop for CapDeque<L, T>
+where
+    L: ListMut<MaybeUninit<T>>,
+{
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, rc::Rc};
+
+    use crate::analysis::rng::{RngLite, SplitMix64};
+
+    use super::*;
+
+    #[test]
+    fn test_cap_deque() {
+        let mut q = CapArrayDeque::<_, 4>::new_array();
+        assert_eq!(q.capacity(), 3);
+        assert_eq!(q.capacity(), CapArrayDeque::<u8, 4>::CAPACITY);
+        assert!(q.is_empty());
+        assert!(q.front().is_none());
+        assert!(q.back().is_none());
+        assert!(q.push_back(2).is_none());
+        assert!(q.push_front(1).is_none());
+        assert!(q.push_back(3).is_none());
+        assert!(q.is_full());
+        assert_eq!(q.push_front(0), Some(0));
+        assert_eq!(q.push_back(4), Some(4));
+        assert_eq!(q.iter().copied().collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(q.iter().rev().copied().collect::<Vec<_>>(), [3, 2, 1]);
+        assert_eq!((q.front(), q.back()), (Some(&1), Some(&3)));
+        assert_eq!(q.pop_back(), Some(3));
+        assert_eq!(q.pop_front(), Some(1));
+        assert_eq!(q.pop_back(), Some(2));
+        assert_eq!(q.pop_back(), None);
+        assert_eq!(q.pop_front(), None);
+        assert_eq!(format!("{q:?}"), "[]");
+    }
+
+    #[test]
+    fn test_against_vec_deque() {
+        let mut q = CapVecDeque::new_vec(5);
+        let mut r = VecDeque::new();
+        let mut rng = SplitMix64::new(7);
+        for i in 0..1 << 12 {
+            match rng.next_usize_below(4) {
+                0 => {
+                    let rejected = q.push_back(i);
+                    assert_eq!(rejected.is_some(), r.len() == 5);
+                    if rejected.is_none() {
+                        r.push_back(i);
+                    }
+                }
+                1 => {
+                    let rejected = q.push_front(i);
+                    assert_eq!(rejected.is_some(), r.len() == 5);
+                    if rejected.is_none() {
+                        r.push_front(i);
+                    }
+                }
+                2 => assert_eq!(q.pop_front(), r.pop_front()),
+                _ => assert_eq!(q.pop_back(), r.pop_back()),
+            }
+            assert_eq!(q.len(), r.len());
+            assert_eq!((q.front(), q.back()), (r.front(), r.back()));
+            assert!(q.iter().eq(r.iter()));
+        }
+    }
+
+    #[test]
+    fn test_drop() {
+        let rc = Rc::new(());
+        let mut q = CapVecDeque::new_vec(3);
+        for _ in 0..3 {
+            assert!(q.push_front(Rc::clone(&rc)).is_none());
+        }
+        q.pop_back();
+        assert_eq!(Rc::strong_count(&rc), 3);
+        q.clear();
+        assert_eq!(Rc::strong_count(&rc), 1);
+        q.push_back(Rc::clone(&rc));
+        drop(q);
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+}
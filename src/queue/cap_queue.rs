@@ -1,16 +1,20 @@
 use core::{marker::PhantomData, mem::MaybeUninit, num::NonZeroUsize};
 
 use crate::{
+    error::QueueFull,
     ops::{
         clear::Clear,
-        len::{Capacity, Len, LenExt},
+        len::{items_heap_size, Capacity, HeapSize, Len, LenExt},
         list::ListMut,
+        prefetch::prefetch_read,
         ring::RingSpace,
-        slice::{AsSlice, AsSliceMut},
+        slice::{assume_init_slice, write_slice_copied, AsSlice, AsSliceMut},
     },
-    set::bit_set::BitSet,
+    set::bit_set::{BitSet, BitStore, StaticBitSet},
 };
 
+use super::ring_region::RingRegion;
+
 #[derive(Debug, Clone, Copy)]
 pub struct CapQueuePointer {
     #[cfg(debug_assertions)]
@@ -28,17 +32,20 @@ impl CapQueuePointer {
             next_tail: 1,
         }
     }
+    #[inline]
     #[cfg(debug_assertions)]
     #[must_use]
     pub fn cap(&self) -> usize {
         self.cap
     }
+    #[inline]
     #[must_use]
     pub fn head(&self, cap: usize) -> usize {
         #[cfg(debug_assertions)]
         assert_eq!(self.cap, cap);
         self.prev_head.ring_add(1, cap)
     }
+    #[inline]
     #[must_use]
     pub fn len(&self, cap: usize) -> usize {
         #[cfg(debug_assertions)]
@@ -46,22 +53,59 @@ impl CapQueuePointer {
         let dist = self.next_tail.ring_sub(self.prev_head, cap);
         dist.checked_sub(1).unwrap_or(cap)
     }
+    #[inline]
     #[must_use]
     fn is_empty(&self, cap: usize) -> bool {
         #[cfg(debug_assertions)]
         assert_eq!(self.cap, cap);
         self.len(cap) == 0
     }
+    /// # Panic
+    ///
+    /// The queue is full
+    #[inline]
     #[must_use]
     pub fn enqueue(&mut self, cap: usize) -> usize {
+        match self.try_enqueue(cap) {
+            Ok(index) => index,
+            Err(e) => panic!("{e}"),
+        }
+    }
+    #[inline]
+    pub fn try_enqueue(&mut self, cap: usize) -> Result<usize, QueueFull> {
         #[cfg(debug_assertions)]
         assert_eq!(self.cap, cap);
         if self.prev_head == self.next_tail {
-            panic!("out of buffer space");
+            return Err(QueueFull(()));
         }
         let index = self.next_tail;
         self.next_tail = self.next_tail.ring_add(1, cap);
-        index
+        Ok(index)
+    }
+    /// [`Self::enqueue()`] but at the head side
+    ///
+    /// # Panic
+    ///
+    /// The queue is full
+    #[inline]
+    #[must_use]
+    pub fn enqueue_front(&mut self, cap: usize) -> usize {
+        match self.try_enqueue_front(cap) {
+            Ok(index) => index,
+            Err(e) => panic!("{e}"),
+        }
+    }
+    /// [`Self::try_enqueue()`] but at the head side
+    #[inline]
+    pub fn try_enqueue_front(&mut self, cap: usize) -> Result<usize, QueueFull> {
+        #[cfg(debug_assertions)]
+        assert_eq!(self.cap, cap);
+        if self.prev_head == self.next_tail {
+            return Err(QueueFull(()));
+        }
+        let index = self.prev_head;
+        self.prev_head = self.prev_head.ring_sub(1, cap);
+        Ok(index)
     }
     #[must_use]
     pub fn batch_enqueue(
@@ -82,6 +126,7 @@ impl CapQueuePointer {
             ((start..cap + 1), Some(0..end))
         }
     }
+    #[inline]
     #[must_use]
     pub fn dequeue(&mut self, cap: usize) -> Option<usize> {
         #[cfg(debug_assertions)]
@@ -93,6 +138,25 @@ impl CapQueuePointer {
         self.prev_head = index;
         Some(index)
     }
+    /// [`Self::dequeue()`] but at the tail side
+    #[inline]
+    #[must_use]
+    pub fn dequeue_back(&mut self, cap: usize) -> Option<usize> {
+        #[cfg(debug_assertions)]
+        assert_eq!(self.cap, cap);
+        if self.is_empty(cap) {
+            return None;
+        }
+        self.next_tail = self.next_tail.ring_sub(1, cap);
+        Some(self.next_tail)
+    }
+    #[inline]
+    #[must_use]
+    pub fn tail(&self, cap: usize) -> usize {
+        #[cfg(debug_assertions)]
+        assert_eq!(self.cap, cap);
+        self.next_tail.ring_sub(1, cap)
+    }
     #[must_use]
     pub fn batch_dequeue(
         &mut self,
@@ -109,7 +173,8 @@ impl CapQueuePointer {
         let start = self.head(cap);
         self.prev_head = self.prev_head.ring_add(amount, cap);
         let end = self.head(cap);
-        Some(if start < end {
+        // `start == end` is either nothing or everything
+        Some(if start < end || amount == 0 {
             (start..end, None)
         } else {
             ((start..cap + 1), Some(0..end))
@@ -141,10 +206,10 @@ impl Default for CapQueuePointer {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct BitQueue {
-    pointer: CapQueuePointer,
-    set: BitSet,
+#[derive(Clone)]
+pub struct BitQueue<S = BitSet> {
+    region: RingRegion,
+    set: S,
 }
 impl BitQueue {
     #[must_use]
@@ -152,66 +217,162 @@ impl BitQueue {
         let set_len = len + 1;
         let set = BitSet::new(set_len);
         Self {
-            pointer: CapQueuePointer::new(
-                #[cfg(debug_assertions)]
-                set.capacity().checked_sub(1).unwrap(),
-            ),
+            region: RingRegion::new(set.capacity().checked_sub(1).unwrap()),
             set,
         }
     }
+}
+/// [`BitQueue`] of `WORDS` machine words held inline so that it can be built in a `static`
+pub type StaticBitQueue<const WORDS: usize> = BitQueue<StaticBitSet<WORDS>>;
+impl<const WORDS: usize> StaticBitQueue<WORDS> {
+    /// One bit short of the set to tell a full queue from an empty one
+    pub const CAPACITY: usize = StaticBitSet::<WORDS>::CAPACITY - 1;
+    /// Fails to compile if `WORDS` is zero:
+    ///
+    /// ```compile_fail
+    /// use primitive::queue::cap_queue::StaticBitQueue;
+    ///
+    /// let _ = StaticBitQueue::<0>::new_static();
+    /// ```
+    #[must_use]
+    pub const fn new_static() -> Self {
+        const { assert!(WORDS != 0) };
+        let set = StaticBitSet::new();
+        Self {
+            region: RingRegion::new(Self::CAPACITY),
+            set,
+        }
+    }
+}
+impl<const WORDS: usize> Default for StaticBitQueue<WORDS> {
+    fn default() -> Self {
+        Self::new_static()
+    }
+}
+impl<S: BitStore> BitQueue<S> {
+    /// # Panic
+    ///
+    /// The queue is full
     pub fn enqueue(&mut self, value: bool) {
-        let index = self.pointer.enqueue(self.capacity());
+        if let Err(e) = self.try_enqueue(value) {
+            panic!("{e}");
+        }
+    }
+    pub fn try_enqueue(&mut self, value: bool) -> Result<(), QueueFull<bool>> {
+        let Ok(index) = self.region.enqueue_index() else {
+            return Err(QueueFull(value));
+        };
         match value {
             true => self.set.set(index),
             false => self.set.clear_bit(index),
         }
+        Ok(())
     }
     pub fn dequeue(&mut self) -> Option<bool> {
-        let index = self.pointer.dequeue(self.capacity())?;
+        let index = self.region.dequeue_index()?;
         Some(self.set.get(index))
     }
-    fn set_index(&self, index: usize) -> usize {
-        let head = self.pointer.head(self.capacity());
-        head.ring_add(index, self.capacity())
+    /// [`Self::try_enqueue()`] but at the head side
+    pub fn try_enqueue_front(&mut self, value: bool) -> Result<(), QueueFull<bool>> {
+        let Ok(index) = self.region.enqueue_front_index() else {
+            return Err(QueueFull(value));
+        };
+        match value {
+            true => self.set.set(index),
+            false => self.set.clear_bit(index),
+        }
+        Ok(())
+    }
+    /// [`Self::dequeue()`] but at the tail side
+    pub fn dequeue_back(&mut self) -> Option<bool> {
+        let index = self.region.dequeue_back_index()?;
+        Some(self.set.get(index))
     }
     pub fn get(&self, index: usize) -> bool {
-        self.set.get(self.set_index(index))
+        self.set.get(self.region.index(index))
     }
     pub fn set(&mut self, index: usize, value: bool) {
-        let index = self.set_index(index);
+        let index = self.region.index(index);
         match value {
             true => self.set.set(index),
             false => self.set.clear_bit(index),
         }
     }
+    #[must_use]
+    pub fn try_get(&self, index: usize) -> Option<bool> {
+        if self.len() <= index {
+            return None;
+        }
+        Some(self.get(index))
+    }
+    /// Return `false` without touching any bit if `index` is out of bounds
+    #[must_use]
+    pub fn try_set(&mut self, index: usize, value: bool) -> bool {
+        if self.len() <= index {
+            return false;
+        }
+        self.set(index, value);
+        true
+    }
+    /// Pack the bits from the oldest into `out`, LSB first
+    ///
+    /// The bits past [`Self::len()`] are zeroed; those past the room of `out` are left out.
+    pub fn copy_to_words(&self, out: &mut [u64]) {
+        self.copy_range_to_words(0..self.len(), out);
+    }
+    /// [`Self::copy_to_words()`] but only of the bits in `range`
+    ///
+    /// # Panic
+    ///
+    /// `range` is out of [`Self::len()`]
+    pub fn copy_range_to_words(&self, range: core::ops::Range<usize>, out: &mut [u64]) {
+        assert!(range.end <= self.len());
+        const WORD_BITS: usize = u64::BITS as usize;
+        for (i, word) in out.iter_mut().enumerate() {
+            let start = range.start.saturating_add(i * WORD_BITS).min(range.end);
+            let end = start.saturating_add(WORD_BITS).min(range.end);
+            // At most one ring wrap within a word
+            let (a, b) = self.region.wrapped(start..end);
+            let a_len = a.len();
+            *word = self.set.get_bits(a);
+            if let Some(b) = b {
+                *word |= self.set.get_bits(b) << a_len;
+            }
+        }
+    }
     pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
-        let head = self.pointer.head(self.capacity());
-        (0..self.len()).map(move |i| {
-            let i = head.ring_add(i, self.capacity());
-            self.set.get(i)
-        })
+        let (a, b) = self.region.wrapped(0..self.len());
+        a.chain(b.unwrap_or(0..0)).map(|i| self.set.get(i))
     }
 }
-impl Capacity for BitQueue {
+impl<S: BitStore> core::fmt::Debug for BitQueue<S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("BitQueue(")?;
+        for bit in self.iter() {
+            f.write_str(if bit { "1" } else { "0" })?;
+        }
+        f.write_str(")")
+    }
+}
+impl<S: BitStore> PartialEq for BitQueue<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+impl<S: BitStore> Eq for BitQueue<S> {}
+impl<S> Capacity for BitQueue<S> {
     fn capacity(&self) -> usize {
-        self.set.capacity().checked_sub(1).unwrap()
+        self.region.capacity()
     }
 }
-impl Len for BitQueue {
+impl<S> Len for BitQueue<S> {
     fn len(&self) -> usize {
-        self.pointer.len(self.capacity())
+        self.region.len()
     }
 }
-impl Clear for BitQueue {
+impl<S: Clear> Clear for BitQueue<S> {
     fn clear(&mut self) {
-        #[cfg(not(debug_assertions))]
-        {
-            self.pointer = CapQueuePointer::new();
-        };
-        #[cfg(debug_assertions)]
-        {
-            self.pointer = CapQueuePointer::new(self.pointer.cap());
-        };
+        self.region.clear();
         self.set.clear();
     }
 }
@@ -221,24 +382,45 @@ impl<T> CapVecQueue<T> {
     pub fn new_vec(capacity: usize) -> Self {
         let buf_len = capacity + 1;
         let mut buf = Vec::with_capacity(buf_len);
-        buf.extend((0..buf.capacity()).map(|_| MaybeUninit::uninit()));
+        // `Vec` may over-allocate, e.g. `usize::MAX` for ZSTs, and the ring modulus derives from `buf.len()`
+        buf.extend((0..buf_len).map(|_| MaybeUninit::uninit()));
         Self::new(buf)
     }
 }
+impl<T: HeapSize> HeapSize for CapVecQueue<T> {
+    fn heap_size(&self) -> usize {
+        self.buf.capacity() * core::mem::size_of::<T>() + items_heap_size(self.iter())
+    }
+}
 pub type CapArrayQueue<T, const N: usize> = CapQueue<[MaybeUninit<T>; N], T>;
 impl<T, const N: usize> CapArrayQueue<T, N> {
-    /// Capacity is actually `N - 1`
-    pub fn new_array() -> Self {
-        let buf = [const { MaybeUninit::uninit() }; N];
-        Self::new(buf)
+    /// `N - 1` since one slot is kept empty; ask for `N + 1` to hold `N` items
+    pub const CAPACITY: usize = N - 1;
+    /// Capacity is actually [`Self::CAPACITY`]
+    ///
+    /// Fails to compile if `N` is zero:
+    ///
+    /// ```compile_fail
+    /// use primitive::queue::cap_queue::CapArrayQueue;
+    ///
+    /// let _ = CapArrayQueue::<u8, 0>::new_array();
+    /// ```
+    #[must_use]
+    pub const fn new_array() -> Self {
+        const { assert!(N != 0) };
+        Self {
+            buf: [const { MaybeUninit::uninit() }; N],
+            region: RingRegion::new(Self::CAPACITY),
+            item: PhantomData,
+        }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct CapQueue<L: ListMut<MaybeUninit<T>>, T> {
     buf: L,
     item: PhantomData<T>,
-    pointer: CapQueuePointer,
+    region: RingRegion,
 }
 impl<L, T> CapQueue<L, T>
 where
@@ -247,46 +429,62 @@ where
     #[must_use]
     pub fn new(buf: L) -> Self {
         assert!(!buf.is_empty());
-        let pointer;
-        #[cfg(debug_assertions)]
-        {
-            pointer = CapQueuePointer::new(buf.len() - 1);
-        }
-        #[cfg(not(debug_assertions))]
-        {
-            pointer = CapQueuePointer::new();
-        }
+        let region = RingRegion::new(buf.len() - 1);
         Self {
             buf,
-            pointer,
+            region,
             item: PhantomData,
         }
     }
+    /// # Panic
+    ///
+    /// The queue is full
+    #[inline]
     pub fn enqueue(&mut self, item: T) {
-        let index = self.pointer.enqueue(self.capacity());
+        if let Err(e) = self.try_enqueue(item) {
+            panic!("{e}");
+        }
+    }
+    #[inline]
+    pub fn try_enqueue(&mut self, item: T) -> Result<(), QueueFull<T>> {
+        let Ok(index) = self.region.enqueue_index() else {
+            return Err(QueueFull(item));
+        };
         self.buf[index] = MaybeUninit::new(item);
+        Ok(())
     }
+    /// # Panic
+    ///
+    /// The items do not fit
     pub fn batch_enqueue(&mut self, items: &[T])
     where
         T: Copy,
         L: AsSliceMut<MaybeUninit<T>>,
     {
-        let Some(items_len) = NonZeroUsize::new(items.len()) else {
-            return;
-        };
-        let (a, b) = self.pointer.batch_enqueue(items_len, self.capacity());
+        if let Err(e) = self.try_batch_enqueue(items) {
+            panic!("{e}");
+        }
+    }
+    /// Enqueue none of the items if they do not all fit
+    pub fn try_batch_enqueue(&mut self, items: &[T]) -> Result<(), QueueFull>
+    where
+        T: Copy,
+        L: AsSliceMut<MaybeUninit<T>>,
+    {
+        if items.is_empty() {
+            return Ok(());
+        }
+        let (a, b) = self.region.batch_enqueue_indices(items.len())?;
         let a_len = a.clone().len();
-        self.buf.as_slice_mut()[a].copy_from_slice(unsafe {
-            core::mem::transmute::<&[T], &[MaybeUninit<T>]>(&items[..a_len])
-        });
+        write_slice_copied(&mut self.buf.as_slice_mut()[a], &items[..a_len]);
         if let Some(b) = b {
-            self.buf.as_slice_mut()[b].copy_from_slice(unsafe {
-                core::mem::transmute::<&[T], &[MaybeUninit<T>]>(&items[a_len..])
-            });
+            write_slice_copied(&mut self.buf.as_slice_mut()[b], &items[a_len..]);
         }
+        Ok(())
     }
+    #[inline]
     pub fn dequeue(&mut self) -> Option<T> {
-        let index = self.pointer.dequeue(self.capacity())?;
+        let index = self.region.dequeue_index()?;
         let value = &mut self.buf[index];
         let value = core::mem::replace(value, MaybeUninit::uninit());
         Some(unsafe { value.assume_init() })
@@ -323,14 +521,18 @@ where
         T: Copy,
         L: AsSlice<MaybeUninit<T>>,
     {
-        let (a, b) = self.pointer.batch_dequeue(amount, self.capacity())?;
+        let (a, b) = self.region.batch_dequeue_indices(amount)?;
+        // Start pulling in the wrapped range while the caller goes through the first one
+        if let Some(b) = &b {
+            prefetch_read(self.buf.as_slice().as_ptr().wrapping_add(b.start));
+        }
         Some(self.slices(a, b))
     }
     pub fn as_slices(&self) -> Option<(&[T], Option<&[T]>)>
     where
         L: AsSlice<MaybeUninit<T>>,
     {
-        let (a, b) = self.pointer.as_slices(self.capacity())?;
+        let (a, b) = self.region.as_ranges()?;
         Some(self.slices(a, b))
     }
     fn slices(
@@ -341,35 +543,100 @@ where
     where
         L: AsSlice<MaybeUninit<T>>,
     {
-        let a = unsafe { core::mem::transmute::<&[MaybeUninit<T>], &[T]>(&self.buf.as_slice()[a]) };
-        let b = b.map(|b| unsafe {
-            core::mem::transmute::<&[MaybeUninit<T>], &[T]>(&self.buf.as_slice()[b])
-        });
+        let a = unsafe { assume_init_slice(&self.buf.as_slice()[a]) };
+        let b = b.map(|b| unsafe { assume_init_slice(&self.buf.as_slice()[b]) });
         (a, b)
     }
+    /// Rotate the buffer so all the items are in the first slice of [`Self::as_slices()`]
+    pub fn make_contiguous(&mut self) {
+        let shift = self.region.make_contiguous();
+        self.buf.rotate_left(shift);
+    }
     pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
-        let head = self.pointer.head(self.capacity());
-        (0..self.len()).map(move |i| {
-            let i = head.ring_add(i, self.capacity());
+        let (a, b) = self.region.wrapped(0..self.len());
+        a.chain(b.unwrap_or(0..0)).map(|i| {
             let value = &self.buf[i];
             unsafe { value.assume_init_ref() }
         })
     }
 }
+impl<L, T> core::fmt::Debug for CapQueue<L, T>
+where
+    L: ListMut<MaybeUninit<T>>,
+    T: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+impl<L, M, T> PartialEq<CapQueue<M, T>> for CapQueue<L, T>
+where
+    L: ListMut<MaybeUninit<T>>,
+    M: ListMut<MaybeUninit<T>>,
+    T: PartialEq,
+{
+    fn eq(&self, other: &CapQueue<M, T>) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+impl<L, T> Eq for CapQueue<L, T>
+where
+    L: ListMut<MaybeUninit<T>>,
+    T: Eq,
+{
+}
+impl<L, T> PartialEq<[T]> for CapQueue<L, T>
+where
+    L: ListMut<MaybeUninit<T>>,
+    T: PartialEq,
+{
+    fn eq(&self, other: &[T]) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+impl<L, T> PartialEq<&[T]> for CapQueue<L, T>
+where
+    L: ListMut<MaybeUninit<T>>,
+    T: PartialEq,
+{
+    fn eq(&self, other: &&[T]) -> bool {
+        *self == **other
+    }
+}
+impl<L, T> PartialEq<Vec<T>> for CapQueue<L, T>
+where
+    L: ListMut<MaybeUninit<T>>,
+    T: PartialEq,
+{
+    fn eq(&self, other: &Vec<T>) -> bool {
+        *self == **other
+    }
+}
+impl<L, T, const N: usize> PartialEq<[T; N]> for CapQueue<L, T>
+where
+    L: ListMut<MaybeUninit<T>>,
+    T: PartialEq,
+{
+    fn eq(&self, other: &[T; N]) -> bool {
+        *self == other[..]
+    }
+}
 impl<L, T> Capacity for CapQueue<L, T>
 where
     L: ListMut<MaybeUninit<T>>,
 {
+    #[inline]
     fn capacity(&self) -> usize {
-        self.buf.len().checked_sub(1).unwrap()
+        self.region.capacity()
     }
 }
 impl<L, T> Len for CapQueue<L, T>
 where
     L: ListMut<MaybeUninit<T>>,
 {
+    #[inline]
     fn len(&self) -> usize {
-        self.pointer.len(self.capacity())
+        self.region.len()
     }
 }
 impl<L, T> Clear for CapQueue<L, T>
@@ -395,6 +662,8 @@ where
 
 #[cfg(test)]
 mod tests {
+    use crate::ops::len::Full;
+
     use super::*;
 
     #[test]
@@ -446,6 +715,174 @@ mod tests {
         }
     }
     #[test]
+    fn test_cap_queue_eq() {
+        let mut a = CapArrayQueue::<_, 4>::new_array();
+        let mut b = CapVecQueue::new_vec(5);
+        for _ in 0..2 {
+            a.enqueue(0);
+            assert_eq!(a.dequeue().unwrap(), 0);
+        }
+        for i in 1..=3 {
+            a.enqueue(i);
+            b.enqueue(i);
+        }
+        assert_eq!(a, b);
+        assert_eq!(a, [1, 2, 3]);
+        assert_eq!(a, vec![1, 2, 3]);
+        assert_eq!(a, &[1, 2, 3][..]);
+        assert_eq!(format!("{a:?}"), "[1, 2, 3]");
+        assert_eq!(format!("{a:?}"), format!("{b:?}"));
+        a.dequeue();
+        assert_ne!(a, b);
+        b.dequeue();
+        assert_eq!(a, b);
+        a.dequeue();
+        a.enqueue(4);
+        assert_ne!(a, b);
+        assert_eq!(a, [3, 4]);
+    }
+    #[test]
+    fn test_new_vec_capacity() {
+        for capacity in [1, 3, 5, 17, 100] {
+            let mut q = CapVecQueue::<u8>::new_vec(capacity);
+            assert_eq!(q.capacity(), capacity);
+            for i in 0..capacity {
+                q.enqueue(i as u8);
+            }
+            assert!(q.is_full());
+            assert_eq!(q.len(), capacity);
+        }
+    }
+    #[cfg(not(loom))]
+    #[test]
+    fn test_statics() {
+        use crate::{
+            arena::stack::{Stack, StaticStack},
+            sync::mutex::SpinMutex,
+        };
+
+        const THREADS: usize = 4;
+        const PER_THREAD: usize = 16;
+        static QUEUE: SpinMutex<CapArrayQueue<usize, { THREADS * PER_THREAD + 1 }>> =
+            SpinMutex::new(CapArrayQueue::new_array());
+        static BITS: SpinMutex<StaticBitQueue<2>> = SpinMutex::new(StaticBitQueue::new_static());
+        static FREE: SpinMutex<StaticStack<usize, { THREADS * PER_THREAD }>> =
+            SpinMutex::new(StaticStack::new());
+
+        assert_eq!(BITS.lock().capacity(), 2 * usize::BITS as usize - 1);
+        std::thread::scope(|s| {
+            for t in 0..THREADS {
+                s.spawn(move || {
+                    for i in 0..PER_THREAD {
+                        let value = t * PER_THREAD + i;
+                        QUEUE.lock().enqueue(value);
+                        BITS.lock().enqueue(t % 2 == 0);
+                        FREE.lock().push(value);
+                    }
+                });
+            }
+        });
+        let mut queued = QUEUE.lock().iter().copied().collect::<Vec<_>>();
+        queued.sort_unstable();
+        assert!(queued.into_iter().eq(0..THREADS * PER_THREAD));
+        let bits = BITS.lock();
+        assert_eq!(bits.len(), THREADS * PER_THREAD);
+        assert_eq!(bits.iter().filter(|&b| b).count(), THREADS * PER_THREAD / 2);
+        let mut freed = FREE.lock().as_slice().to_vec();
+        freed.sort_unstable();
+        assert!(freed.into_iter().eq(0..THREADS * PER_THREAD));
+    }
+    #[test]
+    fn test_capacity_consts() {
+        const {
+            assert!(CapArrayQueue::<u8, 4>::CAPACITY == 3);
+            assert!(StaticBitQueue::<2>::CAPACITY == 2 * usize::BITS as usize - 1);
+        }
+        let mut q = CapArrayQueue::<_, 4>::new_array();
+        assert_eq!(q.capacity(), CapArrayQueue::<u8, 4>::CAPACITY);
+        for i in 0..CapArrayQueue::<u8, 4>::CAPACITY {
+            q.enqueue(i);
+        }
+        assert!(q.is_full());
+        let q = StaticBitQueue::<2>::new_static();
+        assert_eq!(q.capacity(), StaticBitQueue::<2>::CAPACITY);
+    }
+    #[test]
+    fn test_static_bit_queue() {
+        let mut a = StaticBitQueue::<1>::new_static();
+        let mut b = BitQueue::new(a.capacity());
+        assert_eq!(a.capacity(), b.capacity());
+        for i in 0..a.capacity() * 3 {
+            if a.len() == a.capacity() {
+                assert_eq!(a.dequeue(), b.dequeue());
+            }
+            a.enqueue(i % 3 == 0);
+            b.enqueue(i % 3 == 0);
+            assert!(a.iter().eq(b.iter()));
+        }
+        let mut wa = [0; 2];
+        let mut wb = [0; 2];
+        a.copy_to_words(&mut wa);
+        b.copy_to_words(&mut wb);
+        assert_eq!(wa, wb);
+        assert_eq!(format!("{a:?}"), format!("{b:?}"));
+    }
+    #[test]
+    fn test_make_contiguous() {
+        let mut q = CapArrayQueue::<u32, 5>::new_array();
+        for i in 0..4 {
+            q.enqueue(i);
+        }
+        q.dequeue();
+        q.dequeue();
+        q.enqueue(4);
+        q.enqueue(5);
+        assert!(q.as_slices().unwrap().1.is_some());
+        q.make_contiguous();
+        assert_eq!(q.as_slices().unwrap().0, [2, 3, 4, 5]);
+        assert_eq!(q.dequeue(), Some(2));
+        q.enqueue(6);
+        assert!(q.iter().copied().eq(3..7));
+    }
+    #[test]
+    fn test_cap_queue_zst() {
+        let mut q = CapVecQueue::<()>::new_vec(4);
+        assert_eq!(q.capacity(), 4);
+        let mut a = CapArrayQueue::<(), 5>::new_array();
+        assert_eq!(a.capacity(), 4);
+        for _ in 0..3 {
+            for _ in 0..4 {
+                q.enqueue(());
+                a.enqueue(());
+            }
+            assert!(q.is_full() && a.is_full());
+            assert_eq!(q.iter().count(), 4);
+            assert_eq!(q.dequeue(), Some(()));
+            assert_eq!(q.len(), 3);
+            q.batch_enqueue(&[()]);
+            assert_eq!(q.batch_dequeue_iter(8).count(), 4);
+            while a.dequeue().is_some() {}
+            assert!(q.is_empty() && a.is_empty());
+            assert_eq!(q.dequeue(), None);
+        }
+    }
+    #[test]
+    fn test_bit_queue_eq() {
+        let mut a = BitQueue::new(3);
+        let mut b = BitQueue::new(3);
+        a.enqueue(true);
+        a.dequeue();
+        for bit in [false, true, true] {
+            a.enqueue(bit);
+            b.enqueue(bit);
+        }
+        assert_eq!(a, b);
+        assert_eq!(format!("{a:?}"), "BitQueue(011)");
+        b.set(0, true);
+        assert_ne!(a, b);
+        assert_eq!(format!("{b:?}"), "BitQueue(111)");
+    }
+    #[test]
     fn test_bit_queue() {
         let mut q = BitQueue::new(2);
         assert!(q.is_empty());
@@ -460,6 +897,120 @@ mod tests {
         assert!(q.dequeue().unwrap());
         assert!(q.dequeue().is_none());
     }
+    #[test]
+    fn test_bit_queue_both_ends() {
+        let mut q = BitQueue::new(3);
+        assert!(q.dequeue_back().is_none());
+        q.enqueue(true);
+        q.try_enqueue_front(false).unwrap();
+        q.try_enqueue_front(true).unwrap();
+        assert_eq!(q.iter().collect::<Vec<_>>(), [true, false, true]);
+        assert_eq!(q.dequeue_back(), Some(true));
+        // Wrap around the start of the set
+        q.try_enqueue_front(true).unwrap();
+        assert_eq!(q.iter().collect::<Vec<_>>(), [true, true, false]);
+        assert_eq!(q.dequeue(), Some(true));
+        assert_eq!(q.dequeue_back(), Some(false));
+        assert_eq!(q.dequeue_back(), Some(true));
+        assert!(q.is_empty());
+        for _ in 0..q.capacity() {
+            q.try_enqueue_front(false).unwrap();
+        }
+        assert_eq!(q.try_enqueue_front(true), Err(QueueFull(true)));
+    }
+    #[test]
+    fn test_bit_queue_copy_to_words() {
+        let mut q = BitQueue::new(150);
+        let pattern = |i: usize| i.is_multiple_of(3) || i.is_multiple_of(7);
+        for i in 0..100 {
+            q.enqueue(pattern(i));
+        }
+        for _ in 0..90 {
+            q.dequeue();
+        }
+        // Wrap around the ring
+        for i in 100..200 {
+            q.enqueue(pattern(i));
+        }
+        assert_eq!(q.len(), 110);
+        let mut words = [u64::MAX; 3];
+        q.copy_to_words(&mut words);
+        for (i, bit) in q.iter().enumerate() {
+            assert_eq!(words[i / 64] >> (i % 64) & 1 == 1, bit);
+            assert_eq!(bit, pattern(i + 90));
+        }
+        assert_eq!(words[1] >> (110 - 64), 0);
+        assert_eq!(words[2], 0);
+
+        let mut words = [0; 1];
+        q.copy_range_to_words(5..100, &mut words);
+        for i in 0..64 {
+            assert_eq!(words[0] >> i & 1 == 1, q.get(i + 5));
+        }
+    }
+    #[test]
+    fn test_bit_queue_bounds() {
+        let mut q = BitQueue::new(3);
+        q.enqueue(true);
+        q.enqueue(false);
+        assert_eq!(q.try_get(1), Some(false));
+        assert_eq!(q.try_get(2), None);
+        assert!(!q.try_set(2, true));
+        assert!(q.try_set(1, true));
+        q.enqueue(false);
+        assert_eq!(q.try_get(2), Some(false));
+        assert_eq!(format!("{q:?}"), "BitQueue(110)");
+    }
+
+    #[test]
+    fn test_try_enqueue() {
+        let mut p = CapQueuePointer::new(
+            #[cfg(debug_assertions)]
+            2,
+        );
+        assert!(p.try_enqueue(2).is_ok());
+        assert!(p.try_enqueue_front(2).is_ok());
+        assert_eq!(p.try_enqueue(2), Err(QueueFull(())));
+        assert_eq!(p.try_enqueue_front(2), Err(QueueFull(())));
+        assert_eq!(p.len(2), 2);
+
+        let mut q = CapVecQueue::new_vec(2);
+        q.try_enqueue(String::from("a")).unwrap();
+        q.try_enqueue(String::from("b")).unwrap();
+        assert_eq!(
+            q.try_enqueue(String::from("c")),
+            Err(QueueFull(String::from("c")))
+        );
+        assert_eq!(q, [String::from("a"), String::from("b")]);
+
+        let mut q = CapVecQueue::new_vec(3);
+        q.batch_enqueue(&[1, 2]);
+        q.try_batch_enqueue(&[]).unwrap();
+        assert_eq!(q.try_batch_enqueue(&[3, 4]), Err(QueueFull(())));
+        assert_eq!(q, [1, 2]);
+
+        let mut q = BitQueue::new(0);
+        for _ in 0..q.capacity() {
+            q.try_enqueue(true).unwrap();
+        }
+        assert_eq!(q.try_enqueue(false), Err(QueueFull(false)));
+        assert!(q.iter().all(|bit| bit));
+    }
+    #[test]
+    #[should_panic(expected = "out of buffer space")]
+    fn test_enqueue_full_panic() {
+        let mut q = CapVecQueue::new_vec(1);
+        q.enqueue(1);
+        q.enqueue(2);
+    }
+    #[test]
+    #[should_panic(expected = "out of buffer space")]
+    fn test_bit_queue_full_panic() {
+        let mut q = BitQueue::new(0);
+        for _ in 0..=q.capacity() {
+            q.enqueue(true);
+        }
+    }
 }
 
 #[cfg(feature = "nightly")]
@@ -540,6 +1091,22 @@ mod benches {
             recv.clear();
         });
     }
+    /// Compare with a build under `RUSTFLAGS="--cfg no_prefetch"` for the prefetch of the wrapped range
+    #[bench]
+    fn bench_cap_vec_queue_wrapped_sum(bencher: &mut Bencher) {
+        const BIG: usize = 1 << 20;
+        let mut q = CapVecQueue::<u64>::new_vec(BIG);
+        let b = vec![1; BIG / 2];
+        // Every batch from here on straddles the end of the buffer
+        q.batch_enqueue(&b[..BIG / 4]);
+        q.batch_dequeue(BIG / 4);
+        bencher.iter(|| {
+            q.batch_enqueue(&b);
+            let (a, b) = q.batch_dequeue(BIG / 2).unwrap();
+            let sum = a.iter().chain(b.unwrap_or(&[])).sum::<u64>();
+            black_box(sum);
+        });
+    }
 
     fn batch_buf() -> Vec<Item> {
         let mut b = vec![];
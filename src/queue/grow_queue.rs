@@ -1,52 +1,160 @@
-use crate::ops::{
-    clear::Clear,
-    len::{Capacity, Full, Len},
+use core::num::NonZeroUsize;
+use std::io::IoSlice;
+
+use thiserror::Error;
+
+use crate::{
+    analysis::metrics::{MetricSource, MetricVisitor},
+    ops::{
+        clear::Clear,
+        len::{Capacity, Full, HeapSize, Len},
+    },
 };
 
 use super::cap_queue::CapVecQueue;
 
-const START_UP_SIZE: usize = 16;
+pub const START_UP_SIZE: usize = 16;
+
+/// How much [`GrowQueue`] reallocates once full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GrowthPolicy {
+    /// Double the capacity until the items fit
+    #[default]
+    Double,
+    /// Add this many items of capacity at a time until the items fit
+    Fixed(usize),
+    /// Just enough for the items
+    Exact,
+}
+impl GrowthPolicy {
+    #[must_use]
+    fn next_capacity(self, capacity: usize, at_least: usize) -> usize {
+        if at_least <= capacity {
+            return capacity;
+        }
+        match self {
+            Self::Double => {
+                let mut new = capacity.max(1);
+                while new < at_least {
+                    new = new.saturating_mul(2);
+                }
+                new
+            }
+            Self::Fixed(step) => {
+                let step = step.max(1);
+                let steps = (at_least - capacity).div_ceil(step);
+                capacity.saturating_add(steps.saturating_mul(step))
+            }
+            Self::Exact => at_least,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("{len} items would exceed the max of {max}")]
+pub struct WouldExceed {
+    pub len: usize,
+    pub max: NonZeroUsize,
+}
 
-#[derive(Debug)]
 pub struct GrowQueue<T> {
     vec_queue: Option<CapVecQueue<T>>,
+    initial: usize,
+    max: Option<NonZeroUsize>,
+    policy: GrowthPolicy,
+    high_water_mark: usize,
 }
 impl<T> GrowQueue<T> {
     #[must_use]
     pub const fn new() -> Self {
-        Self { vec_queue: None }
+        Self::with_limits(START_UP_SIZE, None)
     }
+    /// Allocate `initial` items of capacity on the first enqueue and never hold more than `max` items
     #[must_use]
-    fn ensure_primed(&mut self) -> &mut CapVecQueue<T> {
-        if self.vec_queue.is_some() {
-            return self.vec_queue.as_mut().unwrap();
+    pub const fn with_limits(initial: usize, max: Option<NonZeroUsize>) -> Self {
+        Self {
+            vec_queue: None,
+            initial,
+            max,
+            policy: GrowthPolicy::Double,
+            high_water_mark: 0,
+        }
+    }
+    #[must_use]
+    pub const fn with_growth_policy(mut self, policy: GrowthPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+    #[must_use]
+    pub const fn max(&self) -> Option<NonZeroUsize> {
+        self.max
+    }
+    /// The most items ever held at once
+    #[must_use]
+    pub const fn high_water_mark(&self) -> usize {
+        self.high_water_mark
+    }
+    #[must_use]
+    fn initial_capacity(&self) -> usize {
+        match self.max {
+            Some(max) => self.initial.min(max.get()),
+            None => self.initial,
         }
-        self.vec_queue = Some(CapVecQueue::new_vec(START_UP_SIZE));
-        self.vec_queue.as_mut().unwrap()
     }
     #[must_use]
-    fn exp_grow(&mut self) -> &mut CapVecQueue<T> {
+    fn ensure_primed(&mut self) -> &mut CapVecQueue<T> {
+        let initial = self.initial_capacity();
+        self.vec_queue
+            .get_or_insert_with(|| CapVecQueue::new_vec(initial))
+    }
+    fn update_high_water_mark(&mut self) {
+        self.high_water_mark = self.high_water_mark.max(self.len());
+    }
+    /// Capacity for `at_least` items by the growth policy but no more than [`Self::max()`]
+    fn next_capacity(&self, capacity: usize, at_least: usize) -> Result<usize, WouldExceed> {
+        let new = self.policy.next_capacity(capacity, at_least);
+        let Some(max) = self.max else {
+            return Ok(new);
+        };
+        if max.get() < at_least {
+            return Err(WouldExceed { len: at_least, max });
+        }
+        Ok(new.min(max.get()))
+    }
+    fn grow(&mut self, cap_at_least: usize) -> Result<&mut CapVecQueue<T>, WouldExceed> {
+        let capacity = self.vec_queue.as_ref().unwrap().capacity();
+        let new_cap = self.next_capacity(capacity, cap_at_least)?;
+        Ok(self.realloc(new_cap))
+    }
+    /// Move the items to the start of a new buffer of `new_cap` items
+    fn realloc(&mut self, new_cap: usize) -> &mut CapVecQueue<T> {
         let vec_queue = self.vec_queue.as_mut().unwrap();
-        let mut new = CapVecQueue::new_vec(vec_queue.capacity() * 2);
+        let mut new = CapVecQueue::new_vec(new_cap);
         while let Some(item) = vec_queue.dequeue() {
             new.enqueue(item);
         }
-        self.vec_queue = Some(new);
-        self.vec_queue.as_mut().unwrap()
+        self.vec_queue.insert(new)
     }
-    #[must_use]
-    fn exp_grow_copy(&mut self, cap_at_least: usize) -> &mut CapVecQueue<T>
+    /// Reallocate down to the capacity of `target` items but never below the items held or the initial capacity
+    ///
+    /// Do nothing if that is not less than the current capacity.
+    pub fn shrink_to(&mut self, target: usize) {
+        let Some(vec_queue) = &self.vec_queue else {
+            return;
+        };
+        let new_cap = target.max(vec_queue.len()).max(self.initial_capacity());
+        if vec_queue.capacity() <= new_cap {
+            return;
+        }
+        self.realloc(new_cap);
+    }
+    fn grow_copy(&mut self, cap_at_least: usize) -> Result<&mut CapVecQueue<T>, WouldExceed>
     where
         T: Copy,
     {
+        let capacity = self.vec_queue.as_ref().unwrap().capacity();
+        let new_cap = self.next_capacity(capacity, cap_at_least)?;
         let vec_queue = self.vec_queue.as_mut().unwrap();
-        let mut new_cap = vec_queue.capacity();
-        loop {
-            if cap_at_least <= new_cap {
-                break;
-            }
-            new_cap *= 2;
-        }
         let mut new = CapVecQueue::new_vec(new_cap);
         if let Some((a, b)) = vec_queue.as_slices() {
             new.batch_enqueue(a);
@@ -55,33 +163,72 @@ impl<T> GrowQueue<T> {
             }
         }
         self.vec_queue = Some(new);
-        self.vec_queue.as_mut().unwrap()
+        Ok(self.vec_queue.as_mut().unwrap())
     }
+    /// # Panic
+    ///
+    /// The queue already holds [`Self::max()`] items
     pub fn enqueue(&mut self, item: T) {
         let vec_queue = self.ensure_primed();
         let vec_queue = if vec_queue.is_full() {
-            self.exp_grow()
+            let at_least = vec_queue.len() + 1;
+            self.grow(at_least).unwrap()
         } else {
             vec_queue
         };
         vec_queue.enqueue(item);
+        self.update_high_water_mark();
     }
     pub fn dequeue(&mut self) -> Option<T> {
         let vec_queue = self.vec_queue.as_mut()?;
         vec_queue.dequeue()
     }
+    /// Drop the oldest items until at most `keep_last` are left in O(dropped)
+    pub fn truncate_front(&mut self, keep_last: usize) {
+        let Some(vec_queue) = &mut self.vec_queue else {
+            return;
+        };
+        for _ in keep_last..vec_queue.len() {
+            vec_queue.dequeue();
+        }
+    }
+    /// Keep the order in O(n) without reallocating
+    pub fn retain(&mut self, mut pred: impl FnMut(&T) -> bool) {
+        let Some(vec_queue) = &mut self.vec_queue else {
+            return;
+        };
+        // Cycle every item through the ring once; the kept ones never outnumber the freed slots
+        for _ in 0..vec_queue.len() {
+            let item = vec_queue.dequeue().unwrap();
+            if pred(&item) {
+                vec_queue.enqueue(item);
+            }
+        }
+    }
+    /// # Panic
+    ///
+    /// The items would exceed [`Self::max()`]
     pub fn batch_enqueue(&mut self, items: &[T])
+    where
+        T: Copy,
+    {
+        self.try_batch_enqueue(items).unwrap();
+    }
+    /// Enqueue none of the items if they would exceed [`Self::max()`]
+    pub fn try_batch_enqueue(&mut self, items: &[T]) -> Result<(), WouldExceed>
     where
         T: Copy,
     {
         let vec_queue = self.ensure_primed();
         let cap_at_least = vec_queue.len() + items.len();
         let vec_queue = if vec_queue.capacity() < cap_at_least {
-            self.exp_grow_copy(cap_at_least)
+            self.grow_copy(cap_at_least)?
         } else {
             vec_queue
         };
         vec_queue.batch_enqueue(items);
+        self.update_high_water_mark();
+        Ok(())
     }
     pub fn batch_dequeue_extend<'a>(
         &'a mut self,
@@ -113,6 +260,40 @@ impl<T> GrowQueue<T> {
         self.vec_queue.iter().flat_map(|q| q.iter())
     }
 }
+impl GrowQueue<u8> {
+    /// # Panic
+    ///
+    /// The bytes would exceed [`Self::max()`]
+    pub fn batch_enqueue_vectored(&mut self, bufs: &[IoSlice<'_>]) {
+        self.try_batch_enqueue_vectored(bufs).unwrap();
+    }
+    /// Enqueue none of the bytes if they would exceed [`Self::max()`]
+    pub fn try_batch_enqueue_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<(), WouldExceed> {
+        let vec_queue = self.ensure_primed();
+        let cap_at_least = vec_queue.len() + bufs.iter().map(|buf| buf.len()).sum::<usize>();
+        let vec_queue = if vec_queue.capacity() < cap_at_least {
+            self.grow_copy(cap_at_least)?
+        } else {
+            vec_queue
+        };
+        for buf in bufs {
+            vec_queue.batch_enqueue(buf);
+        }
+        self.update_high_water_mark();
+        Ok(())
+    }
+    /// Append the readable regions in order for a vectored write, at most two and none empty
+    pub fn as_io_slices<'a>(&'a self, out: &mut Vec<IoSlice<'a>>) {
+        let Some((a, b)) = self.as_slices() else {
+            return;
+        };
+        for slice in [a, b.unwrap_or(&[])] {
+            if !slice.is_empty() {
+                out.push(IoSlice::new(slice));
+            }
+        }
+    }
+}
 impl<T> Default for GrowQueue<T> {
     fn default() -> Self {
         Self::new()
@@ -126,6 +307,26 @@ impl<T> Len for GrowQueue<T> {
         vec_queue.len()
     }
 }
+impl<T> MetricSource for GrowQueue<T> {
+    fn visit(&self, visitor: &mut dyn MetricVisitor) {
+        visitor.gauge("len", self.len() as f64);
+        visitor.gauge("high_water_mark", self.high_water_mark as f64);
+    }
+}
+impl<T> Capacity for GrowQueue<T> {
+    /// Zero before the first enqueue
+    fn capacity(&self) -> usize {
+        let Some(vec_queue) = &self.vec_queue else {
+            return 0;
+        };
+        vec_queue.capacity()
+    }
+}
+impl<T: HeapSize> HeapSize for GrowQueue<T> {
+    fn heap_size(&self) -> usize {
+        self.vec_queue.heap_size()
+    }
+}
 impl<T> Clear for GrowQueue<T> {
     fn clear(&mut self) {
         let Some(vec_queue) = &mut self.vec_queue else {
@@ -134,16 +335,53 @@ impl<T> Clear for GrowQueue<T> {
         vec_queue.clear();
     }
 }
+impl<T: core::fmt::Debug> core::fmt::Debug for GrowQueue<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+impl<T: PartialEq> PartialEq for GrowQueue<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+impl<T: Eq> Eq for GrowQueue<T> {}
+impl<T: PartialEq> PartialEq<[T]> for GrowQueue<T> {
+    fn eq(&self, other: &[T]) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+impl<T: PartialEq> PartialEq<&[T]> for GrowQueue<T> {
+    fn eq(&self, other: &&[T]) -> bool {
+        *self == **other
+    }
+}
+impl<T: PartialEq> PartialEq<Vec<T>> for GrowQueue<T> {
+    fn eq(&self, other: &Vec<T>) -> bool {
+        *self == **other
+    }
+}
+impl<T: PartialEq, const N: usize> PartialEq<[T; N]> for GrowQueue<T> {
+    fn eq(&self, other: &[T; N]) -> bool {
+        *self == other[..]
+    }
+}
 impl<T: Copy> Clone for GrowQueue<T> {
     fn clone(&self) -> Self {
         Self {
             vec_queue: self.vec_queue.clone(),
+            initial: self.initial,
+            max: self.max,
+            policy: self.policy,
+            high_water_mark: self.high_water_mark,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::io::tests::writev;
+
     use super::*;
 
     #[test]
@@ -160,4 +398,254 @@ mod tests {
             (0..START_UP_SIZE + 1).collect::<Vec<_>>()
         );
     }
+
+    #[test]
+    fn test_grow_queue_zst() {
+        let mut q = GrowQueue::new();
+        for _ in 0..START_UP_SIZE * 3 {
+            q.enqueue(());
+        }
+        assert_eq!(q.len(), START_UP_SIZE * 3);
+        q.batch_enqueue(&[(); START_UP_SIZE]);
+        assert_eq!(q.len(), START_UP_SIZE * 4);
+        assert_eq!(q.iter().count(), START_UP_SIZE * 4);
+        for _ in 0..START_UP_SIZE * 4 {
+            assert_eq!(q.dequeue(), Some(()));
+        }
+        assert_eq!(q.dequeue(), None);
+        assert_eq!(q.len(), 0);
+    }
+
+    #[test]
+    fn test_grow_queue_eq() {
+        let mut a = GrowQueue::new();
+        let mut b = GrowQueue::new();
+        assert_eq!(a, b);
+        for i in 0..START_UP_SIZE {
+            a.enqueue(i);
+        }
+        for _ in 0..START_UP_SIZE / 2 {
+            a.dequeue();
+        }
+        for i in START_UP_SIZE / 2..START_UP_SIZE {
+            b.enqueue(i);
+        }
+        assert_eq!(a, b);
+        assert_eq!(a, (START_UP_SIZE / 2..START_UP_SIZE).collect::<Vec<_>>());
+        assert_eq!(format!("{a:?}"), format!("{b:?}"));
+        a.enqueue(START_UP_SIZE);
+        assert_ne!(a, b);
+        let mut c = GrowQueue::new();
+        c.enqueue(1);
+        c.enqueue(2);
+        assert_eq!(format!("{c:?}"), "[1, 2]");
+        assert_eq!(c, [1, 2]);
+    }
+
+    #[test]
+    fn test_growth_policy() {
+        assert_eq!(GrowthPolicy::Double.next_capacity(16, 17), 32);
+        assert_eq!(GrowthPolicy::Double.next_capacity(16, 70), 128);
+        assert_eq!(GrowthPolicy::Double.next_capacity(0, 3), 4);
+        assert_eq!(GrowthPolicy::Fixed(10).next_capacity(16, 17), 26);
+        assert_eq!(GrowthPolicy::Fixed(10).next_capacity(16, 37), 46);
+        assert_eq!(GrowthPolicy::Fixed(0).next_capacity(16, 18), 18);
+        assert_eq!(GrowthPolicy::Exact.next_capacity(16, 21), 21);
+        assert_eq!(GrowthPolicy::Exact.next_capacity(16, 5), 16);
+
+        let mut q = GrowQueue::with_limits(2, None).with_growth_policy(GrowthPolicy::Fixed(3));
+        for i in 0..6 {
+            q.enqueue(i);
+        }
+        assert_eq!(q.vec_queue.as_ref().unwrap().capacity(), 8);
+        q.batch_enqueue(&[6, 7, 8]);
+        assert_eq!(q.vec_queue.as_ref().unwrap().capacity(), 11);
+        assert_eq!(q, (0..9).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_max_across_wrap() {
+        let max = NonZeroUsize::new(8).unwrap();
+        let mut q = GrowQueue::with_limits(4, Some(max));
+        q.batch_enqueue(&[0, 1, 2, 3]);
+        q.batch_dequeue(3);
+        // Wraps around the end of the initial buffer
+        q.batch_enqueue(&[4, 5, 6]);
+        assert_eq!(q.as_slices().unwrap(), (&[3][..], Some(&[4, 5, 6][..])));
+
+        assert_eq!(
+            q.try_batch_enqueue(&[7, 8, 9, 10, 11]),
+            Err(WouldExceed { len: 9, max })
+        );
+        assert_eq!(q, [3, 4, 5, 6]);
+        assert_eq!(q.vec_queue.as_ref().unwrap().capacity(), 4);
+
+        // Doubling is clamped to the max
+        q.try_batch_enqueue(&[7, 8, 9]).unwrap();
+        assert_eq!(q, [3, 4, 5, 6, 7, 8, 9]);
+        q.batch_dequeue(2);
+        q.enqueue(10);
+        q.try_batch_enqueue(&[11, 12]).unwrap();
+        assert_eq!(q.len(), 8);
+        assert!(q.try_batch_enqueue(&[13]).is_err());
+        assert_eq!(q, (5..13).collect::<Vec<_>>());
+        assert_eq!(q.vec_queue.as_ref().unwrap().capacity(), 8);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_enqueue_past_max() {
+        let mut q = GrowQueue::with_limits(16, NonZeroUsize::new(2));
+        q.enqueue(0);
+        q.enqueue(1);
+        q.enqueue(2);
+    }
+
+    #[test]
+    fn test_high_water_mark() {
+        let mut q = GrowQueue::new();
+        assert_eq!(q.high_water_mark(), 0);
+        let mut prev = 0;
+        for i in 0..200 {
+            if i % 3 == 0 {
+                q.dequeue();
+            } else if i % 7 == 0 {
+                q.batch_enqueue(&[i; 5]);
+            } else {
+                q.enqueue(i);
+            }
+            assert!(prev <= q.high_water_mark());
+            assert!(q.len() <= q.high_water_mark());
+            prev = q.high_water_mark();
+        }
+        let hwm = q.high_water_mark();
+        q.clear();
+        q.enqueue(0);
+        assert_eq!(q.high_water_mark(), hwm);
+    }
+
+    #[test]
+    fn test_shrink_to() {
+        let mut q = GrowQueue::<String>::with_limits(4, None);
+        q.shrink_to(0);
+        assert_eq!(q.capacity(), 0);
+        for i in 0..64 {
+            q.enqueue(i.to_string());
+        }
+        assert_eq!(q.capacity(), 64);
+        for _ in 0..60 {
+            q.dequeue();
+        }
+        // Wrap the live items around the end of the buffer
+        for i in 64..70 {
+            q.enqueue(i.to_string());
+        }
+        let (_, wrapped) = q.as_slices().unwrap();
+        assert!(wrapped.is_some());
+        let expected = (60..70).map(|i| i.to_string()).collect::<Vec<_>>();
+
+        q.shrink_to(0);
+        assert_eq!(q.capacity(), 10);
+        assert_eq!(q, expected);
+        assert!(q.as_slices().unwrap().1.is_none_or(<[_]>::is_empty));
+        q.shrink_to(32);
+        assert_eq!(q.capacity(), 10);
+
+        for _ in 0..10 {
+            q.dequeue();
+        }
+        q.shrink_to(1);
+        assert_eq!(q.capacity(), 4);
+        assert_eq!(q.high_water_mark(), 64);
+        q.enqueue(String::from("a"));
+        assert_eq!(q, [String::from("a")]);
+    }
+
+    #[test]
+    fn test_io_slices_across_wrap() {
+        let mut q = GrowQueue::new();
+        let mut out = vec![];
+        q.as_io_slices(&mut out);
+        assert!(out.is_empty());
+        q.batch_enqueue(&[0; START_UP_SIZE - 2]);
+        q.batch_dequeue(START_UP_SIZE - 4);
+        q.batch_enqueue_vectored(&[
+            IoSlice::new(&[1, 2, 3]),
+            IoSlice::new(&[]),
+            IoSlice::new(&[4]),
+        ]);
+        assert_eq!(q.capacity(), START_UP_SIZE);
+        let mut out = vec![];
+        q.as_io_slices(&mut out);
+        assert_eq!(out.len(), 2);
+        assert_eq!(writev(&out), [0, 0, 1, 2, 3, 4]);
+
+        // Grows once for the whole batch and leaves the queue contiguous
+        let bufs = [IoSlice::new(&[5; START_UP_SIZE]), IoSlice::new(&[6])];
+        q.batch_enqueue_vectored(&bufs);
+        assert_eq!(q.len(), 6 + START_UP_SIZE + 1);
+        let mut out = vec![];
+        q.as_io_slices(&mut out);
+        assert_eq!(out.len(), 1);
+        assert_eq!(writev(&out), q.iter().copied().collect::<Vec<_>>());
+
+        let max = NonZeroUsize::new(4).unwrap();
+        let mut q = GrowQueue::with_limits(START_UP_SIZE, Some(max));
+        let bufs = [IoSlice::new(&[1, 2]), IoSlice::new(&[3, 4, 5])];
+        assert_eq!(
+            q.try_batch_enqueue_vectored(&bufs).unwrap_err(),
+            WouldExceed { len: 5, max }
+        );
+        assert_eq!(q.len(), 0);
+    }
+
+    #[test]
+    fn test_truncate_front_across_wrap() {
+        let mut q = GrowQueue::with_limits(8, None);
+        q.truncate_front(0);
+        q.batch_enqueue(&[0, 1, 2, 3, 4, 5]);
+        q.batch_dequeue(4);
+        q.batch_enqueue(&[6, 7, 8, 9]);
+        assert!(q.as_slices().unwrap().1.is_some());
+        q.truncate_front(10);
+        assert_eq!(q, [4, 5, 6, 7, 8, 9]);
+        // Drops across the end of the buffer
+        q.truncate_front(3);
+        assert_eq!(q, [7, 8, 9]);
+        assert_eq!(q.capacity(), 8);
+        q.batch_enqueue(&[10, 11, 12, 13]);
+        q.truncate_front(2);
+        assert_eq!(q, [12, 13]);
+        q.truncate_front(0);
+        assert!(q.iter().next().is_none());
+        q.enqueue(14);
+        assert_eq!(q, [14]);
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut q = GrowQueue::with_limits(8, None);
+        q.retain(|_| unreachable!());
+        q.batch_enqueue(&[0, 1, 2, 3, 4, 5]);
+        q.batch_dequeue(4);
+        q.batch_enqueue(&[6, 7, 8, 9, 10]);
+        q.retain(|x| x % 3 != 0);
+        assert_eq!(q, [4, 5, 7, 8, 10]);
+        assert_eq!(q.capacity(), 8);
+        q.retain(|_| true);
+        assert_eq!(q, [4, 5, 7, 8, 10]);
+        q.retain(|_| false);
+        assert_eq!(q.len(), 0);
+    }
+
+    #[test]
+    fn test_heap_size() {
+        let mut q = GrowQueue::<u64>::new();
+        assert_eq!(q.heap_size(), 0);
+        q.enqueue(1);
+        assert_eq!(q.heap_size(), (START_UP_SIZE + 1) * 8);
+        let mut q = GrowQueue::new();
+        q.enqueue(String::with_capacity(3));
+        assert_eq!(q.heap_size(), (START_UP_SIZE + 1) * 24 + 3);
+    }
 }
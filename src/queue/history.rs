@@ -0,0 +1,120 @@
+use core::num::NonZeroUsize;
+
+use crate::ops::{
+    clear::Clear,
+    len::{Capacity, Full, Len},
+};
+
+use super::cap_queue::CapVecQueue;
+
+/// The last `bound` items pushed
+///
+/// Allocates the room for all `bound` items once on [`Self::new()`] and never again;
+/// a push to a full buffer overwrites the oldest item in place.
+pub struct HistoryBuffer<T> {
+    queue: CapVecQueue<T>,
+}
+impl<T> HistoryBuffer<T> {
+    #[must_use]
+    pub fn new(bound: NonZeroUsize) -> Self {
+        Self {
+            queue: CapVecQueue::new_vec(bound.get()),
+        }
+    }
+    /// Return the oldest item if it has been evicted to make room
+    pub fn push(&mut self, item: T) -> Option<T> {
+        let evicted = if self.queue.is_full() {
+            self.queue.dequeue()
+        } else {
+            None
+        };
+        self.queue.enqueue(item);
+        evicted
+    }
+    /// The last pushed
+    #[must_use]
+    pub fn latest(&self) -> Option<&T> {
+        let (a, b) = self.queue.as_slices()?;
+        b.and_then(<[T]>::last).or_else(|| a.last())
+    }
+    /// From the oldest to the newest
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.queue.iter()
+    }
+}
+impl<T> Len for HistoryBuffer<T> {
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+}
+impl<T> Capacity for HistoryBuffer<T> {
+    fn capacity(&self) -> usize {
+        self.queue.capacity()
+    }
+}
+impl<T> Clear for HistoryBuffer<T> {
+    fn clear(&mut self) {
+        self.queue.clear();
+    }
+}
+impl<T: core::fmt::Debug> core::fmt::Debug for HistoryBuffer<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ops::len::LenExt;
+
+    use super::*;
+
+    #[test]
+    fn test_eviction_order() {
+        let mut h = HistoryBuffer::new(NonZeroUsize::new(3).unwrap());
+        assert!(h.is_empty());
+        assert_eq!(h.latest(), None);
+        assert_eq!(h.push(0), None);
+        assert_eq!(h.push(1), None);
+        assert_eq!(h.push(2), None);
+        assert_eq!(h.latest(), Some(&2));
+        assert_eq!(h.push(3), Some(0));
+        assert_eq!(h.push(4), Some(1));
+        assert_eq!(h.len(), 3);
+        assert_eq!(h.capacity(), 3);
+        assert!(h.iter().eq(&[2, 3, 4]));
+        assert_eq!(h.latest(), Some(&4));
+        assert_eq!(format!("{h:?}"), "[2, 3, 4]");
+
+        h.clear();
+        assert!(h.is_empty());
+        assert_eq!(h.latest(), None);
+        assert_eq!(h.push(5), None);
+        assert!(h.iter().eq(&[5]));
+    }
+
+    #[test]
+    fn test_many_wraps() {
+        let bound = 5;
+        let mut h = HistoryBuffer::new(NonZeroUsize::new(bound).unwrap());
+        for i in 0..100_usize {
+            let evicted = h.push(i.to_string());
+            assert_eq!(evicted, i.checked_sub(bound).map(|i| i.to_string()));
+            let oldest = (i + 1).saturating_sub(bound);
+            assert!(h
+                .iter()
+                .map(String::as_str)
+                .eq((oldest..=i).map(|i| i.to_string())));
+            assert_eq!(h.latest(), Some(&i.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_bound_of_one() {
+        let mut h = HistoryBuffer::new(NonZeroUsize::new(1).unwrap());
+        assert_eq!(h.push('a'), None);
+        assert_eq!(h.push('b'), Some('a'));
+        assert_eq!(h.latest(), Some(&'b'));
+        assert!(h.iter().eq(&['b']));
+    }
+}
@@ -42,10 +42,7 @@ impl<T> IndQueue<T> {
 
     #[must_use]
     pub fn front_mut(&mut self) -> Option<&mut T> {
-        loop {
-            let Some(front) = self.queue.front() else {
-                break;
-            };
+        while let Some(front) = self.queue.front() {
             if front.is_some() {
                 break;
             }
@@ -56,10 +53,7 @@ impl<T> IndQueue<T> {
     }
     #[must_use]
     pub fn back_mut(&mut self) -> Option<&mut T> {
-        loop {
-            let Some(back) = self.queue.back() else {
-                break;
-            };
+        while let Some(back) = self.queue.back() {
             if back.is_some() {
                 break;
             }
@@ -101,6 +95,70 @@ impl<T> IndQueue<T> {
         }
     }
 
+    /// Canonical position of the front slot, which might be a hole
+    #[must_use]
+    pub const fn start_canonical(&self) -> u64 {
+        self.start
+    }
+    /// Canonical position the next [`Self::enqueue()`] will take
+    #[must_use]
+    pub fn end_canonical(&self) -> u64 {
+        let queue_len = u64::try_from(self.queue.len()).unwrap();
+        self.start.wrapping_add(queue_len)
+    }
+    /// [`None`] unless `canonical` is within [`Self::start_canonical()`]`..=`[`Self::end_canonical()`]
+    ///
+    /// The range is compared modulo `2^64` like the positions themselves, so it stays valid across the wraparound of the `u64` space.
+    /// The end position is accepted as a cursor past the last entry.
+    #[must_use]
+    pub fn index_from_canonical(&self, canonical: u64) -> Option<QueueIndex> {
+        let offset = canonical.wrapping_sub(self.start);
+        let offset = usize::try_from(offset).ok()?;
+        if self.queue.len() < offset {
+            return None;
+        }
+        Some(QueueIndex {
+            start: self.start,
+            offset,
+        })
+    }
+
+    /// Live entries from front to back along with the indices [`Self::enqueue()`] returned for them
+    pub fn iter(&self) -> impl Iterator<Item = (QueueIndex, &T)> + '_ {
+        let start = self.start;
+        self.queue
+            .iter()
+            .enumerate()
+            .filter_map(move |(offset, entry)| {
+                let value = entry.as_ref()?;
+                Some((QueueIndex { start, offset }, value))
+            })
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (QueueIndex, &mut T)> + '_ {
+        let start = self.start;
+        self.queue
+            .iter_mut()
+            .enumerate()
+            .filter_map(move |(offset, entry)| {
+                let value = entry.as_mut()?;
+                Some((QueueIndex { start, offset }, value))
+            })
+    }
+    /// Remove the entries failing `pred` in place; their slots become holes like on [`Self::remove()`]
+    pub fn retain(&mut self, mut pred: impl FnMut(QueueIndex, &mut T) -> bool) {
+        let start = self.start;
+        for (offset, entry) in self.queue.iter_mut().enumerate() {
+            let Some(value) = entry else {
+                continue;
+            };
+            if pred(QueueIndex { start, offset }, value) {
+                continue;
+            }
+            *entry = None;
+            self.count -= 1;
+        }
+    }
+
     pub fn trim(&mut self) {
         let _ = self.front_mut();
         let _ = self.back_mut();
@@ -122,6 +180,7 @@ impl<T> Clear for IndQueue<T> {
         let queue_len = u64::try_from(queue_len).unwrap();
         let new_start = self.start.wrapping_add(queue_len);
         self.start = new_start;
+        self.queue.clear();
         self.count = 0;
     }
 }
@@ -132,15 +191,18 @@ pub struct QueueIndex {
     offset: usize,
 }
 impl QueueIndex {
+    /// Position of the entry counted from the first one ever enqueued, wrapping around in the `u64` space
+    ///
+    /// Stays the same for the entry as long as it lives, so it can be persisted and turned back by [`IndQueue::index_from_canonical()`].
     #[must_use]
-    fn canonical(&self) -> u64 {
+    pub fn to_canonical(&self) -> u64 {
         let offset = u64::try_from(self.offset).unwrap();
         self.start.wrapping_add(offset)
     }
 }
 impl PartialEq for QueueIndex {
     fn eq(&self, other: &Self) -> bool {
-        self.canonical() == other.canonical()
+        self.to_canonical() == other.to_canonical()
     }
 }
 impl Eq for QueueIndex {}
@@ -200,4 +262,97 @@ mod tests {
         assert!(queue.get(index_2).is_none());
         assert!(queue.is_empty());
     }
+
+    #[test]
+    fn test_iter() {
+        let mut queue = IndQueue::new();
+        let indices: Vec<QueueIndex> = (0..6).map(|i| queue.enqueue(i)).collect();
+        assert_eq!(queue.dequeue(), Some(0));
+        assert_eq!(queue.dequeue(), Some(1));
+        queue.remove(indices[3]).unwrap();
+        let live: Vec<(QueueIndex, i32)> = queue.iter().map(|(i, v)| (i, *v)).collect();
+        assert_eq!(live, [(indices[2], 2), (indices[4], 4), (indices[5], 5)]);
+        for (index, value) in &live {
+            assert_eq!(queue.get(*index), Some(value));
+        }
+
+        for (index, value) in queue.iter_mut() {
+            assert_eq!(index, indices[*value as usize]);
+            *value *= 10;
+        }
+        queue.retain(|index, value| {
+            assert_eq!(index, indices[*value as usize / 10]);
+            *value != 40
+        });
+        assert_eq!(queue.len(), 2);
+        assert!(queue.get(indices[4]).is_none());
+        assert_eq!(queue.get(indices[5]), Some(&50));
+        assert_eq!(queue.dequeue(), Some(20));
+        assert_eq!(queue.dequeue(), Some(50));
+        assert!(queue.iter().next().is_none());
+
+        let index = queue.enqueue(6);
+        queue.clear();
+        assert!(queue.iter().next().is_none());
+        assert!(queue.get(index).is_none());
+    }
+
+    #[test]
+    fn test_canonical_round_trip() {
+        let mut queue = IndQueue::new();
+        let mut cursors = vec![];
+        for i in 0..16 {
+            let index = queue.enqueue(i);
+            cursors.push(index.to_canonical());
+            if i % 3 == 2 {
+                queue.dequeue().unwrap();
+            }
+        }
+        assert_eq!(queue.start_canonical(), 5);
+        assert_eq!(queue.end_canonical(), 16);
+        for (i, &canonical) in cursors.iter().enumerate() {
+            let index = queue.index_from_canonical(canonical);
+            if canonical < queue.start_canonical() {
+                assert!(index.is_none());
+                continue;
+            }
+            let index = index.unwrap();
+            assert_eq!(index.to_canonical(), canonical);
+            assert_eq!(queue.get(index), Some(&i));
+        }
+        // The end is a cursor but not an entry
+        let end = queue.index_from_canonical(16).unwrap();
+        assert!(queue.get(end).is_none());
+        assert_eq!(queue.enqueue(16), end);
+        assert!(queue.index_from_canonical(18).is_none());
+
+        // Holes stay addressable until dequeued past
+        let index = queue.index_from_canonical(6).unwrap();
+        queue.remove(index).unwrap();
+        assert!(queue.index_from_canonical(6).is_some());
+        assert_eq!(queue.dequeue(), Some(5));
+        assert!(queue.index_from_canonical(6).is_some());
+        assert_eq!(queue.dequeue(), Some(7));
+        assert_eq!(queue.start_canonical(), 8);
+        assert!(queue.index_from_canonical(6).is_none());
+        assert!(queue.index_from_canonical(7).is_none());
+    }
+
+    #[test]
+    fn test_canonical_wraparound() {
+        let mut queue = IndQueue::new();
+        queue.start = u64::MAX - 1;
+        let indices: Vec<QueueIndex> = (0..4).map(|i| queue.enqueue(i)).collect();
+        let canonical: Vec<u64> = indices.iter().map(QueueIndex::to_canonical).collect();
+        assert_eq!(canonical, [u64::MAX - 1, u64::MAX, 0, 1]);
+        assert_eq!(queue.end_canonical(), 2);
+        assert_eq!(queue.dequeue(), Some(0));
+        assert_eq!(queue.dequeue(), Some(1));
+        assert_eq!(queue.start_canonical(), 0);
+        assert!(queue.index_from_canonical(u64::MAX).is_none());
+        let index = queue.index_from_canonical(1).unwrap();
+        assert_eq!(index, indices[3]);
+        assert_eq!(queue.get(index), Some(&3));
+        assert!(queue.index_from_canonical(3).is_none());
+    }
 }
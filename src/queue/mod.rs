@@ -1,7 +1,14 @@
+pub mod cap_deque;
 pub mod cap_queue;
 pub mod grow_queue;
+pub mod history;
 pub mod ind_queue;
 pub mod ord_queue;
+pub mod pending;
 pub mod req_queue;
+pub mod ring_region;
 pub mod send_wnd;
 pub mod seq_queue;
+#[cfg(test)]
+mod seq_queue_model;
+pub mod waste;
@@ -1,5 +1,5 @@
 use core::cmp::Reverse;
-use std::collections::{BinaryHeap, VecDeque};
+use std::collections::{binary_heap, BinaryHeap, VecDeque};
 
 use crate::ops::{clear::Clear, len::Len, opt_cmp::MinNoneOptCmp};
 
@@ -58,12 +58,115 @@ impl<T: Ord> OrdQueue<T> {
         }
         self.min_heap.push(Reverse(value));
     }
+    /// Mutable access to the min value that restores the order on drop
+    pub fn peek_mut(&mut self) -> Option<PeekMut<'_, T>> {
+        let location = self.min_head_location()?;
+        let inner = match location {
+            Location::MinHeap => PeekMutInner::MinHeap(self.min_heap.peek_mut().unwrap()),
+            Location::Linear => PeekMutInner::Linear(self),
+        };
+        Some(PeekMut { inner: Some(inner) })
+    }
+    /// In no particular order
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.linear
+            .iter()
+            .chain(self.min_heap.iter().map(|Reverse(value)| value))
+    }
+    /// Rebuild the heap in O(n)
+    pub fn retain(&mut self, mut pred: impl FnMut(&T) -> bool) {
+        self.linear.retain(&mut pred);
+        self.min_heap.retain(|Reverse(value)| pred(value));
+    }
+    /// Remove the least value that matches `pred` in O(n)
+    pub fn remove_first_by(&mut self, mut pred: impl FnMut(&T) -> bool) -> Option<T> {
+        let linear = self.linear.iter().position(&mut pred);
+        let min_heap = self
+            .min_heap
+            .iter()
+            .enumerate()
+            .filter(|(_, Reverse(value))| pred(value))
+            .min_by(|(_, a), (_, b)| b.cmp(a))
+            .map(|(i, _)| i);
+        let from_min_heap = match (linear, min_heap) {
+            (None, None) => return None,
+            (None, Some(_)) => true,
+            (Some(_), None) => false,
+            (Some(l), Some(h)) => self.min_heap.as_slice()[h].0 < self.linear[l],
+        };
+        if !from_min_heap {
+            return self.linear.remove(linear.unwrap());
+        }
+        let mut heap = core::mem::take(&mut self.min_heap).into_vec();
+        let Reverse(value) = heap.swap_remove(min_heap.unwrap());
+        self.min_heap = BinaryHeap::from(heap);
+        Some(value)
+    }
+    /// Pop the values in ascending order in O(n log n)
+    ///
+    /// The values not yet yielded stay if the iterator is dropped early.
+    pub fn drain_sorted(&mut self) -> impl Iterator<Item = T> + '_ {
+        core::iter::from_fn(|| self.pop())
+    }
+    /// In ascending order
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        let mut linear = self.linear.into_iter().peekable();
+        // Descending since the heap holds `Reverse`s
+        let mut min_heap = self.min_heap.into_sorted_vec();
+        let mut sorted = Vec::with_capacity(linear.len() + min_heap.len());
+        loop {
+            let from_min_heap = match (min_heap.last(), linear.peek()) {
+                (None, None) => break,
+                (None, Some(_)) => false,
+                (Some(_), None) => true,
+                (Some(Reverse(h)), Some(l)) => h < l,
+            };
+            let value = if from_min_heap {
+                min_heap.pop().unwrap().0
+            } else {
+                linear.next().unwrap()
+            };
+            sorted.push(value);
+        }
+        sorted
+    }
+    /// Move all the values of `other` into `self` with an O(n) rebuild of the heap at most
+    pub fn append(&mut self, other: &mut Self) {
+        let in_order = MinNoneOptCmp(self.linear.back()) <= MinNoneOptCmp(other.linear.front());
+        if in_order {
+            self.linear.append(&mut other.linear);
+        } else {
+            self.min_heap.extend(other.linear.drain(..).map(Reverse));
+        }
+        self.min_heap.append(&mut other.min_heap);
+    }
 }
 impl<T: Ord> Default for OrdQueue<T> {
     fn default() -> Self {
         Self::new()
     }
 }
+/// Heapify the out-of-order values once instead of sifting each of them up
+impl<T: Ord> Extend<T> for OrdQueue<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut out_of_order = vec![];
+        for value in iter {
+            if MinNoneOptCmp(self.linear.back()) <= MinNoneOptCmp(Some(&value)) {
+                self.linear.push_back(value);
+                continue;
+            }
+            out_of_order.push(Reverse(value));
+        }
+        self.min_heap.extend(out_of_order);
+    }
+}
+impl<T: Ord> FromIterator<T> for OrdQueue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut q = Self::new();
+        q.extend(iter);
+        q
+    }
+}
 impl<T> Len for OrdQueue<T> {
     fn len(&self) -> usize {
         self.linear.len() + self.min_heap.len()
@@ -81,9 +184,69 @@ enum Location {
     Linear,
 }
 
+/// Returned by [`OrdQueue::peek_mut()`]
+pub struct PeekMut<'a, T: Ord> {
+    /// Only taken by [`Self::pop()`]
+    inner: Option<PeekMutInner<'a, T>>,
+}
+enum PeekMutInner<'a, T: Ord> {
+    MinHeap(binary_heap::PeekMut<'a, Reverse<T>>),
+    Linear(&'a mut OrdQueue<T>),
+}
+impl<T: Ord> PeekMut<'_, T> {
+    pub fn pop(mut this: Self) -> T {
+        match this.inner.take().unwrap() {
+            PeekMutInner::MinHeap(peek) => binary_heap::PeekMut::pop(peek).0,
+            PeekMutInner::Linear(queue) => queue.linear.pop_front().unwrap(),
+        }
+    }
+}
+impl<T: Ord> core::ops::Deref for PeekMut<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        match self.inner.as_ref().unwrap() {
+            PeekMutInner::MinHeap(peek) => &peek.0,
+            PeekMutInner::Linear(queue) => queue.linear.front().unwrap(),
+        }
+    }
+}
+impl<T: Ord> core::ops::DerefMut for PeekMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self.inner.as_mut().unwrap() {
+            PeekMutInner::MinHeap(peek) => &mut peek.0,
+            PeekMutInner::Linear(queue) => queue.linear.front_mut().unwrap(),
+        }
+    }
+}
+impl<T: Ord> Drop for PeekMut<'_, T> {
+    fn drop(&mut self) {
+        // The min-heap sifts down on its own
+        let Some(PeekMutInner::Linear(queue)) = &mut self.inner else {
+            return;
+        };
+        let in_order = match queue.linear.get(1) {
+            Some(second) => queue.linear[0] <= *second,
+            None => true,
+        };
+        if in_order {
+            return;
+        }
+        let value = queue.linear.pop_front().unwrap();
+        queue.push(value);
+    }
+}
+impl<T: Ord + core::fmt::Debug> core::fmt::Debug for PeekMut<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("PeekMut").field(&**self).finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::ops::len::LenExt;
+    use crate::{
+        analysis::rng::{RngLite, SplitMix64},
+        ops::len::LenExt,
+    };
 
     use super::*;
 
@@ -106,6 +269,129 @@ mod tests {
         assert!(q.pop().is_none());
         assert!(q.is_empty());
     }
+
+    fn drain(q: &mut OrdQueue<usize>) -> Vec<usize> {
+        core::iter::from_fn(|| q.pop()).collect()
+    }
+    fn shuffled() -> OrdQueue<usize> {
+        let mut q = OrdQueue::new();
+        for i in 0..16 {
+            q.push(i);
+        }
+        for i in (16..32).rev() {
+            q.push(i);
+        }
+        for i in (0..16).map(|i| i * 7 % 16 + 32) {
+            q.push(i);
+        }
+        q
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut q = shuffled();
+        q.retain(|v| v % 3 != 0);
+        let expected = (0..48).filter(|v| v % 3 != 0).collect::<Vec<_>>();
+        assert_eq!(q.len(), expected.len());
+        assert_eq!(drain(&mut q), expected);
+    }
+
+    #[test]
+    fn test_remove_first_by() {
+        let mut q = shuffled();
+        assert_eq!(q.remove_first_by(|v| *v == 20), Some(20));
+        assert_eq!(q.remove_first_by(|v| *v == 3), Some(3));
+        assert_eq!(q.remove_first_by(|v| 40 <= *v), Some(40));
+        assert_eq!(q.remove_first_by(|v| *v == 100), None);
+        let expected = (0..48)
+            .filter(|v| ![3, 20, 40].contains(v))
+            .collect::<Vec<_>>();
+        assert_eq!(drain(&mut q), expected);
+    }
+
+    #[test]
+    fn test_peek_mut() {
+        let mut q = OrdQueue::new();
+        q.push(1);
+        q.push(2);
+        q.push(0);
+        *q.peek_mut().unwrap() = 3;
+        assert_eq!(q.peek(), Some(&1));
+        *q.peek_mut().unwrap() = 4;
+        assert_eq!(q.peek(), Some(&2));
+        assert_eq!(PeekMut::pop(q.peek_mut().unwrap()), 2);
+        assert_eq!(drain(&mut q), [3, 4]);
+        assert!(q.peek_mut().is_none());
+    }
+
+    fn random_values(rng: &mut SplitMix64) -> Vec<usize> {
+        let len = rng.next_usize_below(64);
+        let mut values = (0..len)
+            .map(|_| rng.next_usize_below(32))
+            .collect::<Vec<_>>();
+        // Mostly in order like the sequence numbers
+        if rng.next_usize_below(2) == 0 {
+            values.sort_unstable();
+            let swaps = rng.next_usize_below(4);
+            for _ in 0..swaps.min(len) {
+                let (i, j) = (rng.next_usize_below(len), rng.next_usize_below(len));
+                values.swap(i, j);
+            }
+        }
+        values
+    }
+
+    #[test]
+    fn test_drain_sorted() {
+        let mut rng = SplitMix64::new(0);
+        for _ in 0..1 << 8 {
+            let values = random_values(&mut rng);
+            let mut expected = values.clone();
+            expected.sort_unstable();
+
+            let mut pushed = OrdQueue::new();
+            for &value in &values {
+                pushed.push(value);
+            }
+            let collected = values.iter().copied().collect::<OrdQueue<_>>();
+            assert_eq!(collected.len(), values.len());
+            assert_eq!(collected.clone().into_sorted_vec(), expected);
+            assert_eq!(pushed.clone().into_sorted_vec(), expected);
+            assert!(pushed.drain_sorted().eq(expected.iter().copied()));
+            assert!(pushed.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_drain_sorted_early_drop() {
+        let mut q = [3, 1, 2, 0].into_iter().collect::<OrdQueue<_>>();
+        assert!(q.drain_sorted().take(2).eq([0, 1]));
+        assert_eq!(q.len(), 2);
+        assert_eq!(drain(&mut q), [2, 3]);
+    }
+
+    #[test]
+    fn test_append() {
+        let mut rng = SplitMix64::new(1);
+        for _ in 0..1 << 8 {
+            let a = random_values(&mut rng);
+            let b = random_values(&mut rng);
+            let mut expected = [a.as_slice(), b.as_slice()].concat();
+            expected.sort_unstable();
+
+            let mut q = a.into_iter().collect::<OrdQueue<_>>();
+            let mut other = b.into_iter().collect::<OrdQueue<_>>();
+            q.append(&mut other);
+            assert!(other.is_empty());
+            assert_eq!(q.len(), expected.len());
+            // Still a heap after more pushes
+            q.push(16);
+            q.extend([31, 0]);
+            expected.extend([16, 31, 0]);
+            expected.sort_unstable();
+            assert_eq!(drain(&mut q), expected);
+        }
+    }
 }
 
 #[cfg(feature = "nightly")]
@@ -0,0 +1,193 @@
+use core::hash::Hash;
+use std::time::Instant;
+
+use crate::{
+    map::{
+        dense_hash_map::DenseHashMap,
+        hash_map::{HashGet, HashGetMut, HashRemove},
+        MapInsert,
+    },
+    ops::{clear::Clear, len::Len},
+};
+
+use super::ind_queue::{IndQueue, QueueIndex};
+
+/// In-flight items by key with deadlines, e.g. unacknowledged packets awaiting retransmission
+///
+/// Deadlines live in a FIFO, so they are expected to be non-decreasing in the order of [`Self::insert()`] and [`Self::reschedule()`], as with a fixed timeout.
+/// [`Self::expired()`] stops at the first deadline not yet passed, so an earlier deadline queued behind a later one waits for it.
+#[derive(Debug, Clone)]
+pub struct PendingMap<K, V, Time = Instant> {
+    queue: IndQueue<Pending<K, V, Time>>,
+    index: DenseHashMap<K, QueueIndex>,
+}
+#[derive(Debug, Clone)]
+struct Pending<K, V, Time> {
+    deadline: Time,
+    key: K,
+    value: V,
+}
+impl<K, V, Time> PendingMap<K, V, Time> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            queue: IndQueue::new(),
+            index: DenseHashMap::new(),
+        }
+    }
+}
+impl<K, V, Time> PendingMap<K, V, Time>
+where
+    K: Eq + Hash + Clone,
+    Time: Ord,
+{
+    /// Return the replaced value if `key` is already pending
+    pub fn insert(&mut self, key: K, value: V, deadline: Time) -> Option<V> {
+        let prev = self.ack(&key);
+        let pending = Pending {
+            deadline,
+            key: key.clone(),
+            value,
+        };
+        let index = self.queue.enqueue(pending);
+        self.index.insert(key, index);
+        prev
+    }
+    /// Stop tracking `key`
+    pub fn ack(&mut self, key: &K) -> Option<V> {
+        let index = self.index.remove(key)?;
+        let pending = self.queue.remove(index).unwrap();
+        Some(pending.value)
+    }
+    /// Move `key` to the back of the queue with `deadline`
+    ///
+    /// Return `false` if `key` is not pending.
+    pub fn reschedule(&mut self, key: &K, deadline: Time) -> bool {
+        let Some(index) = self.index.get_mut(key) else {
+            return false;
+        };
+        let mut pending = self.queue.remove(*index).unwrap();
+        pending.deadline = deadline;
+        *index = self.queue.enqueue(pending);
+        true
+    }
+    /// Hand the items whose deadlines are not after `now` to `f` in queue order
+    pub fn expired(&mut self, now: Time, mut f: impl FnMut(K, V)) {
+        while let Some(front) = self.queue.front_mut() {
+            if now < front.deadline {
+                return;
+            }
+            let pending = self.queue.dequeue().unwrap();
+            self.index.remove(&pending.key).unwrap();
+            f(pending.key, pending.value);
+        }
+    }
+    #[must_use]
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let index = *self.index.get(key)?;
+        Some(&self.queue.get(index).unwrap().value)
+    }
+    /// The deadline [`Self::expired()`] waits for next
+    #[must_use]
+    pub fn next_deadline(&mut self) -> Option<&Time> {
+        self.queue.front_mut().map(|pending| &pending.deadline)
+    }
+}
+impl<K, V, Time> Default for PendingMap<K, V, Time> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<K, V, Time> Len for PendingMap<K, V, Time> {
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+}
+impl<K, V, Time> Clear for PendingMap<K, V, Time> {
+    fn clear(&mut self) {
+        self.queue.clear();
+        self.index.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{
+        analysis::rng::{RngLite, SplitMix64},
+        ops::len::LenExt,
+    };
+
+    use super::*;
+
+    fn expired(pending: &mut PendingMap<u32, char, u64>, now: u64) -> Vec<(u32, char)> {
+        let mut expired = vec![];
+        pending.expired(now, |k, v| expired.push((k, v)));
+        expired
+    }
+
+    #[test]
+    fn test_pending_map() {
+        let mut p = PendingMap::new();
+        assert_eq!(p.insert(1, 'a', 10), None);
+        assert_eq!(p.insert(2, 'b', 11), None);
+        assert_eq!(p.insert(3, 'c', 12), None);
+        assert_eq!(p.next_deadline(), Some(&10));
+        assert_eq!(p.ack(&1), Some('a'));
+        assert_eq!(p.ack(&1), None);
+        assert_eq!(p.next_deadline(), Some(&11));
+        assert!(p.reschedule(&2, 20));
+        assert!(!p.reschedule(&1, 20));
+        assert_eq!(expired(&mut p, 15), [(3, 'c')]);
+        assert_eq!(p.get(&2), Some(&'b'));
+        assert_eq!(p.insert(2, 'B', 21), Some('b'));
+        assert_eq!(p.insert(4, 'd', 21), None);
+        assert_eq!(expired(&mut p, 20), []);
+        assert_eq!(expired(&mut p, 21), [(2, 'B'), (4, 'd')]);
+        assert!(p.is_empty());
+        assert_eq!(p.next_deadline(), None);
+        p.insert(5, 'e', 30);
+        p.clear();
+        assert_eq!(expired(&mut p, 30), []);
+        assert_eq!(p.get(&5), None);
+    }
+
+    #[test]
+    fn test_exactly_once() {
+        const TIMEOUT: u64 = 8;
+
+        let mut p = PendingMap::new();
+        let mut settled: BTreeMap<u32, u32> = BTreeMap::new();
+        let mut inserted = 0;
+        let mut now = 0;
+        let mut rng = SplitMix64::new(5);
+        for _ in 0..1 << 12 {
+            let key = rng.next_usize_below(64) as u32;
+            match rng.next_usize_below(4) {
+                0 => {
+                    if p.get(&key).is_none() {
+                        p.insert(key, inserted, now + TIMEOUT);
+                        inserted += 1;
+                    }
+                }
+                1 => {
+                    if let Some(id) = p.ack(&key) {
+                        *settled.entry(id).or_default() += 1;
+                    }
+                }
+                2 => {
+                    p.reschedule(&key, now + TIMEOUT);
+                }
+                _ => {
+                    now += 1;
+                    p.expired(now, |_, id| *settled.entry(id).or_default() += 1);
+                }
+            }
+            assert_eq!(settled.len() + p.len(), inserted as usize);
+        }
+        p.expired(u64::MAX, |_, id| *settled.entry(id).or_default() += 1);
+        assert_eq!(settled.len(), inserted as usize);
+        assert!(settled.values().all(|&times| times == 1));
+    }
+}
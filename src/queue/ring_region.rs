@@ -0,0 +1,270 @@
+use core::{num::NonZeroUsize, ops::Range};
+
+use crate::{
+    error::QueueFull,
+    ops::{
+        clear::Clear,
+        len::{Capacity, Len, LenExt},
+        ring::RingSpace,
+    },
+};
+
+use super::cap_queue::CapQueuePointer;
+
+/// Slot ranges of the storage: the first one, and the one wrapped around to the start if any
+pub type RingRanges = (Range<usize>, Option<Range<usize>>);
+
+/// Index bookkeeping of a FIFO ring over storage owned elsewhere, e.g. a shared memory region
+///
+/// The storage must have [`Self::slots()`] slots, one more than [`Self::capacity()`].
+#[derive(Debug, Clone, Copy)]
+pub struct RingRegion {
+    pointer: CapQueuePointer,
+    cap: usize,
+}
+impl RingRegion {
+    #[must_use]
+    pub const fn new(capacity: usize) -> Self {
+        Self {
+            pointer: CapQueuePointer::new(
+                #[cfg(debug_assertions)]
+                capacity,
+            ),
+            cap: capacity,
+        }
+    }
+    /// Number of slots the storage must have
+    #[must_use]
+    pub const fn slots(&self) -> usize {
+        self.cap + 1
+    }
+    /// Slot to write the new item into
+    #[inline]
+    pub fn enqueue_index(&mut self) -> Result<usize, QueueFull> {
+        self.pointer.try_enqueue(self.cap)
+    }
+    /// Slot to read the oldest item from
+    #[inline]
+    pub fn dequeue_index(&mut self) -> Option<usize> {
+        self.pointer.dequeue(self.cap)
+    }
+    /// [`Self::enqueue_index()`] but for an item older than all the others
+    #[inline]
+    pub fn enqueue_front_index(&mut self) -> Result<usize, QueueFull> {
+        self.pointer.try_enqueue_front(self.cap)
+    }
+    /// Slot to read the newest item from
+    #[inline]
+    pub fn dequeue_back_index(&mut self) -> Option<usize> {
+        self.pointer.dequeue_back(self.cap)
+    }
+    /// Slots to write `n` new items into
+    ///
+    /// Nothing is reserved on error.
+    pub fn batch_enqueue_indices(&mut self, n: usize) -> Result<RingRanges, QueueFull> {
+        if self.cap - self.len() < n {
+            return Err(QueueFull(()));
+        }
+        let Some(n) = NonZeroUsize::new(n) else {
+            let end = self.pointer.tail(self.cap).ring_add(1, self.cap);
+            return Ok((end..end, None));
+        };
+        Ok(self.pointer.batch_enqueue(n, self.cap))
+    }
+    /// Slots to read up to `n` oldest items from
+    pub fn batch_dequeue_indices(&mut self, n: usize) -> Option<RingRanges> {
+        self.pointer.batch_dequeue(n, self.cap)
+    }
+    /// Slots of all the items from the oldest to the newest
+    #[must_use]
+    pub fn as_ranges(&self) -> Option<RingRanges> {
+        self.pointer.as_slices(self.cap)
+    }
+    /// Move the items back to the slots starting at `1`, as if the storage were rotated left by the returned count
+    ///
+    /// The caller must rotate the storage the same way.
+    pub fn make_contiguous(&mut self) -> usize {
+        if self.is_empty() {
+            *self = Self::new(self.cap);
+            return 0;
+        }
+        let shift = self.pointer.head(self.cap).ring_sub(1, self.cap);
+        let len = self.len();
+        *self = Self::new(self.cap);
+        self.batch_enqueue_indices(len).unwrap();
+        shift
+    }
+    /// Slot of the `i`-th oldest item
+    ///
+    /// # Panic
+    ///
+    /// `i` is not less than [`Self::len()`]
+    #[must_use]
+    pub fn index(&self, i: usize) -> usize {
+        assert!(i < self.len());
+        self.pointer.head(self.cap).ring_add(i, self.cap)
+    }
+    /// Slots of the items in `range`, counted from the oldest
+    ///
+    /// # Panic
+    ///
+    /// `range` is out of [`Self::len()`]
+    #[must_use]
+    pub fn wrapped(&self, range: Range<usize>) -> RingRanges {
+        assert!(range.start <= range.end);
+        assert!(range.end <= self.len());
+        let head = self.pointer.head(self.cap);
+        let start = head.ring_add(range.start, self.cap);
+        if range.is_empty() {
+            return (start..start, None);
+        }
+        let end = head.ring_add(range.end, self.cap);
+        if start < end {
+            (start..end, None)
+        } else {
+            (start..self.slots(), Some(0..end))
+        }
+    }
+}
+impl Capacity for RingRegion {
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.cap
+    }
+}
+impl Len for RingRegion {
+    #[inline]
+    fn len(&self) -> usize {
+        self.pointer.len(self.cap)
+    }
+}
+impl Clear for RingRegion {
+    fn clear(&mut self) {
+        *self = Self::new(self.cap);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use crate::{
+        analysis::rng::{RngLite, SplitMix64},
+        ops::len::Full,
+    };
+
+    use super::{super::cap_queue::CapVecQueue, *};
+
+    fn collect(ranges: Option<RingRanges>) -> Vec<usize> {
+        let Some((a, b)) = ranges else {
+            return vec![];
+        };
+        a.chain(b.unwrap_or(0..0)).collect()
+    }
+
+    #[test]
+    fn test_ring_region() {
+        let mut r = RingRegion::new(3);
+        assert_eq!(r.slots(), 4);
+        assert_eq!(r.batch_enqueue_indices(0), Ok((1..1, None)));
+        assert_eq!(r.enqueue_index(), Ok(1));
+        assert_eq!(r.batch_enqueue_indices(2), Ok((2..4, Some(0..0))));
+        assert!(r.is_full());
+        assert_eq!(r.enqueue_index(), Err(QueueFull(())));
+        assert_eq!(r.batch_enqueue_indices(1), Err(QueueFull(())));
+        assert_eq!(r.len(), 3);
+        assert_eq!(r.dequeue_index(), Some(1));
+        assert_eq!(r.dequeue_index(), Some(2));
+        assert_eq!(r.batch_enqueue_indices(2), Ok((0..2, None)));
+        assert_eq!(r.as_ranges(), Some((3..4, Some(0..2))));
+        assert_eq!(r.wrapped(0..3), (3..4, Some(0..2)));
+        assert_eq!(r.wrapped(1..3), (0..2, None));
+        assert_eq!(r.wrapped(3..3), (2..2, None));
+        assert_eq!([r.index(0), r.index(1), r.index(2)], [3, 0, 1]);
+        let mut contiguous = r;
+        assert_eq!(contiguous.make_contiguous(), 2);
+        assert_eq!(contiguous.as_ranges(), Some((1..4, Some(0..0))));
+        assert_eq!(r.batch_dequeue_indices(0), Some((3..3, None)));
+        assert_eq!(r.batch_dequeue_indices(8), Some((3..4, Some(0..2))));
+        assert!(r.is_empty());
+        assert_eq!(r.make_contiguous(), 0);
+        assert_eq!(r.dequeue_index(), None);
+        assert_eq!(r.batch_dequeue_indices(1), None);
+        r.enqueue_index().unwrap();
+        r.clear();
+        assert!(r.is_empty());
+        assert_eq!(r.enqueue_index(), Ok(1));
+    }
+
+    /// Same as driving [`CapQueuePointer`] directly wherever it does not panic
+    #[test]
+    fn test_same_as_pointer() {
+        for cap in 1..6 {
+            let mut r = RingRegion::new(cap);
+            let mut p = CapQueuePointer::new(
+                #[cfg(debug_assertions)]
+                cap,
+            );
+            let mut rng = SplitMix64::new(7);
+            for _ in 0..1 << 10 {
+                let n = rng.next_usize_below(cap + 2);
+                let full = p.len(cap) == cap;
+                match rng.next_usize_below(8) {
+                    0 | 1 => match r.enqueue_index() {
+                        Ok(i) => assert_eq!(i, p.enqueue(cap)),
+                        Err(QueueFull(())) => assert!(full),
+                    },
+                    2 | 3 => assert_eq!(r.dequeue_index(), p.dequeue(cap)),
+                    4 => match r.batch_enqueue_indices(n) {
+                        Ok(ranges) if n == 0 => assert!(ranges.0.is_empty()),
+                        Ok(ranges) => {
+                            assert_eq!(ranges, p.batch_enqueue(n.try_into().unwrap(), cap))
+                        }
+                        Err(QueueFull(())) => assert!(cap - p.len(cap) < n),
+                    },
+                    5 => assert_eq!(r.batch_dequeue_indices(n), p.batch_dequeue(n, cap)),
+                    _ => {
+                        assert_eq!(r.as_ranges(), p.as_slices(cap));
+                        let all = collect(r.as_ranges());
+                        let range = n.min(all.len())..all.len();
+                        assert_eq!(collect(Some(r.wrapped(range.clone()))), all[range]);
+                        for (i, &slot) in all.iter().enumerate() {
+                            assert_eq!(r.index(i), slot);
+                        }
+                    }
+                }
+                assert_eq!(r.len(), p.len(cap));
+            }
+        }
+    }
+
+    #[test]
+    fn test_cap_queue_same_as_vec_deque() {
+        for cap in 1..6 {
+            let mut q = CapVecQueue::new_vec(cap);
+            let mut reference = VecDeque::new();
+            let mut rng = SplitMix64::new(3);
+            for i in 0..1 << 10 {
+                let n = rng.next_usize_below(cap + 1);
+                match rng.next_usize_below(4) {
+                    0 if !q.is_full() => {
+                        q.enqueue(i);
+                        reference.push_back(i);
+                    }
+                    1 => assert_eq!(q.dequeue(), reference.pop_front()),
+                    2 if n <= cap - q.len() => {
+                        let items = (i..i + n).collect::<Vec<_>>();
+                        q.batch_enqueue(&items);
+                        reference.extend(items);
+                    }
+                    _ => {
+                        let dequeued = q.batch_dequeue_iter(n).copied().collect::<Vec<_>>();
+                        let expected = reference.drain(..n.min(reference.len()));
+                        assert!(dequeued.into_iter().eq(expected));
+                    }
+                }
+                assert!(q.iter().eq(reference.iter()));
+            }
+        }
+    }
+}
@@ -1,18 +1,46 @@
 use core::{hash::Hash, num::NonZeroUsize, ops::ControlFlow};
 use std::collections::{BTreeMap, HashSet};
 
-use num_traits::{CheckedAdd, CheckedSub, NumCast, One};
+use num_traits::{CheckedAdd, CheckedSub, One, ToPrimitive};
+use thiserror::Error;
 
 use crate::{
     ops::{
         clear::Clear,
         len::{Capacity, Full, Len},
         ord_entry::OrdEntry,
+        ring::RingSpace,
     },
     queue::ord_queue::OrdQueue,
 };
 
-use super::cap_queue::BitQueue;
+use super::{
+    cap_queue::BitQueue,
+    waste::{WasteSink, WasteSinkExt},
+};
+
+/// What the seq queues need from a sequence number
+///
+/// Implemented for the integers through `num_traits`.
+pub trait SeqKey: Sized {
+    /// The key right after `self`; [`None`] on overflow
+    fn successor(&self) -> Option<Self>;
+    /// How many successors it takes from `from` to reach `to`
+    ///
+    /// [`None`] if `to` is before `from` or the distance does not fit.
+    fn distance(from: &Self, to: &Self) -> Option<usize>;
+}
+impl<K> SeqKey for K
+where
+    K: CheckedAdd + CheckedSub + One + ToPrimitive,
+{
+    fn successor(&self) -> Option<Self> {
+        self.checked_add(&K::one())
+    }
+    fn distance(from: &Self, to: &Self) -> Option<usize> {
+        to.checked_sub(from)?.to_usize()
+    }
+}
 
 /// To keep incoming messages in contiguous order enforced by the sequence numbers associated with the messages respectively
 #[derive(Debug, Clone)]
@@ -59,25 +87,23 @@ where
 }
 impl<K, V> SeqQueue<K, V>
 where
-    K: Ord + CheckedSub + NumCast + Hash,
+    K: Ord + SeqKey + Hash,
 {
-    pub fn set_next(&mut self, next: K, mut stale: impl FnMut((K, V))) {
-        loop {
-            let Some(entry) = self.queue.peek() else {
-                break;
-            };
+    pub fn set_next(&mut self, next: K, mut stale: impl WasteSink<K, V>) {
+        while let Some(entry) = self.queue.peek() {
             let (head, _) = entry.flatten();
             if next <= *head {
                 break;
             }
-            if let Some(SeqQueueKeys { win: _, sparse }) = &mut self.keys {
-                assert!(sparse.remove(head));
-            }
-            stale(self.queue.pop().unwrap().into_flatten());
+            stale.waste(
+                WasteReason::StaleBySetNext,
+                self.queue.pop().unwrap().into_flatten(),
+            );
         }
         if let Some(SeqQueueKeys { win, sparse }) = &mut self.keys {
             reset_bit_win(win);
-            for key in sparse.iter() {
+            // The queued keys might have been tracked by either `sparse` or the window relative to the previous `next`
+            for OrdEntry { key, .. } in self.queue.iter() {
                 let Some(index) = key_index(&next, key) else {
                     // The key can't be fit in the window.
                     // Touching the hash set is very expensive.
@@ -85,7 +111,8 @@ where
                     // Therefore, we give up tracking the key.
                     continue;
                 };
-                win.set(index, true);
+                // Same as above for a key beyond the window
+                let _ = win.try_set(index, true);
             }
             sparse.clear();
         }
@@ -94,7 +121,7 @@ where
 }
 impl<K, V> SeqQueue<K, V>
 where
-    K: Ord + CheckedAdd + One + Clone + CheckedSub + NumCast + Hash,
+    K: Ord + SeqKey + Clone + Hash,
 {
     #[must_use]
     pub fn peek(&self) -> Option<(&K, &V)> {
@@ -106,18 +133,22 @@ where
         Some((k, v))
     }
     #[must_use]
-    pub fn pop(&mut self, waste: impl FnMut((K, V))) -> Option<(K, V)> {
+    pub fn pop(&mut self, waste: impl WasteSink<K, V>) -> Option<(K, V)> {
         let _ = self.peek()?;
         let (k, v) = self.queue.pop().unwrap().into_flatten();
+        self.remove_dupe_queue_head(waste);
+        self.advance_next();
+        Some((k, v))
+    }
+    /// Slide the window along with [`Self::next`]
+    fn advance_next(&mut self) {
         if let Some(SeqQueueKeys { win, sparse: _ }) = &mut self.keys {
             win.dequeue().unwrap();
             win.enqueue(false);
         }
-        self.remove_dupe_queue_head(waste);
-        self.next = self.next().unwrap().checked_add(&K::one());
-        Some((k, v))
+        self.next = self.next().unwrap().successor();
     }
-    fn remove_dupe_queue_head(&mut self, mut waste: impl FnMut((K, V))) {
+    fn remove_dupe_queue_head(&mut self, mut waste: impl WasteSink<K, V>) {
         let Some(next) = self.next.as_ref() else {
             return;
         };
@@ -125,20 +156,29 @@ where
             if &entry.key != next {
                 break;
             }
-            waste(self.queue.pop().unwrap().into_flatten());
+            waste.waste(
+                WasteReason::ReplacedDuplicateHead,
+                self.queue.pop().unwrap().into_flatten(),
+            );
         }
     }
     #[must_use]
-    pub fn insert(&mut self, key: K, value: V, mut waste: impl FnMut((K, V))) -> SeqInsertResult {
+    pub fn insert(&mut self, key: K, value: V, mut waste: impl WasteSink<K, V>) -> SeqInsertResult {
         let win_size = self.keys.as_ref().map(|keys| keys.win.capacity());
         let case = insert_case(self.next(), &key, win_size);
         match case {
             SeqInsertResult::Stalled | SeqInsertResult::InOrder | SeqInsertResult::OutOfOrder => {
-                self.force_insert(key, value, &mut waste);
+                if !self.force_insert(key, value, waste.by_mut()) {
+                    return SeqInsertResult::Duplicate;
+                }
             }
-            SeqInsertResult::Stale | SeqInsertResult::OutOfWindow => {
-                waste((key, value));
+            SeqInsertResult::Stale => {
+                waste.waste(WasteReason::Stale, (key, value));
+            }
+            SeqInsertResult::OutOfWindow => {
+                waste.waste(WasteReason::OutOfWindow, (key, value));
             }
+            SeqInsertResult::Duplicate => unreachable!("only known on insert"),
         }
         case
     }
@@ -148,36 +188,44 @@ where
         &mut self,
         key: K,
         value: V,
-        mut waste: impl FnMut((K, V)),
+        mut waste: impl WasteSink<K, V>,
     ) -> SeqInsertPopResult<K, V> {
         let win_size = self.keys.as_ref().map(|keys| keys.win.capacity());
         let case = insert_case(self.next(), &key, win_size);
         match case {
             SeqInsertResult::Stalled => {
-                self.force_insert(key, value, &mut waste);
+                if !self.force_insert(key, value, waste.by_mut()) {
+                    return SeqInsertPopResult::Duplicate;
+                }
                 SeqInsertPopResult::Stalled
             }
             SeqInsertResult::Stale => {
-                waste((key, value));
+                waste.waste(WasteReason::Stale, (key, value));
                 SeqInsertPopResult::Stale
             }
             SeqInsertResult::InOrder => {
-                if self.pop(waste).is_none() {
-                    self.next = self.next().unwrap().checked_add(&K::one());
+                match self.pop(waste.by_mut()) {
+                    Some(ejected) => waste.waste(WasteReason::ReplacedDuplicateHead, ejected),
+                    None => self.advance_next(),
                 }
                 SeqInsertPopResult::InOrder((key, value))
             }
             SeqInsertResult::OutOfOrder => {
-                self.force_insert(key, value, &mut waste);
+                if !self.force_insert(key, value, waste.by_mut()) {
+                    return SeqInsertPopResult::Duplicate;
+                }
                 SeqInsertPopResult::OutOfOrder
             }
             SeqInsertResult::OutOfWindow => {
-                waste((key, value));
+                waste.waste(WasteReason::OutOfWindow, (key, value));
                 SeqInsertPopResult::OutOfWindow
             }
+            SeqInsertResult::Duplicate => unreachable!("only known on insert"),
         }
     }
-    fn force_insert(&mut self, key: K, value: V, mut waste: impl FnMut((K, V))) {
+    /// `false` if the entry has been wasted as a duplicate
+    #[must_use]
+    fn force_insert(&mut self, key: K, value: V, mut waste: impl WasteSink<K, V>) -> bool {
         if let Some(SeqQueueKeys { win, sparse }) = &mut self.keys {
             let mut is_duped = || {
                 match &self.next {
@@ -185,7 +233,11 @@ where
                         let Some(index) = key_index(next, &key) else {
                             return true;
                         };
-                        if win.get(index) {
+                        // `insert_case()` has ruled out keys beyond the window; never alias a vacant slot
+                        let Some(seen) = win.try_get(index) else {
+                            return true;
+                        };
+                        if seen {
                             return true;
                         }
                         win.set(index, true);
@@ -200,26 +252,63 @@ where
                 false
             };
             if is_duped() {
-                waste((key, value));
-                return;
+                waste.waste(WasteReason::Duplicate, (key, value));
+                return false;
             }
         }
         let entry = OrdEntry { key, value };
         self.queue.push(entry);
+        true
+    }
+    /// Also forget `key` so that it can be inserted again
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let (_, value) = self
+            .queue
+            .remove_first_by(|entry| entry.key == *key)?
+            .into_flatten();
+        if let Some(SeqQueueKeys { win, sparse }) = &mut self.keys {
+            match &self.next {
+                Some(next) => {
+                    if let Some(index) = key_index(next, key) {
+                        let _ = win.try_set(index, false);
+                    }
+                }
+                None => {
+                    sparse.remove(key);
+                }
+            }
+        }
+        Some(value)
     }
+    /// [`Self::insert_pop()`] and then [`Self::resume_pop_all()`] if `key` is in order
+    ///
+    /// On [`ControlFlow::Break`], the entry handed to `read` has been popped and [`Self::next()`] is already past it;
+    /// the rest of the contiguous run stays queued for [`Self::resume_pop_all()`] or [`Self::pop()`].
     pub fn insert_pop_all<O>(
         &mut self,
         key: K,
         value: V,
-        mut waste: impl FnMut((K, V)),
+        mut waste: impl WasteSink<K, V>,
         mut read: impl FnMut((K, V)) -> ControlFlow<O>,
     ) -> Option<O> {
-        let (k, v) = self.insert_pop(key, value, &mut waste).into_in_order()?;
+        let (k, v) = self
+            .insert_pop(key, value, waste.by_mut())
+            .into_in_order()?;
         let ctrl = read((k, v));
         if let Some(o) = ctrl.break_value() {
             return Some(o);
         }
-        while let Some((k, v)) = self.pop(&mut waste) {
+        self.resume_pop_all(waste, read)
+    }
+    /// Pop and `read` entries in order until the contiguous run ends or `read` breaks
+    ///
+    /// Neither skips nor repeats an entry across a [`ControlFlow::Break`] of [`Self::insert_pop_all()`] or of itself.
+    pub fn resume_pop_all<O>(
+        &mut self,
+        mut waste: impl WasteSink<K, V>,
+        mut read: impl FnMut((K, V)) -> ControlFlow<O>,
+    ) -> Option<O> {
+        while let Some((k, v)) = self.pop(waste.by_mut()) {
             let ctrl = read((k, v));
             if let Some(o) = ctrl.break_value() {
                 return Some(o);
@@ -227,6 +316,240 @@ where
         }
         None
     }
+    /// [`Self::insert_pop_all()`] but `read` can also push back an entry it has no room for
+    ///
+    /// On [`SinkResult::Full`], the entry is queued again and [`Self::next()`] rolled back to its key, so that the next pop hands out the same entry.
+    ///
+    /// # Panic
+    ///
+    /// [`SinkResult::Full`] returns a key other than the one just handed to `read`
+    pub fn insert_pop_all_bounded<O>(
+        &mut self,
+        key: K,
+        value: V,
+        mut waste: impl WasteSink<K, V>,
+        mut read: impl FnMut((K, V)) -> SinkResult<K, V, O>,
+    ) -> BoundedPopOutcome<O> {
+        let Some((k, v)) = self.insert_pop(key, value, waste.by_mut()).into_in_order() else {
+            return BoundedPopOutcome::Drained;
+        };
+        if let Some(outcome) = self.sink(k, v, &mut read) {
+            return outcome;
+        }
+        self.resume_pop_all_bounded(waste, read)
+    }
+    /// [`Self::resume_pop_all()`] with the backpressure of [`Self::insert_pop_all_bounded()`]
+    pub fn resume_pop_all_bounded<O>(
+        &mut self,
+        mut waste: impl WasteSink<K, V>,
+        mut read: impl FnMut((K, V)) -> SinkResult<K, V, O>,
+    ) -> BoundedPopOutcome<O> {
+        while let Some((k, v)) = self.pop(waste.by_mut()) {
+            if let Some(outcome) = self.sink(k, v, &mut read) {
+                return outcome;
+            }
+        }
+        BoundedPopOutcome::Drained
+    }
+    /// Hand the entry just popped to `read`; [`None`] to carry on
+    fn sink<O>(
+        &mut self,
+        key: K,
+        value: V,
+        read: &mut impl FnMut((K, V)) -> SinkResult<K, V, O>,
+    ) -> Option<BoundedPopOutcome<O>> {
+        let delivered = key.clone();
+        match read((key, value)) {
+            SinkResult::Accepted => None,
+            SinkResult::Break(o) => Some(BoundedPopOutcome::Break(o)),
+            SinkResult::Full((key, value)) => {
+                assert!(key == delivered, "not the entry just delivered");
+                self.unpop(key, value);
+                Some(BoundedPopOutcome::Backpressured)
+            }
+        }
+    }
+    /// Undo the [`Self::pop()`] of the entry keyed by `next - 1`
+    fn unpop(&mut self, key: K, value: V) {
+        if let Some(SeqQueueKeys { win, sparse: _ }) = &mut self.keys {
+            // Slide the window back by one; the slot slid out at the back was vacant since it has just slid in
+            let back = win.dequeue_back();
+            debug_assert_eq!(back, Some(false));
+            // The key is queued again, so its bit stays set to turn away duplicates
+            win.try_enqueue_front(true).unwrap();
+        }
+        self.queue.push(OrdEntry {
+            key: key.clone(),
+            value,
+        });
+        self.next = Some(key);
+    }
+}
+impl<K, V> SeqQueue<K, V>
+where
+    K: Ord,
+{
+    /// [`Self::clear()`] but hand every queued entry to `f` in ascending key order
+    ///
+    /// Return the number of the entries handed out.
+    pub fn drain_buffered(&mut self, mut f: impl FnMut(K, V)) -> usize {
+        let mut n = 0;
+        while let Some(entry) = self.queue.pop() {
+            let (key, value) = entry.into_flatten();
+            f(key, value);
+            n += 1;
+        }
+        self.clear();
+        n
+    }
+    /// Selective acknowledgment of the keys received past [`Self::next()`], LSB being `next + 1`
+    ///
+    /// Return the number of the meaningful bits in `words`;
+    /// zero if the keys are not tracked by [`Self::new_unstable()`] or [`Self::next()`] is unknown.
+    pub fn ack_bitmap(&self, words: &mut [u64]) -> usize {
+        let (Some(_), Some(SeqQueueKeys { win, .. })) = (&self.next, &self.keys) else {
+            words.fill(0);
+            return 0;
+        };
+        let bits = win
+            .len()
+            .saturating_sub(1)
+            .min(words.len() * u64::BITS as usize);
+        win.copy_range_to_words(1..1 + bits, words);
+        bits
+    }
+    /// One line of the state that stays short however many entries are queued
+    #[must_use]
+    pub fn debug_summary(&self) -> SeqQueueSummary<K>
+    where
+        K: Clone,
+    {
+        let first_key = self.queue.peek().map(|entry| entry.key.clone());
+        let last_key = self.queue.iter().map(|entry| &entry.key).max().cloned();
+        let (window_size, window_set) = match &self.keys {
+            Some(keys) => (
+                Some(keys.win.capacity()),
+                keys.win.iter().filter(|&seen| seen).count(),
+            ),
+            None => (None, 0),
+        };
+        SeqQueueSummary {
+            next: self.next.clone(),
+            len: self.queue.len(),
+            window_size,
+            first_key,
+            last_key,
+            window_set,
+        }
+    }
+    /// The queued keys in ascending order
+    #[must_use]
+    pub fn buffered_keys(&self) -> Vec<K>
+    where
+        K: Clone,
+    {
+        let mut keys: Vec<K> = self.queue.iter().map(|entry| entry.key.clone()).collect();
+        keys.sort_unstable();
+        keys
+    }
+}
+impl<K, V> SeqQueue<K, V>
+where
+    K: Ord + Clone,
+{
+    /// Copy out what [`Self::import_state()`] needs to carry on elsewhere
+    #[must_use]
+    pub fn export_state(&self) -> SeqQueueState<K, V>
+    where
+        V: Clone,
+    {
+        let mut buffered: Vec<(K, V)> = self
+            .queue
+            .iter()
+            .map(|entry| (entry.key.clone(), entry.value.clone()))
+            .collect();
+        buffered.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let window = self
+            .keys
+            .as_ref()
+            .map(|keys| NonZeroUsize::new(keys.win.capacity()).unwrap());
+        SeqQueueState {
+            next: self.next.clone(),
+            buffered,
+            window,
+        }
+    }
+}
+impl<K, V> SeqQueue<K, V>
+where
+    K: Ord + Clone + SeqKey + Hash,
+{
+    /// Rebuild a queue from [`Self::export_state()`]
+    ///
+    /// Only the buffered keys are tracked against duplicates again;
+    /// a key popped before the export is no longer recognized and is taken as new if it arrives again.
+    /// Buffered keys before [`SeqQueueState::next`] are dropped.
+    #[must_use]
+    pub fn import_state(state: SeqQueueState<K, V>) -> Self {
+        let mut queue = match state.window {
+            Some(window) => Self::new(window),
+            None => Self::new_unstable(),
+        };
+        for (key, value) in state.buffered {
+            if let Some(SeqQueueKeys { sparse, .. }) = &mut queue.keys {
+                sparse.insert(key.clone());
+            }
+            queue.queue.push(OrdEntry { key, value });
+        }
+        if let Some(next) = state.next {
+            // Moves the tracking of the keys from `sparse` to the window
+            queue.set_next(next, |_, _| {});
+        }
+        queue
+    }
+}
+/// Progress of a [`SeqQueue`] to be moved to another one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeqQueueState<K, V> {
+    pub next: Option<K>,
+    /// In ascending key order
+    pub buffered: Vec<(K, V)>,
+    /// [`None`] for [`SeqQueue::new_unstable()`]
+    pub window: Option<NonZeroUsize>,
+}
+/// Returned by [`SeqQueue::debug_summary()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeqQueueSummary<K> {
+    pub next: Option<K>,
+    pub len: usize,
+    /// [`None`] for [`SeqQueue::new_unstable()`]
+    pub window_size: Option<usize>,
+    pub first_key: Option<K>,
+    pub last_key: Option<K>,
+    /// Keys in the window seen and not yet popped
+    pub window_set: usize,
+}
+impl<K: core::fmt::Display> core::fmt::Display for SeqQueueSummary<K> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        fn opt<T: core::fmt::Display>(
+            f: &mut core::fmt::Formatter<'_>,
+            value: Option<&T>,
+        ) -> core::fmt::Result {
+            match value {
+                Some(value) => write!(f, "{value}"),
+                None => f.write_str("-"),
+            }
+        }
+        f.write_str("next=")?;
+        opt(f, self.next.as_ref())?;
+        write!(f, " len={} window=", self.len)?;
+        opt(f, self.window_size.as_ref())?;
+        f.write_str(" keys=")?;
+        opt(f, self.first_key.as_ref())?;
+        f.write_str("..=")?;
+        opt(f, self.last_key.as_ref())?;
+        write!(f, " set={}", self.window_set)
+    }
 }
 impl<K, V> Len for SeqQueue<K, V> {
     fn len(&self) -> usize {
@@ -243,6 +566,30 @@ impl<K, V> Clear for SeqQueue<K, V> {
         self.queue.clear();
     }
 }
+/// Hand every key acknowledged by a [`SeqQueue::ack_bitmap()`] relative to `next` to `acked` in ascending order
+pub fn apply_ack_bitmap<K>(next: K, words: &[u64], mut acked: impl FnMut(K))
+where
+    K: SeqKey + Clone,
+{
+    // Walk the successors since a key can only step by one
+    let mut key = next;
+    let mut offset = 0;
+    for (i, &word) in words.iter().enumerate() {
+        let mut word = word;
+        while word != 0 {
+            let bit = word.trailing_zeros() as usize;
+            word &= word - 1;
+            while offset <= i * u64::BITS as usize + bit {
+                let Some(successor) = key.successor() else {
+                    return;
+                };
+                key = successor;
+                offset += 1;
+            }
+            acked(key.clone());
+        }
+    }
+}
 /// To prevent duplicate keys in best-effort
 #[derive(Debug, Clone)]
 struct SeqQueueKeys<K> {
@@ -251,12 +598,8 @@ struct SeqQueueKeys<K> {
     /// Used when the next sequence number is unknown
     pub sparse: HashSet<K>,
 }
-fn key_index<K>(next: &K, key: &K) -> Option<usize>
-where
-    K: CheckedSub + NumCast,
-{
-    let index = key.checked_sub(next)?.to_usize()?;
-    Some(index)
+fn key_index<K: SeqKey>(next: &K, key: &K) -> Option<usize> {
+    K::distance(next, key)
 }
 fn reset_bit_win(win: &mut BitQueue) {
     win.clear();
@@ -288,36 +631,35 @@ impl<K, V> BTreeSeqQueue<K, V>
 where
     K: Ord + Clone,
 {
-    pub fn set_next(&mut self, next: K, mut stale: impl FnMut((K, V))) {
-        loop {
-            let Some((head, _)) = self.queue.first_key_value() else {
-                break;
-            };
+    pub fn set_next(&mut self, next: K, mut stale: impl WasteSink<K, V>) {
+        while let Some((head, _)) = self.queue.first_key_value() {
             if next <= *head {
                 break;
             }
             let key = head.clone();
             let value = self.queue.remove(&key).unwrap();
-            stale((key, value));
+            stale.waste(WasteReason::StaleBySetNext, (key, value));
         }
         self.next = Some(next);
     }
 }
 impl<K, V> BTreeSeqQueue<K, V>
 where
-    K: Ord + Clone + One + CheckedAdd + CheckedSub + NumCast,
+    K: Ord + Clone + SeqKey,
 {
+    /// A duplicate key replaces the queued value, which goes to `waste` as [`WasteReason::Duplicate`]
     #[must_use]
-    pub fn insert(&mut self, key: K, value: V, mut waste: impl FnMut((K, V))) -> SeqInsertResult {
+    pub fn insert(&mut self, key: K, value: V, mut waste: impl WasteSink<K, V>) -> SeqInsertResult {
         let case = insert_case(self.next(), &key, None);
         match case {
             SeqInsertResult::Stalled | SeqInsertResult::InOrder | SeqInsertResult::OutOfOrder => {
-                self.force_insert(key, value, &mut waste);
+                self.force_insert(key, value, waste.by_mut());
             }
             SeqInsertResult::Stale => {
-                waste((key, value));
+                waste.waste(WasteReason::Stale, (key, value));
             }
-            SeqInsertResult::OutOfWindow => panic!(),
+            SeqInsertResult::OutOfWindow => unreachable!("no window to fall out of"),
+            SeqInsertResult::Duplicate => unreachable!("only known on insert"),
         }
         case
     }
@@ -326,36 +668,37 @@ where
         &mut self,
         key: K,
         value: V,
-        mut waste: impl FnMut((K, V)),
+        mut waste: impl WasteSink<K, V>,
     ) -> SeqInsertPopResult<K, V> {
         let case = insert_case(self.next(), &key, None);
         match case {
             SeqInsertResult::Stalled => {
-                self.force_insert(key, value, &mut waste);
+                self.force_insert(key, value, waste.by_mut());
                 SeqInsertPopResult::Stalled
             }
             SeqInsertResult::Stale => {
-                waste((key, value));
+                waste.waste(WasteReason::Stale, (key, value));
                 SeqInsertPopResult::Stale
             }
             SeqInsertResult::InOrder => {
                 if let Some(ejected) = self.pop() {
-                    waste(ejected);
+                    waste.waste(WasteReason::ReplacedDuplicateHead, ejected);
                 } else {
-                    self.next = key.checked_add(&K::one());
+                    self.next = key.successor();
                 }
                 SeqInsertPopResult::InOrder((key, value))
             }
             SeqInsertResult::OutOfOrder => {
-                self.force_insert(key, value, &mut waste);
+                self.force_insert(key, value, waste.by_mut());
                 SeqInsertPopResult::OutOfOrder
             }
-            SeqInsertResult::OutOfWindow => panic!(),
+            SeqInsertResult::OutOfWindow => unreachable!("no window to fall out of"),
+            SeqInsertResult::Duplicate => unreachable!("only known on insert"),
         }
     }
-    fn force_insert(&mut self, key: K, value: V, mut waste: impl FnMut((K, V))) {
+    fn force_insert(&mut self, key: K, value: V, mut waste: impl WasteSink<K, V>) {
         if let Some(ejected) = self.queue.insert(key.clone(), value) {
-            waste((key, ejected));
+            waste.waste(WasteReason::Duplicate, (key, ejected));
         }
     }
     #[must_use]
@@ -367,14 +710,18 @@ where
     pub fn pop(&mut self) -> Option<(K, V)> {
         let key = self.next()?.clone();
         let value = self.queue.remove(&key)?;
-        self.next = key.checked_add(&K::one());
+        self.next = key.successor();
         Some((key, value))
     }
+    /// [`Self::insert_pop()`] and then [`Self::resume_pop_all()`] if `key` is in order
+    ///
+    /// On [`ControlFlow::Break`], the entry handed to `read` has been popped and [`Self::next()`] is already past it;
+    /// the rest of the contiguous run stays queued for [`Self::resume_pop_all()`] or [`Self::pop()`].
     pub fn insert_pop_all<O>(
         &mut self,
         key: K,
         value: V,
-        waste: impl FnMut((K, V)),
+        waste: impl WasteSink<K, V>,
         mut read: impl FnMut((K, V)) -> ControlFlow<O>,
     ) -> Option<O> {
         let (k, v) = self.insert_pop(key, value, waste).into_in_order()?;
@@ -382,6 +729,15 @@ where
         if let Some(o) = ctrl.break_value() {
             return Some(o);
         }
+        self.resume_pop_all(read)
+    }
+    /// Pop and `read` entries in order until the contiguous run ends or `read` breaks
+    ///
+    /// Neither skips nor repeats an entry across a [`ControlFlow::Break`] of [`Self::insert_pop_all()`] or of itself.
+    pub fn resume_pop_all<O>(
+        &mut self,
+        mut read: impl FnMut((K, V)) -> ControlFlow<O>,
+    ) -> Option<O> {
         while let Some((k, v)) = self.pop() {
             let ctrl = read((k, v));
             if let Some(o) = ctrl.break_value() {
@@ -391,11 +747,48 @@ where
         None
     }
 }
+impl<K, V> BTreeSeqQueue<K, V> {
+    /// [`Self::clear()`] but hand every queued entry to `f` in ascending key order
+    ///
+    /// Return the number of the entries handed out.
+    pub fn drain_buffered(&mut self, mut f: impl FnMut(K, V)) -> usize {
+        let n = self.queue.len();
+        core::mem::take(&mut self.queue)
+            .into_iter()
+            .for_each(|(key, value)| f(key, value));
+        self.clear();
+        n
+    }
+    /// The queued keys in ascending order
+    #[must_use]
+    pub fn buffered_keys(&self) -> Vec<K>
+    where
+        K: Clone,
+    {
+        self.queue.keys().cloned().collect()
+    }
+    /// The queued entries in ascending key order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.queue.iter()
+    }
+}
 impl<K, V> Default for BTreeSeqQueue<K, V> {
     fn default() -> Self {
         Self::new()
     }
 }
+/// All stalled until [`BTreeSeqQueue::set_next()`]; the last of the equal keys wins
+impl<K, V> FromIterator<(K, V)> for BTreeSeqQueue<K, V>
+where
+    K: Ord,
+{
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        Self {
+            next: None,
+            queue: BTreeMap::from_iter(iter),
+        }
+    }
+}
 impl<K, V> Len for BTreeSeqQueue<K, V> {
     fn len(&self) -> usize {
         self.queue.len()
@@ -408,6 +801,282 @@ impl<K, V> Clear for BTreeSeqQueue<K, V> {
     }
 }
 
+/// A [`SeqQueue`] over dense `u64` keys whose entries are written directly into a ring of slots indexed by `key - next`
+///
+/// optimal if the reorder window is small and most keys in the window eventually arrive
+#[derive(Debug, Clone)]
+pub struct DenseSeqQueue<V> {
+    next: Option<u64>,
+    /// The slot of [`Self::next`]
+    head: usize,
+    slots: Box<[Option<V>]>,
+    slots_len: usize,
+    /// Entries inserted before [`Self::next`] is known
+    stalled: Vec<(u64, V)>,
+}
+impl<V> DenseSeqQueue<V> {
+    #[must_use]
+    pub fn new(window: NonZeroUsize) -> Self {
+        Self {
+            next: None,
+            head: 0,
+            slots: (0..window.get()).map(|_| None).collect(),
+            slots_len: 0,
+            stalled: vec![],
+        }
+    }
+    #[must_use]
+    pub fn next(&self) -> Option<&u64> {
+        self.next.as_ref()
+    }
+    #[must_use]
+    fn slot(&self, index: usize) -> usize {
+        self.head.ring_add(index, self.slots.len() - 1)
+    }
+    pub fn set_next(&mut self, next: u64, mut stale: impl WasteSink<u64, V>) {
+        let mut entries = core::mem::take(&mut self.stalled);
+        if let Some(prev_next) = self.next {
+            for i in 0..self.slots.len() {
+                if self.slots_len == 0 {
+                    break;
+                }
+                let slot = self.slot(i);
+                if let Some(value) = self.slots[slot].take() {
+                    self.slots_len -= 1;
+                    entries.push((prev_next + i as u64, value));
+                }
+            }
+        }
+        self.head = 0;
+        self.next = Some(next);
+        for (key, value) in entries {
+            match insert_case(self.next(), &key, Some(self.slots.len())) {
+                SeqInsertResult::Stale => stale.waste(WasteReason::StaleBySetNext, (key, value)),
+                SeqInsertResult::OutOfWindow => stale.waste(WasteReason::OutOfWindow, (key, value)),
+                SeqInsertResult::Stalled
+                | SeqInsertResult::InOrder
+                | SeqInsertResult::OutOfOrder => {
+                    let _ = self.force_insert(key, value, stale.by_mut());
+                }
+                SeqInsertResult::Duplicate => unreachable!("only known on insert"),
+            }
+        }
+    }
+    #[must_use]
+    pub fn peek(&self) -> Option<(&u64, &V)> {
+        let next = self.next()?;
+        let value = self.slots[self.head].as_ref()?;
+        Some((next, value))
+    }
+    #[must_use]
+    pub fn pop(&mut self) -> Option<(u64, V)> {
+        let next = *self.next()?;
+        let value = self.slots[self.head].take()?;
+        self.slots_len -= 1;
+        self.head = self.slot(1);
+        self.next = next.checked_add(1);
+        Some((next, value))
+    }
+    #[must_use]
+    pub fn insert(
+        &mut self,
+        key: u64,
+        value: V,
+        mut waste: impl WasteSink<u64, V>,
+    ) -> SeqInsertResult {
+        let case = insert_case(self.next(), &key, Some(self.slots.len()));
+        match case {
+            SeqInsertResult::Stalled | SeqInsertResult::InOrder | SeqInsertResult::OutOfOrder => {
+                if !self.force_insert(key, value, waste.by_mut()) {
+                    return SeqInsertResult::Duplicate;
+                }
+            }
+            SeqInsertResult::Stale => {
+                waste.waste(WasteReason::Stale, (key, value));
+            }
+            SeqInsertResult::OutOfWindow => {
+                waste.waste(WasteReason::OutOfWindow, (key, value));
+            }
+            SeqInsertResult::Duplicate => unreachable!("only known on insert"),
+        }
+        case
+    }
+    /// Return the input if `key` is [`Self::next()`]
+    #[must_use]
+    pub fn insert_pop(
+        &mut self,
+        key: u64,
+        value: V,
+        mut waste: impl WasteSink<u64, V>,
+    ) -> SeqInsertPopResult<u64, V> {
+        let case = insert_case(self.next(), &key, Some(self.slots.len()));
+        match case {
+            SeqInsertResult::Stalled => {
+                if !self.force_insert(key, value, waste.by_mut()) {
+                    return SeqInsertPopResult::Duplicate;
+                }
+                SeqInsertPopResult::Stalled
+            }
+            SeqInsertResult::Stale => {
+                waste.waste(WasteReason::Stale, (key, value));
+                SeqInsertPopResult::Stale
+            }
+            SeqInsertResult::InOrder => {
+                if let Some(popped) = self.pop() {
+                    waste.waste(WasteReason::Duplicate, (key, value));
+                    return SeqInsertPopResult::InOrder(popped);
+                }
+                self.head = self.slot(1);
+                self.next = key.checked_add(1);
+                SeqInsertPopResult::InOrder((key, value))
+            }
+            SeqInsertResult::OutOfOrder => {
+                if !self.force_insert(key, value, waste.by_mut()) {
+                    return SeqInsertPopResult::Duplicate;
+                }
+                SeqInsertPopResult::OutOfOrder
+            }
+            SeqInsertResult::OutOfWindow => {
+                waste.waste(WasteReason::OutOfWindow, (key, value));
+                SeqInsertPopResult::OutOfWindow
+            }
+            SeqInsertResult::Duplicate => unreachable!("only known on insert"),
+        }
+    }
+    /// `false` if the entry has been wasted as a duplicate
+    #[must_use]
+    fn force_insert(&mut self, key: u64, value: V, mut waste: impl WasteSink<u64, V>) -> bool {
+        let Some(next) = self.next else {
+            if self.stalled.iter().any(|(k, _)| *k == key) {
+                waste.waste(WasteReason::Duplicate, (key, value));
+                return false;
+            }
+            self.stalled.push((key, value));
+            return true;
+        };
+        let slot = self.slot((key - next) as usize);
+        if self.slots[slot].is_some() {
+            waste.waste(WasteReason::Duplicate, (key, value));
+            return false;
+        }
+        self.slots[slot] = Some(value);
+        self.slots_len += 1;
+        true
+    }
+    /// [`Self::insert_pop()`] and then [`Self::resume_pop_all()`] if `key` is in order
+    ///
+    /// On [`ControlFlow::Break`], the entry handed to `read` has been popped and [`Self::next()`] is already past it;
+    /// the rest of the contiguous run stays queued for [`Self::resume_pop_all()`] or [`Self::pop()`].
+    pub fn insert_pop_all<O>(
+        &mut self,
+        key: u64,
+        value: V,
+        waste: impl WasteSink<u64, V>,
+        mut read: impl FnMut((u64, V)) -> ControlFlow<O>,
+    ) -> Option<O> {
+        let (k, v) = self.insert_pop(key, value, waste).into_in_order()?;
+        let ctrl = read((k, v));
+        if let Some(o) = ctrl.break_value() {
+            return Some(o);
+        }
+        self.resume_pop_all(read)
+    }
+    /// Pop and `read` entries in order until the contiguous run ends or `read` breaks
+    ///
+    /// Neither skips nor repeats an entry across a [`ControlFlow::Break`] of [`Self::insert_pop_all()`] or of itself.
+    pub fn resume_pop_all<O>(
+        &mut self,
+        mut read: impl FnMut((u64, V)) -> ControlFlow<O>,
+    ) -> Option<O> {
+        while let Some((k, v)) = self.pop() {
+            let ctrl = read((k, v));
+            if let Some(o) = ctrl.break_value() {
+                return Some(o);
+            }
+        }
+        None
+    }
+}
+impl<V> DenseSeqQueue<V> {
+    /// [`Self::clear()`] but hand every queued entry to `f` in ascending key order
+    ///
+    /// Return the number of the entries handed out.
+    pub fn drain_buffered(&mut self, mut f: impl FnMut(u64, V)) -> usize {
+        let n = self.len();
+        // Only one of them is populated
+        let mut stalled = core::mem::take(&mut self.stalled);
+        stalled.sort_unstable_by_key(|(key, _)| *key);
+        stalled.into_iter().for_each(|(key, value)| f(key, value));
+        if let Some(next) = self.next {
+            for i in 0..self.slots.len() {
+                let slot = self.slot(i);
+                if let Some(value) = self.slots[slot].take() {
+                    f(next + i as u64, value);
+                }
+            }
+        }
+        self.clear();
+        n
+    }
+    /// The queued keys in ascending order
+    #[must_use]
+    pub fn buffered_keys(&self) -> Vec<u64> {
+        let mut keys: Vec<u64> = self.stalled.iter().map(|(key, _)| *key).collect();
+        keys.sort_unstable();
+        if let Some(next) = self.next {
+            keys.extend(
+                (0..self.slots.len())
+                    .filter(|&i| self.slots[self.slot(i)].is_some())
+                    .map(|i| next + i as u64),
+            );
+        }
+        keys
+    }
+}
+impl<V> Len for DenseSeqQueue<V> {
+    fn len(&self) -> usize {
+        self.slots_len + self.stalled.len()
+    }
+}
+impl<V> Capacity for DenseSeqQueue<V> {
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+}
+impl<V> Clear for DenseSeqQueue<V> {
+    fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+        self.slots_len = 0;
+        self.stalled.clear();
+        self.head = 0;
+        self.next = None;
+    }
+}
+
+/// Why an entry was handed to a waste callback instead of being kept or popped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WasteReason {
+    /// The key was already behind [`SeqQueue::next()`] on insert
+    Stale,
+    /// The key was too far ahead of [`SeqQueue::next()`] to fit in the window
+    OutOfWindow,
+    /// An entry with the same key has already been queued
+    Duplicate,
+    /// The entry was left behind by a `set_next()`
+    StaleBySetNext,
+    /// A queued entry had the same key as the one just popped or inserted in order
+    ReplacedDuplicateHead,
+}
+impl WasteReason {
+    pub const ALL: [Self; 5] = [
+        Self::Stale,
+        Self::OutOfWindow,
+        Self::Duplicate,
+        Self::StaleBySetNext,
+        Self::ReplacedDuplicateHead,
+    ];
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SeqInsertResult {
     Stalled,
@@ -415,11 +1084,77 @@ pub enum SeqInsertResult {
     InOrder,
     OutOfOrder,
     OutOfWindow,
+    /// An entry with the same key has already been queued; the input went to the waste callback
+    Duplicate,
+}
+impl SeqInsertResult {
+    /// `Ok` if the entry was kept
+    pub fn into_result(self) -> Result<(), SeqInsertError> {
+        match self {
+            SeqInsertResult::Stalled | SeqInsertResult::InOrder | SeqInsertResult::OutOfOrder => {
+                Ok(())
+            }
+            SeqInsertResult::Stale => Err(SeqInsertError::Stale),
+            SeqInsertResult::OutOfWindow => Err(SeqInsertError::OutOfWindow),
+            SeqInsertResult::Duplicate => Err(SeqInsertError::Duplicate),
+        }
+    }
+    #[must_use]
+    pub fn is_accepted(&self) -> bool {
+        !self.is_rejected()
+    }
+    #[must_use]
+    pub fn is_rejected(&self) -> bool {
+        matches!(
+            self,
+            SeqInsertResult::Stale | SeqInsertResult::OutOfWindow | SeqInsertResult::Duplicate
+        )
+    }
+}
+impl core::fmt::Display for SeqInsertResult {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let s = match self {
+            SeqInsertResult::Stalled => "stalled",
+            SeqInsertResult::Stale => "stale",
+            SeqInsertResult::InOrder => "in order",
+            SeqInsertResult::OutOfOrder => "out of order",
+            SeqInsertResult::OutOfWindow => "out of window",
+            SeqInsertResult::Duplicate => "duplicate",
+        };
+        f.write_str(s)
+    }
+}
+/// What the `read` of [`SeqQueue::insert_pop_all_bounded()`] did with an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SinkResult<K, V, O> {
+    Accepted,
+    /// Take the entry and stop
+    Break(O),
+    /// Hand the entry back undelivered and stop
+    Full((K, V)),
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundedPopOutcome<O> {
+    /// The contiguous run has ended
+    Drained,
+    Break(O),
+    /// The entry handed back by [`SinkResult::Full`] is queued again at [`SeqQueue::next()`]
+    Backpressured,
+}
+/// Why an inserted entry was handed to the waste callback
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum SeqInsertError {
+    #[error("stale")]
+    Stale,
+    #[error("out of window")]
+    OutOfWindow,
+    #[error("duplicate")]
+    Duplicate,
 }
 #[must_use]
 fn insert_case<K>(next: Option<&K>, key: &K, win_size: Option<usize>) -> SeqInsertResult
 where
-    K: Ord + CheckedSub + NumCast,
+    K: Ord + SeqKey,
 {
     let Some(next) = next else {
         return SeqInsertResult::Stalled;
@@ -431,7 +1166,7 @@ where
         return SeqInsertResult::InOrder;
     }
     if let Some(win_size) = win_size {
-        let Some(diff) = key.checked_sub(next).unwrap().to_usize() else {
+        let Some(diff) = K::distance(next, key) else {
             return SeqInsertResult::OutOfWindow;
         };
         if win_size <= diff {
@@ -448,6 +1183,8 @@ pub enum SeqInsertPopResult<K, V> {
     InOrder((K, V)),
     OutOfOrder,
     OutOfWindow,
+    /// An entry with the same key has already been queued; the input went to the waste callback
+    Duplicate,
 }
 impl<K, V> SeqInsertPopResult<K, V> {
     pub fn into_in_order(self) -> Option<(K, V)> {
@@ -456,80 +1193,727 @@ impl<K, V> SeqInsertPopResult<K, V> {
             _ => None,
         }
     }
+    /// `Ok(None)` if the entry was buffered
+    pub fn into_result(self) -> Result<Option<(K, V)>, SeqInsertError> {
+        match self {
+            SeqInsertPopResult::Stalled | SeqInsertPopResult::OutOfOrder => Ok(None),
+            SeqInsertPopResult::InOrder(entry) => Ok(Some(entry)),
+            SeqInsertPopResult::Stale => Err(SeqInsertError::Stale),
+            SeqInsertPopResult::OutOfWindow => Err(SeqInsertError::OutOfWindow),
+            SeqInsertPopResult::Duplicate => Err(SeqInsertError::Duplicate),
+        }
+    }
+    #[must_use]
+    pub fn is_accepted(&self) -> bool {
+        !self.is_rejected()
+    }
+    #[must_use]
+    pub fn is_rejected(&self) -> bool {
+        matches!(
+            self,
+            SeqInsertPopResult::Stale
+                | SeqInsertPopResult::OutOfWindow
+                | SeqInsertPopResult::Duplicate
+        )
+    }
+}
+impl<K: core::fmt::Display, V> core::fmt::Display for SeqInsertPopResult<K, V> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SeqInsertPopResult::Stalled => f.write_str("stalled"),
+            SeqInsertPopResult::Stale => f.write_str("stale"),
+            SeqInsertPopResult::InOrder((key, _)) => write!(f, "in order: {key}"),
+            SeqInsertPopResult::OutOfOrder => f.write_str("out of order"),
+            SeqInsertPopResult::OutOfWindow => f.write_str("out of window"),
+            SeqInsertPopResult::Duplicate => f.write_str("duplicate"),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::queue::waste::VecWaste;
+
     use super::*;
 
+    #[test]
+    fn test_seq_queue_remove() {
+        let mut q = SeqQueue::new(NonZeroUsize::new(1 << 4).unwrap());
+        let mut waste = VecWaste::new();
+        assert_eq!(q.insert(3, 3, &mut waste), SeqInsertResult::Stalled);
+        assert_eq!(q.remove(&3), Some(3));
+        assert_eq!(q.remove(&3), None);
+        assert_eq!(q.insert(3, 4, &mut waste), SeqInsertResult::Stalled);
+        q.set_next(1, &mut waste);
+        assert_eq!(q.insert(2, 2, &mut waste), SeqInsertResult::OutOfOrder);
+        assert_eq!(q.remove(&2), Some(2));
+        assert_eq!(q.insert(2, 5, &mut waste), SeqInsertResult::OutOfOrder);
+        assert_eq!(q.insert(2, 6, &mut waste), SeqInsertResult::Duplicate);
+        assert_eq!(waste.wasted, [(WasteReason::Duplicate, (2, 6))]);
+        assert_eq!(q.remove(&3), Some(4));
+        assert_eq!(
+            q.insert_pop(1, 1, |_, _| {}).into_in_order().unwrap(),
+            (1, 1)
+        );
+        assert_eq!(q.pop(|_, _| {}).unwrap(), (2, 5));
+        assert!(q.pop(|_, _| {}).is_none());
+        assert_eq!(q.len(), 0);
+    }
+
+    #[test]
+    fn test_btree_from_iter() {
+        let mut q: BTreeSeqQueue<u64, char> = [(3, 'a'), (1, 'b'), (3, 'c')].into_iter().collect();
+        assert_eq!(q.len(), 2);
+        assert!(q.next().is_none());
+        q.set_next(1, |_, _| unreachable!());
+        assert_eq!(q.pop(), Some((1, 'b')));
+        assert_eq!(q.pop(), None);
+        q.set_next(3, |_, _| unreachable!());
+        assert_eq!(q.pop(), Some((3, 'c')));
+    }
+
+    #[test]
+    fn test_migrate_state() {
+        let arrivals = [5, 0, 2, 7, 1, 9, 3, 6, 4, 8, 10, 12, 11];
+        let run = |migrate_at: Option<usize>| {
+            let mut q = SeqQueue::new(NonZeroUsize::new(1 << 4).unwrap());
+            q.set_next(0_u32, |_, _| {});
+            let mut delivered = vec![];
+            for (i, &key) in arrivals.iter().enumerate() {
+                if migrate_at == Some(i) {
+                    let state = q.export_state();
+                    assert!(state.buffered.windows(2).all(|w| w[0].0 < w[1].0));
+                    q = SeqQueue::import_state(state);
+                }
+                let _ = q.insert_pop_all(
+                    key,
+                    key * 10,
+                    |reason, entry| panic!("{reason:?}: {entry:?}"),
+                    |entry| {
+                        delivered.push(entry);
+                        ControlFlow::<()>::Continue(())
+                    },
+                );
+            }
+            (delivered, q.next().copied())
+        };
+        let control = run(None);
+        assert_eq!(control.0.len(), arrivals.len());
+        for migrate_at in 0..arrivals.len() {
+            assert_eq!(run(Some(migrate_at)), control);
+        }
+
+        // Gaps and the duplicate suppression of the buffered keys survive
+        let mut q = SeqQueue::new(NonZeroUsize::new(1 << 4).unwrap());
+        q.set_next(0_u32, |_, _| {});
+        for key in [3, 5] {
+            let _ = q.insert(key, (), |_, _| {});
+        }
+        let state = q.export_state();
+        assert_eq!(state.next, Some(0));
+        assert_eq!(state.buffered, [(3, ()), (5, ())]);
+        let mut q = SeqQueue::import_state(state);
+        let mut wasted_keys = vec![];
+        assert_eq!(
+            q.insert(5, (), |reason, (k, ())| wasted_keys.push((reason, k))),
+            SeqInsertResult::Duplicate
+        );
+        assert_eq!(wasted_keys, [(WasteReason::Duplicate, 5)]);
+
+        // Stalled keys are tracked by the sparse set
+        let mut q = SeqQueue::new(NonZeroUsize::new(1 << 4).unwrap());
+        let _ = q.insert(4_u32, 'a', |_, _| {});
+        let mut q = SeqQueue::import_state(q.export_state());
+        assert!(q.next().is_none());
+        let mut wasted = VecWaste::new();
+        assert_eq!(q.insert(4, 'b', &mut wasted), SeqInsertResult::Duplicate);
+        assert_eq!(wasted.wasted, [(WasteReason::Duplicate, (4, 'b'))]);
+        let unstable = SeqQueue::<u32, ()>::new_unstable();
+        assert_eq!(unstable.export_state().window, None);
+    }
+
+    #[test]
+    fn test_drain_buffered() {
+        let mut q = SeqQueue::new(NonZeroUsize::new(1 << 4).unwrap());
+        let mut b = BTreeSeqQueue::new();
+        let mut d = DenseSeqQueue::new(NonZeroUsize::new(1 << 4).unwrap());
+        let mut waste = |reason, entry| panic!("{reason:?}: {entry:?}");
+        for (k, v) in [(5, 'a'), (3, 'b'), (9, 'c')] {
+            let _ = q.insert(k, v, &mut waste);
+            let _ = b.insert(k, v, &mut waste);
+            let _ = d.insert(k, v, &mut waste);
+        }
+        assert_eq!(d.buffered_keys(), [3, 5, 9]);
+        q.set_next(3, &mut waste);
+        b.set_next(3, &mut waste);
+        d.set_next(3, &mut waste);
+        assert_eq!(q.pop(&mut waste), Some((3, 'b')));
+        assert_eq!(b.pop(), Some((3, 'b')));
+        assert_eq!(d.pop(), Some((3, 'b')));
+        for (k, v) in [(7, 'd'), (4, 'e')] {
+            let _ = q.insert(k, v, &mut waste);
+            let _ = b.insert(k, v, &mut waste);
+            let _ = d.insert(k, v, &mut waste);
+        }
+
+        let expected = [(4, 'e'), (5, 'a'), (7, 'd'), (9, 'c')];
+        assert_eq!(q.buffered_keys(), [4, 5, 7, 9]);
+        assert_eq!(b.buffered_keys(), [4, 5, 7, 9]);
+        assert_eq!(d.buffered_keys(), [4, 5, 7, 9]);
+        let mut drained = vec![];
+        assert_eq!(q.drain_buffered(|k, v| drained.push((k, v))), 4);
+        assert_eq!(drained, expected);
+        drained.clear();
+        assert_eq!(b.drain_buffered(|k, v| drained.push((k, v))), 4);
+        assert_eq!(drained, expected);
+        drained.clear();
+        assert_eq!(d.drain_buffered(|k, v| drained.push((k, v))), 4);
+        assert_eq!(drained, expected);
+        assert_eq!(q.len(), 0);
+        assert!(q.next().is_none());
+        assert!(b.next().is_none());
+        assert!(d.next().is_none());
+        assert!(q.buffered_keys().is_empty());
+
+        // The keys seen before the drain are no duplicates afterwards
+        q.set_next(4, &mut waste);
+        b.set_next(4, &mut waste);
+        d.set_next(4, &mut waste);
+        for (k, v) in [(5, 'f'), (7, 'g'), (4, 'h')] {
+            let _ = q.insert(k, v, &mut waste);
+            let _ = b.insert(k, v, &mut waste);
+            let _ = d.insert(k, v, &mut waste);
+        }
+        assert_eq!(q.pop(&mut waste), Some((4, 'h')));
+        assert_eq!(q.pop(&mut waste), Some((5, 'f')));
+        assert_eq!(q.pop(&mut waste), None);
+        assert_eq!(b.buffered_keys(), [4, 5, 7]);
+        assert_eq!(d.buffered_keys(), [4, 5, 7]);
+        assert_eq!(q.drain_buffered(|_, _| {}), 1);
+        assert_eq!(q.drain_buffered(|_, _| {}), 0);
+    }
+
+    #[test]
+    fn test_ack_bitmap() {
+        let mut rx = SeqQueue::new(NonZeroUsize::new(100).unwrap());
+        let mut words = [u64::MAX; 4];
+        assert_eq!(rx.ack_bitmap(&mut words), 0);
+        assert_eq!(words, [0; 4]);
+        rx.set_next(1000_u64, |_, _| {});
+
+        let lost = |k: u64| k % 5 == 2 || (1020..1030).contains(&k) || k == 1003;
+        let mut delivered = vec![];
+        for k in (1000..1300).filter(|&k| !lost(k)) {
+            let _ = rx.insert_pop_all(
+                k,
+                (),
+                |_, _| {},
+                |(k, ())| {
+                    delivered.push(k);
+                    ControlFlow::<()>::Continue(())
+                },
+            );
+        }
+        assert_eq!(delivered, [1000, 1001]);
+        let next = *rx.next().unwrap();
+        assert_eq!(next, 1002);
+
+        let bits = rx.ack_bitmap(&mut words);
+        assert!(100 <= bits);
+        let mut acked = vec![];
+        apply_ack_bitmap(next, &words, |k| acked.push(k));
+        let buffered = rx.buffered_keys();
+        let in_window = buffered
+            .iter()
+            .copied()
+            .filter(|&k| k <= next + bits as u64)
+            .collect::<Vec<_>>();
+        assert_eq!(acked, in_window);
+        assert!(acked.iter().all(|&k| !lost(k)));
+        assert!(!acked.contains(&1003));
+
+        // A retransmission of the lost head slides the window
+        let _ = rx.insert_pop_all(
+            1002,
+            (),
+            |_, _| {},
+            |(k, ())| {
+                delivered.push(k);
+                ControlFlow::<()>::Continue(())
+            },
+        );
+        assert_eq!(delivered, [1000, 1001, 1002]);
+        let next = *rx.next().unwrap();
+        rx.ack_bitmap(&mut words);
+        acked.clear();
+        apply_ack_bitmap(next, &words, |k| acked.push(k));
+        assert_eq!(acked[..2], [1004, 1005]);
+
+        // Too few words
+        let mut words = [0; 1];
+        assert_eq!(rx.ack_bitmap(&mut words), 64);
+    }
+
     #[test]
     fn test_seq_queue() {
+        seq_queue_scenario(|k| k);
+    }
+    /// Not a number: only steps by one
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct Tick(u64);
+    impl SeqKey for Tick {
+        fn successor(&self) -> Option<Self> {
+            Some(Self(self.0.checked_add(1)?))
+        }
+        fn distance(from: &Self, to: &Self) -> Option<usize> {
+            usize::try_from(to.0.checked_sub(from.0)?).ok()
+        }
+    }
+    #[test]
+    fn test_seq_queue_custom_key() {
+        seq_queue_scenario(Tick);
+
+        let mut q = BTreeSeqQueue::new();
+        q.set_next(Tick(u64::MAX - 1), |_, _| panic!());
+        let _ = q.insert(Tick(u64::MAX), (), |_, _| panic!());
+        let (k, ()) = q
+            .insert_pop(Tick(u64::MAX - 1), (), |_, _| panic!())
+            .into_in_order()
+            .unwrap();
+        assert_eq!(k, Tick(u64::MAX - 1));
+        assert_eq!(q.pop(), Some((Tick(u64::MAX), ())));
+        // No successor to `u64::MAX`
+        assert_eq!(q.next(), None);
+
+        let mut acked = vec![];
+        apply_ack_bitmap(Tick(10), &[0b101, 1], |k| acked.push(k));
+        assert_eq!(acked, [Tick(11), Tick(13), Tick(75)]);
+    }
+    fn seq_queue_scenario<K>(key: impl Fn(u64) -> K)
+    where
+        K: SeqKey + Ord + Clone + Hash + core::fmt::Debug,
+    {
         let q = [
             SeqQueue::new(NonZeroUsize::new(1 << 10).unwrap()),
             SeqQueue::new_unstable(),
         ];
         for mut q in q {
-            assert!(q.insert_pop(1, 1, |_| {}).into_in_order().is_none());
-            assert!(q.insert_pop(2, 2, |_| {}).into_in_order().is_none());
-            assert!(q.insert_pop(3, 3, |_| {}).into_in_order().is_none());
+            let kv = |k| (key(k), key(k));
+            assert!(q
+                .insert_pop(key(1), key(1), |_, _| {})
+                .into_in_order()
+                .is_none());
+            assert!(q
+                .insert_pop(key(2), key(2), |_, _| {})
+                .into_in_order()
+                .is_none());
+            assert!(q
+                .insert_pop(key(3), key(3), |_, _| {})
+                .into_in_order()
+                .is_none());
             assert_eq!(q.len(), 3);
-            q.set_next(2, |(k, v)| {
+            q.set_next(key(2), |reason, (k, v)| {
+                assert_eq!(reason, WasteReason::StaleBySetNext);
                 assert_eq!(k, v);
-                assert_eq!(k, 1);
+                assert_eq!(k, key(1));
             });
             assert_eq!(q.len(), 2);
-            assert!(q.insert_pop(1, 1, |_| {}).into_in_order().is_none());
+            let mut waste = VecWaste::new();
+            assert!(q
+                .insert_pop(key(1), key(1), &mut waste)
+                .into_in_order()
+                .is_none());
+            assert_eq!(waste.wasted, [(WasteReason::Stale, kv(1))]);
             assert_eq!(q.len(), 2);
-            assert_eq!(q.insert_pop(2, 2, |_| {}).into_in_order().unwrap(), (2, 2));
+            assert_eq!(
+                q.insert_pop(key(2), key(2), |_, _| {})
+                    .into_in_order()
+                    .unwrap(),
+                kv(2)
+            );
             assert_eq!(q.len(), 1);
-            assert_eq!(q.pop(|_| {}).unwrap(), (3, 3));
-            assert!(q.insert_pop(6, 6, |_| {}).into_in_order().is_none());
-            assert!(q.insert_pop(5, 5, |_| {}).into_in_order().is_none());
-            assert!(q.insert_pop(5, 5, |_| {}).into_in_order().is_none());
+            assert_eq!(q.pop(|_, _| {}).unwrap(), kv(3));
+            assert!(q
+                .insert_pop(key(6), key(6), |_, _| {})
+                .into_in_order()
+                .is_none());
+            assert!(q
+                .insert_pop(key(5), key(5), |_, _| {})
+                .into_in_order()
+                .is_none());
+            let is_unstable = q.keys.is_none();
+            let mut wasted = VecWaste::new();
+            assert!(q
+                .insert_pop(key(5), key(5), &mut wasted)
+                .into_in_order()
+                .is_none());
             let mut start = 4;
-            let res: Option<()> = q.insert_pop_all(
-                4,
-                4,
-                |_| {},
-                |(k, v)| {
-                    assert_eq!(k, v);
-                    assert_eq!(start, k);
-                    start += 1;
-                    ControlFlow::Continue(())
-                },
-            );
-            dbg!(&q);
+            let res: Option<()> = q.insert_pop_all(key(4), key(4), &mut wasted, |(k, v)| {
+                assert_eq!(k, v);
+                assert_eq!(key(start), k);
+                start += 1;
+                ControlFlow::Continue(())
+            });
+            dbg!(q.debug_summary());
             assert!(res.is_none());
             assert_eq!(start, 7);
-            assert!(q.pop(|_| {}).is_none());
+            assert!(q.pop(|_, _| {}).is_none());
+            if is_unstable {
+                assert_eq!(wasted.wasted, [(WasteReason::ReplacedDuplicateHead, kv(5))]);
+            } else {
+                assert_eq!(wasted.wasted, [(WasteReason::Duplicate, kv(5))]);
+            }
         }
     }
     #[test]
+    fn test_debug_summary() {
+        let mut q = SeqQueue::new(NonZeroUsize::new(1 << 4).unwrap());
+        // At least the asked size
+        let window = q.debug_summary().window_size.unwrap();
+        assert!(1 << 4 <= window);
+        assert_eq!(
+            q.debug_summary().to_string(),
+            format!("next=- len=0 window={window} keys=-..=- set=0")
+        );
+        for k in [9, 3, 5] {
+            assert_eq!(q.insert(k, k, |_, _| panic!()), SeqInsertResult::Stalled);
+        }
+        q.set_next(4, |_, _| {});
+        for k in [4, 6, 100] {
+            let _ = q.insert(k, k, |_, _| {});
+        }
+        q.remove(&6).unwrap();
+        let summary = q.debug_summary();
+        assert_eq!(
+            summary.to_string(),
+            format!("next=4 len=3 window={window} keys=4..=9 set=3")
+        );
+        let next = *q.next().unwrap();
+        let in_window = q
+            .buffered_keys()
+            .into_iter()
+            .filter(|&k| next <= k && k - next < window as u64)
+            .count();
+        assert_eq!(summary.window_set, in_window);
+
+        q.pop(|_, _| {}).unwrap();
+        assert_eq!(q.debug_summary().window_set, in_window - 1);
+
+        let mut q = SeqQueue::new_unstable();
+        q.set_next(1_u64, |_, _| {});
+        let _ = q.insert(3, (), |_, _| {});
+        assert_eq!(
+            q.debug_summary().to_string(),
+            "next=1 len=1 window=- keys=3..=3 set=0"
+        );
+    }
+
+    /// A sink with room for `room` entries
+    fn sink_with_room(
+        mut room: usize,
+        read: &mut Vec<u64>,
+    ) -> impl FnMut((u64, u64)) -> SinkResult<u64, u64, ()> + '_ {
+        move |(k, v)| {
+            if room == 0 {
+                return SinkResult::Full((k, v));
+            }
+            room -= 1;
+            read.push(k);
+            SinkResult::Accepted
+        }
+    }
+
+    #[test]
+    fn test_insert_pop_all_bounded() {
+        let q = [
+            SeqQueue::new(NonZeroUsize::new(1 << 4).unwrap()),
+            SeqQueue::new_unstable(),
+        ];
+        for mut q in q {
+            q.set_next(0, |_, _| panic!());
+            for k in [1, 2, 3, 5] {
+                assert_eq!(q.insert(k, k, |_, _| panic!()), SeqInsertResult::OutOfOrder);
+            }
+            let mut read = vec![];
+            let res = q.insert_pop_all_bounded(0, 0, |_, _| panic!(), sink_with_room(2, &mut read));
+            assert_eq!(res, BoundedPopOutcome::Backpressured);
+            assert_eq!(read, [0, 1]);
+            assert_eq!(q.next(), Some(&2));
+            assert_eq!(q.peek(), Some((&2, &2)));
+            assert_eq!(q.len(), 3);
+
+            // The entry handed back is delivered again without being taken as a duplicate
+            let res = q.resume_pop_all_bounded(|_, _| panic!(), sink_with_room(1, &mut read));
+            assert_eq!(res, BoundedPopOutcome::Backpressured);
+            assert_eq!(read, [0, 1, 2]);
+            assert_eq!(q.next(), Some(&3));
+            let res =
+                q.resume_pop_all_bounded(|_, _| panic!(), sink_with_room(usize::MAX, &mut read));
+            assert_eq!(res, BoundedPopOutcome::Drained);
+            assert_eq!(read, [0, 1, 2, 3]);
+            assert_eq!(q.next(), Some(&4));
+
+            // The input itself can be handed back
+            let res = q.insert_pop_all_bounded(4, 4, |_, _| panic!(), sink_with_room(0, &mut read));
+            assert_eq!(res, BoundedPopOutcome::Backpressured);
+            assert_eq!(q.next(), Some(&4));
+            let res = q.insert_pop_all_bounded(6, 6, |_, _| panic!(), |_| SinkResult::Break(()));
+            assert_eq!(res, BoundedPopOutcome::Drained);
+            let res = q.resume_pop_all_bounded(|_, _| panic!(), |(k, _)| SinkResult::Break(k));
+            assert_eq!(res, BoundedPopOutcome::Break(4));
+            let res =
+                q.resume_pop_all_bounded(|_, _| panic!(), sink_with_room(usize::MAX, &mut read));
+            assert_eq!(res, BoundedPopOutcome::Drained);
+            assert_eq!(read, [0, 1, 2, 3, 5, 6]);
+            assert_eq!(q.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_backpressure_restores_window() {
+        let mut q = SeqQueue::new(NonZeroUsize::new(1 << 3).unwrap());
+        q.set_next(10, |_, _| panic!());
+        for k in [11, 13, 17] {
+            assert_eq!(q.insert(k, k, |_, _| panic!()), SeqInsertResult::OutOfOrder);
+        }
+        let mut before = [0];
+        let bits = q.ack_bitmap(&mut before);
+        let mut read = vec![];
+        let res = q.insert_pop_all_bounded(10, 10, |_, _| panic!(), sink_with_room(0, &mut read));
+        assert_eq!(res, BoundedPopOutcome::Backpressured);
+        assert!(read.is_empty());
+        let mut after = [0];
+        assert_eq!(q.ack_bitmap(&mut after), bits);
+        assert_eq!(after, before);
+
+        // The re-queued key still turns away duplicates, and so do the ones past it
+        let mut wasted = VecWaste::new();
+        for k in [10, 11, 17] {
+            assert_eq!(q.insert(k, 0, &mut wasted), SeqInsertResult::Duplicate);
+        }
+        assert_eq!(
+            wasted.wasted,
+            [
+                (WasteReason::Duplicate, (10, 0)),
+                (WasteReason::Duplicate, (11, 0)),
+                (WasteReason::Duplicate, (17, 0)),
+            ]
+        );
+
+        let res = q.resume_pop_all_bounded(|_, _| panic!(), sink_with_room(usize::MAX, &mut read));
+        assert_eq!(res, BoundedPopOutcome::Drained);
+        assert_eq!(read, [10, 11]);
+        assert_eq!(q.next(), Some(&12));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_backpressure_other_entry() {
+        let mut q = SeqQueue::<u64, u64>::new_unstable();
+        q.set_next(0, |_, _| panic!());
+        let _ = q.insert_pop_all_bounded(
+            0,
+            0,
+            |_, _| panic!(),
+            |_| SinkResult::<_, _, ()>::Full((1, 1)),
+        );
+    }
+
+    fn read_until<K>(stop: K, read: &mut Vec<K>) -> impl FnMut((K, K)) -> ControlFlow<K> + '_
+    where
+        K: PartialEq + Copy + core::fmt::Debug,
+    {
+        move |(k, v)| {
+            assert_eq!(k, v);
+            read.push(k);
+            if k == stop {
+                return ControlFlow::Break(k);
+            }
+            ControlFlow::Continue(())
+        }
+    }
+    #[test]
+    fn test_resume_pop_all() {
+        let q = [
+            SeqQueue::new(NonZeroUsize::new(1 << 4).unwrap()),
+            SeqQueue::new_unstable(),
+        ];
+        for mut q in q {
+            q.set_next(0, |_, _| panic!());
+            for k in [1, 2, 3, 4, 6] {
+                assert_eq!(q.insert(k, k, |_, _| panic!()), SeqInsertResult::OutOfOrder);
+            }
+            let mut read = vec![];
+            let res = q.insert_pop_all(0, 0, |_, _| panic!(), read_until(2, &mut read));
+            assert_eq!(res, Some(2));
+            assert_eq!(q.next(), Some(&3));
+            assert_eq!(q.len(), 3);
+            let res = q.resume_pop_all(|_, _| panic!(), read_until(-1, &mut read));
+            assert_eq!(res, None);
+            assert_eq!(read, [0, 1, 2, 3, 4]);
+            assert_eq!(q.next(), Some(&5));
+            let res = q.resume_pop_all(|_, _| panic!(), read_until(-1, &mut read));
+            assert_eq!(res, None);
+            let res = q.insert_pop_all(5, 5, |_, _| panic!(), read_until(5, &mut read));
+            assert_eq!(res, Some(5));
+            let res = q.resume_pop_all(|_, _| panic!(), read_until(6, &mut read));
+            assert_eq!(res, Some(6));
+            assert_eq!(read, [0, 1, 2, 3, 4, 5, 6]);
+            assert_eq!(q.len(), 0);
+        }
+
+        let mut q = DenseSeqQueue::new(NonZeroUsize::new(1 << 4).unwrap());
+        q.set_next(0, |_, _| panic!());
+        for k in [1, 2, 3] {
+            assert_eq!(q.insert(k, k, |_, _| panic!()), SeqInsertResult::OutOfOrder);
+        }
+        let mut read = vec![];
+        let res = q.insert_pop_all(0, 0, |_, _| panic!(), read_until(1, &mut read));
+        assert_eq!(res, Some(1));
+        assert_eq!(q.resume_pop_all(read_until(u64::MAX, &mut read)), None);
+        assert_eq!(read, [0, 1, 2, 3]);
+        assert_eq!(q.len(), 0);
+    }
+    #[test]
+    fn test_seq_queue_out_of_window() {
+        let mut q = SeqQueue::new(NonZeroUsize::new(1 << 10).unwrap());
+        q.set_next(0, |_, _| {});
+        let mut wasted = VecWaste::new();
+        let res = q.insert(1 << 11, 0, &mut wasted);
+        assert_eq!(res.into_result(), Err(SeqInsertError::OutOfWindow));
+        assert_eq!(res.to_string(), "out of window");
+        assert_eq!(wasted.wasted, [(WasteReason::OutOfWindow, (1 << 11, 0))]);
+        assert_eq!(q.len(), 0);
+    }
+    #[test]
+    fn test_seq_queue_window_edge() {
+        let mut q = SeqQueue::new(NonZeroUsize::new(1 << 4).unwrap());
+        let win_size = q.keys.as_ref().unwrap().win.capacity();
+        let mut waste = VecWaste::new();
+        assert_eq!(q.insert(win_size, 0, &mut waste), SeqInsertResult::Stalled);
+        q.set_next(0, &mut waste);
+        let win = &q.keys.as_ref().unwrap().win;
+        assert!(win.iter().all(|bit| !bit));
+        assert_eq!(
+            q.insert(win_size, 1, &mut waste),
+            SeqInsertResult::OutOfWindow
+        );
+        assert_eq!(
+            q.insert(win_size - 1, 2, &mut waste),
+            SeqInsertResult::OutOfOrder
+        );
+        let win = &q.keys.as_ref().unwrap().win;
+        assert_eq!(win.len(), win_size);
+        assert_eq!(win.iter().filter(|bit| *bit).count(), 1);
+        assert!(win.get(win_size - 1));
+        assert_eq!(waste.wasted, [(WasteReason::OutOfWindow, (win_size, 1))]);
+    }
+    #[test]
+    fn test_dense_seq_queue() {
+        let mut q = DenseSeqQueue::new(NonZeroUsize::new(4).unwrap());
+        for k in 1..=3 {
+            assert_eq!(q.insert_pop(k, k, |_, _| {}).into_result(), Ok(None));
+        }
+        assert_eq!(q.len(), 3);
+        let mut wasted = VecWaste::new();
+        assert_eq!(q.insert(3, 33, &mut wasted), SeqInsertResult::Duplicate);
+        q.set_next(2, &mut wasted);
+        assert_eq!(q.len(), 2);
+        assert!(q.insert_pop(1, 1, &mut wasted).is_rejected());
+        assert_eq!(q.len(), 2);
+        let res = q.insert(3, 33, &mut wasted);
+        assert!(!res.is_accepted());
+        assert_eq!(res.into_result(), Err(SeqInsertError::Duplicate));
+        let res = q.insert_pop(2, 2, |_, _| {});
+        assert_eq!(res.to_string(), "in order: 2");
+        assert_eq!(res.into_result(), Ok(Some((2, 2))));
+        assert_eq!(q.len(), 1);
+        assert_eq!(q.peek().unwrap(), (&3, &3));
+        assert_eq!(q.pop().unwrap(), (3, 3));
+        assert_eq!(q.insert(8, 8, &mut wasted), SeqInsertResult::OutOfWindow);
+        assert!(q.insert_pop(6, 6, |_, _| {}).into_in_order().is_none());
+        assert!(q.insert_pop(5, 5, |_, _| {}).into_in_order().is_none());
+        assert_eq!(
+            q.insert_pop(5, 55, &mut wasted),
+            SeqInsertPopResult::Duplicate
+        );
+        assert_eq!(
+            wasted.wasted,
+            [
+                (WasteReason::Duplicate, (3, 33)),
+                (WasteReason::StaleBySetNext, (1, 1)),
+                (WasteReason::Stale, (1, 1)),
+                (WasteReason::Duplicate, (3, 33)),
+                (WasteReason::OutOfWindow, (8, 8)),
+                (WasteReason::Duplicate, (5, 55)),
+            ]
+        );
+        let mut start = 4;
+        let res: Option<()> = q.insert_pop_all(
+            4,
+            4,
+            |_, _| panic!(),
+            |(k, v)| {
+                assert_eq!(k, v);
+                assert_eq!(start, k);
+                start += 1;
+                ControlFlow::Continue(())
+            },
+        );
+        assert!(res.is_none());
+        assert_eq!(start, 7);
+        assert!(q.pop().is_none());
+        assert_eq!(q.len(), 0);
+
+        assert!(q.insert_pop(8, 8, |_, _| {}).into_in_order().is_none());
+        assert!(q.insert_pop(9, 9, |_, _| {}).into_in_order().is_none());
+        let mut wasted = VecWaste::new();
+        q.set_next(9, &mut wasted);
+        assert_eq!(wasted.wasted, [(WasteReason::StaleBySetNext, (8, 8))]);
+        assert_eq!(q.pop().unwrap(), (9, 9));
+        q.clear();
+        assert!(q.next().is_none());
+        assert_eq!(q.len(), 0);
+    }
+    #[test]
     fn test_b_tree_seq_queue() {
         let q = [BTreeSeqQueue::new()];
         for mut q in q {
-            assert!(q.insert_pop(1, 1, |_| {}).into_in_order().is_none());
-            assert!(q.insert_pop(2, 2, |_| {}).into_in_order().is_none());
-            assert!(q.insert_pop(3, 3, |_| {}).into_in_order().is_none());
+            assert!(q.insert_pop(1, 1, |_, _| {}).into_in_order().is_none());
+            assert!(q.insert_pop(2, 2, |_, _| {}).into_in_order().is_none());
+            assert!(q.insert_pop(3, 3, |_, _| {}).into_in_order().is_none());
             assert_eq!(q.len(), 3);
-            q.set_next(2, |(k, v)| {
+            q.set_next(2, |reason, (k, v)| {
+                assert_eq!(reason, WasteReason::StaleBySetNext);
                 assert_eq!(k, v);
                 assert_eq!(k, 1);
             });
             assert_eq!(q.len(), 2);
-            assert!(q.insert_pop(1, 1, |_| {}).into_in_order().is_none());
+            let mut wasted = VecWaste::new();
+            assert!(q.insert_pop(1, 1, &mut wasted).into_in_order().is_none());
             assert_eq!(q.len(), 2);
-            assert_eq!(q.insert_pop(2, 2, |_| {}).into_in_order().unwrap(), (2, 2));
+            assert_eq!(
+                q.insert_pop(2, 2, &mut wasted).into_in_order().unwrap(),
+                (2, 2)
+            );
             assert_eq!(q.len(), 1);
             assert_eq!(q.pop().unwrap(), (3, 3));
-            assert!(q.insert_pop(6, 6, |_| {}).into_in_order().is_none());
-            assert!(q.insert_pop(5, 5, |_| {}).into_in_order().is_none());
-            assert!(q.insert_pop(5, 5, |_| {}).into_in_order().is_none());
+            assert!(q.insert_pop(6, 6, |_, _| {}).into_in_order().is_none());
+            assert!(q.insert_pop(5, 5, |_, _| {}).into_in_order().is_none());
+            assert!(q.insert_pop(5, 5, &mut wasted).into_in_order().is_none());
+            assert_eq!(
+                wasted.wasted,
+                [
+                    (WasteReason::Stale, (1, 1)),
+                    (WasteReason::ReplacedDuplicateHead, (2, 2)),
+                    (WasteReason::Duplicate, (5, 5)),
+                ]
+            );
             let mut start = 4;
             let res: Option<()> = q.insert_pop_all(
                 4,
                 4,
-                |_| {},
+                |_, _| {},
                 |(k, v)| {
                     assert_eq!(k, v);
                     assert_eq!(start, k);
@@ -559,7 +1943,7 @@ mod benches {
     macro_rules! insert_pop {
         ($bencher: ident, $q: ident) => {
             $bencher.iter(|| {
-                $q.set_next(0, |_| {});
+                $q.set_next(0, |_, _| {});
                 let mut rev = false;
                 for round in 0..(N / SEG_LEN) {
                     let start = round * SEG_LEN;
@@ -570,7 +1954,7 @@ mod benches {
                         } else {
                             start + i
                         };
-                        $q.insert_pop_all(i, i, |_| {}, |_| ControlFlow::<()>::Continue(()));
+                        $q.insert_pop_all(i, i, |_, _| {}, |_| ControlFlow::<()>::Continue(()));
                     }
                     rev = !rev;
                 }
@@ -588,6 +1972,27 @@ mod benches {
         insert_pop!(bencher, q);
     }
     #[bench]
+    fn bench_insert_pop_dense_seq_queue(bencher: &mut Bencher) {
+        let mut q = DenseSeqQueue::new(NonZeroUsize::new(WINDOW_SIZE).unwrap());
+        bencher.iter(|| {
+            q.set_next(0, |_, _| {});
+            let mut rev = false;
+            for round in 0..(N / SEG_LEN) {
+                let start = (round * SEG_LEN) as u64;
+                assert_eq!(*q.next().unwrap(), start);
+                for i in 0..SEG_LEN as u64 {
+                    let i = if rev {
+                        start + (SEG_LEN as u64 - 1 - i)
+                    } else {
+                        start + i
+                    };
+                    q.insert_pop_all(i, i, |_, _| {}, |_| ControlFlow::<()>::Continue(()));
+                }
+                rev = !rev;
+            }
+        });
+    }
+    #[bench]
     fn bench_insert_pop_b_tree(bencher: &mut Bencher) {
         let mut q = BTreeSeqQueue::new();
         insert_pop!(bencher, q);
@@ -605,7 +2010,7 @@ mod benches {
     }
     fn insert_then_pop_unstable_seq_queue(bencher: &mut Bencher, q: &mut SeqQueue<usize, usize>) {
         bencher.iter(|| {
-            q.set_next(0, |_| {});
+            q.set_next(0, |_, _| {});
             let mut rev = false;
             for round in 0..(N / SEG_LEN) {
                 let start = round * SEG_LEN;
@@ -616,9 +2021,31 @@ mod benches {
                     } else {
                         start + i
                     };
-                    let _ = q.insert(i, i, |_| {});
+                    let _ = q.insert(i, i, |_, _| {});
                 }
-                while q.pop(|_| {}).is_some() {}
+                while q.pop(|_, _| {}).is_some() {}
+                rev = !rev;
+            }
+        });
+    }
+    #[bench]
+    fn bench_insert_then_pop_dense_seq_queue(bencher: &mut Bencher) {
+        let mut q = DenseSeqQueue::new(NonZeroUsize::new(WINDOW_SIZE).unwrap());
+        bencher.iter(|| {
+            q.set_next(0, |_, _| {});
+            let mut rev = false;
+            for round in 0..(N / SEG_LEN) {
+                let start = (round * SEG_LEN) as u64;
+                assert_eq!(*q.next().unwrap(), start);
+                for i in 0..SEG_LEN as u64 {
+                    let i = if rev {
+                        start + (SEG_LEN as u64 - 1 - i)
+                    } else {
+                        start + i
+                    };
+                    let _ = q.insert(i, i, |_, _| {});
+                }
+                while q.pop().is_some() {}
                 rev = !rev;
             }
         });
@@ -627,7 +2054,7 @@ mod benches {
     fn bench_insert_then_pop_b_tree(bencher: &mut Bencher) {
         let mut q = BTreeSeqQueue::new();
         bencher.iter(|| {
-            q.set_next(0, |_| {});
+            q.set_next(0, |_, _| {});
             let mut rev = false;
             for round in 0..(N / SEG_LEN) {
                 let start = round * SEG_LEN;
@@ -638,7 +2065,7 @@ mod benches {
                     } else {
                         start + i
                     };
-                    let _ = q.insert(i, i, |_| {});
+                    let _ = q.insert(i, i, |_, _| {});
                 }
                 while q.pop().is_some() {}
                 rev = !rev;
@@ -0,0 +1,301 @@
+//! Property tests driving the sequence queues with random actions against a reference model
+
+use core::{cell::RefCell, num::NonZeroUsize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    rc::Rc,
+};
+
+use crate::ops::{clear::Clear, len::Len};
+
+use super::seq_queue::{BTreeSeqQueue, DenseSeqQueue, SeqQueue, WasteReason};
+
+const STEPS: usize = if cfg!(miri) { 1 << 7 } else { 1 << 13 };
+const SEEDS: u64 = if cfg!(miri) { 2 } else { 16 };
+const WINDOW: usize = 1 << 3;
+/// Leave room below for stale keys
+const BASE_KEY: u64 = 1 << 10;
+
+#[derive(Debug, Clone, Copy)]
+enum Action {
+    Insert(u64),
+    InsertPop(u64),
+    SetNext(u64),
+    Pop,
+    Clear,
+}
+
+struct XorShift(u64);
+impl XorShift {
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+    fn action(&mut self, next: Option<u64>, last_next: u64) -> Action {
+        let base = next.unwrap_or(last_next);
+        // Mostly around the head with some stale and some out-of-window keys
+        let key = base + self.below(WINDOW as u64 * 2) - self.below(3).min(base);
+        match self.below(32) {
+            0..=11 => Action::Insert(key),
+            12..=23 => Action::InsertPop(key),
+            24..=27 => Action::Pop,
+            28..=30 => Action::SetNext(base + self.below(WINDOW as u64) - self.below(2)),
+            _ => Action::Clear,
+        }
+    }
+}
+
+/// Records its id on drop unless handed back to the harness through [`Self::into_parts()`]
+#[derive(Debug)]
+struct Token {
+    id: u64,
+    key: u64,
+    dropped: Option<Rc<RefCell<Vec<u64>>>>,
+}
+impl Token {
+    fn into_parts(mut self) -> (u64, u64) {
+        self.dropped = None;
+        (self.id, self.key)
+    }
+}
+impl Drop for Token {
+    fn drop(&mut self) {
+        if let Some(dropped) = &self.dropped {
+            dropped.borrow_mut().push(self.id);
+        }
+    }
+}
+
+trait Harnessed: Len + Clear {
+    fn next(&self) -> Option<u64>;
+    fn insert(&mut self, key: u64, value: Token, waste: &mut dyn FnMut(WasteReason, (u64, Token)));
+    fn insert_pop(
+        &mut self,
+        key: u64,
+        value: Token,
+        waste: &mut dyn FnMut(WasteReason, (u64, Token)),
+    ) -> Option<(u64, Token)>;
+    fn set_next(&mut self, next: u64, waste: &mut dyn FnMut(WasteReason, (u64, Token)));
+    fn pop(&mut self, waste: &mut dyn FnMut(WasteReason, (u64, Token))) -> Option<(u64, Token)>;
+}
+impl Harnessed for SeqQueue<u64, Token> {
+    fn next(&self) -> Option<u64> {
+        SeqQueue::next(self).copied()
+    }
+    fn insert(&mut self, key: u64, value: Token, waste: &mut dyn FnMut(WasteReason, (u64, Token))) {
+        let _ = SeqQueue::insert(self, key, value, waste);
+    }
+    fn insert_pop(
+        &mut self,
+        key: u64,
+        value: Token,
+        waste: &mut dyn FnMut(WasteReason, (u64, Token)),
+    ) -> Option<(u64, Token)> {
+        SeqQueue::insert_pop(self, key, value, waste).into_in_order()
+    }
+    fn set_next(&mut self, next: u64, waste: &mut dyn FnMut(WasteReason, (u64, Token))) {
+        SeqQueue::set_next(self, next, waste);
+    }
+    fn pop(&mut self, waste: &mut dyn FnMut(WasteReason, (u64, Token))) -> Option<(u64, Token)> {
+        SeqQueue::pop(self, waste)
+    }
+}
+impl Harnessed for BTreeSeqQueue<u64, Token> {
+    fn next(&self) -> Option<u64> {
+        BTreeSeqQueue::next(self).copied()
+    }
+    fn insert(&mut self, key: u64, value: Token, waste: &mut dyn FnMut(WasteReason, (u64, Token))) {
+        let _ = BTreeSeqQueue::insert(self, key, value, waste);
+    }
+    fn insert_pop(
+        &mut self,
+        key: u64,
+        value: Token,
+        waste: &mut dyn FnMut(WasteReason, (u64, Token)),
+    ) -> Option<(u64, Token)> {
+        BTreeSeqQueue::insert_pop(self, key, value, waste).into_in_order()
+    }
+    fn set_next(&mut self, next: u64, waste: &mut dyn FnMut(WasteReason, (u64, Token))) {
+        BTreeSeqQueue::set_next(self, next, waste);
+    }
+    fn pop(&mut self, _waste: &mut dyn FnMut(WasteReason, (u64, Token))) -> Option<(u64, Token)> {
+        BTreeSeqQueue::pop(self)
+    }
+}
+impl Harnessed for DenseSeqQueue<Token> {
+    fn next(&self) -> Option<u64> {
+        DenseSeqQueue::next(self).copied()
+    }
+    fn insert(&mut self, key: u64, value: Token, waste: &mut dyn FnMut(WasteReason, (u64, Token))) {
+        let _ = DenseSeqQueue::insert(self, key, value, waste);
+    }
+    fn insert_pop(
+        &mut self,
+        key: u64,
+        value: Token,
+        waste: &mut dyn FnMut(WasteReason, (u64, Token)),
+    ) -> Option<(u64, Token)> {
+        DenseSeqQueue::insert_pop(self, key, value, waste).into_in_order()
+    }
+    fn set_next(&mut self, next: u64, waste: &mut dyn FnMut(WasteReason, (u64, Token))) {
+        DenseSeqQueue::set_next(self, next, waste);
+    }
+    fn pop(&mut self, _waste: &mut dyn FnMut(WasteReason, (u64, Token))) -> Option<(u64, Token)> {
+        DenseSeqQueue::pop(self)
+    }
+}
+
+/// What the queue must have done so far regardless of its dedup strategy
+#[derive(Debug, Default)]
+struct Model {
+    next: Option<u64>,
+    /// Keys of the ids inserted and not yet delivered, wasted or cleared
+    outstanding: BTreeMap<u64, u64>,
+    /// Every id that has left the queue
+    settled: BTreeSet<u64>,
+}
+impl Model {
+    fn settle(&mut self, id: u64) {
+        assert!(
+            self.outstanding.remove(&id).is_some(),
+            "{id} is not outstanding"
+        );
+        assert!(self.settled.insert(id), "{id} settled twice");
+    }
+    fn deliver(&mut self, (key, token): (u64, Token)) {
+        let (id, token_key) = token.into_parts();
+        assert_eq!(key, token_key);
+        // Contiguous: every delivery is exactly the expected next key
+        assert_eq!(self.next, Some(key));
+        self.next = key.checked_add(1);
+        self.settle(id);
+    }
+    fn waste(&mut self, reason: WasteReason, (key, token): (u64, Token), next: Option<u64>) {
+        let (id, token_key) = token.into_parts();
+        assert_eq!(key, token_key);
+        match reason {
+            WasteReason::Stale | WasteReason::StaleBySetNext => {
+                assert!(next.is_some_and(|next| key < next));
+            }
+            WasteReason::OutOfWindow => assert!(next.is_some_and(|next| next < key)),
+            WasteReason::Duplicate | WasteReason::ReplacedDuplicateHead => {
+                let twin = self.outstanding.iter().any(|(&i, &k)| i != id && k == key);
+                assert!(twin, "{key} has no twin");
+            }
+        }
+        self.settle(id);
+    }
+}
+
+fn run<Q: Harnessed>(mut q: Q, seed: u64) {
+    let dropped = Rc::new(RefCell::new(vec![]));
+    let mut rng = XorShift(seed);
+    let mut model = Model::default();
+    let mut last_next = BASE_KEY;
+    let mut max_key = BASE_KEY;
+    let new_token = |model: &mut Model, key: u64| {
+        let id = (model.outstanding.len() + model.settled.len()) as u64;
+        model.outstanding.insert(id, key);
+        Token {
+            id,
+            key,
+            dropped: Some(Rc::clone(&dropped)),
+        }
+    };
+    for _ in 0..STEPS {
+        let action = rng.action(model.next, last_next);
+        let mut wasted = vec![];
+        let mut waste = |reason, entry| wasted.push((reason, entry));
+        let next_before = q.next();
+        let mut delivered = vec![];
+        match action {
+            Action::Insert(key) => {
+                max_key = max_key.max(key);
+                let token = new_token(&mut model, key);
+                q.insert(key, token, &mut waste);
+            }
+            Action::InsertPop(key) => {
+                max_key = max_key.max(key);
+                let token = new_token(&mut model, key);
+                delivered.extend(q.insert_pop(key, token, &mut waste));
+            }
+            Action::SetNext(next) => {
+                q.set_next(next, &mut waste);
+                model.next = Some(next);
+                last_next = next;
+            }
+            Action::Pop => delivered.extend(q.pop(&mut waste)),
+            Action::Clear => {
+                q.clear();
+                model.next = None;
+                for id in dropped.borrow_mut().drain(..) {
+                    model.settle(id);
+                }
+            }
+        }
+        // The waste reasons are relative to the head before the action
+        let waste_next = match action {
+            Action::SetNext(next) => Some(next),
+            _ => next_before,
+        };
+        for (reason, entry) in wasted {
+            model.waste(reason, entry, waste_next);
+        }
+        for entry in delivered {
+            model.deliver(entry);
+        }
+        assert!(dropped.borrow().is_empty(), "{action:?} lost entries");
+        assert_eq!(q.next(), model.next, "{action:?}");
+        assert_eq!(q.len(), model.outstanding.len(), "{action:?}");
+        if let Some(next) = model.next {
+            last_next = next;
+        }
+    }
+    // Everything left is either delivered in order or wasted by jumping past it
+    let mut wasted = vec![];
+    let drain_from = model.next.unwrap_or(BASE_KEY);
+    q.set_next(drain_from, &mut |reason, entry| {
+        wasted.push((reason, entry))
+    });
+    model.next = Some(drain_from);
+    for (reason, entry) in wasted.drain(..) {
+        model.waste(reason, entry, Some(drain_from));
+    }
+    while let Some(entry) = q.pop(&mut |reason, entry| wasted.push((reason, entry))) {
+        model.deliver(entry);
+    }
+    let end = max_key + 1;
+    q.set_next(end, &mut |reason, entry| wasted.push((reason, entry)));
+    for (reason, entry) in wasted {
+        model.waste(reason, entry, Some(end));
+    }
+    assert!(model.outstanding.is_empty());
+    assert_eq!(q.len(), 0);
+    assert!(dropped.borrow().is_empty());
+}
+
+#[test]
+fn test_seq_queue_model() {
+    for seed in 1..=SEEDS {
+        run(SeqQueue::new(NonZeroUsize::new(WINDOW).unwrap()), seed);
+    }
+}
+
+#[test]
+fn test_b_tree_seq_queue_model() {
+    for seed in 1..=SEEDS {
+        run(BTreeSeqQueue::new(), seed);
+    }
+}
+
+#[test]
+fn test_dense_seq_queue_model() {
+    for seed in 1..=SEEDS {
+        run(DenseSeqQueue::new(NonZeroUsize::new(WINDOW).unwrap()), seed);
+    }
+}
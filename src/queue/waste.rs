@@ -0,0 +1,285 @@
+use crate::analysis::metrics::{MetricSource, MetricVisitor};
+
+use super::seq_queue::WasteReason;
+
+/// Takes the entries a queue drops instead of keeping or popping them
+///
+/// Any `FnMut(WasteReason, (K, V))` is a sink.
+pub trait WasteSink<K, V> {
+    fn waste(&mut self, reason: WasteReason, kv: (K, V));
+}
+impl<K, V, F> WasteSink<K, V> for F
+where
+    F: FnMut(WasteReason, (K, V)),
+{
+    fn waste(&mut self, reason: WasteReason, kv: (K, V)) {
+        self(reason, kv);
+    }
+}
+
+/// Looks at the wasted entries without taking them
+pub trait WasteInspect<K, V> {
+    fn inspect(&mut self, reason: WasteReason, kv: &(K, V));
+}
+impl<K, V, I: WasteInspect<K, V>> WasteInspect<K, V> for &mut I {
+    fn inspect(&mut self, reason: WasteReason, kv: &(K, V)) {
+        I::inspect(self, reason, kv);
+    }
+}
+
+/// Combinators free of `K` and `V` so that the sinks generic over them need no annotations
+pub trait WasteSinkExt: Sized {
+    /// Show each entry to `self` before handing it to `next`
+    ///
+    /// A [`WasteSink`] if `self` is a [`WasteInspect`] and `next` is a [`WasteSink`].
+    fn and_then<S>(self, next: S) -> AndThen<Self, S> {
+        AndThen { first: self, next }
+    }
+    /// Lend `self` to a callee that takes the sink by value
+    fn by_mut(&mut self) -> ByMut<'_, Self> {
+        ByMut(self)
+    }
+}
+impl<T> WasteSinkExt for T {}
+
+/// Implement [`WasteSink`] for `&mut` the sink too so that the caller can still read it afterwards
+macro_rules! impl_sink_by_mut {
+    ([$($generics: tt)*] $ty: ty) => {
+        impl<$($generics)*> WasteSink<K, V> for &mut $ty
+        where
+            $ty: WasteSink<K, V>,
+        {
+            fn waste(&mut self, reason: WasteReason, kv: (K, V)) {
+                <$ty as WasteSink<K, V>>::waste(self, reason, kv);
+            }
+        }
+    };
+}
+
+/// Made by [`WasteSinkExt::by_mut()`]
+#[derive(Debug)]
+pub struct ByMut<'a, S>(&'a mut S);
+impl<K, V, S: WasteSink<K, V>> WasteSink<K, V> for ByMut<'_, S> {
+    fn waste(&mut self, reason: WasteReason, kv: (K, V)) {
+        self.0.waste(reason, kv);
+    }
+}
+
+/// Made by [`WasteSinkExt::and_then()`]
+#[derive(Debug, Clone)]
+pub struct AndThen<A, B> {
+    first: A,
+    next: B,
+}
+impl<A, B> AndThen<A, B> {
+    pub fn into_parts(self) -> (A, B) {
+        (self.first, self.next)
+    }
+}
+impl<K, V, A, B> WasteSink<K, V> for AndThen<A, B>
+where
+    A: WasteInspect<K, V>,
+    B: WasteSink<K, V>,
+{
+    fn waste(&mut self, reason: WasteReason, kv: (K, V)) {
+        self.first.inspect(reason, &kv);
+        self.next.waste(reason, kv);
+    }
+}
+impl<K, V, A, B> WasteInspect<K, V> for AndThen<A, B>
+where
+    A: WasteInspect<K, V>,
+    B: WasteInspect<K, V>,
+{
+    fn inspect(&mut self, reason: WasteReason, kv: &(K, V)) {
+        self.first.inspect(reason, kv);
+        self.next.inspect(reason, kv);
+    }
+}
+impl_sink_by_mut!([K, V, A, B] AndThen<A, B>);
+
+/// [`WasteInspect`] out of a closure
+#[derive(Debug, Clone)]
+pub struct InspectWaste<F>(pub F);
+impl<K, V, F> WasteInspect<K, V> for InspectWaste<F>
+where
+    F: FnMut(WasteReason, &(K, V)),
+{
+    fn inspect(&mut self, reason: WasteReason, kv: &(K, V)) {
+        (self.0)(reason, kv);
+    }
+}
+
+/// Counts the entries per [`WasteReason`] and drops them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CountWaste {
+    counts: [usize; WasteReason::ALL.len()],
+}
+impl CountWaste {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    #[must_use]
+    pub fn get(&self, reason: WasteReason) -> usize {
+        self.counts[reason as usize]
+    }
+    #[must_use]
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+}
+impl MetricSource for CountWaste {
+    fn visit(&self, visitor: &mut dyn MetricVisitor) {
+        for reason in WasteReason::ALL {
+            let name = match reason {
+                WasteReason::Stale => "stale_total",
+                WasteReason::OutOfWindow => "out_of_window_total",
+                WasteReason::Duplicate => "duplicate_total",
+                WasteReason::StaleBySetNext => "stale_by_set_next_total",
+                WasteReason::ReplacedDuplicateHead => "replaced_duplicate_head_total",
+            };
+            visitor.counter(name, self.get(reason) as u64);
+        }
+    }
+}
+impl<K, V> WasteInspect<K, V> for CountWaste {
+    fn inspect(&mut self, reason: WasteReason, _: &(K, V)) {
+        self.counts[reason as usize] += 1;
+    }
+}
+impl<K, V> WasteSink<K, V> for CountWaste {
+    fn waste(&mut self, reason: WasteReason, kv: (K, V)) {
+        self.inspect(reason, &kv);
+    }
+}
+impl_sink_by_mut!([K, V] CountWaste);
+
+/// Drops the entries
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DropWaste;
+impl<K, V> WasteSink<K, V> for DropWaste {
+    fn waste(&mut self, _: WasteReason, _: (K, V)) {}
+}
+
+/// Drops the keys and hands the values to `R`, e.g. to put their buffers back into a pool
+#[derive(Debug, Clone)]
+pub struct RecycleWaste<R> {
+    recycle: R,
+}
+impl<R> RecycleWaste<R> {
+    #[must_use]
+    pub fn new(recycle: R) -> Self {
+        Self { recycle }
+    }
+}
+impl<K, V, R: FnMut(V)> WasteSink<K, V> for RecycleWaste<R> {
+    fn waste(&mut self, _: WasteReason, (_, value): (K, V)) {
+        (self.recycle)(value);
+    }
+}
+impl_sink_by_mut!([K, V, R] RecycleWaste<R>);
+
+/// Keeps every entry with its reason in order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VecWaste<K, V> {
+    pub wasted: Vec<(WasteReason, (K, V))>,
+}
+impl<K, V> VecWaste<K, V> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self { wasted: vec![] }
+    }
+    pub fn keys(&self) -> impl Iterator<Item = &K> + '_ {
+        self.wasted.iter().map(|(_, (key, _))| key)
+    }
+}
+impl<K, V> Default for VecWaste<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<K, V> WasteSink<K, V> for VecWaste<K, V> {
+    fn waste(&mut self, reason: WasteReason, kv: (K, V)) {
+        self.wasted.push((reason, kv));
+    }
+}
+impl_sink_by_mut!([K, V] VecWaste<K, V>);
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::RefCell, num::NonZeroUsize};
+
+    use crate::queue::seq_queue::{SeqInsertResult, SeqQueue};
+
+    use super::*;
+
+    #[test]
+    fn test_and_then_order() {
+        let log = RefCell::new(vec![]);
+        let mut recycled = vec![];
+        let mut sink = CountWaste::new()
+            .and_then(InspectWaste(|_, kv: &(u32, char)| {
+                log.borrow_mut().push(("first", kv.0))
+            }))
+            .and_then(InspectWaste(|_, kv: &(u32, char)| {
+                log.borrow_mut().push(("second", kv.0))
+            }))
+            .and_then(RecycleWaste::new(|value| recycled.push(value)));
+        sink.waste(WasteReason::Stale, (1, 'a'));
+        sink.waste(WasteReason::Duplicate, (2, 'b'));
+        let (inspectors, _) = sink.into_parts();
+        let (inspectors, _) = inspectors.into_parts();
+        let (count, _) = inspectors.into_parts();
+        // Every inspector sees an entry before the last sink takes it
+        assert_eq!(
+            log.into_inner(),
+            [("first", 1), ("second", 1), ("first", 2), ("second", 2)]
+        );
+        assert_eq!(recycled, ['a', 'b']);
+        assert_eq!(count.total(), 2);
+        assert_eq!(count.get(WasteReason::Duplicate), 1);
+        assert_eq!(count.get(WasteReason::OutOfWindow), 0);
+    }
+
+    #[test]
+    fn test_borrowed_sinks() {
+        let mut q = SeqQueue::new(NonZeroUsize::new(4).unwrap());
+        q.set_next(1_u32, DropWaste);
+        let mut count = CountWaste::new();
+        let mut wasted = VecWaste::new();
+        let mut sink = (&mut count).and_then(&mut wasted);
+        assert_eq!(q.insert(0, 'a', &mut sink), SeqInsertResult::Stale);
+        assert_eq!(q.insert(2, 'b', &mut sink), SeqInsertResult::OutOfOrder);
+        assert_eq!(q.insert(2, 'c', &mut sink), SeqInsertResult::Duplicate);
+        assert_eq!(
+            q.insert(1 << 10, 'd', sink.by_mut()),
+            SeqInsertResult::OutOfWindow
+        );
+        assert_eq!(count.get(WasteReason::Stale), 1);
+        assert_eq!(count.total(), 3);
+        assert_eq!(
+            wasted.wasted,
+            [
+                (WasteReason::Stale, (0, 'a')),
+                (WasteReason::Duplicate, (2, 'c')),
+                (WasteReason::OutOfWindow, (1 << 10, 'd')),
+            ]
+        );
+        assert!(wasted.keys().eq(&[0, 2, 1 << 10]));
+    }
+
+    #[test]
+    fn test_closure_compat() {
+        let mut q = SeqQueue::new(NonZeroUsize::new(4).unwrap());
+        q.set_next(1_u32, |_, _| {});
+        let mut wasted = vec![];
+        let mut waste = |reason, kv| wasted.push((reason, kv));
+        let _ = q.insert(0, (), &mut waste);
+        let _ = q.insert(0, (), &mut waste as &mut dyn FnMut(WasteReason, (u32, ())));
+        let mut by_value = |_, _| {};
+        let _ = q.insert(0, (), by_value.by_mut());
+        assert_eq!(wasted.len(), 2);
+        assert_eq!(q.pop(|_, _| unreachable!()), None);
+    }
+}
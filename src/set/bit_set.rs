@@ -1,3 +1,5 @@
+use core::ops::Range;
+
 use crate::ops::{clear::Clear, len::Len};
 
 const BITS_PER_BYTE: usize = 8;
@@ -18,46 +20,47 @@ impl BitSet {
             count: 0,
         }
     }
+    #[inline]
     #[must_use]
     pub fn capacity(&self) -> usize {
         self.words.len() * USIZE_BITS
     }
 
+    #[inline]
     #[must_use]
     pub fn get(&self, index: usize) -> bool {
-        let word = self.words[word_index(index)];
-        let pos = 1 << bit_offset(index);
-        let is_empty = word & pos == 0;
-        !is_empty
-    }
-    fn bit_op(&mut self, bit_index: usize, op: impl Fn(BitOpArgs) -> usize) {
-        let word = &mut self.words[word_index(bit_index)];
-        let prev = word.count_ones();
-        let pos = 1 << bit_offset(bit_index);
-        let args = BitOpArgs { word: *word, pos };
-        *word = op(args);
-        let curr = word.count_ones();
-        match prev.cmp(&curr) {
-            core::cmp::Ordering::Less => self.count += usize::try_from(curr - prev).unwrap(),
-            core::cmp::Ordering::Equal => (),
-            core::cmp::Ordering::Greater => self.count -= usize::try_from(prev - curr).unwrap(),
-        }
+        get(&self.words, index)
+    }
+    /// The bits in `range` packed from the LSB
+    ///
+    /// # Panic
+    ///
+    /// `range` is longer than 64 bits or out of [`Self::capacity()`]
+    #[must_use]
+    pub fn get_bits(&self, range: Range<usize>) -> u64 {
+        get_bits(&self.words, range)
     }
+    #[inline]
     pub fn set(&mut self, index: usize) {
-        self.bit_op(index, |args| args.word | args.pos);
+        bit_op(&mut self.words, &mut self.count, index, |args| {
+            args.word | args.pos
+        });
     }
+    #[inline]
     pub fn clear_bit(&mut self, index: usize) {
-        self.bit_op(index, |args| args.word & !args.pos);
+        bit_op(&mut self.words, &mut self.count, index, |args| {
+            args.word & !args.pos
+        });
     }
+    #[inline]
     pub fn toggle(&mut self, index: usize) {
-        self.bit_op(index, |args| args.word ^ args.pos);
+        bit_op(&mut self.words, &mut self.count, index, |args| {
+            args.word ^ args.pos
+        });
     }
 }
-struct BitOpArgs {
-    pub word: usize,
-    pub pos: usize,
-}
 impl Len for BitSet {
+    #[inline]
     fn len(&self) -> usize {
         self.count
     }
@@ -69,15 +72,188 @@ impl Clear for BitSet {
     }
 }
 
+/// [`BitSet`] of `WORDS` machine words held inline so that it can be built in a `static`
+#[derive(Debug, Clone)]
+pub struct StaticBitSet<const WORDS: usize> {
+    words: [usize; WORDS],
+    count: usize,
+}
+impl<const WORDS: usize> StaticBitSet<WORDS> {
+    pub const CAPACITY: usize = WORDS * USIZE_BITS;
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            words: [0; WORDS],
+            count: 0,
+        }
+    }
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        Self::CAPACITY
+    }
+    #[inline]
+    #[must_use]
+    pub fn get(&self, index: usize) -> bool {
+        get(&self.words, index)
+    }
+    /// Same as [`BitSet::get_bits()`]
+    #[must_use]
+    pub fn get_bits(&self, range: Range<usize>) -> u64 {
+        get_bits(&self.words, range)
+    }
+    #[inline]
+    pub fn set(&mut self, index: usize) {
+        bit_op(&mut self.words, &mut self.count, index, |args| {
+            args.word | args.pos
+        });
+    }
+    #[inline]
+    pub fn clear_bit(&mut self, index: usize) {
+        bit_op(&mut self.words, &mut self.count, index, |args| {
+            args.word & !args.pos
+        });
+    }
+    #[inline]
+    pub fn toggle(&mut self, index: usize) {
+        bit_op(&mut self.words, &mut self.count, index, |args| {
+            args.word ^ args.pos
+        });
+    }
+}
+impl<const WORDS: usize> Default for StaticBitSet<WORDS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<const WORDS: usize> Len for StaticBitSet<WORDS> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.count
+    }
+}
+impl<const WORDS: usize> Clear for StaticBitSet<WORDS> {
+    fn clear(&mut self) {
+        self.words = [0; WORDS];
+        self.count = 0;
+    }
+}
+
+/// The storage of the bits of a [`crate::queue::cap_queue::BitQueue`]
+pub trait BitStore: Len + Clear {
+    #[must_use]
+    fn capacity(&self) -> usize;
+    #[must_use]
+    fn get(&self, index: usize) -> bool;
+    #[must_use]
+    fn get_bits(&self, range: Range<usize>) -> u64;
+    fn set(&mut self, index: usize);
+    fn clear_bit(&mut self, index: usize);
+}
+impl BitStore for BitSet {
+    fn capacity(&self) -> usize {
+        self.capacity()
+    }
+    fn get(&self, index: usize) -> bool {
+        self.get(index)
+    }
+    fn get_bits(&self, range: Range<usize>) -> u64 {
+        self.get_bits(range)
+    }
+    fn set(&mut self, index: usize) {
+        self.set(index);
+    }
+    fn clear_bit(&mut self, index: usize) {
+        self.clear_bit(index);
+    }
+}
+impl<const WORDS: usize> BitStore for StaticBitSet<WORDS> {
+    fn capacity(&self) -> usize {
+        self.capacity()
+    }
+    fn get(&self, index: usize) -> bool {
+        self.get(index)
+    }
+    fn get_bits(&self, range: Range<usize>) -> u64 {
+        self.get_bits(range)
+    }
+    fn set(&mut self, index: usize) {
+        self.set(index);
+    }
+    fn clear_bit(&mut self, index: usize) {
+        self.clear_bit(index);
+    }
+}
+
+#[inline]
+#[must_use]
+fn get(words: &[usize], index: usize) -> bool {
+    let word = words[word_index(index)];
+    let pos = 1 << bit_offset(index);
+    let is_empty = word & pos == 0;
+    !is_empty
+}
+#[must_use]
+fn get_bits(words: &[usize], range: Range<usize>) -> u64 {
+    assert!(range.len() <= u64::BITS as usize);
+    let mut bits = 0;
+    let mut filled = 0;
+    let mut index = range.start;
+    while index < range.end {
+        let offset = bit_offset(index);
+        let take = (USIZE_BITS - offset).min(range.end - index);
+        let chunk = (words[word_index(index)] >> offset) as u64 & low_mask(take);
+        bits |= chunk << filled;
+        filled += take;
+        index += take;
+    }
+    bits
+}
+/// Apply `op` to the word of `bit_index` and keep `count` in sync
+#[inline]
+fn bit_op(
+    words: &mut [usize],
+    count: &mut usize,
+    bit_index: usize,
+    op: impl Fn(BitOpArgs) -> usize,
+) {
+    let word = &mut words[word_index(bit_index)];
+    let prev = word.count_ones();
+    let pos = 1 << bit_offset(bit_index);
+    let args = BitOpArgs { word: *word, pos };
+    *word = op(args);
+    let curr = word.count_ones();
+    match prev.cmp(&curr) {
+        core::cmp::Ordering::Less => *count += usize::try_from(curr - prev).unwrap(),
+        core::cmp::Ordering::Equal => (),
+        core::cmp::Ordering::Greater => *count -= usize::try_from(prev - curr).unwrap(),
+    }
+}
+struct BitOpArgs {
+    pub word: usize,
+    pub pos: usize,
+}
+
+#[inline]
 #[must_use]
 const fn word_index(bit_index: usize) -> usize {
     bit_index / USIZE_BITS
 }
+#[inline]
 #[must_use]
 const fn bit_offset(bit_index: usize) -> usize {
     bit_index % USIZE_BITS
 }
 
+#[inline]
+#[must_use]
+const fn low_mask(bits: usize) -> u64 {
+    match bits {
+        64.. => u64::MAX,
+        _ => (1 << bits) - 1,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::ops::len::LenExt;
@@ -96,4 +272,49 @@ mod tests {
         assert!(b.get(15));
         assert_eq!(b.len(), 2);
     }
+
+    #[test]
+    fn test_get_bits() {
+        let mut b = BitSet::new(200);
+        for i in [0, 3, 63, 64, 70, 127, 128, 199] {
+            b.set(i);
+        }
+        assert_eq!(b.get_bits(0..4), 0b1001);
+        assert_eq!(b.get_bits(0..0), 0);
+        assert_eq!(b.get_bits(62..66), 0b0110);
+        assert_eq!(b.get_bits(64..128), 1 | 1 << 6 | 1 << 63);
+        assert_eq!(b.get_bits(127..191), 0b11);
+        for start in 0..136 {
+            let bits = b.get_bits(start..start + 64);
+            for i in 0..64 {
+                assert_eq!(bits >> i & 1 == 1, b.get(start + i));
+            }
+        }
+    }
+
+    #[test]
+    fn test_static_bit_set() {
+        const EMPTY: StaticBitSet<4> = StaticBitSet::new();
+        let mut a = EMPTY;
+        let mut b = BitSet::new(a.capacity());
+        assert_eq!(a.capacity(), b.capacity());
+        for i in [0, 3, 63, 64, 70, 127, 128, 199, 3] {
+            a.toggle(i);
+            b.toggle(i);
+        }
+        a.set(5);
+        b.set(5);
+        a.clear_bit(0);
+        b.clear_bit(0);
+        assert_eq!(a.len(), b.len());
+        for i in 0..a.capacity() {
+            assert_eq!(a.get(i), b.get(i));
+        }
+        for start in 0..a.capacity() - 64 {
+            assert_eq!(a.get_bits(start..start + 64), b.get_bits(start..start + 64));
+        }
+        a.clear();
+        assert!(a.is_empty());
+        assert!(!a.get(70));
+    }
 }
@@ -0,0 +1,61 @@
+//! Atomics and cells of either `core` or [loom](https://docs.rs/loom) under `--cfg loom`
+//!
+//! Run only the loom tests under loom:
+//!
+//! ```sh
+//! LOOM_MAX_PREEMPTIONS=3 RUSTFLAGS="--cfg loom" cargo test --release loom_
+//! ```
+//!
+//! Without the bound on the preemptions, the models of [`super::mcast`] take many minutes each.
+//!
+//! The value of [`super::seq_lock::SeqLock`] stays in [`super::sync_unsafe_cell::SyncUnsafeCell`] since its optimistic reads race with the writer by design;
+//! under loom, the reads go through a record of the stores instead.
+
+#[cfg(not(loom))]
+pub(crate) use core::{
+    hint::spin_loop,
+    sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicU32, AtomicU64, AtomicUsize, Ordering},
+};
+#[cfg(loom)]
+pub(crate) use loom::cell::UnsafeCell;
+#[cfg(loom)]
+pub(crate) use loom::{
+    sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicU32, AtomicU64, AtomicUsize, Ordering},
+    thread::yield_now as spin_loop,
+};
+
+/// [`core::cell::UnsafeCell`] behind the accessors of `loom::cell::UnsafeCell`, which checks every access for races under loom
+#[cfg(not(loom))]
+#[derive(Debug)]
+#[repr(transparent)]
+pub(crate) struct UnsafeCell<T: ?Sized>(core::cell::UnsafeCell<T>);
+#[cfg(not(loom))]
+impl<T> UnsafeCell<T> {
+    pub(crate) const fn new(value: T) -> Self {
+        Self(core::cell::UnsafeCell::new(value))
+    }
+}
+#[cfg(not(loom))]
+impl<T: ?Sized> UnsafeCell<T> {
+    /// `f` must not race with a [`Self::with_mut()`]
+    pub(crate) fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+        f(self.0.get())
+    }
+    /// `f` must not race with any other access
+    pub(crate) fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}
+
+/// `const fn` unless the loom atomics, which cannot be constructed in const contexts, are in use
+macro_rules! const_unless_loom {
+    ($(#[$attr: meta])* $vis: vis fn $($rest: tt)*) => {
+        #[cfg(not(loom))]
+        $(#[$attr])*
+        $vis const fn $($rest)*
+        #[cfg(loom)]
+        $(#[$attr])*
+        $vis fn $($rest)*
+    };
+}
+pub(crate) use const_unless_loom;
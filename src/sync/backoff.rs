@@ -0,0 +1,138 @@
+use std::time::{Duration, Instant};
+
+use super::atomic_shim::spin_loop;
+
+/// Longest spin step is `1 << MAX_SPIN_SHIFT` hints
+const MAX_SPIN_SHIFT: u32 = 10;
+
+/// Ladder of [`Backoff`]: spin, then yield, then sleep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackoffConfig {
+    /// Spin steps, each twice as long as the previous one, starting at one hint
+    pub spins: u32,
+    /// Steps yielding the thread after the spins
+    pub yields: u32,
+    /// Length of every step after the yields
+    pub sleep: Duration,
+}
+impl Default for BackoffConfig {
+    fn default() -> Self {
+        Self {
+            spins: 6,
+            yields: 10,
+            sleep: Duration::from_micros(100),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackoffStep {
+    /// Number of spin-loop hints
+    Spin(u32),
+    Yield,
+    Sleep(Duration),
+}
+
+/// Escalating wait between retries of a spinning loop
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    config: BackoffConfig,
+    step: u32,
+}
+impl Backoff {
+    #[must_use]
+    pub fn new(config: BackoffConfig) -> Self {
+        Self { config, step: 0 }
+    }
+    /// Back to the shortest spin, e.g. after the awaited condition was met once
+    pub fn reset(&mut self) {
+        self.step = 0;
+    }
+    /// Advance the ladder without waiting
+    pub fn next_step(&mut self) -> BackoffStep {
+        let step = self.step;
+        self.step = self.step.saturating_add(1);
+        if step < self.config.spins {
+            return BackoffStep::Spin(1 << step.min(MAX_SPIN_SHIFT));
+        }
+        if step - self.config.spins < self.config.yields {
+            return BackoffStep::Yield;
+        }
+        BackoffStep::Sleep(self.config.sleep)
+    }
+    pub fn snooze(&mut self) {
+        match self.next_step() {
+            BackoffStep::Spin(hints) => (0..hints).for_each(|_| spin_loop()),
+            BackoffStep::Yield => std::thread::yield_now(),
+            BackoffStep::Sleep(duration) => std::thread::sleep(duration),
+        }
+    }
+    /// [`Self::snooze()`] but never sleeps past `deadline`
+    pub fn snooze_until(&mut self, deadline: Instant) {
+        match self.next_step() {
+            BackoffStep::Spin(hints) => (0..hints).for_each(|_| spin_loop()),
+            BackoffStep::Yield => std::thread::yield_now(),
+            BackoffStep::Sleep(duration) => {
+                let left = deadline.saturating_duration_since(Instant::now());
+                std::thread::sleep(duration.min(left));
+            }
+        }
+    }
+}
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(BackoffConfig::default())
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ladder() {
+        let sleep = Duration::from_millis(1);
+        let mut b = Backoff::new(BackoffConfig {
+            spins: 3,
+            yields: 2,
+            sleep,
+        });
+        let steps = (0..7).map(|_| b.next_step()).collect::<Vec<_>>();
+        assert_eq!(
+            steps,
+            [
+                BackoffStep::Spin(1),
+                BackoffStep::Spin(2),
+                BackoffStep::Spin(4),
+                BackoffStep::Yield,
+                BackoffStep::Yield,
+                BackoffStep::Sleep(sleep),
+                BackoffStep::Sleep(sleep),
+            ]
+        );
+        b.reset();
+        assert_eq!(b.next_step(), BackoffStep::Spin(1));
+
+        let mut b = Backoff::new(BackoffConfig {
+            spins: 20,
+            yields: 0,
+            sleep,
+        });
+        let last = (0..20).map(|_| b.next_step()).last().unwrap();
+        assert_eq!(last, BackoffStep::Spin(1 << MAX_SPIN_SHIFT));
+        assert_eq!(b.next_step(), BackoffStep::Sleep(sleep));
+    }
+
+    #[test]
+    fn test_snooze_until_deadline() {
+        let mut b = Backoff::new(BackoffConfig {
+            spins: 0,
+            yields: 0,
+            sleep: Duration::from_secs(10),
+        });
+        let start = Instant::now();
+        b.snooze_until(start + Duration::from_millis(5));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
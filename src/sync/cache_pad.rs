@@ -0,0 +1,61 @@
+use core::ops::{Deref, DerefMut};
+
+/// Align and pad `T` to a cache line so that it does not false-share with its neighbors
+///
+/// The adjacent-line prefetcher of aarch64 pulls cache lines in pairs, hence 128 bytes there.
+#[cfg_attr(target_arch = "aarch64", repr(C, align(128)))]
+#[cfg_attr(not(target_arch = "aarch64"), repr(C, align(64)))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CachePadded<T> {
+    value: T,
+}
+impl<T> CachePadded<T> {
+    pub const fn new(value: T) -> Self {
+        Self { value }
+    }
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+impl<T> DerefMut for CachePadded<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+impl<T> From<T> for CachePadded<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{mem::align_of, sync::atomic::AtomicUsize};
+
+    use super::*;
+
+    #[cfg(target_arch = "aarch64")]
+    const LINE: usize = 128;
+    #[cfg(not(target_arch = "aarch64"))]
+    const LINE: usize = 64;
+
+    #[test]
+    fn test_cache_padded() {
+        assert_eq!(align_of::<CachePadded<u8>>(), LINE);
+        assert_eq!(align_of::<CachePadded<AtomicUsize>>(), LINE);
+        assert_eq!(size_of::<CachePadded<u8>>(), LINE);
+        assert_eq!(size_of::<[CachePadded<u8>; 2]>(), 2 * LINE);
+
+        let mut p = CachePadded::new(vec![1]);
+        p.push(2);
+        assert_eq!(p.len(), 2);
+        assert_eq!(*p, [1, 2]);
+        assert_eq!(p.into_inner(), [1, 2]);
+    }
+}
@@ -0,0 +1,130 @@
+//! Pins the auto traits of the thread-facing types so that a field change cannot silently drop or add them
+//!
+//! The negative side is pinned by the doc tests below.
+//!
+//! A [`SafePtr16`] shares `&T` with its clones:
+//!
+//! ```compile_fail
+//! use std::cell::Cell;
+//! use primitive::arena::stable_vec::SafePtr16;
+//!
+//! fn assert_send<T: Send>() {}
+//! assert_send::<SafePtr16<Cell<u8>, 2>>();
+//! ```
+//!
+//! ```compile_fail
+//! use std::rc::Rc;
+//! use primitive::arena::stable_vec::SafePtrMut24;
+//!
+//! fn assert_send<T: Send>() {}
+//! assert_send::<SafePtrMut24<Rc<u8>>>();
+//! ```
+//!
+//! A shared guard shares `&T`:
+//!
+//! ```compile_fail
+//! use std::cell::Cell;
+//! use primitive::sync::mutex::SpinMutexScoped;
+//!
+//! fn assert_sync<T: Sync>() {}
+//! assert_sync::<SpinMutexScoped<'static, Cell<u8>>>();
+//! ```
+//!
+//! A reader on another thread gets its own copy of the value:
+//!
+//! ```compile_fail
+//! use std::rc::Rc;
+//! use primitive::sync::seq_lock::SeqLock;
+//!
+//! fn assert_sync<T: Sync>() {}
+//! assert_sync::<SeqLock<Rc<u8>>>();
+//! ```
+//!
+//! ```compile_fail
+//! use std::{rc::Rc, sync::Arc};
+//! use primitive::sync::mcast::{SpMcast, SpMcastReader};
+//!
+//! fn assert_send<T: Send>() {}
+//! assert_send::<SpMcastReader<Rc<u8>, 2, Arc<SpMcast<Rc<u8>, 2>>>>();
+//! ```
+
+use std::sync::Arc;
+
+use crate::arena::{
+    obj_pool::{ArcObjPool, ObjPool, ObjRecycler, ObjScoped},
+    stable_vec::{SafePtr16, SafePtr24, SafePtrMut16, SafePtrMut24, SafeStableVec, StableVec},
+};
+
+use super::{
+    backoff::Backoff,
+    cache_pad::CachePadded,
+    mcast::{MpMcast, MpMcastReader, SharedMcast, SpMcast, SpMcastReader, SpMcastWriter},
+    mpsc_ring::{Receiver, Sender},
+    mutex::{HybridMutex, HybridMutexScoped, Mutex1, SpinMutex, SpinMutexScoped},
+    notify::Notify,
+    seq_lock::{SeqLock, SeqLockReader, SeqLockWriter},
+    set_once::{SetOnceGetter, SetOnceSetter},
+    sync_unsafe_cell::SyncUnsafeCell,
+    sync_wrapper::SyncWrapper,
+    treiber::TreiberStack,
+};
+
+/// Fail to compile unless every type implements all the traits for any generics that meet the bounds in the brackets
+macro_rules! assert_impl {
+    ([$($generics: tt)*] $($bound: ident),+: $($ty: ty),+ $(,)?) => {
+        const _: () = {
+            fn assert<X: ?Sized $(+ $bound)+>() {}
+            #[allow(dead_code)]
+            fn generic<$($generics)*>() {
+                $(assert::<$ty>();)+
+            }
+        };
+    };
+}
+
+assert_impl!([] Send, Sync: Backoff, Mutex1, Notify);
+assert_impl!([T: Send] Send: CachePadded<T>);
+assert_impl!([T: Sync] Sync: CachePadded<T>);
+
+assert_impl!(
+    [T: Send] Send, Sync:
+    SpMcast<T, 2>,
+    SpMcastWriter<T, 2>,
+    MpMcast<T, 2>,
+    SharedMcast<T, 2>,
+    Sender<T, 2>,
+    Receiver<T, 2>,
+    SpinMutex<T>,
+    HybridMutex<T>,
+    SeqLock<T>,
+    SeqLockReader<T>,
+    SeqLockWriter<T>,
+    TreiberStack<T>,
+    ArcObjPool<T>,
+);
+assert_impl!(
+    [T: Send + 'static] Send:
+    SpMcastReader<T, 2, Arc<SpMcast<T, 2>>>,
+    MpMcastReader<T, 2, Arc<MpMcast<T, 2>>>,
+    SpinMutexScoped<'static, T>,
+    HybridMutexScoped<'static, T>,
+    SetOnceSetter<T>,
+    SetOnceGetter<T>,
+    ObjPool<T>,
+    ObjRecycler<T>,
+    ObjScoped<T>,
+    StableVec<T, 2>,
+    SafePtrMut16<T, 2>,
+    SafePtrMut24<T>,
+    SafeStableVec<T, 2>,
+);
+assert_impl!(
+    [T: Sync + 'static] Sync:
+    SpinMutexScoped<'static, T>,
+    HybridMutexScoped<'static, T>,
+    SyncUnsafeCell<T>,
+    SafePtrMut16<T, 2>,
+    SafePtrMut24<T>,
+);
+assert_impl!([T: Send + Sync + 'static] Send, Sync: SafePtr16<T, 2>, SafePtr24<T>);
+assert_impl!([T] Sync: SyncWrapper<T>);
@@ -1,35 +1,78 @@
-use core::{
-    marker::PhantomData,
-    mem::MaybeUninit,
-    sync::atomic::{fence, AtomicUsize, Ordering},
-};
-use std::sync::Arc;
+#[cfg(not(loom))]
+use core::ptr::{self, NonNull};
+use core::{marker::PhantomData, mem::MaybeUninit};
+use std::{sync::Arc, time::Instant};
+
+use thiserror::Error;
 
-use crate::ops::{dyn_ref::DynRef, ring::RingSpace};
+use crate::ops::{dyn_ref::DynRef, prefetch::prefetch_read, ring::RingSpace};
+
+use super::{
+    atomic_shim::{const_unless_loom, fence, AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    backoff::{Backoff, BackoffConfig},
+    cache_pad::CachePadded,
+    mutex::Mutex1,
+    seq_lock::SeqLock,
+};
 
-use super::{mutex::Mutex1, seq_lock::SeqLock};
+pub const SNAPSHOT_RETRIES: usize = 1 << 4;
 
 /// - message overwriting
+#[repr(C)]
 #[derive(Debug)]
 pub struct SpMcast<T, const N: usize> {
     ring: [SeqLock<MaybeUninit<T>>; N],
-    next: AtomicUsize,
+    /// Written on every push; kept off the cache lines of [`Self::ring`]
+    next: CachePadded<AtomicUsize>,
+    /// Set after the last push
+    closed: AtomicBool,
 }
 impl<T, const N: usize> SpMcast<T, N> {
+    #[cfg(not(loom))]
     pub const fn new() -> Self {
         const {
             assert!(1 < N);
         }
         let ring = [const { SeqLock::new(MaybeUninit::uninit()) }; N];
-        let next = AtomicUsize::new(0);
-        Self { ring, next }
+        let next = CachePadded::new(AtomicUsize::new(0));
+        let closed = AtomicBool::new(false);
+        Self { ring, next, closed }
+    }
+    #[cfg(loom)]
+    pub fn new() -> Self {
+        const {
+            assert!(1 < N);
+        }
+        let ring = core::array::from_fn(|_| SeqLock::new(MaybeUninit::uninit()));
+        let next = CachePadded::new(AtomicUsize::new(0));
+        let closed = AtomicBool::new(false);
+        Self { ring, next, closed }
     }
 
+    // Version protocol, with `v` the even version of a cell before a push into it:
+    //
+    // - the writer bumps the cell to `v + 1` (odd: being written), moves `next` past the cell (Release),
+    //   stores the value, and bumps the cell to `v + 2` (Release)
+    // - a value is delivered only if its cell is at an even version of at least `min_ver`;
+    //   [`Self::load()`] takes `min_ver - 2` for the lap before and refuses it
+    // - a new reader starts at the cell `next` points to and must neither take the value of the lap before
+    //   nor skip the push into the cell:
+    //   - odd `v + 1`: the push is in flight; wait for `v + 2`
+    //   - even with `next` unmoved: `v`, since `next` moves before the cell reaches `v + 2`; wait for `v + 2`
+    //   - even with `next` moved: `v` read before the push started or `v + 2` after it ended; take either.
+    //     The Acquire re-read of `next` synchronizes with the move, which follows the bump to `v + 1`,
+    //     so the later loads of the cell never see `v` again and never take the value of the lap before.
+    //
+    // The Acquire fence keeps the version read before the re-read of `next`;
+    // without it, a stale `next` could pair with the version after the push and skip the value.
+    //
+    // Covered by `super::ordering_tests`.
     pub fn next_version(&self) -> (usize, MinVer) {
         let next = self.next.load(Ordering::Acquire);
         let version = self.ring[next].version();
-        fence(Ordering::Release);
-        let new_next = self.next.load(Ordering::Relaxed);
+        // Keep the read of the version before the second read of `next`
+        fence(Ordering::Acquire);
+        let new_next = self.next.load(Ordering::Acquire);
         if version & 1 == 1 {
             let min_ver = version.wrapping_add(1);
             return (next, MinVer(min_ver));
@@ -41,6 +84,53 @@ impl<T, const N: usize> SpMcast<T, N> {
         };
         (next, MinVer(min_ver))
     }
+
+    /// Tell the readers that no more values are coming
+    ///
+    /// The values pushed before stay readable.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+    }
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+    /// One line of the state that stays short however big `T` and `N` are
+    #[must_use]
+    pub fn debug_summary(&self) -> SpMcastSummary {
+        let cells = N.min(SUMMARY_CELLS);
+        let mut writing = 0;
+        for (i, lock) in self.ring[..cells].iter().enumerate() {
+            writing |= u16::from(lock.version() & 1 == 1) << i;
+        }
+        SpMcastSummary {
+            next_pos: self.next.load(Ordering::Acquire),
+            writing,
+            truncated: cells < N,
+            closed: self.is_closed(),
+        }
+    }
+}
+/// Cells covered by [`SpMcastSummary::writing`]
+pub const SUMMARY_CELLS: usize = u16::BITS as usize;
+/// Returned by [`SpMcast::debug_summary()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpMcastSummary {
+    pub next_pos: usize,
+    /// Bit `i` is set if cell `i` has an odd version, i.e., is being written
+    pub writing: u16,
+    /// More than [`SUMMARY_CELLS`] cells, the rest of which are left out of [`Self::writing`]
+    pub truncated: bool,
+    pub closed: bool,
+}
+impl core::fmt::Display for SpMcastSummary {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "next={} writing={:b}", self.next_pos, self.writing)?;
+        if self.truncated {
+            f.write_str("..")?;
+        }
+        write!(f, " closed={}", self.closed)
+    }
 }
 impl<T, const N: usize> SpMcast<T, N>
 where
@@ -53,9 +143,34 @@ where
         let next = self.next.load(Ordering::Acquire);
         let value = MaybeUninit::new(value);
         let lock = &self.ring[next];
-        unsafe { lock.store(value) };
-        let next = next.ring_add(1, N - 1);
-        self.next.store(next, Ordering::Release);
+        // `next` moves while the cell is odd so that a reader seeing `v + 2` also sees `next` moved
+        unsafe {
+            lock.store_with(value, || {
+                self.next.store(next.ring_add(1, N - 1), Ordering::Release);
+            });
+        };
+    }
+
+    /// Current contents of the ring from the oldest to the newest
+    ///
+    /// Safe to call alongside the writer and the readers.
+    /// Each cell is read atomically but not all cells at the same instant, so the snapshot may mix generations under a concurrent writer.
+    /// Skip cells never written and cells still torn after [`SNAPSHOT_RETRIES`] reads.
+    #[must_use]
+    pub fn snapshot(&self) -> Vec<T> {
+        let next = self.next.load(Ordering::Acquire);
+        let mut values = Vec::with_capacity(N);
+        for i in 0..N {
+            let lock = &self.ring[next.ring_add(i, N - 1)];
+            let Some((value, ver)) = (0..SNAPSHOT_RETRIES).find_map(|_| lock.load()) else {
+                continue;
+            };
+            if ver == 0 {
+                continue;
+            }
+            values.push(unsafe { value.assume_init() });
+        }
+        values
     }
 
     /// # Safety
@@ -82,6 +197,24 @@ impl<T, const N: usize> Default for SpMcast<T, N> {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct MinVer(u32);
 
+/// Outcome of a read that tells an idle writer from a gone one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PopResult<T> {
+    Item(T),
+    /// Nothing new yet
+    Empty,
+    /// Nothing new and nothing will ever be
+    Closed,
+}
+impl<T> PopResult<T> {
+    pub fn into_item(self) -> Option<T> {
+        match self {
+            PopResult::Item(item) => Some(item),
+            PopResult::Empty | PopResult::Closed => None,
+        }
+    }
+}
+
 pub fn spmcast_channel<T, const N: usize>(
 ) -> (SpMcastReader<T, N, Arc<SpMcast<T, N>>>, SpMcastWriter<T, N>) {
     let queue = SpMcast::new();
@@ -91,6 +224,7 @@ pub fn spmcast_channel<T, const N: usize>(
     let writer = SpMcastWriter { queue };
     (reader, writer)
 }
+/// Closes the queue on drop
 #[derive(Debug)]
 pub struct SpMcastWriter<T, const N: usize> {
     queue: Arc<SpMcast<T, N>>,
@@ -103,6 +237,15 @@ where
         unsafe { self.queue.push(value) };
     }
 }
+impl<T, const N: usize> SpMcastWriter<T, N> {
+    /// Same as dropping `self`
+    pub fn close(self) {}
+}
+impl<T, const N: usize> Drop for SpMcastWriter<T, N> {
+    fn drop(&mut self) {
+        self.queue.close();
+    }
+}
 #[derive(Debug, Clone)]
 pub struct SpMcastReader<T, const N: usize, Q> {
     queue: DynRef<Q, SpMcast<T, N>>,
@@ -126,7 +269,8 @@ impl<T, const N: usize, Q> SpMcastReader<T, N, Q> {
     where
         T: Copy,
     {
-        let (val, ver) = unsafe { self.queue.convert().load(self.position, self.min_ver) }?;
+        let queue = self.queue.convert();
+        let (val, ver) = unsafe { queue.load(self.position, self.min_ver) }?;
         let ver_bump = self.min_ver != ver;
         let at_ver_start_pos = 0 == self.position;
         if !ver_bump && at_ver_start_pos && self.read_once {
@@ -135,25 +279,105 @@ impl<T, const N: usize, Q> SpMcastReader<T, N, Q> {
         self.min_ver = ver;
         self.position = self.position.ring_add(1, N - 1);
         self.read_once = true;
+        // The next pop likely follows soon and the writer has probably touched the cell since
+        prefetch_read(&queue.ring[self.position]);
         Some(val)
     }
+    /// Same as [`Self::pop()`] but tell a closed queue from an empty one
+    ///
+    /// Every value pushed before [`SpMcast::close()`] and not yet overwritten is popped before [`PopResult::Closed`].
+    pub fn try_pop_or_closed(&mut self) -> PopResult<T>
+    where
+        T: Copy,
+    {
+        if let Some(val) = self.pop() {
+            return PopResult::Item(val);
+        }
+        if !self.queue.convert().is_closed() {
+            return PopResult::Empty;
+        }
+        // The close is ordered after the last push, which might have been missed by the pop above
+        match self.pop() {
+            Some(val) => PopResult::Item(val),
+            None => PopResult::Closed,
+        }
+    }
+    /// Skip the values not matching `pred`
+    ///
+    /// Give up with [`None`] after skipping one lap of the ring so that a busy writer cannot keep the call spinning.
+    pub fn pop_filtered(&mut self, mut pred: impl FnMut(&T) -> bool) -> Option<T>
+    where
+        T: Copy,
+    {
+        for _ in 0..N {
+            let val = self.pop()?;
+            if pred(&val) {
+                return Some(val);
+            }
+        }
+        None
+    }
+    /// Hand the value to `f` by reference
+    ///
+    /// The value is still copied out of the ring onto the stack first since [`SeqLock`] only loads by value.
+    /// This saves the copy of the caller, not the one of the lock.
+    pub fn pop_map<U>(&mut self, f: impl FnOnce(&T) -> U) -> Option<U>
+    where
+        T: Copy,
+    {
+        let val = self.pop()?;
+        Some(f(&val))
+    }
+    /// One line of the read position
+    #[must_use]
+    pub fn debug_summary(&self) -> SpMcastReaderSummary {
+        SpMcastReaderSummary {
+            next_pos: self.position,
+            next_ver: self.min_ver.0,
+            read_once: self.read_once,
+        }
+    }
+}
+/// Returned by [`SpMcastReader::debug_summary()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpMcastReaderSummary {
+    pub next_pos: usize,
+    /// The least version of the cell at [`Self::next_pos`] still unread
+    pub next_ver: u32,
+    pub read_once: bool,
+}
+impl core::fmt::Display for SpMcastReaderSummary {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "next={} ver={} read_once={}",
+            self.next_pos, self.next_ver, self.read_once
+        )
+    }
 }
 
 /// - message overwriting
+#[repr(C)]
 #[derive(Debug)]
 pub struct MpMcast<T, const N: usize> {
-    write: Mutex1,
+    write: CachePadded<Mutex1>,
     queue: SpMcast<T, N>,
 }
 impl<T, const N: usize> MpMcast<T, N> {
-    pub const fn new() -> Self {
-        let write = Mutex1::new();
-        let queue = SpMcast::new();
-        Self { write, queue }
+    const_unless_loom! {
+        pub fn new() -> Self {
+            let write = CachePadded::new(Mutex1::new());
+            let queue = SpMcast::new();
+            Self { write, queue }
+        }
     }
     pub const fn queue(&self) -> &SpMcast<T, N> {
         &self.queue
     }
+    /// Same as [`SpMcast::close()`]; no writer is told and any can still push
+    pub fn close(&self) {
+        self.queue.close();
+    }
 }
 impl<T, const N: usize> Default for MpMcast<T, N> {
     fn default() -> Self {
@@ -192,6 +416,11 @@ impl<T, const N: usize, Q> MpMcastReader<T, N, Q> {
         let reader = SpMcastReader::new(queue_ref);
         Self { reader }
     }
+    /// Same as [`SpMcastReader::debug_summary()`]
+    #[must_use]
+    pub fn debug_summary(&self) -> SpMcastReaderSummary {
+        self.reader.debug_summary()
+    }
 }
 impl<T, const N: usize, Q> MpMcastReader<T, N, Q>
 where
@@ -200,8 +429,174 @@ where
     pub fn pop(&mut self) -> Option<T> {
         self.reader.pop()
     }
+    /// Same as [`SpMcastReader::try_pop_or_closed()`]
+    pub fn try_pop_or_closed(&mut self) -> PopResult<T> {
+        self.reader.try_pop_or_closed()
+    }
+    /// Same as [`SpMcastReader::pop_filtered()`]
+    pub fn pop_filtered(&mut self, pred: impl FnMut(&T) -> bool) -> Option<T> {
+        self.reader.pop_filtered(pred)
+    }
+    /// Same as [`SpMcastReader::pop_map()`]
+    pub fn pop_map<U>(&mut self, f: impl FnOnce(&T) -> U) -> Option<U> {
+        self.reader.pop_map(f)
+    }
+    /// Wait for a value with the default [`BackoffConfig`]
+    ///
+    /// [`None`] once `deadline` passes or the queue is closed and drained.
+    pub fn pop_deadline(&mut self, deadline: Instant) -> Option<T> {
+        self.pop_deadline_with(deadline, BackoffConfig::default())
+    }
+    /// Wait for a value backing off by `config` between the tries
+    ///
+    /// [`None`] once `deadline` passes or the queue is closed and drained.
+    pub fn pop_deadline_with(&mut self, deadline: Instant, config: BackoffConfig) -> Option<T> {
+        let mut backoff = Backoff::new(config);
+        loop {
+            match self.try_pop_or_closed() {
+                PopResult::Item(value) => return Some(value),
+                PopResult::Closed => return None,
+                PopResult::Empty => (),
+            }
+            if deadline <= Instant::now() {
+                return None;
+            }
+            backoff.snooze_until(deadline);
+        }
+    }
+}
+
+/// Bumped on any change to the layout of [`SharedMcast`] or of the types it is made of
+#[cfg(not(loom))]
+const LAYOUT_VERSION: u32 = 2;
+#[cfg(not(loom))]
+const SHARED_MAGIC: u64 = u64::from_le_bytes(*b"PRIMCAST");
+
+/// [`MpMcast`] laid out for a memory region mapped by several processes
+///
+/// [`Self::init_in()`] builds the queue in the region and publishes a header last;
+/// [`Self::attach()`] checks the header, the size and the alignment before handing out a reference.
+///
+/// What stays unchecked:
+/// - `T` must be plain old data, free of pointers and anything else meaningful to one process only
+/// - all processes must be built with the same `T`, `N`, target and [`Self::layout_version()`]; only the size is checked against a mismatch of `T` or `N`
+/// - the region must stay mapped as long as any reference to it is alive
+/// - the atomics must be lock-free and address-based, as they are on all the mainstream targets
+#[repr(C)]
+#[derive(Debug)]
+pub struct SharedMcast<T, const N: usize> {
+    header: SharedHeader,
+    queue: MpMcast<T, N>,
+}
+#[repr(C)]
+#[derive(Debug)]
+struct SharedHeader {
+    /// Stored last with [`Ordering::Release`] by [`SharedMcast::init_in()`]
+    magic: AtomicU64,
+    version: u32,
+    align: u32,
+    size: u64,
 }
+#[cfg(not(loom))]
+impl<T, const N: usize> SharedMcast<T, N>
+where
+    T: Copy,
+{
+    #[must_use]
+    pub const fn layout_version() -> u32 {
+        LAYOUT_VERSION
+    }
+    pub const fn queue(&self) -> &MpMcast<T, N> {
+        &self.queue
+    }
 
+    /// Build an empty queue at the start of `place`, let `init` prime it and then publish the header
+    ///
+    /// Attachers never see the queue before `init` returns.
+    pub fn init_in(
+        place: &mut [MaybeUninit<u8>],
+        init: impl FnOnce(&Self),
+    ) -> Result<&Self, LayoutError> {
+        let ptr = NonNull::new(place.as_mut_ptr().cast::<u8>()).unwrap();
+        Self::check_region(ptr, place.len())?;
+        let this = ptr.cast::<Self>().as_ptr();
+        // SAFETY: `check_region()` has made sure the region is large and aligned enough; `place` is borrowed exclusively in this process.
+        // Attachers in other processes might be polling the magic, so it is only ever accessed atomically and stored last.
+        let this = unsafe {
+            let magic = AtomicU64::from_ptr(ptr::addr_of_mut!((*this).header.magic).cast());
+            magic.store(0, Ordering::Relaxed);
+            ptr::addr_of_mut!((*this).header.version).write(LAYOUT_VERSION);
+            ptr::addr_of_mut!((*this).header.align).write(core::mem::align_of::<Self>() as u32);
+            ptr::addr_of_mut!((*this).header.size).write(core::mem::size_of::<Self>() as u64);
+            ptr::addr_of_mut!((*this).queue).write(MpMcast::new());
+            &*this
+        };
+        init(this);
+        this.header.magic.store(SHARED_MAGIC, Ordering::Release);
+        Ok(this)
+    }
+
+    /// Refer to the queue built by [`Self::init_in()`] at `ptr`, possibly by another process
+    ///
+    /// # Safety
+    ///
+    /// - `ptr` must be valid for reads and writes of `len` bytes for `'a`
+    /// - the region must at least be zeroed, as a fresh mapping is, if [`Self::init_in()`] might not have run yet
+    /// - the region must only be touched through [`SharedMcast`] for `'a`
+    pub unsafe fn attach<'a>(ptr: NonNull<u8>, len: usize) -> Result<&'a Self, LayoutError> {
+        Self::check_region(ptr, len)?;
+        // SAFETY: the header is in bounds and aligned; any bit pattern is a valid `u64`
+        let header = unsafe { ptr.cast::<SharedHeader>().as_ref() };
+        if header.magic.load(Ordering::Acquire) != SHARED_MAGIC {
+            return Err(LayoutError::Uninit);
+        }
+        if header.version != LAYOUT_VERSION {
+            return Err(LayoutError::Version {
+                found: header.version,
+                expected: LAYOUT_VERSION,
+            });
+        }
+        let size = core::mem::size_of::<Self>() as u64;
+        let align = core::mem::align_of::<Self>() as u32;
+        if header.size != size || header.align != align {
+            return Err(LayoutError::Mismatch {
+                size: header.size,
+                align: header.align,
+            });
+        }
+        // SAFETY: the header vouches for a queue of this very type published by `init_in()`
+        Ok(unsafe { ptr.cast::<Self>().as_ref() })
+    }
+
+    fn check_region(ptr: NonNull<u8>, len: usize) -> Result<(), LayoutError> {
+        let required = core::mem::size_of::<Self>();
+        if len < required {
+            return Err(LayoutError::TooSmall { len, required });
+        }
+        if !ptr.cast::<Self>().as_ptr().is_aligned() {
+            return Err(LayoutError::Misaligned {
+                addr: ptr.as_ptr() as usize,
+                align: core::mem::align_of::<Self>(),
+            });
+        }
+        Ok(())
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum LayoutError {
+    #[error("region of {len} bytes is smaller than the {required} bytes required")]
+    TooSmall { len: usize, required: usize },
+    #[error("region at {addr:#x} is not aligned to {align} bytes")]
+    Misaligned { addr: usize, align: usize },
+    #[error("no shared mcast has been published in the region")]
+    Uninit,
+    #[error("layout version {found} is not {expected}")]
+    Version { found: u32, expected: u32 },
+    #[error("shared mcast of {size} bytes aligned to {align} bytes is of another type")]
+    Mismatch { size: u64, align: u32 },
+}
+
+#[cfg(not(loom))]
 #[cfg(test)]
 mod tests {
     use crate::sync::tests::RepeatedData;
@@ -232,7 +627,7 @@ mod tests {
                         data.assert();
                         let value = data.get()[0];
                         if let Some(prev) = prev {
-                            assert!(prev < value, "{prev}; {value}; {rdr:?}");
+                            assert!(prev < value, "{prev}; {value}; {}", rdr.debug_summary());
                         }
                         prev = Some(value);
                         if value + 1 == N {
@@ -255,61 +650,399 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_debug_summary() {
+        let (mut rdr, mut wtr) = spmcast_channel::<[u8; 1 << 10], 4>();
+        let queue = Arc::clone(&wtr.queue);
+        assert_eq!(
+            queue.debug_summary().to_string(),
+            "next=0 writing=0 closed=false"
+        );
+        assert_eq!(
+            rdr.debug_summary().to_string(),
+            "next=0 ver=2 read_once=false"
+        );
+        for _ in 0..5 {
+            wtr.push([0; 1 << 10]);
+        }
+        rdr.pop().unwrap();
+        assert_eq!(
+            queue.debug_summary().to_string(),
+            "next=1 writing=0 closed=false"
+        );
+        assert_eq!(
+            rdr.debug_summary().to_string(),
+            // The second lap has overwritten the first cell
+            "next=1 ver=4 read_once=true"
+        );
+        wtr.close();
+        assert!(queue.debug_summary().closed);
+
+        let queue = SpMcast::<u8, 32>::new();
+        let summary = queue.debug_summary();
+        assert!(summary.truncated);
+        assert_eq!(summary.to_string(), "next=0 writing=0.. closed=false");
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let queue = SpMcast::<usize, 4>::new();
+        assert!(queue.snapshot().is_empty());
+        unsafe { queue.push(0) };
+        unsafe { queue.push(1) };
+        assert_eq!(queue.snapshot(), [0, 1]);
+        for i in 2..7 {
+            unsafe { queue.push(i) };
+        }
+        assert_eq!(queue.snapshot(), [3, 4, 5, 6]);
+
+        let (rdr, mut wtr) = spmcast_channel::<RepeatedData<_, DATA_COUNT>, 4>();
+        let queue = rdr.queue.convert();
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                for i in 0..N {
+                    wtr.push(RepeatedData::new(i));
+                }
+            });
+            while queue.snapshot().last().map(|data| data.get()[0] + 1) != Some(N) {
+                for data in queue.snapshot() {
+                    data.assert();
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_close() {
+        let (mut rdr, mut wtr) = spmcast_channel::<u32, 4>();
+        let mut late = rdr.clone();
+        assert_eq!(rdr.try_pop_or_closed(), PopResult::Empty);
+        wtr.push(1);
+        wtr.push(2);
+        wtr.close();
+        // Drained before closed
+        assert_eq!(rdr.try_pop_or_closed(), PopResult::Item(1));
+        assert_eq!(rdr.try_pop_or_closed(), PopResult::Item(2));
+        assert_eq!(rdr.try_pop_or_closed(), PopResult::Closed);
+        assert_eq!(rdr.try_pop_or_closed(), PopResult::Closed);
+        assert_eq!(late.pop(), Some(1));
+        let mut fresh = SpMcastReader::new(late.queue.clone());
+        assert_eq!(fresh.try_pop_or_closed(), PopResult::Closed);
+
+        let (mut rdr, wtr) = mpmcast_channel::<u32, 4>();
+        assert!(wtr.try_push(3));
+        wtr.close();
+        assert_eq!(rdr.try_pop_or_closed(), PopResult::Item(3));
+        assert_eq!(rdr.try_pop_or_closed(), PopResult::Closed);
+        // Returns before the deadline
+        let start = Instant::now();
+        let deadline = start + std::time::Duration::from_secs(60);
+        assert_eq!(rdr.pop_deadline(deadline), None);
+        assert!(Instant::now() < deadline);
+    }
+
+    #[test]
+    fn test_close_concurrent() {
+        const N: usize = 1 << 12;
+
+        let (rdr, mut wtr) = spmcast_channel::<RepeatedData<usize, 16>, 4>();
+        std::thread::scope(|s| {
+            for _ in 0..THREADS {
+                let mut rdr = rdr.clone();
+                s.spawn(move || {
+                    let mut last = None;
+                    loop {
+                        match rdr.try_pop_or_closed() {
+                            PopResult::Item(data) => {
+                                data.assert();
+                                assert!(last < Some(data.get()[0]));
+                                last = Some(data.get()[0]);
+                            }
+                            PopResult::Empty => std::thread::yield_now(),
+                            PopResult::Closed => break,
+                        }
+                    }
+                    // The last push always survives in the ring
+                    assert_eq!(last, Some(N - 1));
+                });
+            }
+            for i in 0..N {
+                wtr.push(RepeatedData::new(i));
+            }
+            drop(wtr);
+        });
+    }
+
+    #[test]
+    fn test_pop_filtered() {
+        let (rdr, mut wtr) = spmcast_channel::<(u8, u32), 4>();
+        let mut even = rdr.clone();
+        let mut odd = rdr;
+        for i in 0..3 {
+            wtr.push((i as u8 % 2, i));
+        }
+        assert_eq!(even.pop_filtered(|&(tag, _)| tag == 0), Some((0, 0)));
+        assert_eq!(even.pop_filtered(|&(tag, _)| tag == 0), Some((0, 2)));
+        assert_eq!(even.pop_filtered(|&(tag, _)| tag == 0), None);
+        assert_eq!(odd.pop_map(|&(_, i)| i), Some(0));
+        assert_eq!(odd.pop_filtered(|&(tag, _)| tag == 1), Some((1, 1)));
+        assert_eq!(odd.pop_filtered(|&(tag, _)| tag == 1), None);
+        assert_eq!(odd.pop(), None);
+
+        // Bounded by one lap
+        for i in 0..4 {
+            wtr.push((1, i));
+        }
+        assert_eq!(even.pop_filtered(|&(tag, _)| tag == 0), None);
+        wtr.push((0, 4));
+        assert_eq!(even.pop_filtered(|&(tag, _)| tag == 0), Some((0, 4)));
+        assert_eq!(even.pop_map(|&(_, i)| i), None);
+    }
+
+    #[test]
+    fn test_pop_filtered_concurrent() {
+        const N: u32 = 1 << 14;
+
+        let (rdr, mut wtr) = spmcast_channel::<(u32, RepeatedData<u32, 16>), 4>();
+        let queue = Arc::clone(&wtr.queue);
+        std::thread::scope(|s| {
+            for parity in 0..2 {
+                let mut rdr = rdr.clone();
+                let queue = &queue;
+                s.spawn(move || {
+                    let mut prev = None;
+                    loop {
+                        // A lapped reader can miss the last value of its parity, so stop at the close instead
+                        let closed = queue.is_closed();
+                        let Some((tag, data)) = rdr.pop_filtered(|&(tag, _)| tag % 2 == parity)
+                        else {
+                            if closed {
+                                break;
+                            }
+                            continue;
+                        };
+                        assert_eq!(tag % 2, parity);
+                        data.assert();
+                        assert_eq!(data.get()[0], tag);
+                        assert!(prev < Some(tag), "{prev:?}; {tag}");
+                        prev = Some(tag);
+                    }
+                });
+            }
+            for i in 0..N {
+                wtr.push((i, RepeatedData::new(i)));
+            }
+            wtr.close();
+        });
+    }
+
     #[test]
     fn test_transmute() {
         type Queue = MpMcast<RepeatedData<usize, DATA_COUNT>, QUEUE_SIZE>;
         const BUF_SIZE: usize = core::mem::size_of::<Queue>();
-        type Buf = [u8; BUF_SIZE];
-        let mut buf = Box::new([0; BUF_SIZE]);
+        // Keep the alignment of `Queue` so that both `Arc`s place the value at the same offset
+        type Buf = CachePadded<[u8; BUF_SIZE]>;
+        let mut buf = Box::new(CachePadded::new([0; BUF_SIZE]));
         let buf = {
             let queue: Queue = MpMcast::new();
             let bytes = unsafe { core::mem::transmute::<Queue, Buf>(queue) };
-            buf.copy_from_slice(&bytes);
+            buf.copy_from_slice(&*bytes);
             buf.into()
         };
         let queue = unsafe { core::mem::transmute::<Arc<Buf>, Arc<Queue>>(buf) };
-        test_mpmcast(queue);
+        test_mpmcast(&queue, &queue);
+    }
+    #[test]
+    fn test_shared_attach() {
+        type Shared = SharedMcast<RepeatedData<usize, DATA_COUNT>, QUEUE_SIZE>;
+        let layout = std::alloc::Layout::new::<Shared>();
+        let ptr = NonNull::new(unsafe { std::alloc::alloc_zeroed(layout) }).unwrap();
+        let len = layout.size();
+        assert_eq!(
+            unsafe { Shared::attach(ptr, len) }.unwrap_err(),
+            LayoutError::Uninit
+        );
+
+        let place = unsafe { core::slice::from_raw_parts_mut(ptr.as_ptr().cast(), len) };
+        let primed = Shared::init_in(place, |shared| {
+            assert!(shared.queue().try_push(RepeatedData::new(0)));
+        })
+        .unwrap();
+        assert_eq!(primed.queue().queue().snapshot().len(), 1);
+
+        // Two references to the same memory as if from two processes
+        let writer = unsafe { Shared::attach(ptr, len) }.unwrap();
+        let reader = unsafe { Shared::attach(ptr, len) }.unwrap();
+        assert_eq!(Shared::layout_version(), 2);
+        test_mpmcast(writer.queue(), reader.queue());
+
+        assert_eq!(
+            unsafe { Shared::attach(ptr, len - 1) }.unwrap_err(),
+            LayoutError::TooSmall {
+                len: len - 1,
+                required: len
+            }
+        );
+        let misaligned = NonNull::new(ptr.as_ptr().wrapping_add(1)).unwrap();
+        assert!(matches!(
+            unsafe { Shared::attach(misaligned, len) },
+            Err(LayoutError::Misaligned { .. })
+        ));
+        assert!(matches!(
+            unsafe { SharedMcast::<u8, QUEUE_SIZE>::attach(ptr, len) },
+            Err(LayoutError::Mismatch { .. })
+        ));
+        unsafe { std::alloc::dealloc(ptr.as_ptr(), layout) };
     }
     fn test_mpmcast<const QUEUE_SIZE: usize>(
-        queue: Arc<MpMcast<RepeatedData<usize, DATA_COUNT>, QUEUE_SIZE>>,
+        writer: &MpMcast<RepeatedData<usize, DATA_COUNT>, QUEUE_SIZE>,
+        reader: &MpMcast<RepeatedData<usize, DATA_COUNT>, QUEUE_SIZE>,
     ) {
-        let rdr = MpMcastReader::new(DynRef::new(queue.clone(), |q| q.as_ref()));
-        let wtr = queue;
-        let mut threads = vec![];
-        for _ in 0..THREADS {
-            let handle = std::thread::spawn({
+        let rdr = MpMcastReader::new(DynRef::new(reader, |q| *q));
+        std::thread::scope(|s| {
+            for _ in 0..THREADS {
                 let mut rdr = rdr.clone();
-                move || {
+                s.spawn(move || {
                     let mut n = 0;
                     let mut prev: Option<usize> = None;
+                    // Stop at the close rather than at the last value so that a reader missing it fails instead of hanging
                     loop {
-                        let Some(data) = rdr.pop() else {
-                            continue;
+                        let data = match rdr.try_pop_or_closed() {
+                            PopResult::Item(data) => data,
+                            PopResult::Empty => continue,
+                            PopResult::Closed => break,
                         };
                         n += 1;
                         data.assert();
                         let value = data.get()[0];
                         if let Some(prev) = prev {
-                            assert!(prev < value, "{prev}; {value}; {rdr:?}");
+                            assert!(prev < value, "{prev}; {value}; {}", rdr.debug_summary());
                         }
                         prev = Some(value);
-                        if value + 1 == N {
-                            break;
-                        }
                     }
+                    // Never overwritten, so even a lapped reader gets it
+                    assert_eq!(prev, Some(N - 1), "{}", rdr.debug_summary());
                     let rate = n as f64 / N as f64;
                     println!("{n}; {N}; {rate}");
                     // assert!(RATE < rate);
+                });
+            }
+            for i in 0..N {
+                let data = RepeatedData::new(i);
+                while !writer.try_push(data) {}
+            }
+            writer.close();
+        });
+    }
+
+    #[test]
+    fn test_pop_deadline() {
+        use std::time::Duration;
+
+        let (mut rdr, wtr) = mpmcast_channel::<u32, QUEUE_SIZE>();
+        let start = Instant::now();
+        assert_eq!(rdr.pop_deadline(start + Duration::from_millis(10)), None);
+        assert!(start.elapsed() >= Duration::from_millis(10));
+
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            while !wtr.try_push(7) {}
+        });
+        let config = BackoffConfig {
+            spins: 2,
+            yields: 2,
+            sleep: Duration::from_millis(1),
+        };
+        let start = Instant::now();
+        assert_eq!(
+            rdr.pop_deadline_with(start + Duration::from_secs(10), config),
+            Some(7)
+        );
+        assert!(start.elapsed() < Duration::from_secs(10));
+        writer.join().unwrap();
+    }
+}
+
+#[cfg(loom)]
+#[cfg(test)]
+mod loom_tests {
+    use loom::sync::{atomic::AtomicU32, Arc};
+
+    use super::*;
+
+    #[test]
+    fn loom_spmcast_no_value_from_the_future() {
+        loom::model(|| {
+            let queue = Arc::new(SpMcast::<u32, 2>::new());
+            let published = Arc::new(AtomicU32::new(0));
+            let writer = loom::thread::spawn({
+                let queue = Arc::clone(&queue);
+                let published = Arc::clone(&published);
+                move || {
+                    for i in 1..=3 {
+                        unsafe { queue.push(i) };
+                        published.store(i, Ordering::Release);
+                    }
                 }
             });
-            threads.push(handle);
-        }
-        for i in 0..N {
-            let data = RepeatedData::new(i);
-            while !wtr.try_push(data) {}
-        }
-        for handle in threads {
-            handle.join().unwrap();
-        }
+            // Subscribe at an arbitrary point of the pushes
+            let before = published.load(Ordering::Acquire);
+            let mut rdr = SpMcastReader::new(DynRef::new(Arc::clone(&queue), |q| q.as_ref()));
+            let mut prev = before;
+            for _ in 0..3 {
+                if let Some(value) = rdr.pop() {
+                    // Neither a value pushed before the subscription nor one out of order
+                    assert!(prev < value, "{prev}; {value}");
+                    prev = value;
+                }
+            }
+            writer.join().unwrap();
+        });
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(feature = "nightly")]
+#[cfg(test)]
+mod benches {
+    use crate::sync::tests::RepeatedData;
+
+    use super::*;
+
+    const DATA_COUNT: usize = 16;
+    const N: usize = 1 << 14;
+    const THREADS: usize = 1 << 2;
+    const QUEUE_SIZE: usize = 2;
+
+    #[bench]
+    fn bench_spmcast(bencher: &mut test::Bencher) {
+        bencher.iter(|| {
+            let (rdr, mut wtr) = spmcast_channel::<RepeatedData<_, DATA_COUNT>, QUEUE_SIZE>();
+            std::thread::scope(|s| {
+                for _ in 0..THREADS {
+                    let mut rdr = rdr.clone();
+                    s.spawn(move || while rdr.pop().map(|data| data.get()[0] + 1) != Some(N) {});
+                }
+                for i in 0..N {
+                    wtr.push(RepeatedData::new(i));
+                }
+            });
+        });
+    }
+    /// Compare with a build under `RUSTFLAGS="--cfg no_prefetch"` for the prefetch of the next cell
+    #[bench]
+    fn bench_spmcast_pop_lap(bencher: &mut test::Bencher) {
+        const RING: usize = 1 << 12;
+        let (mut rdr, mut wtr) = spmcast_channel::<RepeatedData<usize, DATA_COUNT>, RING>();
+        bencher.iter(|| {
+            for i in 0..RING {
+                wtr.push(RepeatedData::new(i));
+            }
+            let mut sum = 0;
+            while let Some(data) = rdr.pop() {
+                sum += data.get()[0];
+            }
+            test::black_box(sum);
+        });
     }
 }
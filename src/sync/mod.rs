@@ -1,9 +1,18 @@
+mod atomic_shim;
+pub mod backoff;
+pub mod cache_pad;
+mod markers;
 pub mod mcast;
+pub mod mpsc_ring;
 pub mod mutex;
 pub mod notify;
+#[cfg(test)]
+mod ordering_tests;
 pub mod seq_lock;
 pub mod set_once;
 pub mod sync_unsafe_cell;
+pub mod sync_wrapper;
+pub mod treiber;
 
 #[cfg(test)]
 pub mod tests {
@@ -0,0 +1,311 @@
+use core::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    sync::atomic::{fence, AtomicU8, AtomicUsize, Ordering},
+};
+use std::sync::Arc;
+
+use thiserror::Error;
+
+use super::{cache_pad::CachePadded, notify::Notify};
+
+const EMPTY: u8 = 0;
+const FULL: u8 = 1;
+const SPIN: usize = 1 << 6;
+
+/// Lossless bounded multi-producer single-consumer queue
+///
+/// vs. [`super::mcast`]:
+/// - no message overwriting; [`Sender::try_send()`] fails on a full queue instead
+///
+/// [`Receiver::recv()`] blocks forever if all the senders are gone.
+pub fn bounded<T, const N: usize>() -> (Sender<T, N>, Receiver<T, N>) {
+    let ring = Arc::new(Ring::new());
+    let sender = Sender {
+        ring: Arc::clone(&ring),
+    };
+    let receiver = Receiver { ring };
+    (sender, receiver)
+}
+
+#[derive(Debug)]
+pub struct Sender<T, const N: usize> {
+    ring: Arc<Ring<T, N>>,
+}
+impl<T, const N: usize> Sender<T, N> {
+    /// Same as [`Receiver::CAPACITY`]
+    pub const CAPACITY: usize = N;
+    pub fn try_send(&self, value: T) -> Result<(), Full<T>> {
+        self.ring.try_push(value)?;
+        self.ring
+            .wake(&self.ring.recv_waiters, &self.ring.not_empty);
+        Ok(())
+    }
+    /// Spin and then park until there is room for `value`
+    pub fn send(&self, mut value: T) {
+        for _ in 0..SPIN {
+            match self.try_send(value) {
+                Ok(()) => return,
+                Err(Full(v)) => value = v,
+            }
+            core::hint::spin_loop();
+        }
+        loop {
+            self.ring.send_waiters.fetch_add(1, Ordering::SeqCst);
+            let notified = self.ring.not_full.notified();
+            fence(Ordering::SeqCst);
+            let res = self.try_send(value);
+            if res.is_err() {
+                notified.wait();
+            }
+            self.ring.send_waiters.fetch_sub(1, Ordering::SeqCst);
+            match res {
+                Ok(()) => return,
+                Err(Full(v)) => value = v,
+            }
+        }
+    }
+}
+impl<T, const N: usize> Clone for Sender<T, N> {
+    fn clone(&self) -> Self {
+        Self {
+            ring: Arc::clone(&self.ring),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Receiver<T, const N: usize> {
+    ring: Arc<Ring<T, N>>,
+}
+impl<T, const N: usize> Receiver<T, N> {
+    /// Every slot holds an item; no slot is kept empty
+    pub const CAPACITY: usize = N;
+    pub fn try_recv(&mut self) -> Result<T, Empty> {
+        // SAFETY: `self` is the only consumer and is borrowed mutably
+        unsafe { self.ring.try_recv() }
+    }
+    /// Spin and then park until a value arrives
+    pub fn recv(&mut self) -> T {
+        for _ in 0..SPIN {
+            if let Ok(value) = self.try_recv() {
+                return value;
+            }
+            core::hint::spin_loop();
+        }
+        loop {
+            self.ring.recv_waiters.fetch_add(1, Ordering::SeqCst);
+            let notified = self.ring.not_empty.notified();
+            fence(Ordering::SeqCst);
+            let res = unsafe { self.ring.try_recv() };
+            if res.is_err() {
+                notified.wait();
+            }
+            self.ring.recv_waiters.fetch_sub(1, Ordering::SeqCst);
+            if let Ok(value) = res {
+                return value;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("queue is full")]
+pub struct Full<T>(pub T);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("queue is empty")]
+pub struct Empty;
+
+#[derive(Debug)]
+struct Ring<T, const N: usize> {
+    slots: [Slot<T>; N],
+    /// Position of the next read; only written by the consumer
+    head: CachePadded<AtomicUsize>,
+    /// Position of the next reserved write
+    tail: CachePadded<AtomicUsize>,
+    send_waiters: AtomicUsize,
+    recv_waiters: AtomicUsize,
+    not_full: Notify,
+    not_empty: Notify,
+}
+unsafe impl<T: Send, const N: usize> Sync for Ring<T, N> {}
+unsafe impl<T: Send, const N: usize> Send for Ring<T, N> {}
+impl<T, const N: usize> Ring<T, N> {
+    fn new() -> Self {
+        const {
+            assert!(0 < N);
+        }
+        Self {
+            slots: [const { Slot::new() }; N],
+            head: CachePadded::new(AtomicUsize::new(0)),
+            tail: CachePadded::new(AtomicUsize::new(0)),
+            send_waiters: AtomicUsize::new(0),
+            recv_waiters: AtomicUsize::new(0),
+            not_full: Notify::new(),
+            not_empty: Notify::new(),
+        }
+    }
+    fn try_push(&self, value: T) -> Result<(), Full<T>> {
+        let mut tail = self.tail.load(Ordering::Relaxed);
+        loop {
+            let head = self.head.load(Ordering::Acquire);
+            let len = tail.wrapping_sub(head);
+            // `tail` is stale
+            if N < len {
+                tail = self.tail.load(Ordering::Relaxed);
+                continue;
+            }
+            if len == N {
+                return Err(Full(value));
+            }
+            match self.tail.compare_exchange_weak(
+                tail,
+                tail.wrapping_add(1),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(new) => tail = new,
+            }
+        }
+        // The consumer has emptied the slot before moving `head` past it
+        let slot = &self.slots[tail % N];
+        debug_assert_eq!(slot.state.load(Ordering::Relaxed), EMPTY);
+        unsafe { (*slot.value.get()).write(value) };
+        slot.state.store(FULL, Ordering::Release);
+        Ok(())
+    }
+    /// # Safety
+    ///
+    /// Must only be called by one thread at a time
+    unsafe fn pop(&self) -> Option<T> {
+        let head = self.head.load(Ordering::Relaxed);
+        let slot = &self.slots[head % N];
+        if slot.state.load(Ordering::Acquire) != FULL {
+            return None;
+        }
+        let value = unsafe { (*slot.value.get()).assume_init_read() };
+        slot.state.store(EMPTY, Ordering::Release);
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(value)
+    }
+    /// # Safety
+    ///
+    /// Must only be called by one thread at a time
+    unsafe fn try_recv(&self) -> Result<T, Empty> {
+        let value = unsafe { self.pop() }.ok_or(Empty)?;
+        self.wake(&self.send_waiters, &self.not_full);
+        Ok(value)
+    }
+    /// Pairs with the `fetch_add` and fence of a parking thread so that either the waker sees the waiter or the waiter sees the progress
+    fn wake(&self, waiters: &AtomicUsize, notify: &Notify) {
+        fence(Ordering::SeqCst);
+        if waiters.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+        // A woken waiter might have already succeeded and left without consuming the notification
+        notify.notify_all();
+    }
+}
+impl<T, const N: usize> Drop for Ring<T, N> {
+    fn drop(&mut self) {
+        for slot in &mut self.slots {
+            if *slot.state.get_mut() == FULL {
+                unsafe { slot.value.get_mut().assume_init_drop() };
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Slot<T> {
+    state: AtomicU8,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+impl<T> Slot<T> {
+    const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(EMPTY),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try() {
+        let (tx, mut rx) = bounded::<_, 2>();
+        assert_eq!(rx.try_recv(), Err(Empty));
+        assert_eq!(Sender::<i32, 2>::CAPACITY, 2);
+        tx.try_send(1).unwrap();
+        tx.clone().try_send(2).unwrap();
+        assert_eq!(tx.try_send(3), Err(Full(3)));
+        assert_eq!(rx.try_recv(), Ok(1));
+        tx.try_send(3).unwrap();
+        assert_eq!(rx.try_recv(), Ok(2));
+        assert_eq!(rx.try_recv(), Ok(3));
+        assert_eq!(rx.try_recv(), Err(Empty));
+    }
+
+    #[test]
+    fn test_drop_unread() {
+        let rc = Arc::new(());
+        let (tx, rx) = bounded::<_, 4>();
+        for _ in 0..3 {
+            tx.try_send(Arc::clone(&rc)).unwrap();
+        }
+        assert_eq!(Arc::strong_count(&rc), 4);
+        drop((tx, rx));
+        assert_eq!(Arc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    fn test_mpsc() {
+        const PRODUCERS: usize = 4;
+        const N: usize = 1 << 20;
+        const PER_PRODUCER: usize = N / PRODUCERS;
+
+        let (tx, mut rx) = bounded::<(usize, usize), 64>();
+        std::thread::scope(|s| {
+            for p in 0..PRODUCERS {
+                let tx = tx.clone();
+                s.spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        tx.send((p, i));
+                    }
+                });
+            }
+            let mut next = [0; PRODUCERS];
+            for _ in 0..N {
+                let (p, i) = rx.recv();
+                assert_eq!(next[p], i);
+                next[p] += 1;
+            }
+            assert_eq!(next, [PER_PRODUCER; PRODUCERS]);
+        });
+        assert_eq!(rx.try_recv(), Err(Empty));
+    }
+
+    #[test]
+    fn test_capacity_one() {
+        const N: usize = 1 << 12;
+
+        let (tx, mut rx) = bounded::<usize, 1>();
+        let (ack_tx, mut ack_rx) = bounded::<usize, 1>();
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                for i in 0..N {
+                    tx.send(i);
+                    assert_eq!(ack_rx.recv(), i);
+                }
+            });
+            for i in 0..N {
+                assert_eq!(rx.recv(), i);
+                ack_tx.send(i);
+            }
+        });
+    }
+}
@@ -1,19 +1,22 @@
-use core::{
-    ops::{Deref, DerefMut},
-    sync::atomic::AtomicBool,
-    sync::atomic::Ordering,
-};
+use core::ops::{Deref, DerefMut};
+use std::{collections::VecDeque, thread::Thread};
 
-use super::sync_unsafe_cell::SyncUnsafeCell;
+use super::{
+    atomic_shim::{const_unless_loom, spin_loop, AtomicBool, AtomicUsize, Ordering, UnsafeCell},
+    backoff::{Backoff, BackoffConfig},
+};
 
+#[repr(C)]
 #[derive(Debug)]
 pub struct Mutex1 {
     lock: AtomicBool,
 }
 impl Mutex1 {
-    pub const fn new() -> Self {
-        let lock = AtomicBool::new(false);
-        Self { lock }
+    const_unless_loom! {
+        pub fn new() -> Self {
+            let lock = AtomicBool::new(false);
+            Self { lock }
+        }
     }
     pub fn try_lock(&self) -> bool {
         let lock = self
@@ -37,41 +40,47 @@ impl Default for Mutex1 {
 #[derive(Debug)]
 pub struct SpinMutex<T> {
     lock: Mutex1,
-    value: SyncUnsafeCell<T>,
+    value: UnsafeCell<T>,
 }
 impl<T> SpinMutex<T> {
-    pub const fn new(value: T) -> Self {
-        Self {
-            lock: Mutex1::new(),
-            value: SyncUnsafeCell::new(value),
+    const_unless_loom! {
+        pub fn new(value: T) -> Self {
+            Self {
+                lock: Mutex1::new(),
+                value: UnsafeCell::new(value),
+            }
         }
     }
-    pub fn lock(&self) -> SpinMutexScoped<T> {
+    pub fn lock(&self) -> SpinMutexScoped<'_, T> {
         while !self.lock.try_lock() {
-            core::hint::spin_loop();
+            spin_loop();
         }
         SpinMutexScoped { mutex: self }
     }
-    pub fn try_lock(&self) -> Option<SpinMutexScoped<T>> {
+    pub fn try_lock(&self) -> Option<SpinMutexScoped<'_, T>> {
         if !self.lock.try_lock() {
             return None;
         }
         Some(SpinMutexScoped { mutex: self })
     }
 }
+// Like `std::sync::Mutex`, only one thread at a time reaches the value, so it only has to be `Send`
+unsafe impl<T: Send> Sync for SpinMutex<T> {}
 #[derive(Debug)]
 pub struct SpinMutexScoped<'a, T> {
     mutex: &'a SpinMutex<T>,
 }
+// Sharing the guard shares `&T`
+unsafe impl<T: Sync> Sync for SpinMutexScoped<'_, T> {}
 impl<T> Deref for SpinMutexScoped<'_, T> {
     type Target = T;
     fn deref(&self) -> &Self::Target {
-        unsafe { &*self.mutex.value.get() }
+        self.mutex.value.with(|value| unsafe { &*value })
     }
 }
 impl<T> DerefMut for SpinMutexScoped<'_, T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        unsafe { &mut *self.mutex.value.get() }
+        self.mutex.value.with_mut(|value| unsafe { &mut *value })
     }
 }
 impl<T> Drop for SpinMutexScoped<'_, T> {
@@ -80,6 +89,204 @@ impl<T> Drop for SpinMutexScoped<'_, T> {
     }
 }
 
+/// Spin steps of [`HybridMutex::new()`] before parking
+pub const HYBRID_SPIN_STEPS: u32 = 6;
+
+/// Mutex that spins for a few backoff steps and then parks the thread
+///
+/// Unlike [`SpinMutex`], a preempted lock holder does not make every other thread burn its time slice, which matters once there are more runnable threads than cores.
+///
+/// A waiter registers itself in [`Self::waiters`] before its last try of the lock, and the unlocker checks [`Self::parked`] after releasing the lock, so either the waiter gets the lock or the unlocker sees and unparks it.
+#[derive(Debug)]
+pub struct HybridMutex<T> {
+    locked: AtomicBool,
+    spin_steps: u32,
+    /// Length of [`Self::waiters`] readable without locking it
+    parked: AtomicUsize,
+    waiters: SpinMutex<VecDeque<Thread>>,
+    value: UnsafeCell<T>,
+}
+// Same as `SpinMutex`
+unsafe impl<T: Send> Sync for HybridMutex<T> {}
+impl<T> HybridMutex<T> {
+    const_unless_loom! {
+        pub fn new(value: T) -> Self {
+            Self::with_spin_steps(value, HYBRID_SPIN_STEPS)
+        }
+    }
+    const_unless_loom! {
+        /// Park right away on contention if `spin_steps` is zero
+        pub fn with_spin_steps(value: T, spin_steps: u32) -> Self {
+            Self {
+                locked: AtomicBool::new(false),
+                spin_steps,
+                parked: AtomicUsize::new(0),
+                waiters: SpinMutex::new(VecDeque::new()),
+                value: UnsafeCell::new(value),
+            }
+        }
+    }
+    pub fn lock(&self) -> HybridMutexScoped<'_, T> {
+        if self.try_acquire() {
+            return HybridMutexScoped { mutex: self };
+        }
+        let mut backoff = Backoff::new(BackoffConfig {
+            spins: self.spin_steps,
+            yields: 0,
+            sleep: core::time::Duration::ZERO,
+        });
+        for _ in 0..self.spin_steps {
+            backoff.snooze();
+            if self.try_acquire() {
+                return HybridMutexScoped { mutex: self };
+            }
+        }
+        self.lock_parked();
+        HybridMutexScoped { mutex: self }
+    }
+    pub fn try_lock(&self) -> Option<HybridMutexScoped<'_, T>> {
+        if !self.try_acquire() {
+            return None;
+        }
+        Some(HybridMutexScoped { mutex: self })
+    }
+    fn try_acquire(&self) -> bool {
+        self.locked
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::Relaxed)
+            .is_ok()
+    }
+    fn lock_parked(&self) {
+        let me = std::thread::current();
+        loop {
+            {
+                let mut waiters = self.lock_waiters();
+                waiters.push_back(me.clone());
+                self.parked.fetch_add(1, Ordering::SeqCst);
+            }
+            let acquired = self.try_acquire();
+            if !acquired {
+                std::thread::park();
+            }
+            // Gone if an unlocker popped it; still there if the lock was taken or the wake was spurious
+            {
+                let mut waiters = self.lock_waiters();
+                if let Some(i) = waiters.iter().position(|t| t.id() == me.id()) {
+                    waiters.remove(i);
+                    self.parked.fetch_sub(1, Ordering::SeqCst);
+                }
+            }
+            if acquired || self.try_acquire() {
+                return;
+            }
+        }
+    }
+    fn unlock(&self) {
+        self.locked.store(false, Ordering::SeqCst);
+        if self.parked.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+        let waiter = {
+            let mut waiters = self.lock_waiters();
+            let waiter = waiters.pop_front();
+            if waiter.is_some() {
+                self.parked.fetch_sub(1, Ordering::SeqCst);
+            }
+            waiter
+        };
+        if let Some(waiter) = waiter {
+            waiter.unpark();
+        }
+    }
+    /// Held only for a few instructions, but back off anyway in case the holder is preempted
+    fn lock_waiters(&self) -> SpinMutexScoped<'_, VecDeque<Thread>> {
+        let mut backoff = Backoff::default();
+        loop {
+            if let Some(waiters) = self.waiters.try_lock() {
+                return waiters;
+            }
+            backoff.snooze();
+        }
+    }
+}
+#[derive(Debug)]
+pub struct HybridMutexScoped<'a, T> {
+    mutex: &'a HybridMutex<T>,
+}
+// Same as `SpinMutexScoped`
+unsafe impl<T: Sync> Sync for HybridMutexScoped<'_, T> {}
+impl<T> Deref for HybridMutexScoped<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        self.mutex.value.with(|value| unsafe { &*value })
+    }
+}
+impl<T> DerefMut for HybridMutexScoped<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.mutex.value.with_mut(|value| unsafe { &mut *value })
+    }
+}
+impl<T> Drop for HybridMutexScoped<'_, T> {
+    fn drop(&mut self) {
+        self.mutex.unlock();
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::*;
+
+    #[test]
+    fn test_hybrid_mutex_counter() {
+        const THREADS: usize = 8;
+        const N: usize = 1 << 12;
+
+        for spin_steps in [0, HYBRID_SPIN_STEPS] {
+            let mutex = HybridMutex::with_spin_steps(0, spin_steps);
+            std::thread::scope(|s| {
+                for _ in 0..THREADS {
+                    s.spawn(|| {
+                        for _ in 0..N {
+                            *mutex.lock() += 1;
+                        }
+                    });
+                }
+            });
+            assert_eq!(*mutex.lock(), THREADS * N);
+            assert_eq!(mutex.parked.load(Ordering::SeqCst), 0);
+            assert!(mutex.waiters.lock().is_empty());
+        }
+    }
+
+    /// The unlock races a waiter that has just registered itself
+    #[test]
+    fn test_unpark_race() {
+        const ROUNDS: usize = 1 << 10;
+
+        let mutex = Arc::new(HybridMutex::with_spin_steps((), 0));
+        for round in 0..ROUNDS {
+            let guard = mutex.lock();
+            let waiter = std::thread::spawn({
+                let mutex = Arc::clone(&mutex);
+                move || drop(mutex.lock())
+            });
+            // Vary how far the waiter gets before the unlock
+            match round % 3 {
+                0 => (),
+                1 => std::thread::yield_now(),
+                _ => std::thread::sleep(Duration::from_micros(50)),
+            }
+            drop(guard);
+            waiter.join().unwrap();
+        }
+        assert!(mutex.try_lock().is_some());
+        assert_eq!(mutex.parked.load(Ordering::SeqCst), 0);
+    }
+}
+
+#[cfg(not(loom))]
 #[cfg(test)]
 mod benches {
     use std::{
@@ -145,3 +352,54 @@ mod benches {
         dbg!(now.elapsed());
     }
 }
+
+#[cfg(loom)]
+#[cfg(test)]
+mod loom_tests {
+    use loom::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::*;
+
+    #[test]
+    fn loom_mutex1_one_winner() {
+        loom::model(|| {
+            let lock = Arc::new(Mutex1::new());
+            let winners = Arc::new(AtomicUsize::new(0));
+            let threads = (0..2)
+                .map(|_| {
+                    let lock = Arc::clone(&lock);
+                    let winners = Arc::clone(&winners);
+                    loom::thread::spawn(move || {
+                        if lock.try_lock() {
+                            winners.fetch_add(1, Ordering::Relaxed);
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            for thread in threads {
+                thread.join().unwrap();
+            }
+            assert_eq!(winners.load(Ordering::Relaxed), 1);
+        });
+    }
+
+    #[test]
+    fn loom_spin_mutex_increments() {
+        loom::model(|| {
+            let mutex = Arc::new(SpinMutex::new(0));
+            let threads = (0..2)
+                .map(|_| {
+                    let mutex = Arc::clone(&mutex);
+                    loom::thread::spawn(move || *mutex.lock() += 1)
+                })
+                .collect::<Vec<_>>();
+            for thread in threads {
+                thread.join().unwrap();
+            }
+            assert_eq!(*mutex.lock(), 2);
+        });
+    }
+}
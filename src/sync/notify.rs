@@ -11,6 +11,15 @@ struct CriticalNotify {
     pub wait_queue: IndQueue<Arc<WaitToken>>,
     pub reused_wait_tokens: Vec<Arc<WaitToken>>,
 }
+impl CriticalNotify {
+    /// A woken token might still be waited on by a [`Notified::wait()`] that cloned it earlier and must not be cleared under it
+    fn reuse(&mut self, token: Arc<WaitToken>) {
+        if Arc::strong_count(&token) != 1 {
+            return;
+        }
+        self.reused_wait_tokens.push(token);
+    }
+}
 impl Notify {
     #[must_use]
     pub const fn new() -> Self {
@@ -46,13 +55,13 @@ impl Notify {
             return;
         };
         token.wake();
-        state.reused_wait_tokens.push(token);
+        state.reuse(token);
     }
     pub fn notify_all(&self) {
         let mut state = self.state.lock().unwrap();
         while let Some(token) = state.wait_queue.dequeue() {
             token.wake();
-            state.reused_wait_tokens.push(token);
+            state.reuse(token);
         }
     }
 }
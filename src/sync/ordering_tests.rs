@@ -0,0 +1,95 @@
+//! A new [`SpMcastReader`] racing a push
+//!
+//! The version protocol is laid out above [`SpMcast::next_version()`].
+//! A reader subscribing at the cell `next_pos` must get exactly the values pushed at and after that cell:
+//!
+//! - it must not skip the value of a push into that cell in flight during the subscription
+//! - it must not take the value left in that cell by the lap before
+//!
+//! The loom model checks the interleavings of the subscription and the push along with the stale loads the memory model allows.
+//! The stress test only samples the interleavings the scheduler happens to produce;
+//! it runs longer on aarch64, where stale loads show too.
+
+#[cfg(loom)]
+use loom::sync::Arc;
+#[cfg(not(loom))]
+use std::sync::{Arc, Barrier};
+
+use crate::ops::dyn_ref::DynRef;
+
+use super::mcast::{SpMcast, SpMcastReader};
+
+/// Cells of the ring; small so that every cell holds a value of the lap before
+const N: usize = 4;
+
+/// A full lap of `1..=N` so that the next push overwrites cell zero
+fn lapped_queue() -> Arc<SpMcast<usize, N>> {
+    let queue = Arc::new(SpMcast::new());
+    for i in 1..=N {
+        unsafe { queue.push(i) };
+    }
+    queue
+}
+
+/// Check what a reader subscribed during the push of `N + 1` into cell zero delivers
+///
+/// `early` is what it popped before the push completed.
+fn assert_subscription(
+    reader: &mut SpMcastReader<usize, N, Arc<SpMcast<usize, N>>>,
+    start: usize,
+    early: Option<usize>,
+) {
+    let popped = early
+        .into_iter()
+        .chain(core::iter::from_fn(|| reader.pop()))
+        .collect::<Vec<_>>();
+    match start {
+        // Subscribed before the push completed
+        0 => assert_eq!(popped, [N + 1], "skipped the push or took the lap before"),
+        // Subscribed after; cell one still holds the lap before
+        1 => assert_eq!(popped, [], "took the lap before"),
+        _ => unreachable!("{start}"),
+    }
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_subscribe_during_push() {
+    const ROUNDS: usize = if cfg!(target_arch = "aarch64") {
+        1 << 16
+    } else {
+        1 << 12
+    };
+    for _ in 0..ROUNDS {
+        let queue = lapped_queue();
+        let barrier = Barrier::new(2);
+        let mut reader = std::thread::scope(|s| {
+            s.spawn(|| {
+                barrier.wait();
+                unsafe { queue.push(N + 1) };
+            });
+            barrier.wait();
+            SpMcastReader::new(DynRef::new(Arc::clone(&queue), |q| q.as_ref()))
+        });
+        let start = reader.debug_summary().next_pos;
+        assert_subscription(&mut reader, start, None);
+    }
+}
+
+#[cfg(loom)]
+#[test]
+fn loom_subscribe_during_push() {
+    loom::model(|| {
+        let queue = lapped_queue();
+        let writer = loom::thread::spawn({
+            let queue = Arc::clone(&queue);
+            move || unsafe { queue.push(N + 1) }
+        });
+        let mut reader = SpMcastReader::new(DynRef::new(Arc::clone(&queue), |q| q.as_ref()));
+        let start = reader.debug_summary().next_pos;
+        // Unlike the pops after the join, this one can still see the lap before in the cell
+        let early = reader.pop();
+        writer.join().unwrap();
+        assert_subscription(&mut reader, start, early);
+    });
+}
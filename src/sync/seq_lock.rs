@@ -1,21 +1,37 @@
-use core::sync::atomic::{fence, AtomicU32, Ordering};
+#[cfg(loom)]
+use core::{mem::MaybeUninit, ptr};
 use std::sync::Arc;
 
-use super::sync_unsafe_cell::SyncUnsafeCell;
+#[cfg(loom)]
+use super::atomic_shim::AtomicUsize;
+use super::{
+    atomic_shim::{const_unless_loom, fence, AtomicBool, AtomicU32, Ordering},
+    mcast::PopResult,
+    sync_unsafe_cell::SyncUnsafeCell,
+};
 
 /// - single producer, multiple consumers
 /// - prioritized in write
+#[repr(C)]
 #[derive(Debug)]
 pub struct SeqLock<T> {
     value: SyncUnsafeCell<T>,
     version: AtomicU32,
+    #[cfg(loom)]
+    stores: LoomStores<T>,
 }
+// Readers only ever get copies of the value, each of which is sent to the reading thread
+unsafe impl<T: Send> Sync for SeqLock<T> {}
 impl<T> SeqLock<T> {
-    #[must_use]
-    pub const fn new(value: T) -> Self {
-        Self {
-            value: SyncUnsafeCell::new(value),
-            version: AtomicU32::new(0),
+    const_unless_loom! {
+        #[must_use]
+        pub fn new(value: T) -> Self {
+            Self {
+                #[cfg(loom)]
+                stores: LoomStores::new(&value),
+                value: SyncUnsafeCell::new(value),
+                version: AtomicU32::new(0),
+            }
         }
     }
 
@@ -23,22 +39,40 @@ impl<T> SeqLock<T> {
     ///
     /// Must only be accessed by one thread at a time
     pub unsafe fn store(&self, value: T) {
-        let prev_start = self.version.fetch_add(1, Ordering::Acquire);
+        unsafe { self.store_with(value, || {}) };
+    }
+    /// Same as [`Self::store()`] but run `locked` once the version is odd and before the value is written
+    ///
+    /// # Safety
+    ///
+    /// Same as [`Self::store()`]
+    pub(crate) unsafe fn store_with(&self, value: T, locked: impl FnOnce()) {
+        let prev_start = self.version.fetch_add(1, Ordering::Relaxed);
+        // Readers that see any of the new value must also see the odd version
+        fence(Ordering::Release);
+        locked();
         let v = unsafe { self.value.get().as_mut() }.unwrap();
         *v = value;
+        #[cfg(loom)]
+        self.stores.push(v);
         let prev_end = self.version.fetch_add(1, Ordering::Release);
         assert_eq!(prev_start & 1, 0);
         assert_eq!(prev_end & 1, 1);
     }
 
+    #[inline]
     #[must_use]
     pub fn load(&self) -> Option<(T, u32)>
     where
         T: Copy,
     {
         let start = self.version.load(Ordering::Acquire);
+        #[cfg(not(loom))]
         let v = *unsafe { self.value.get().as_ref() }.unwrap();
-        fence(Ordering::Release);
+        #[cfg(loom)]
+        let v = self.stores.load();
+        // Keep the read of the value before the second read of the version
+        fence(Ordering::Acquire);
         let end = self.version.load(Ordering::Relaxed);
         let start_in_write = start & 1 == 1;
         let span_thru_write = start != end;
@@ -48,45 +82,114 @@ impl<T> SeqLock<T> {
         Some((v, start))
     }
 
+    #[inline]
     #[must_use]
     pub fn version(&self) -> u32 {
         self.version.load(Ordering::SeqCst)
     }
 }
 
+/// Every value stored, for loom to pick the one a racing read sees
+///
+/// Loom does not model plain memory, so a read of [`SeqLock::value`] always sees the latest write
+/// even if loom hands out an older version to the same reader.
+/// Reading through the index in [`Self::last`] instead lets loom pair the value and the versions the way the memory model allows.
+#[cfg(loom)]
+#[derive(Debug)]
+struct LoomStores<T> {
+    /// Bitwise copies; never dropped
+    values: std::sync::Mutex<Vec<MaybeUninit<T>>>,
+    last: AtomicUsize,
+}
+#[cfg(loom)]
+impl<T> LoomStores<T> {
+    fn new(value: &T) -> Self {
+        let values = vec![MaybeUninit::new(unsafe { ptr::read(value) })];
+        Self {
+            values: std::sync::Mutex::new(values),
+            last: AtomicUsize::new(0),
+        }
+    }
+    fn push(&self, value: &T) {
+        let i = {
+            let mut values = self.values.lock().unwrap();
+            values.push(MaybeUninit::new(unsafe { ptr::read(value) }));
+            values.len() - 1
+        };
+        // Not under the lock since loom may switch threads at any of its atomics
+        self.last.store(i, Ordering::Relaxed);
+    }
+    fn load(&self) -> T
+    where
+        T: Copy,
+    {
+        let i = self.last.load(Ordering::Relaxed);
+        unsafe { self.values.lock().unwrap()[i].assume_init() }
+    }
+}
+
 pub fn safe_seq_lock<T>(value: T) -> (SeqLockReader<T>, SeqLockWriter<T>) {
     let lock = SeqLock::new(value);
-    let lock = Arc::new(lock);
+    let closed = AtomicBool::new(false);
+    let shared = Arc::new(SharedSeqLock { lock, closed });
     let reader = SeqLockReader {
-        lock: Arc::clone(&lock),
-    };
-    let writer = SeqLockWriter {
-        lock: Arc::clone(&lock),
+        shared: Arc::clone(&shared),
     };
+    let writer = SeqLockWriter { shared };
     (reader, writer)
 }
+#[derive(Debug)]
+struct SharedSeqLock<T> {
+    lock: SeqLock<T>,
+    /// Set once the writer is gone
+    closed: AtomicBool,
+}
 #[derive(Debug, Clone)]
 pub struct SeqLockReader<T> {
-    lock: Arc<SeqLock<T>>,
+    shared: Arc<SharedSeqLock<T>>,
 }
 impl<T> SeqLockReader<T> {
     pub fn load(&self) -> Option<T>
     where
         T: Copy,
     {
-        self.lock.load().map(|(x, _)| x)
+        self.shared.lock.load().map(|(x, _)| x)
+    }
+    /// [`PopResult::Closed`] once the writer is gone, [`PopResult::Empty`] if the load overlapped a store
+    ///
+    /// The last value stays readable by [`Self::load()`] after the close.
+    pub fn load_or_closed(&self) -> PopResult<T>
+    where
+        T: Copy,
+    {
+        if self.shared.closed.load(Ordering::Acquire) {
+            return PopResult::Closed;
+        }
+        match self.load() {
+            Some(value) => PopResult::Item(value),
+            None => PopResult::Empty,
+        }
     }
 }
+/// Closes the lock on drop
 #[derive(Debug)]
 pub struct SeqLockWriter<T> {
-    lock: Arc<SeqLock<T>>,
+    shared: Arc<SharedSeqLock<T>>,
 }
 impl<T> SeqLockWriter<T> {
     pub fn store(&mut self, value: T) {
-        unsafe { self.lock.store(value) };
+        unsafe { self.shared.lock.store(value) };
+    }
+    /// Same as dropping `self`
+    pub fn close(self) {}
+}
+impl<T> Drop for SeqLockWriter<T> {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
     }
 }
 
+#[cfg(not(loom))]
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -134,4 +237,63 @@ mod tests {
             handle.join().unwrap();
         }
     }
+
+    #[test]
+    fn test_close() {
+        const N: usize = 1 << 12;
+        let (reader, mut writer) = safe_seq_lock(RepeatedData::<_, DATA_COUNT>::new(0));
+        std::thread::scope(|s| {
+            for _ in 0..THREADS {
+                let reader = reader.clone();
+                s.spawn(move || {
+                    let mut prev = 0;
+                    loop {
+                        match reader.load_or_closed() {
+                            PopResult::Item(data) => {
+                                data.assert();
+                                assert!(prev <= data.get()[0]);
+                                prev = data.get()[0];
+                            }
+                            PopResult::Empty => (),
+                            PopResult::Closed => break,
+                        }
+                    }
+                    // The last store is still there
+                    assert_eq!(reader.load().unwrap().get()[0], N - 1);
+                });
+            }
+            for i in 0..N {
+                writer.store(RepeatedData::new(i));
+            }
+            writer.close();
+        });
+        assert!(matches!(reader.load_or_closed(), PopResult::Closed));
+    }
+}
+
+#[cfg(loom)]
+#[cfg(test)]
+mod loom_tests {
+    use super::*;
+
+    #[test]
+    fn loom_seq_lock_no_torn_read() {
+        loom::model(|| {
+            let (reader, mut writer) = safe_seq_lock([0_u32; 2]);
+            let reader = loom::thread::spawn(move || {
+                let mut prev = 0;
+                for _ in 0..2 {
+                    let Some([a, b]) = reader.load() else {
+                        continue;
+                    };
+                    assert_eq!(a, b);
+                    assert!(prev <= a);
+                    prev = a;
+                }
+            });
+            writer.store([1; 2]);
+            writer.store([2; 2]);
+            reader.join().unwrap();
+        });
+    }
 }
@@ -18,6 +18,8 @@ pub struct SyncUnsafeCell<T: ?Sized> {
     value: UnsafeCell<T>,
 }
 
+// `T: Sync` is required since `&SyncUnsafeCell<T>` hands out `&T` through `get()` to every sharing thread.
+// Use [`super::sync_wrapper::SyncWrapper`] for a `T` that is not `Sync`.
 unsafe impl<T: ?Sized + Sync> Sync for SyncUnsafeCell<T> {}
 
 impl<T> SyncUnsafeCell<T> {
@@ -82,3 +84,25 @@ impl<T> From<T> for SyncUnsafeCell<T> {
         SyncUnsafeCell::new(t)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn test_accessors() {
+        let mut cell = SyncUnsafeCell::new(vec![1]);
+        cell.get_mut().push(2);
+        unsafe { (*SyncUnsafeCell::raw_get(&cell)).push(3) };
+        assert_eq!(unsafe { &*cell.get() }, &[1, 2, 3]);
+        assert_eq!(cell.into_inner(), [1, 2, 3]);
+
+        let cell = Arc::new(SyncUnsafeCell::new(0));
+        std::thread::scope(|s| {
+            s.spawn(|| unsafe { *cell.get() = 1 });
+        });
+        assert_eq!(Arc::into_inner(cell).unwrap().into_inner(), 1);
+    }
+}
@@ -0,0 +1,72 @@
+/// [`Sync`] for any `T` by only handing out exclusive access
+///
+/// A shared `&SyncWrapper<T>` can do nothing with the inner value, so sharing it between threads is sound even if `T` is not [`Sync`].
+///
+/// ```compile_fail
+/// use core::cell::Cell;
+///
+/// use primitive::sync::sync_wrapper::SyncWrapper;
+///
+/// let w = SyncWrapper::new(Cell::new(0));
+/// let shared = &w;
+/// shared.get_mut().set(1);
+/// ```
+#[derive(Default)]
+#[repr(transparent)]
+pub struct SyncWrapper<T: ?Sized> {
+    value: T,
+}
+// SAFETY: no `&T` can be obtained through a `&SyncWrapper<T>`
+unsafe impl<T: ?Sized> Sync for SyncWrapper<T> {}
+impl<T> SyncWrapper<T> {
+    pub const fn new(value: T) -> Self {
+        Self { value }
+    }
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+impl<T: ?Sized> SyncWrapper<T> {
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+/// Leaves the inner value out since it would take a `&T`
+impl<T: ?Sized> core::fmt::Debug for SyncWrapper<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SyncWrapper").finish_non_exhaustive()
+    }
+}
+impl<T> From<T> for SyncWrapper<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use std::sync::Mutex;
+
+    use super::*;
+
+    fn assert_sync<T: Sync>(_: &T) {}
+
+    #[test]
+    fn test_sync_wrapper() {
+        let mut w = SyncWrapper::new(Cell::new(0));
+        assert_sync(&w);
+        w.get_mut().set(1);
+        let shared = Mutex::new(w);
+        std::thread::scope(|s| {
+            s.spawn(|| shared.lock().unwrap().get_mut().set(2));
+        });
+        assert_eq!(shared.into_inner().unwrap().into_inner().get(), 2);
+    }
+
+    #[test]
+    fn test_debug_opaque() {
+        let w = SyncWrapper::new(Cell::new(3));
+        assert_eq!(format!("{w:?}"), "SyncWrapper { .. }");
+    }
+}
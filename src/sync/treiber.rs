@@ -0,0 +1,332 @@
+use core::{
+    mem::MaybeUninit,
+    ptr::{self, NonNull},
+};
+
+use crate::ops::len::Len;
+
+use super::{
+    atomic_shim::{AtomicPtr, AtomicU64, AtomicUsize, Ordering, UnsafeCell},
+    cache_pad::CachePadded,
+    mutex::SpinMutex,
+};
+
+const ARENA_CHUNK: usize = 64;
+/// The tag takes the address bits a user-space pointer never sets
+const TAG_SHIFT: u32 = if usize::BITS == 64 { 48 } else { 32 };
+const ADDR_MASK: u64 = (1 << TAG_SHIFT) - 1;
+
+/// Lock-free LIFO stack
+///
+/// The nodes live in an arena that only grows, so a node popped by one thread stays valid memory for the others still reading it,
+/// and each head carries a generation tag bumped on every change so a node popped and pushed back in between fails the CAS instead of ABA.
+/// The tag is 16 bits on 64-bit targets, so a thread has to stall across exactly a multiple of 65536 head changes to be fooled.
+///
+/// Popped nodes are kept for the next pushes; the arena is freed on drop.
+#[derive(Debug)]
+pub struct TreiberStack<T> {
+    items: CachePadded<TaggedHead<T>>,
+    free: CachePadded<TaggedHead<T>>,
+    len: AtomicUsize,
+    arena: SpinMutex<Arena<T>>,
+}
+unsafe impl<T: Send> Send for TreiberStack<T> {}
+unsafe impl<T: Send> Sync for TreiberStack<T> {}
+impl<T> TreiberStack<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            items: CachePadded::new(TaggedHead::new()),
+            free: CachePadded::new(TaggedHead::new()),
+            len: AtomicUsize::new(0),
+            arena: SpinMutex::new(Arena::new()),
+        }
+    }
+    /// Only takes the arena lock if no popped node is left to reuse
+    pub fn push(&self, value: T) {
+        let node = match self.free.pop() {
+            Some(node) => node,
+            None => self.arena.lock().alloc(),
+        };
+        let node_ref = unsafe { node.as_ref() };
+        node_ref
+            .value
+            .with_mut(|slot| unsafe { (*slot).write(value) });
+        self.len.fetch_add(1, Ordering::Relaxed);
+        self.items.push(node);
+    }
+    pub fn pop(&self) -> Option<T> {
+        let node = self.items.pop()?;
+        self.len.fetch_sub(1, Ordering::Relaxed);
+        let value = unsafe { node.as_ref() }
+            .value
+            .with(|slot| unsafe { (*slot).assume_init_read() });
+        self.free.push(node);
+        Some(value)
+    }
+    /// Number of nodes allocated so far, i.e. the peak [`Len::len()`]
+    #[must_use]
+    pub fn nodes(&self) -> usize {
+        self.arena.lock().nodes()
+    }
+}
+impl<T> Default for TreiberStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+/// Approximate while other threads push or pop since the counter is updated apart from the heads
+impl<T> Len for TreiberStack<T> {
+    fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+}
+impl<T> Drop for TreiberStack<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+#[derive(Debug)]
+struct Node<T> {
+    next: AtomicPtr<Node<T>>,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// Head pointer and generation tag packed in one word
+#[derive(Debug)]
+struct TaggedHead<T> {
+    word: AtomicU64,
+    _node: core::marker::PhantomData<*mut Node<T>>,
+}
+impl<T> TaggedHead<T> {
+    fn new() -> Self {
+        Self {
+            word: AtomicU64::new(pack(ptr::null_mut::<Node<T>>(), 0)),
+            _node: core::marker::PhantomData,
+        }
+    }
+    fn push(&self, node: NonNull<Node<T>>) {
+        let node_ref = unsafe { node.as_ref() };
+        let mut word = self.word.load(Ordering::Relaxed);
+        loop {
+            let (head, tag) = unpack::<T>(word);
+            node_ref.next.store(head, Ordering::Relaxed);
+            let new = pack(node.as_ptr(), tag.wrapping_add(1));
+            match self
+                .word
+                .compare_exchange_weak(word, new, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => return,
+                Err(actual) => word = actual,
+            }
+        }
+    }
+    fn pop(&self) -> Option<NonNull<Node<T>>> {
+        let mut word = self.word.load(Ordering::Acquire);
+        loop {
+            let (head, tag) = unpack::<T>(word);
+            let head = NonNull::new(head)?;
+            // The node may be popped and reused meanwhile; its memory is still there and the tag rejects the stale `next`
+            let next = unsafe { head.as_ref() }.next.load(Ordering::Relaxed);
+            let new = pack(next, tag.wrapping_add(1));
+            match self
+                .word
+                .compare_exchange_weak(word, new, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => return Some(head),
+                Err(actual) => word = actual,
+            }
+        }
+    }
+}
+
+fn pack<T>(ptr: *mut Node<T>, tag: u64) -> u64 {
+    let addr = ptr.expose_provenance() as u64;
+    debug_assert_eq!(addr & !ADDR_MASK, 0);
+    addr | (tag << TAG_SHIFT)
+}
+fn unpack<T>(word: u64) -> (*mut Node<T>, u64) {
+    let ptr = ptr::with_exposed_provenance_mut((word & ADDR_MASK) as usize);
+    (ptr, word >> TAG_SHIFT)
+}
+
+/// Fixed-size chunks never moved nor freed until drop
+///
+/// The chunks are kept as raw pointers so no `&mut` to a chunk ever invalidates the nodes other threads point into.
+#[derive(Debug)]
+struct Arena<T> {
+    chunks: Vec<NonNull<[Node<T>]>>,
+    /// Next unused node in the last chunk
+    next: usize,
+}
+impl<T> Arena<T> {
+    const fn new() -> Self {
+        Self {
+            chunks: vec![],
+            next: ARENA_CHUNK,
+        }
+    }
+    /// # Panic
+    ///
+    /// The allocator returns an address the tag would overlap
+    fn alloc(&mut self) -> NonNull<Node<T>> {
+        if self.next == ARENA_CHUNK {
+            let chunk = (0..ARENA_CHUNK)
+                .map(|_| Node {
+                    next: AtomicPtr::new(ptr::null_mut()),
+                    value: UnsafeCell::new(MaybeUninit::uninit()),
+                })
+                .collect::<Box<[_]>>();
+            let chunk = NonNull::from(Box::leak(chunk));
+            let end = chunk.as_ptr().cast::<Node<T>>().wrapping_add(ARENA_CHUNK);
+            assert_eq!(end.addr() as u64 & !ADDR_MASK, 0);
+            self.chunks.push(chunk);
+            self.next = 0;
+        }
+        let chunk = *self.chunks.last().unwrap();
+        let node = unsafe { chunk.cast::<Node<T>>().add(self.next) };
+        self.next += 1;
+        node
+    }
+    fn nodes(&self) -> usize {
+        self.chunks.len() * ARENA_CHUNK - (ARENA_CHUNK - self.next)
+    }
+}
+impl<T> Drop for Arena<T> {
+    fn drop(&mut self) {
+        for chunk in self.chunks.drain(..) {
+            drop(unsafe { Box::from_raw(chunk.as_ptr()) });
+        }
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use crate::ops::len::LenExt;
+
+    use super::*;
+
+    #[test]
+    fn test_lifo() {
+        let s = TreiberStack::new();
+        assert_eq!(s.pop(), None);
+        for i in 0..100 {
+            s.push(i);
+        }
+        assert_eq!(s.len(), 100);
+        for i in (50..100).rev() {
+            assert_eq!(s.pop(), Some(i));
+        }
+        for i in 0..50 {
+            s.push(i + 1000);
+        }
+        assert_eq!(s.nodes(), 100);
+        assert_eq!(s.pop(), Some(1049));
+        assert_eq!(s.len(), 99);
+    }
+
+    /// Small enough for Miri
+    #[test]
+    fn test_threads_small() {
+        const THREADS: usize = 2;
+        const N: usize = 8;
+
+        let s = TreiberStack::new();
+        thread::scope(|scope| {
+            for t in 0..THREADS {
+                let s = &s;
+                scope.spawn(move || {
+                    for i in 0..N {
+                        s.push(t * N + i);
+                        s.pop().unwrap();
+                        s.push(t * N + i);
+                    }
+                });
+            }
+        });
+        let mut popped = core::iter::from_fn(|| s.pop()).collect::<Vec<_>>();
+        popped.sort_unstable();
+        assert_eq!(popped, (0..THREADS * N).collect::<Vec<_>>());
+        assert!(s.is_empty());
+    }
+
+    #[derive(Debug)]
+    struct Counted(Arc<AtomicUsize>);
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_stress_drop_count() {
+        const PRODUCERS: usize = 4;
+        const CONSUMERS: usize = 4;
+        const N: usize = 1 << 14;
+
+        let drops = Arc::new(AtomicUsize::new(0));
+        let popped = AtomicUsize::new(0);
+        let s = TreiberStack::new();
+        thread::scope(|scope| {
+            for _ in 0..PRODUCERS {
+                scope.spawn(|| {
+                    for _ in 0..N {
+                        s.push(Counted(Arc::clone(&drops)));
+                    }
+                });
+            }
+            for _ in 0..CONSUMERS {
+                scope.spawn(|| {
+                    for _ in 0..N / 2 {
+                        if s.pop().is_some() {
+                            popped.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                });
+            }
+        });
+        let popped = popped.load(Ordering::Relaxed);
+        assert_eq!(drops.load(Ordering::Relaxed), popped);
+        assert_eq!(s.len(), PRODUCERS * N - popped);
+        assert!(s.nodes() <= PRODUCERS * N);
+        drop(s);
+        assert_eq!(drops.load(Ordering::Relaxed), PRODUCERS * N);
+    }
+}
+
+#[cfg(loom)]
+#[cfg(test)]
+mod loom_tests {
+    use loom::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn loom_treiber_push_pop() {
+        loom::model(|| {
+            let s = Arc::new(TreiberStack::new());
+            s.push(0);
+            let threads = (1..3)
+                .map(|i| {
+                    let s = Arc::clone(&s);
+                    loom::thread::spawn(move || {
+                        s.push(i);
+                        s.pop().unwrap()
+                    })
+                })
+                .collect::<Vec<_>>();
+            let mut values = threads
+                .into_iter()
+                .map(|thread| thread.join().unwrap())
+                .collect::<Vec<_>>();
+            values.push(s.pop().unwrap());
+            values.sort_unstable();
+            assert_eq!(values, [0, 1, 2]);
+            assert_eq!(s.pop(), None);
+        });
+    }
+}
@@ -0,0 +1,195 @@
+use core::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use std::{
+    sync::{Arc, Weak},
+    thread::JoinHandle,
+    time::Instant,
+};
+
+/// Source of [`Instant`]s
+pub trait Clock {
+    #[must_use]
+    fn now(&self) -> Instant;
+}
+
+/// [`Instant::now()`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Nanoseconds past an origin, shared by the clones
+#[derive(Debug, Clone)]
+struct SharedOffset {
+    origin: Instant,
+    nanos: Arc<AtomicU64>,
+}
+impl SharedOffset {
+    fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            nanos: Arc::new(AtomicU64::new(0)),
+        }
+    }
+    fn now(&self) -> Instant {
+        self.origin + Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+}
+
+/// [`Instant`] cached at the last [`Self::tick()`], which costs an atomic load to read
+///
+/// Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct CoarseClock {
+    offset: SharedOffset,
+}
+impl CoarseClock {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            offset: SharedOffset::new(),
+        }
+    }
+    /// Catch up with [`Instant::now()`]
+    ///
+    /// Never moves the clock backwards, even if the tickers race.
+    pub fn tick(&self) {
+        let nanos = self.offset.origin.elapsed().as_nanos();
+        let nanos = u64::try_from(nanos).unwrap_or(u64::MAX);
+        self.offset.nanos.fetch_max(nanos, Ordering::Relaxed);
+    }
+    /// [`Self::tick()`] every `period` on a background thread until all the clones are dropped
+    pub fn spawn_updater(&self, period: Duration) -> JoinHandle<()> {
+        let origin = self.offset.origin;
+        let nanos = Arc::downgrade(&self.offset.nanos);
+        std::thread::spawn(move || loop {
+            std::thread::sleep(period);
+            let Some(nanos) = Weak::upgrade(&nanos) else {
+                return;
+            };
+            let clock = Self {
+                offset: SharedOffset { origin, nanos },
+            };
+            clock.tick();
+        })
+    }
+}
+impl Default for CoarseClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Clock for CoarseClock {
+    fn now(&self) -> Instant {
+        self.offset.now()
+    }
+}
+
+/// Only moves on [`Self::advance()`]
+///
+/// Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    offset: SharedOffset,
+}
+impl ManualClock {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            offset: SharedOffset::new(),
+        }
+    }
+    /// # Panic
+    ///
+    /// The clock would be more than `u64::MAX` nanoseconds (about 584 years) past its creation
+    pub fn advance(&self, duration: Duration) {
+        let nanos = u64::try_from(duration.as_nanos()).expect("clock overflow");
+        self.offset
+            .nanos
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                n.checked_add(nanos)
+            })
+            .expect("clock overflow");
+    }
+}
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.offset.now()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        assert_eq!(clock.now(), start);
+        let other = clock.clone();
+        other.advance(Duration::from_millis(3));
+        clock.advance(Duration::from_nanos(1));
+        assert_eq!(clock.now() - start, Duration::new(0, 3_000_001));
+        assert_eq!(other.now(), clock.now());
+    }
+
+    #[test]
+    fn test_coarse_monotonic() {
+        let clock = CoarseClock::new();
+        let start = clock.now();
+        let mut prev = start;
+        for _ in 0..1 << 10 {
+            clock.tick();
+            let now = clock.now();
+            assert!(prev <= now);
+            prev = now;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(clock.now(), prev);
+        clock.tick();
+        assert!(prev < clock.now());
+
+        // Concurrent tickers never roll back what the others have published
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let clock = clock.clone();
+                std::thread::spawn(move || {
+                    let mut prev = clock.now();
+                    for _ in 0..1 << 12 {
+                        clock.tick();
+                        let now = clock.now();
+                        assert!(prev <= now);
+                        prev = now;
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert!(start < clock.now());
+    }
+
+    #[test]
+    fn test_coarse_updater() {
+        let clock = CoarseClock::new();
+        let start = clock.now();
+        let updater = clock.spawn_updater(Duration::from_millis(1));
+        while clock.now() == start {
+            std::thread::yield_now();
+        }
+        drop(clock);
+        updater.join().unwrap();
+    }
+}
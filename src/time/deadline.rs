@@ -0,0 +1,147 @@
+use core::time::Duration;
+use std::time::Instant;
+
+/// Deadlines beyond what [`Instant`] can represent are clamped to this far from the base instant
+///
+/// It is far enough to never expire in practice.
+pub const FAR_FUTURE: Duration = Duration::from_secs(60 * 60 * 24 * 365 * 30);
+
+/// A point in time after which something expires
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Deadline {
+    at: Instant,
+}
+impl Deadline {
+    #[must_use]
+    pub fn after(duration: Duration) -> Self {
+        Self::after_from(Instant::now(), duration)
+    }
+    /// Clamped to [`FAR_FUTURE`] past `now` if `now + duration` overflows [`Instant`]
+    #[must_use]
+    pub fn after_from(now: Instant, duration: Duration) -> Self {
+        Self {
+            at: saturating_add(now, duration),
+        }
+    }
+    #[must_use]
+    pub const fn at(at: Instant) -> Self {
+        Self { at }
+    }
+    #[must_use]
+    pub const fn instant(&self) -> Instant {
+        self.at
+    }
+    /// Zero if expired
+    #[must_use]
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.at.saturating_duration_since(now)
+    }
+    /// [`None`] if expired
+    #[must_use]
+    pub fn checked_remaining(&self, now: Instant) -> Option<Duration> {
+        let remaining = self.at.checked_duration_since(now)?;
+        if remaining.is_zero() {
+            return None;
+        }
+        Some(remaining)
+    }
+    #[must_use]
+    pub fn is_expired(&self, now: Instant) -> bool {
+        self.at <= now
+    }
+    /// Clamped the same way as [`Self::after_from()`]
+    pub fn extend(&mut self, duration: Duration) {
+        self.at = saturating_add(self.at, duration);
+    }
+}
+impl From<Instant> for Deadline {
+    fn from(value: Instant) -> Self {
+        Self::at(value)
+    }
+}
+
+fn saturating_add(instant: Instant, duration: Duration) -> Instant {
+    if let Some(at) = instant.checked_add(duration) {
+        return at;
+    }
+    if let Some(at) = instant.checked_add(FAR_FUTURE) {
+        return at;
+    }
+    // `instant` itself is near the representable edge; approach it by halving
+    let mut at = instant;
+    let mut step = duration.min(FAR_FUTURE);
+    while !step.is_zero() {
+        if let Some(next) = at.checked_add(step) {
+            at = next;
+        } else {
+            step /= 2;
+        }
+    }
+    at
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    /// The latest representable instant reachable from `now`, give or take a nanosecond
+    fn edge(now: Instant) -> Instant {
+        let mut at = now;
+        let mut step = Duration::MAX;
+        while !step.is_zero() {
+            match at.checked_add(step) {
+                Some(next) => at = next,
+                None => step /= 2,
+            }
+        }
+        at
+    }
+
+    #[test]
+    fn test_deadline() {
+        let now = Instant::now();
+        let d = Deadline::after_from(now, SECOND);
+        assert_eq!(d.remaining(now), SECOND);
+        assert_eq!(d.checked_remaining(now), Some(SECOND));
+        assert!(!d.is_expired(now));
+        let later = now + SECOND;
+        assert!(d.is_expired(later));
+        assert_eq!(d.remaining(later), Duration::ZERO);
+        assert_eq!(d.checked_remaining(later), None);
+        assert_eq!(d.checked_remaining(later + SECOND), None);
+        let mut e = d;
+        e.extend(SECOND);
+        assert_eq!(e, Deadline::at(now + 2 * SECOND));
+        assert!(!Deadline::after(SECOND).is_expired(Instant::now()));
+    }
+
+    #[test]
+    fn test_far_future() {
+        let now = Instant::now();
+        let far = Deadline::after_from(now, Duration::MAX);
+        assert_eq!(far.instant(), now + FAR_FUTURE);
+        assert!(!far.is_expired(now));
+        let mut extended = far;
+        extended.extend(Duration::MAX);
+        assert!(far <= extended);
+
+        let edge = edge(now);
+        assert!(edge.checked_add(SECOND).is_none());
+        let mut d = Deadline::at(edge - SECOND);
+        d.extend(2 * SECOND);
+        assert!(edge - SECOND < d.instant());
+        assert!(d.instant() <= edge);
+        assert_eq!(Deadline::after_from(edge, SECOND).instant(), edge);
+    }
+
+    #[test]
+    fn test_ordering() {
+        let now = Instant::now();
+        let mut deadlines = [3, 1, 2].map(|s| Deadline::after_from(now, s * SECOND));
+        deadlines.sort();
+        assert_eq!(deadlines.map(|d| d.remaining(now).as_secs()), [1, 2, 3]);
+        assert!(Deadline::after_from(now, Duration::MAX) > deadlines[2]);
+    }
+}
@@ -1,2 +1,5 @@
+pub mod clock;
+pub mod deadline;
+pub mod rate;
 pub mod stopwatch;
 pub mod timer;
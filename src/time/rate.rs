@@ -0,0 +1,301 @@
+use core::{num::NonZeroUsize, time::Duration};
+use std::time::Instant;
+
+use crate::{
+    ops::{
+        clear::Clear,
+        float::PosR,
+        len::{Capacity, Len},
+    },
+    queue::cap_queue::CapArrayQueue,
+};
+
+use super::timer::Timer;
+
+/// Absorbs the rounding of the refill so whole tokens are not missed by an ulp
+const TOKEN_EPSILON: f64 = 1e-9;
+
+/// Token bucket
+///
+/// Starts full; the tokens refill at a constant rate and saturate at the burst.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    rate_per_sec: PosR<f64>,
+    burst: NonZeroUsize,
+    tokens: f64,
+    /// Started at the last refill
+    refilled: Timer,
+}
+impl RateLimiter {
+    #[must_use]
+    pub fn new(rate_per_sec: PosR<f64>, burst: NonZeroUsize) -> Self {
+        Self {
+            rate_per_sec,
+            burst,
+            tokens: burst.get() as f64,
+            refilled: Timer::new(),
+        }
+    }
+    pub fn try_acquire(&mut self, now: Instant) -> bool {
+        self.try_acquire_n(1, now)
+    }
+    /// Take all of the `n` tokens or none of them
+    ///
+    /// Never succeeds if `n` is greater than the burst.
+    pub fn try_acquire_n(&mut self, n: usize, now: Instant) -> bool {
+        self.refill(now);
+        let n = n as f64;
+        if self.tokens + TOKEN_EPSILON < n {
+            return false;
+        }
+        self.tokens = (self.tokens - n).max(0.);
+        true
+    }
+    /// Time until one token is available; zero if it is already
+    #[must_use]
+    pub fn next_available(&self, now: Instant) -> Option<Duration> {
+        self.next_available_n(1, now)
+    }
+    /// Time until `n` tokens are available; [`None`] if `n` is greater than the burst
+    ///
+    /// Saturates at [`Duration::MAX`] if the rate is too low for the wait to be represented.
+    #[must_use]
+    pub fn next_available_n(&self, n: usize, now: Instant) -> Option<Duration> {
+        if self.burst.get() < n {
+            return None;
+        }
+        let missing = n as f64 - self.tokens(now);
+        if missing <= TOKEN_EPSILON {
+            return Some(Duration::ZERO);
+        }
+        let wait = Duration::try_from_secs_f64(missing / self.rate_per_sec.get());
+        Some(wait.unwrap_or(Duration::MAX))
+    }
+    /// Whole and fractional tokens available at `now`
+    #[must_use]
+    pub fn tokens(&self, now: Instant) -> f64 {
+        // `Timer` only hands out its start through `&mut`
+        let mut refilled = self.refilled.clone();
+        let start = refilled.ensure_started(now);
+        let elapsed = now.saturating_duration_since(start).as_secs_f64();
+        let tokens = self.tokens + elapsed * self.rate_per_sec.get();
+        tokens.min(self.burst.get() as f64)
+    }
+    #[must_use]
+    pub fn rate_per_sec(&self) -> PosR<f64> {
+        self.rate_per_sec
+    }
+    #[must_use]
+    pub fn burst(&self) -> NonZeroUsize {
+        self.burst
+    }
+
+    fn refill(&mut self, now: Instant) {
+        self.tokens = self.tokens(now);
+        let start = self.refilled.ensure_started(now);
+        // A `now` earlier than the last refill must not rewind the timer and refill twice
+        if start < now {
+            self.refilled.restart(now);
+        }
+    }
+}
+/// Refill the bucket
+impl Clear for RateLimiter {
+    fn clear(&mut self) {
+        self.tokens = self.burst.get() as f64;
+        self.refilled.clear();
+    }
+}
+
+/// Events counted over the last `window`, for measuring a rate rather than limiting it
+///
+/// The window is split into `N` sub-buckets of `window / N` each;
+/// the newest one is being filled and the oldest one is dropped as time goes by,
+/// so the count is that of the last `window` up to the length of one sub-bucket.
+#[derive(Debug)]
+pub struct SlidingWindowCounter<const N: usize> {
+    /// The finished sub-buckets, oldest first
+    finished: CapArrayQueue<u64, N>,
+    current: u64,
+    /// Over all the sub-buckets
+    total: u64,
+    bucket_len: Duration,
+    current_start: Option<Instant>,
+}
+impl<const N: usize> SlidingWindowCounter<N> {
+    /// # Panic
+    ///
+    /// `N` is less than 2 or `window` is shorter than `N` nanoseconds
+    #[must_use]
+    pub fn new(window: Duration) -> Self {
+        assert!(2 <= N);
+        let bucket_len = window / N as u32;
+        assert!(!bucket_len.is_zero());
+        Self {
+            finished: CapArrayQueue::new_array(),
+            current: 0,
+            total: 0,
+            bucket_len,
+            current_start: None,
+        }
+    }
+    pub fn record(&mut self, now: Instant) {
+        self.record_n(1, now);
+    }
+    pub fn record_n(&mut self, n: u64, now: Instant) {
+        self.rotate(now);
+        self.current = self.current.saturating_add(n);
+        self.total = self.total.saturating_add(n);
+    }
+    /// Events in the window ending at `now`
+    pub fn count(&mut self, now: Instant) -> u64 {
+        self.rotate(now);
+        self.total
+    }
+    /// Events per second in the window ending at `now`
+    pub fn rate_per_sec(&mut self, now: Instant) -> f64 {
+        self.count(now) as f64 / self.window().as_secs_f64()
+    }
+    #[must_use]
+    pub fn window(&self) -> Duration {
+        self.bucket_len * N as u32
+    }
+
+    fn rotate(&mut self, now: Instant) {
+        let Some(start) = self.current_start else {
+            self.current_start = Some(now);
+            return;
+        };
+        let elapsed = now.saturating_duration_since(start);
+        let steps = elapsed.as_nanos() / self.bucket_len.as_nanos();
+        if steps == 0 {
+            return;
+        }
+        if N as u128 <= steps {
+            self.clear();
+            self.current_start = Some(now);
+            return;
+        }
+        for _ in 0..steps {
+            if self.finished.len() == self.finished.capacity() {
+                let oldest = self.finished.dequeue().unwrap();
+                self.total -= oldest;
+            }
+            self.finished.enqueue(self.current);
+            self.current = 0;
+        }
+        self.current_start = Some(start + self.bucket_len * steps as u32);
+    }
+}
+impl<const N: usize> Clear for SlidingWindowCounter<N> {
+    fn clear(&mut self) {
+        self.finished.clear();
+        self.current = 0;
+        self.total = 0;
+        self.current_start = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(rate_per_sec: f64, burst: usize) -> RateLimiter {
+        RateLimiter::new(
+            PosR::new(rate_per_sec).unwrap(),
+            NonZeroUsize::new(burst).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_burst() {
+        let now = Instant::now();
+        let mut l = limiter(10., 3);
+        for _ in 0..3 {
+            assert!(l.try_acquire(now));
+        }
+        assert!(!l.try_acquire(now));
+        assert_eq!(l.next_available(now), Some(Duration::from_millis(100)));
+
+        let mut l = limiter(10., 3);
+        assert!(!l.try_acquire_n(4, now));
+        assert_eq!(l.next_available_n(4, now), None);
+        assert!(l.try_acquire_n(3, now));
+        assert!(!l.try_acquire_n(1, now));
+    }
+
+    #[test]
+    fn test_refill() {
+        let start = Instant::now();
+        let mut l = limiter(5., 2);
+        assert!(l.try_acquire_n(2, start));
+        assert!(!l.try_acquire(start + Duration::from_millis(199)));
+        assert!(l.try_acquire(start + Duration::from_millis(200)));
+        assert!(!l.try_acquire(start + Duration::from_millis(200)));
+
+        // Saturates at the burst
+        let later = start + Duration::from_secs(60);
+        assert_eq!(l.tokens(later), 2.);
+        assert!(l.try_acquire_n(2, later));
+        assert!(!l.try_acquire(later));
+
+        // Stale instants neither refill nor panic
+        assert!(!l.try_acquire(start));
+        l.clear();
+        assert!(l.try_acquire_n(2, start));
+    }
+
+    #[test]
+    fn test_fractional_rate() {
+        let start = Instant::now();
+        let mut l = limiter(0.5, 1);
+        assert!(l.try_acquire(start));
+        assert_eq!(l.next_available(start), Some(Duration::from_secs(2)));
+        let half = start + Duration::from_secs(1);
+        assert!(!l.try_acquire(half));
+        assert_eq!(l.next_available(half), Some(Duration::from_secs(1)));
+        assert!(l.try_acquire(start + Duration::from_secs(2)));
+        assert!(!l.try_acquire(start + Duration::from_secs(3)));
+        assert_eq!(
+            l.next_available(start + Duration::from_secs(4)),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_tiny_rate() {
+        let now = Instant::now();
+        let mut l = limiter(f64::MIN_POSITIVE, 1);
+        assert!(l.try_acquire(now));
+        assert_eq!(l.next_available(now), Some(Duration::MAX));
+        assert!(!l.try_acquire(now + Duration::from_secs(1 << 40)));
+    }
+
+    #[test]
+    fn test_sliding_window() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut c = SlidingWindowCounter::<4>::new(ms(400));
+        assert_eq!(c.window(), ms(400));
+        c.record_n(2, start);
+        c.record(start + ms(50));
+        assert_eq!(c.count(start + ms(99)), 3);
+        c.record(start + ms(100));
+        c.record(start + ms(250));
+        assert_eq!(c.count(start + ms(399)), 5);
+        // The first sub-bucket drops out
+        assert_eq!(c.count(start + ms(400)), 2);
+        assert_eq!(c.rate_per_sec(start + ms(400)), 5.);
+        assert_eq!(c.count(start + ms(500)), 1);
+        // The sub-bucket of the event at 250 ms drops out
+        assert_eq!(c.count(start + ms(600)), 0);
+
+        c.record(start + ms(700));
+        // Idle for longer than the window
+        assert_eq!(c.count(start + ms(5000)), 0);
+        c.record(start + ms(5000));
+        assert_eq!(c.count(start + ms(5399)), 1);
+        c.clear();
+        assert_eq!(c.count(start), 0);
+    }
+}
@@ -1,22 +1,42 @@
 use core::time::Duration;
 use std::time::Instant;
 
-use crate::ops::clear::Clear;
+use crate::{
+    analysis::metrics::{MetricSource, MetricVisitor},
+    ops::clear::Clear,
+};
+
+use super::{
+    clock::{Clock, SystemClock},
+    deadline::Deadline,
+};
 
 #[derive(Debug, Clone)]
-pub struct Stopwatch {
+pub struct Stopwatch<C = SystemClock> {
     elapsed: Duration,
     start: Option<Instant>,
+    clock: C,
 }
 impl Stopwatch {
     pub const fn new(elapsed: Duration) -> Self {
+        Self::with_clock(elapsed, SystemClock)
+    }
+}
+impl<C> Stopwatch<C> {
+    pub const fn with_clock(elapsed: Duration, clock: C) -> Self {
         Self {
             elapsed,
             start: None,
+            clock,
         }
     }
-    pub fn start_scoped(&mut self) -> RunningWatch<'_> {
-        let now = Instant::now();
+    pub const fn clock(&self) -> &C {
+        &self.clock
+    }
+}
+impl<C: Clock> Stopwatch<C> {
+    pub fn start_scoped(&mut self) -> RunningWatch<'_, C> {
+        let now = self.clock.now();
         if let Some(start) = self.start.take() {
             self.elapsed += now - start;
         }
@@ -29,16 +49,19 @@ impl Stopwatch {
         if self.start.is_some() {
             return;
         }
-        self.start = Some(Instant::now());
+        self.start = Some(self.clock.now());
     }
     pub fn pause(&mut self) {
         let Some(start) = self.start.take() else {
             return;
         };
-        self.elapsed += start.elapsed();
+        self.elapsed += self.clock.now().duration_since(start);
     }
     pub fn elapsed(&self) -> Duration {
-        self.elapsed + self.start.map(|start| start.elapsed()).unwrap_or_default()
+        let running = self
+            .start
+            .map(|start| self.clock.now().duration_since(start));
+        self.elapsed + running.unwrap_or_default()
     }
 }
 impl Default for Stopwatch {
@@ -46,56 +69,78 @@ impl Default for Stopwatch {
         Self::new(Duration::ZERO)
     }
 }
-impl Clear for Stopwatch {
+impl<C> Clear for Stopwatch<C> {
     fn clear(&mut self) {
         self.elapsed = Duration::ZERO;
     }
 }
 
 #[derive(Debug)]
-pub struct RunningWatch<'a> {
-    stopwatch: &'a mut Stopwatch,
+pub struct RunningWatch<'a, C: Clock = SystemClock> {
+    stopwatch: &'a mut Stopwatch<C>,
     start: Instant,
 }
-impl RunningWatch<'_> {
+impl<C: Clock> RunningWatch<'_, C> {
     pub const fn start(&self) -> Instant {
         self.start
     }
     pub fn stop(mut self) -> Duration {
-        self.record_elapsed()
+        let elapsed = self.record_elapsed();
+        // Recorded once already
+        core::mem::forget(self);
+        elapsed
     }
     fn record_elapsed(&mut self) -> Duration {
-        let elapsed = self.start.elapsed();
+        let elapsed = self.stopwatch.clock.now().duration_since(self.start);
         self.stopwatch.elapsed += elapsed;
         elapsed
     }
 }
-impl Drop for RunningWatch<'_> {
+impl<C: Clock> Drop for RunningWatch<'_, C> {
     fn drop(&mut self) {
         self.record_elapsed();
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct ElapsedStopwatch {
+pub struct ElapsedStopwatch<C = SystemClock> {
     watermark: Duration,
-    stopwatch: Stopwatch,
+    stopwatch: Stopwatch<C>,
 }
 impl ElapsedStopwatch {
     pub fn new(watermark: Duration) -> Self {
+        Self::with_clock(watermark, SystemClock)
+    }
+}
+impl<C> ElapsedStopwatch<C> {
+    pub fn with_clock(watermark: Duration, clock: C) -> Self {
         Self {
             watermark,
-            stopwatch: Stopwatch::default(),
+            stopwatch: Stopwatch::with_clock(Duration::ZERO, clock),
         }
     }
+    pub const fn stopwatch(&self) -> &Stopwatch<C> {
+        &self.stopwatch
+    }
+    pub const fn stopwatch_mut(&mut self) -> &mut Stopwatch<C> {
+        &mut self.stopwatch
+    }
+}
+impl<C: Clock> ElapsedStopwatch<C> {
     pub fn is_elapsed(&self) -> bool {
         self.watermark <= self.stopwatch.elapsed()
     }
-    pub const fn stopwatch(&self) -> &Stopwatch {
-        &self.stopwatch
+    /// When the watermark will be reached if the stopwatch keeps running from `now` on
+    pub fn as_deadline(&self, now: Instant) -> Deadline {
+        let remaining = self.watermark.saturating_sub(self.stopwatch.elapsed());
+        Deadline::after_from(now, remaining)
     }
-    pub const fn stopwatch_mut(&mut self) -> &mut Stopwatch {
-        &mut self.stopwatch
+}
+
+impl<C: Clock> MetricSource for ElapsedStopwatch<C> {
+    fn visit(&self, visitor: &mut dyn MetricVisitor) {
+        visitor.gauge("elapsed_seconds", self.stopwatch.elapsed().as_secs_f64());
+        visitor.gauge("watermark_seconds", self.watermark.as_secs_f64());
     }
 }
 
@@ -108,11 +153,50 @@ mod tests {
         analysis::bench::ExpMovVar,
         ops::unit::{DurationExt, HumanDuration},
         sync::mcast::{self, spmcast_channel},
-        time::timer::Timer,
+        time::{clock::ManualClock, timer::Timer},
     };
 
     use super::*;
 
+    #[test]
+    fn test_as_deadline() {
+        let clock = ManualClock::new();
+        let mut watch = ElapsedStopwatch::with_clock(Duration::from_secs(1), clock.clone());
+        let now = clock.now();
+        assert_eq!(
+            watch.as_deadline(now),
+            Deadline::after_from(now, Duration::from_secs(1))
+        );
+        watch.stopwatch_mut().start();
+        clock.advance(Duration::from_millis(10));
+        watch.stopwatch_mut().pause();
+        clock.advance(Duration::from_millis(5));
+        let now = clock.now();
+        let remaining = watch.as_deadline(now).remaining(now);
+        assert_eq!(remaining, Duration::from_millis(990));
+        let watch = ElapsedStopwatch::new(Duration::ZERO);
+        assert!(watch.as_deadline(now).is_expired(now));
+    }
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new();
+        let mut watch = Stopwatch::with_clock(Duration::ZERO, clock.clone());
+        {
+            let running = watch.start_scoped();
+            clock.advance(Duration::from_millis(3));
+            assert_eq!(running.stop(), Duration::from_millis(3));
+        }
+        watch.start();
+        clock.advance(Duration::from_millis(4));
+        assert_eq!(watch.elapsed(), Duration::from_millis(7));
+        watch.pause();
+        clock.advance(Duration::from_millis(5));
+        assert_eq!(watch.elapsed(), Duration::from_millis(7));
+        watch.clear();
+        assert_eq!(watch.elapsed(), Duration::ZERO);
+    }
+
     #[test]
     fn test_collect_metrics() {
         let mut batch_watch = ElapsedStopwatch::new(Duration::from_secs(1));